@@ -0,0 +1,84 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/19 10:05:21
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use webparse::{BinaryMut, Buf, Helper, Request};
+
+fn small_get() -> &'static [u8] {
+    b"GET /index.html HTTP/1.1\r\nHost: www.example.com\r\n\r\n"
+}
+
+fn many_headers() -> Vec<u8> {
+    let mut data = b"GET /api/v1/users?id=10 HTTP/1.1\r\nHost: www.example.com\r\n".to_vec();
+    for i in 0..29 {
+        data.extend_from_slice(format!("X-Header-{}: value-{}-abcdefghijklmnopqrstuvwxyz\r\n", i, i).as_bytes());
+    }
+    data.extend_from_slice(b"\r\n");
+    data
+}
+
+fn chunked_body() -> Vec<u8> {
+    let mut data = vec![];
+    for _ in 0..64 {
+        data.extend_from_slice(b"400\r\n");
+        data.extend_from_slice(&[b'a'; 0x400]);
+        data.extend_from_slice(b"\r\n");
+    }
+    data.extend_from_slice(b"0\r\n\r\n");
+    data
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("http1");
+
+    let data = small_get();
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("small_get", |b| {
+        b.iter(|| {
+            let mut req = Request::new();
+            req.parse(black_box(data)).unwrap()
+        })
+    });
+
+    let data = many_headers();
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("30_headers", |b| {
+        b.iter(|| {
+            let mut req = Request::new();
+            req.parse(black_box(&data)).unwrap()
+        })
+    });
+
+    let data = chunked_body();
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("chunked_decode", |b| {
+        b.iter(|| {
+            let mut buffer = BinaryMut::from(black_box(&data[..]));
+            let mut body = Vec::with_capacity(data.len());
+            loop {
+                let (head, num) = Helper::parse_chunk_data(&mut buffer).unwrap();
+                if num == 0 {
+                    break;
+                }
+                body.extend_from_slice(&buffer.chunk()[head..head + num]);
+                buffer.advance(head + num);
+                Helper::skip_new_line(&mut buffer).unwrap();
+            }
+            body
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
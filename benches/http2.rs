@@ -0,0 +1,78 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/19 10:18:42
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use webparse::http2::frame::{Flag, FrameHeader, Headers, Kind, StreamIdentifier};
+use webparse::http2::encoder::Encoder;
+use webparse::http2::Decoder;
+use webparse::{BinaryMut, HeaderMap, HeaderName, HeaderValue};
+
+// RFC 7541 C.3/C.4 中的请求示例, 依次解码以覆盖动态表
+const REQUESTS: [&str; 3] = [
+    "828684410f7777772e6578616d706c652e636f6d",
+    "828684be58086e6f2d6361636865",
+    "828785bf400a637573746f6d2d6b65790c637573746f6d2d76616c7565",
+];
+const HUFFMAN_REQUESTS: [&str; 3] = [
+    "828684418cf1e3c2e5f23a6ba0ab90f4ff",
+    "828684be5886a8eb10649cbf",
+    "828785bf408825a849e95ba97d7f8925a849e95bb8e8b4bf",
+];
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn bench_hpack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hpack");
+    for (name, vectors) in [("rfc_plain", REQUESTS), ("rfc_huffman", HUFFMAN_REQUESTS)] {
+        let blocks: Vec<Vec<u8>> = vectors.iter().map(|s| hex(s)).collect();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut decoder = Decoder::new();
+                for block in &blocks {
+                    let mut buf = BinaryMut::from(black_box(&block[..]));
+                    black_box(decoder.decode(&mut buf).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_headers_frame(c: &mut Criterion) {
+    let mut fields = HeaderMap::new();
+    fields.insert(":method", "GET");
+    fields.insert(":scheme", "https");
+    fields.insert(":path", "/index.html");
+    fields.insert(":authority", "www.example.com");
+    fields.insert(HeaderName::USER_AGENT, "webparse-bench");
+    fields.insert(HeaderName::ACCEPT, "*/*");
+    fields.insert("custom-key", HeaderValue::from_static("custom-value"));
+
+    c.bench_function("h2_headers_encode", |b| {
+        let mut encoder = Encoder::new();
+        b.iter(|| {
+            let head = FrameHeader::new(Kind::Headers, Flag::end_headers(), StreamIdentifier::from(1));
+            let frame = Headers::new(head, fields.clone());
+            let mut dst = BinaryMut::new();
+            frame.encode(&mut encoder, &mut dst).unwrap();
+            dst
+        })
+    });
+}
+
+criterion_group!(benches, bench_hpack, bench_headers_frame);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/19 10:31:07
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use webparse::ws::Masker;
+use webparse::{BinaryMut, BufMut};
+
+fn bench_mask(c: &mut Criterion) {
+    let key = [0x37, 0xfa, 0x21, 0x3d];
+    let mut group = c.benchmark_group("ws_mask");
+    for size in [125usize, 64 * 1024] {
+        let data = vec![0x5au8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(format!("mask_unmask_{}", size), |b| {
+            b.iter(|| {
+                let mut masked = BinaryMut::with_capacity(size);
+                Masker::new(key, &mut masked).put_slice(black_box(&data));
+                let mut unmasked = BinaryMut::with_capacity(size);
+                Masker::new(key, &mut unmasked).put_slice(masked.as_slice());
+                unmasked
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_mask);
+criterion_main!(benches);
@@ -60,14 +60,7 @@ fn hexstr_to_vec(s: &str) -> Vec<u8> {
 // }
 
 fn hex_debug_print(val: &[u8]) {
-    for v in val {
-        print!(
-            "{}{}  ",
-            String::from_utf8_lossy(&vec![Helper::to_hex(v / 16)]),
-            String::from_utf8_lossy(&vec![Helper::to_hex(v % 16)])
-        );
-    }
-    println!();
+    println!("{}", Helper::hex_dump(val));
 }
 
 
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::{BinaryRef, Helper};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Helper::parse_chunk_data(&mut BinaryRef::from(data));
+});
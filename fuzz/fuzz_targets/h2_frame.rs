@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::{
+    http2::{
+        frame::{Frame, FrameHeader},
+        Decoder,
+    },
+    Binary, Buf,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let mut buffer = Binary::from(data.to_vec());
+    let mut decoder = Decoder::new();
+    while let Ok(header) = FrameHeader::parse(&mut buffer) {
+        let length = header.length as usize;
+        if buffer.remaining() < length {
+            break;
+        }
+        let payload = Binary::copy_from_slice(&buffer.chunk()[..length]);
+        buffer.advance(length);
+        if Frame::parse(header, payload, &mut decoder, 16_384).is_err() {
+            break;
+        }
+    }
+});
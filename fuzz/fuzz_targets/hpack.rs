@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::{http2::Decoder, BinaryRef};
+
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = Decoder::new();
+    let _ = decoder.decode(&mut BinaryRef::from(data));
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::Request;
+
+fuzz_target!(|data: &[u8]| {
+    let mut req = Request::new();
+    let _ = req.parse(data);
+});
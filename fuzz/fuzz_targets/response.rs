@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::Response;
+
+fuzz_target!(|data: &[u8]| {
+    let mut res = Response::builder().body(()).unwrap();
+    let _ = res.parse(data);
+});
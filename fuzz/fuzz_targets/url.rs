@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::Url;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(url) = Url::try_from(data) {
+        let _ = url.to_string();
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webparse::{
+    ws::{DataFrame, OwnedMessage},
+    BinaryRef, Buf,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let mut buffer = BinaryRef::from(data);
+    let mut frames = vec![];
+    while buffer.has_remaining() {
+        match DataFrame::read_dataframe_with_limit(&mut buffer, false, 65_536) {
+            Ok(frame) => frames.push(frame),
+            Err(_) => break,
+        }
+    }
+    let _ = OwnedMessage::from_dataframes(frames);
+});
@@ -10,23 +10,25 @@
 // -----
 // Created Date: 2023/08/28 09:38:10
 
-use std::fmt::Debug;
-use std::io;
-use std::io::Error;
-use std::ops::{Deref};
-use std::{
-    alloc::{dealloc, Layout},
+use core::fmt::Debug;
+use core::mem::ManuallyDrop;
+use core::ops::{Bound, Deref, RangeBounds};
+use core::ptr;
+use core::{
     borrow::Borrow,
-    cell::RefCell,
     cmp, hash,
-    io::Read,
-    io::Result,
-    rc::Rc,
     slice,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{self, AtomicUsize, Ordering},
+};
+use alloc::{
+    alloc::{dealloc, Layout},
+    sync::Arc,
 };
+#[cfg(feature = "std")]
+use std::io::{self, Error, Read, Result};
 
-use super::Buf;
+use crate::prelude::*;
+use super::{BinaryMut, Buf, MarkBuf};
 
 static EMPTY_ARRAY: &[u8] = &[];
 const STATIC_TYPE: u8 = 1;
@@ -36,13 +38,17 @@ const SHARED_TYPE: u8 = 2;
 pub struct Binary {
     ptr: *const u8,
     // 共享引用计数
-    counter: Rc<RefCell<AtomicUsize>>,
+    counter: Arc<AtomicUsize>,
     // 游标值, 可以得出当前指向的位置
     cursor: usize,
     // 标记值, 从上一次标记到现在的游标值, 可以得出偏移的对象
     mark: usize,
     // 长度值, 还剩下多少的长度
     len: usize,
+    // 切片视图之前的字节数, 游标为0时相对于原始数据的偏移
+    offset: usize,
+    // 切片视图之后被隐藏的字节数
+    tail: usize,
     // 对象虚表的引用函数
     vtable: &'static Vtable,
 }
@@ -87,13 +93,15 @@ const SHARED_VTABLE: Vtable = Vtable {
 };
 
 unsafe fn shared_clone(bin: &Binary) -> Binary {
-    bin.counter.borrow_mut().fetch_add(1, Ordering::Relaxed);
+    bin.counter.fetch_add(1, Ordering::Relaxed);
     Binary {
         ptr: bin.ptr,
         counter: bin.counter.clone(),
         cursor: bin.cursor,
         mark: bin.mark,
         len: bin.len,
+        offset: bin.offset,
+        tail: bin.tail,
         vtable: bin.vtable,
     }
 }
@@ -104,11 +112,14 @@ unsafe fn shared_to_vec(bin: &Binary) -> Vec<u8> {
 }
 
 unsafe fn shared_drop(bin: &mut Binary) {
-    if (*bin.counter).borrow_mut().fetch_sub(1, Ordering::Release) == 1 {
-        let ori = bin.ptr.sub(bin.cursor);
+    if bin.counter.fetch_sub(1, Ordering::Release) == 1 {
+        // 与其它线程的Release配对, 保证释放前所有的访问均已完成
+        atomic::fence(Ordering::Acquire);
+        let start = bin.offset + bin.cursor;
+        let ori = bin.ptr.sub(start);
         dealloc(
             ori as *mut u8,
-            Layout::from_size_align(bin.cursor + bin.len, 1).unwrap(),
+            Layout::from_size_align(start + bin.len + bin.tail, 1).unwrap(),
         );
     }
 }
@@ -120,10 +131,12 @@ impl Binary {
     pub fn from_static(val: &'static [u8]) -> Binary {
         Binary {
             ptr: val.as_ptr(),
-            counter: Rc::new(RefCell::new(AtomicUsize::new(0))),
+            counter: Arc::new(AtomicUsize::new(0)),
             cursor: 0,
             mark: 0,
             len: val.len(),
+            offset: 0,
+            tail: 0,
             vtable: &STATIC_VTABLE,
         }
     }
@@ -180,9 +193,7 @@ impl Binary {
     /// assert!(b.get_refs() == 1);
     /// ```
     pub fn get_refs(&self) -> usize {
-        (*self.counter)
-            .borrow()
-            .load(std::sync::atomic::Ordering::SeqCst)
+        self.counter.load(Ordering::SeqCst)
     }
 
 
@@ -214,20 +225,119 @@ impl Binary {
         self.len += by;
         self.ptr = self.ptr.sub(by);
         self.cursor -= by;
-        self.mark = std::cmp::min(self.mark, self.cursor);
+        self.mark = core::cmp::min(self.mark, self.cursor);
+    }
+
+    /// 获取当前数据中`range`范围的只读视图, 与原数据共享内存不产生拷贝,
+    /// 视图的`clear`及`mark_reset`不会超出该范围, 范围无效时panic
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Binary, Buf};
+    ///
+    /// let mut b = Binary::from(b"hello world".to_vec());
+    /// b.advance(2);
+    /// let mut view = b.slice(3..);
+    /// assert_eq!(&view[..], b" world");
+    /// assert_eq!(b.get_refs(), 2);
+    /// assert_eq!(&b.slice(..3)[..], b"llo");
+    /// view.advance(3);
+    /// view.clear();
+    /// assert_eq!(&view[..], b" world");
+    /// assert!(b.try_slice(3..10).is_none());
+    /// ```
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Binary {
+        let (start, end) = self.slice_bounds(range);
+        assert!(
+            start <= end && end <= self.len,
+            "range {}..{} out of bounds for Binary of length {}",
+            start,
+            end,
+            self.len
+        );
+        if (self.vtable.vtype)() == STATIC_TYPE {
+            let slice = unsafe { slice::from_raw_parts(self.ptr.add(start), end - start) };
+            return Binary::from_static(slice);
+        }
+        let mut view = self.clone();
+        view.offset += view.cursor + start;
+        view.tail += view.len - end;
+        view.ptr = unsafe { view.ptr.add(start) };
+        view.cursor = 0;
+        view.mark = 0;
+        view.len = end - start;
+        view
+    }
+
+    /// 同`slice`, 范围无效时返回None
+    pub fn try_slice<R: RangeBounds<usize>>(&self, range: R) -> Option<Binary> {
+        let (start, end) = self.slice_bounds(range);
+        if start > end || end > self.len {
+            return None;
+        }
+        Some(self.slice(start..end))
+    }
+
+    fn slice_bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.saturating_add(1),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len,
+        };
+        (start, end)
     }
 
     pub fn copy_from_slice(data: &[u8]) -> Self {
         data.to_vec().into()
     }
 
+    /// 尝试将`Binary`转成可写的`BinaryMut`, 仅当为共享数据且引用数为1时
+    /// 直接接管原有的内存, 不产生拷贝, 否则原样返回
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Binary, Buf};
+    ///
+    /// let mut b = Binary::from(vec![1, 2, 3]);
+    /// b.advance(1);
+    /// let b1 = b.clone();
+    /// let b = b.try_into_mut().unwrap_err();
+    /// drop(b1);
+    /// let bm = b.try_into_mut().unwrap();
+    /// assert_eq!(&bm[..], &[2, 3]);
+    /// assert!(Binary::from_static(b"abc").try_into_mut().is_err());
+    /// ```
+    pub fn try_into_mut(self) -> core::result::Result<BinaryMut, Binary> {
+        if (self.vtable.vtype)() != SHARED_TYPE || self.counter.load(Ordering::Acquire) != 1 {
+            return Err(self);
+        }
+        let this = ManuallyDrop::new(self);
+        let cursor = this.offset + this.cursor;
+        let total = cursor + this.len + this.tail;
+        unsafe {
+            drop(ptr::read(&this.counter));
+            // 共享数据由Box<[u8]>生成, 内存布局与容量等于长度的Vec一致
+            let ori = this.ptr.sub(cursor) as *mut u8;
+            let mut vec = Vec::from_raw_parts(ori, total, total);
+            vec.truncate(cursor + this.len);
+            Ok(BinaryMut::from_vec_cursor(vec, cursor))
+        }
+    }
+
     #[inline]
     pub fn into_slice_all(&self) -> Vec<u8> {
         if (self.vtable.vtype)() == STATIC_TYPE {
             self.to_vec()
         } else {
-            if (*self.counter).borrow().load(Ordering::SeqCst) == 1 {
-                (*self.counter).borrow().fetch_add(1, Ordering::Relaxed);
+            if self.counter.load(Ordering::SeqCst) == 1 {
+                self.counter.fetch_add(1, Ordering::Relaxed);
                 self.to_vec()
             } else {
                 self.to_vec()
@@ -240,8 +350,8 @@ impl Binary {
         if (self.vtable.vtype)() == STATIC_TYPE {
             self.to_vec()[self.cursor..(self.cursor + self.len)].to_vec()
         } else {
-            if (*self.counter).borrow().load(Ordering::SeqCst) == 1 {
-                (*self.counter).borrow().fetch_add(1, Ordering::Relaxed);
+            if self.counter.load(Ordering::SeqCst) == 1 {
+                self.counter.fetch_add(1, Ordering::Relaxed);
                 self.to_vec()[self.cursor..(self.cursor + self.len)].to_vec()
             } else {
                 self.to_vec()[self.cursor..(self.cursor + self.len)].to_vec()
@@ -286,7 +396,9 @@ impl From<Box<[u8]>> for Binary {
             len,
             mark: 0,
             cursor: 0,
-            counter: Rc::new(RefCell::new(AtomicUsize::new(1))),
+            offset: 0,
+            tail: 0,
+            counter: Arc::new(AtomicUsize::new(1)),
             vtable: &SHARED_VTABLE,
         }
     }
@@ -324,8 +436,37 @@ impl Buf for Binary {
         self
     }
 
+    fn copy_to_binary(&mut self, len: usize) -> Binary {
+        let data = self.slice(..len);
+        self.advance(len);
+        data
+    }
+}
+
+impl MarkBuf for Binary {
+    fn mark_commit(&mut self) -> usize {
+        self.mark = self.cursor;
+        self.mark
+    }
+
+    fn mark_len(&self) -> usize {
+        self.cursor - self.mark
+    }
+
+    fn mark_slice_skip(&mut self, skip: usize) -> &[u8] {
+        debug_assert!(self.mark_len() >= skip);
+        let len = self.mark_len();
+        let ret = unsafe { slice::from_raw_parts(self.ptr.sub(len), len - skip) };
+        self.mark = self.cursor;
+        ret
+    }
+
+    fn mark_reset(&mut self) {
+        unsafe { self.sub_start(self.cursor - self.mark) }
+    }
 }
 
+#[cfg(feature = "std")]
 impl Read for Binary {
     #[inline(always)]
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
@@ -333,9 +474,9 @@ impl Read for Binary {
         if left == 0 || buf.len() == 0 {
             return Err(Error::new(io::ErrorKind::WouldBlock, ""));
         }
-        let read = std::cmp::min(left, buf.len());
+        let read = core::cmp::min(left, buf.len());
         unsafe {
-            std::ptr::copy(&self.chunk()[0], &mut buf[0], read);
+            core::ptr::copy(&self.chunk()[0], &mut buf[0], read);
         }
         self.advance(read);
         Ok(read)
@@ -360,7 +501,7 @@ impl Deref for Binary {
 }
 
 impl Debug for Binary {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Binary")
             .field("ptr", &self.ptr)
             .field("counter", &self.counter)
@@ -581,4 +722,21 @@ mod tests {
             assert!(b.get_refs() == 1);
         }
     }
+
+    #[test]
+    fn binary_into_mut() {
+        use crate::{Buf, BinaryMut};
+        let mut b = Binary::from(vec![1, 2, 3, 4]);
+        b.advance(2);
+        let ptr = b.chunk().as_ptr();
+        let mut bm = BinaryMut::from(b);
+        assert_eq!(bm.chunk().as_ptr(), ptr);
+        assert_eq!(&bm[..], &[3, 4]);
+        bm.put_slice(&[5]);
+        assert_eq!(&bm[..], &[3, 4, 5]);
+
+        let b = Binary::from_static(b"abc");
+        let bm = BinaryMut::from(b);
+        assert_eq!(&bm[..], b"abc");
+    }
 }
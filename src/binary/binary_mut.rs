@@ -10,20 +10,21 @@
 // -----
 // Created Date: 2023/08/28 09:38:10
 
-use std::{
-    cell::RefCell,
+use core::{
     cmp,
     fmt::{self, Debug},
     hash,
-    io::{self, Error, Read, Result, Write},
     mem::MaybeUninit,
-    ops::{Deref, DerefMut},
+    ops::{Bound, Deref, DerefMut, RangeBounds},
     ptr,
-    rc::Rc,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{self, AtomicUsize, Ordering},
 };
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::io::{self, Error, Read, Result, Write};
 
-use crate::{Binary, Buf, WebError};
+use crate::prelude::*;
+use crate::{Binary, Buf, MarkBuf, WebError, WebResult};
 
 use super::BufMut;
 
@@ -33,7 +34,7 @@ static RESORT_MEMORY_SIZE: usize = 102400;
 pub struct BinaryMut {
     ptr: *mut Vec<u8>,
     // 共享引用计数
-    counter: Rc<RefCell<AtomicUsize>>,
+    counter: Arc<AtomicUsize>,
     // 游标值, 可以得出当前指向的位置
     cursor: usize,
     // 手动设置长度, 分片时使用
@@ -42,6 +43,8 @@ pub struct BinaryMut {
     mark: usize,
     // 尝试重排的大小
     resort: usize,
+    // 未读取数据的最大长度, 超出时写入失败
+    max_capacity: usize,
 }
 
 impl BinaryMut {
@@ -50,6 +53,40 @@ impl BinaryMut {
         BinaryMut::from_vec(Vec::with_capacity(n))
     }
 
+    /// 限制未读取数据的最大长度, 防止对端无限制的增长缓存,
+    /// 超出时`try_put_slice`返回错误, `put_slice`等写入将panic, 写入对端的数据时应使用`try_put_slice`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf, BufMut};
+    ///
+    /// let mut b = BinaryMut::with_max_capacity(8);
+    /// b.try_put_slice(b"hello").unwrap();
+    /// assert_eq!(b.remaining_mut(), 3);
+    /// assert!(b.try_put_slice(b"world").is_err());
+    /// assert_eq!(b.chunk(), b"hello");
+    /// b.advance(5);
+    /// b.try_put_slice(b"world").unwrap();
+    /// ```
+    #[inline]
+    pub fn with_max_capacity(cap: usize) -> BinaryMut {
+        let mut binary = BinaryMut::new();
+        binary.max_capacity = cap;
+        binary
+    }
+
+    /// 未读取数据的最大长度, 未限制时为`usize::MAX`
+    #[inline]
+    pub fn max_capacity(&self) -> usize {
+        self.max_capacity
+    }
+
+    #[inline]
+    pub fn set_max_capacity(&mut self, cap: usize) {
+        self.max_capacity = cap;
+    }
+
     /// 新建对象
     ///
     /// # Examples
@@ -76,12 +113,22 @@ impl BinaryMut {
             ptr,
             cursor: 0,
             manual_len: usize::MAX,
-            mark: 0,
-            counter: Rc::new(RefCell::new(AtomicUsize::new(1))),
+            mark: usize::MAX,
+            counter: Arc::new(AtomicUsize::new(1)),
             resort: RESORT_MEMORY_SIZE,
+            max_capacity: usize::MAX,
         }
     }
 
+    /// 从已有的数据中构建, 游标之前的数据视为已读取
+    #[inline]
+    pub(crate) fn from_vec_cursor(vec: Vec<u8>, cursor: usize) -> BinaryMut {
+        debug_assert!(cursor <= vec.len());
+        let mut binary = BinaryMut::from_vec(vec);
+        binary.cursor = cursor;
+        binary
+    }
+
     /// 获取引用的数量
     ///
     /// # Examples
@@ -99,15 +146,13 @@ impl BinaryMut {
     /// assert!(b.get_refs() == 1);
     /// ```
     pub fn get_refs(&self) -> usize {
-        (*self.counter)
-            .borrow()
-            .load(std::sync::atomic::Ordering::SeqCst)
+        self.counter.load(Ordering::SeqCst)
     }
 
     #[inline]
     pub fn into_slice_all(&self) -> Vec<u8> {
-        if (*self.counter).borrow().load(Ordering::SeqCst) == 1 {
-            (*self.counter).borrow().fetch_add(1, Ordering::Relaxed);
+        if self.counter.load(Ordering::SeqCst) == 1 {
+            self.counter.fetch_add(1, Ordering::Relaxed);
             let vec = unsafe { Box::from_raw(self.ptr) };
             *vec
         } else {
@@ -118,16 +163,27 @@ impl BinaryMut {
     #[inline]
     pub fn as_slice(&self) -> &[u8] {
         unsafe {
-            let end = std::cmp::min(self.manual_len, (*self.ptr).len());
-            &(*self.ptr)[self.cursor..end]
+            let end = core::cmp::min(self.manual_len, (*self.ptr).len());
+            &(&*self.ptr)[self.cursor..end]
         }
     }
 
     #[inline]
     fn as_slice_mut(&mut self) -> &mut [u8] {
+        self.detach_view();
         unsafe {
-            let end = std::cmp::min(self.manual_len, (*self.ptr).len());
-            &mut (*self.ptr)[self.cursor..end]
+            let end = core::cmp::min(self.manual_len, (*self.ptr).len());
+            &mut (&mut *self.ptr)[self.cursor..end]
+        }
+    }
+
+    /// 视图(`slice_range`或共享时`truncate`得到)在写入前拷贝出独立的数据, 避免修改共享内存的对象
+    #[inline]
+    fn detach_view(&mut self) {
+        if self.manual_len != usize::MAX {
+            let max_capacity = self.max_capacity;
+            *self = BinaryMut::from(self.chunk().to_vec());
+            self.max_capacity = max_capacity;
         }
     }
 
@@ -158,7 +214,7 @@ impl BinaryMut {
     /// ```
     #[inline]
     pub fn len(&self) -> usize {
-        unsafe { (*self.ptr).len() - self.cursor }
+        self.remaining()
     }
 
     #[inline]
@@ -168,7 +224,9 @@ impl BinaryMut {
 
     #[inline]
     pub fn clear(&mut self) {
+        self.detach_view();
         self.cursor = 0;
+        self.mark = usize::MAX;
         unsafe {
             (*self.ptr).set_len(0);
         }
@@ -205,7 +263,9 @@ impl BinaryMut {
         unsafe { (*self.ptr).capacity() }
     }
 
+    /// 预留空间, 不会超出`max_capacity`的限制
     pub fn reserve(&mut self, additional: usize) {
+        let additional = cmp::min(additional, self.remaining_mut());
         unsafe {
             let len = (*self.ptr).len();
             let rem = (*self.ptr).capacity() - len;
@@ -233,6 +293,54 @@ impl BinaryMut {
         src.len()
     }
 
+    /// 写入数据, 超出`max_capacity`或内存分配失败时不写入任何数据并返回错误
+    pub fn try_put_slice(&mut self, src: &[u8]) -> WebResult<usize> {
+        self.try_extend_from_slice(src)?;
+        Ok(src.len())
+    }
+
+    /// 同`extend_from_slice`, 超出`max_capacity`或内存分配失败时返回错误
+    pub fn try_extend_from_slice(&mut self, extend: &[u8]) -> WebResult<()> {
+        self.try_reserve(extend.len())?;
+        self.extend_from_slice(extend);
+        Ok(())
+    }
+
+    /// 预留空间, 超出`max_capacity`或内存分配失败时返回错误而不是中止程序
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::BinaryMut;
+    ///
+    /// let mut b = BinaryMut::new();
+    /// b.try_reserve(64).unwrap();
+    /// assert!(b.capacity() >= 64);
+    /// assert!(b.try_reserve(usize::MAX).is_err());
+    ///
+    /// let mut b = BinaryMut::with_max_capacity(4);
+    /// assert!(b.try_reserve(5).is_err());
+    /// assert!(b.try_extend_from_slice(b"hello").is_err());
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> WebResult<()> {
+        let out_of_memory = |msg| {
+            WebError::Io(crate::io::Error::new(crate::io::ErrorKind::OutOfMemory, msg))
+        };
+        if additional > self.remaining_mut() {
+            return Err(out_of_memory("buffer capacity exceeded"));
+        }
+        unsafe {
+            let len = (*self.ptr).len();
+            if (*self.ptr).capacity() - len >= additional {
+                return Ok(());
+            }
+            (*self.ptr)
+                .try_reserve(additional)
+                .map_err(|_| out_of_memory("buffer allocation failed"))
+        }
+    }
+
     /// 将当前的数据转成不可写的对象Binary
     ///
     ///
@@ -274,7 +382,14 @@ impl BinaryMut {
     /// ```
     #[inline]
     pub fn extend_from_slice(&mut self, extend: &[u8]) {
+        self.detach_view();
         let cnt = extend.len();
+        assert!(
+            self.remaining_mut() >= cnt,
+            "buffer overflow; remaining = {}; src = {}",
+            self.remaining_mut(),
+            cnt
+        );
         self.reserve(cnt);
 
         unsafe {
@@ -290,6 +405,103 @@ impl BinaryMut {
         }
     }
 
+    /// 取出当前数据中`range`范围的视图, 与原对象共享内存, 不拷贝数据,
+    /// 视图在写入时先拷贝出独立的数据, 不会修改原对象. 范围超出当前长度时panic
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf};
+    ///
+    /// let mut b = BinaryMut::from(&b"GET /index HTTP/1.1"[..]);
+    /// b.advance(4);
+    /// let mut path = b.slice_range(..6);
+    /// assert_eq!(path.chunk(), b"/index");
+    /// assert_eq!(b.slice_range(7..).chunk(), b"HTTP/1.1");
+    /// path.advance(1);
+    /// assert_eq!(path.len(), 5);
+    /// assert_eq!(b.get_refs(), 2);
+    ///
+    /// path.put_slice(b"?a=1");
+    /// assert_eq!(path.chunk(), b"index?a=1");
+    /// assert_eq!(b.chunk(), b"/index HTTP/1.1");
+    /// ```
+    pub fn slice_range<R: RangeBounds<usize>>(&self, range: R) -> BinaryMut {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n + 1,
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => len,
+        };
+        assert!(start <= end, "slice_range start {} > end {}", start, end);
+        assert!(end <= len, "slice_range end {} out of range for length {}", end, len);
+        let mut view = self.clone();
+        view.cursor = self.cursor + start;
+        view.manual_len = self.cursor + end;
+        view.mark = usize::MAX;
+        view
+    }
+
+    /// 保留前`len`字节, 大于当前长度时不处理, 与其它对象共享内存时只修改当前的视图
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf};
+    ///
+    /// let mut b = BinaryMut::from(&b"hello world"[..]);
+    /// let other = b.clone();
+    /// b.truncate(5);
+    /// assert_eq!(b.chunk(), b"hello");
+    /// assert_eq!(other.chunk(), b"hello world");
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+        if self.manual_len == usize::MAX && self.get_refs() == 1 {
+            unsafe { (*self.ptr).truncate(self.cursor + len) };
+        } else {
+            self.manual_len = self.cursor + len;
+        }
+    }
+
+    /// 调整为`len`字节, 变长时以`fill`填充, 视图或共享内存的对象变长时先拷贝当前的数据
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf};
+    ///
+    /// let mut b = BinaryMut::from(&b"abc"[..]);
+    /// b.resize(5, b'-');
+    /// assert_eq!(b.chunk(), b"abc--");
+    /// b.resize(2, 0);
+    /// assert_eq!(b.chunk(), b"ab");
+    ///
+    /// let mut view = b.slice_range(1..);
+    /// view.resize(3, b'!');
+    /// assert_eq!(view.chunk(), b"b!!");
+    /// assert_eq!(b.chunk(), b"ab");
+    /// ```
+    pub fn resize(&mut self, len: usize, fill: u8) {
+        let cur = self.len();
+        if len <= cur {
+            self.truncate(len);
+            return;
+        }
+        if self.manual_len != usize::MAX || self.get_refs() != 1 {
+            *self = BinaryMut::from(self.chunk().to_vec());
+        }
+        self.reserve(len - cur);
+        unsafe { (*self.ptr).resize(self.cursor + len, fill) };
+    }
+
     pub fn get_resort(&self) -> usize {
         self.resort
     }
@@ -303,17 +515,25 @@ impl BinaryMut {
         if (*self.ptr).len() < self.resort || self.cursor < self.resort / 2 {
             return;
         }
-        let left = self.remaining();
+        // 标记之后的数据仍可能被回退或读取, 迁移时需保留
+        let start = core::cmp::min(self.mark, self.cursor);
+        if start < self.resort / 2 {
+            return;
+        }
+        let left = self.remaining() + (self.cursor - start);
         // 只有当前只有一个引用的时候尝试做数据迁移，否则会影响另外的数据
-        if (*self.counter).borrow().load(Ordering::SeqCst) == 1 {
+        if self.counter.load(Ordering::SeqCst) == 1 {
             if left == 0 {
                 (*self.ptr).set_len(0);
             } else {
-                std::ptr::copy((*self.ptr).as_ptr().add(self.cursor), (*self.ptr).as_mut_ptr(), left);
+                core::ptr::copy((*self.ptr).as_ptr().add(start), (*self.ptr).as_mut_ptr(), left);
                 (*self.ptr).set_len(left);
             }
 
-            self.cursor = 0;
+            self.cursor -= start;
+            if self.mark != usize::MAX {
+                self.mark -= start;
+            }
             if self.manual_len != usize::MAX {
                 self.manual_len = left;
             }
@@ -329,9 +549,7 @@ impl From<Vec<u8>> for BinaryMut {
 
 impl Clone for BinaryMut {
     fn clone(&self) -> Self {
-        (*self.counter)
-            .borrow()
-            .fetch_add(1, std::sync::atomic::Ordering::Acquire);
+        self.counter.fetch_add(1, Ordering::Relaxed);
         Self {
             ptr: self.ptr.clone(),
             cursor: self.cursor.clone(),
@@ -339,13 +557,15 @@ impl Clone for BinaryMut {
             mark: self.mark.clone(),
             counter: self.counter.clone(),
             resort: self.resort,
+            max_capacity: self.max_capacity,
         }
     }
 }
 
 impl Drop for BinaryMut {
     fn drop(&mut self) {
-        if (*self.counter).borrow_mut().fetch_sub(1, Ordering::Release) == 1 {
+        if self.counter.fetch_sub(1, Ordering::Release) == 1 {
+            atomic::fence(Ordering::Acquire);
             let _vec = unsafe { Box::from_raw(self.ptr) };
         }
     }
@@ -354,7 +574,7 @@ impl Drop for BinaryMut {
 impl Buf for BinaryMut {
     fn remaining(&self) -> usize {
         unsafe {
-            std::cmp::min(self.manual_len, (*self.ptr).len()) - self.cursor
+            core::cmp::min(self.manual_len, (*self.ptr).len()) - self.cursor
         }
     }
 
@@ -364,8 +584,8 @@ impl Buf for BinaryMut {
 
     fn advance_chunk(&mut self, n: usize) -> &[u8] {
         let ret = &unsafe {
-            let end = std::cmp::min(self.manual_len, (*self.ptr).len());
-            &(*self.ptr)[self.cursor..end]
+            let end = core::cmp::min(self.manual_len, (*self.ptr).len());
+            &(&*self.ptr)[self.cursor..end]
         }[..n];
         self.advance(n);
         ret
@@ -384,9 +604,49 @@ impl Buf for BinaryMut {
 
 }
 
+/// 标记位置之后的数据在内存重排时会被保留, 保证回退及取片的正确性,
+/// 未调用`mark_commit`前视为无标记
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{BinaryMut, Buf, MarkBuf};
+///
+/// let mut b = BinaryMut::from(&b"GET /index HTTP/1.1"[..]);
+/// b.advance(4);
+/// b.mark_commit();
+/// b.advance(7);
+/// assert_eq!(b.mark_slice_skip(1), b"/index");
+/// b.advance(4);
+/// b.mark_reset();
+/// assert_eq!(b.chunk(), b"HTTP/1.1");
+/// ```
+impl MarkBuf for BinaryMut {
+    fn mark_commit(&mut self) -> usize {
+        self.mark = self.cursor;
+        self.mark
+    }
+
+    fn mark_len(&self) -> usize {
+        self.cursor.saturating_sub(self.mark)
+    }
+
+    fn mark_slice_skip(&mut self, skip: usize) -> &[u8] {
+        debug_assert!(self.mark_len() >= skip);
+        let start = core::cmp::min(self.mark, self.cursor);
+        let end = self.cursor - skip;
+        self.mark = self.cursor;
+        unsafe { &(&*self.ptr)[start..end] }
+    }
+
+    fn mark_reset(&mut self) {
+        self.cursor = core::cmp::min(self.mark, self.cursor);
+    }
+}
+
 unsafe impl BufMut for BinaryMut {
     fn remaining_mut(&self) -> usize {
-        usize::MAX - self.len()
+        self.max_capacity.saturating_sub(self.len())
     }
 
     unsafe fn advance_mut(&mut self, cnt: usize) {
@@ -395,11 +655,15 @@ unsafe impl BufMut for BinaryMut {
     }
 
     fn chunk_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.detach_view();
         unsafe {
             if (*self.ptr).len() == (*self.ptr).capacity() {
                 self.reserve(128);
             }
-            (*self.ptr).spare_capacity_mut()
+            let limit = self.remaining_mut();
+            let spare = (*self.ptr).spare_capacity_mut();
+            let len = cmp::min(spare.len(), limit);
+            &mut spare[..len]
         }
     }
 }
@@ -460,7 +724,10 @@ impl From<BinaryMut> for Binary {
 
 impl From<Binary> for BinaryMut {
     fn from(src: Binary) -> BinaryMut {
-        BinaryMut::from(src.into_slice())
+        match src.try_into_mut() {
+            Ok(binary) => binary,
+            Err(src) => BinaryMut::from(src.chunk()),
+        }
     }
 }
 
@@ -529,11 +796,12 @@ impl fmt::Write for BinaryMut {
 impl TryInto<String> for BinaryMut {
     type Error = WebError;
 
-    fn try_into(self) -> std::result::Result<String, Self::Error> {
+    fn try_into(self) -> core::result::Result<String, Self::Error> {
         Ok(String::from_utf8_lossy(&self.chunk()).to_string())
     }
 }
 
+#[cfg(feature = "std")]
 impl Read for BinaryMut {
     #[inline(always)]
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
@@ -541,20 +809,23 @@ impl Read for BinaryMut {
         if left == 0 || buf.len() == 0 {
             return Err(Error::new(io::ErrorKind::WouldBlock, ""));
         }
-        let read = std::cmp::min(left, buf.len());
+        let read = core::cmp::min(left, buf.len());
         unsafe {
-            std::ptr::copy(&self.chunk()[0], &mut buf[0], read);
+            core::ptr::copy(&self.chunk()[0], &mut buf[0], read);
         }
         self.advance(read);
         Ok(read)
     }
 }
 
+#[cfg(feature = "std")]
 impl Write for BinaryMut {
     #[inline(always)]
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        self.put_slice(buf);
-        Ok(buf.len())
+        // 超出`max_capacity`时只写入能容纳的部分
+        let len = cmp::min(buf.len(), self.remaining_mut());
+        self.put_slice(&buf[..len]);
+        Ok(len)
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -570,6 +841,7 @@ impl Debug for BinaryMut {
             .field("cursor", &self.cursor)
             .field("manual_len", &self.manual_len)
             .field("mark", &self.mark)
+            .field("max_capacity", &self.max_capacity)
             .finish()
     }
 }
@@ -578,4 +850,23 @@ unsafe impl Sync for BinaryMut {}
 unsafe impl Send for BinaryMut {}
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use crate::{BinaryMut, Buf, MarkBuf};
+
+    #[test]
+    fn mark_survives_resort() {
+        let mut b = BinaryMut::new();
+        b.set_resort(16);
+        b.put_slice(&[b'a'; 20]);
+        b.put_slice(b"hello world");
+        b.advance(18);
+        b.mark_commit();
+        b.advance(4);
+        // 游标超过重排阈值, 但标记处之后的数据需保留
+        assert_eq!(b.cursor(), 4);
+        b.mark_reset();
+        assert_eq!(b.chunk(), b"aahello world");
+        b.advance(7);
+        assert_eq!(b.mark_slice(), b"aahello");
+    }
+}
@@ -10,21 +10,21 @@
 // -----
 // Created Date: 2023/09/14 12:21:02
 
-use std::fmt::Debug;
-use std::io::{Error, self};
-use std::marker::PhantomData;
-use std::ops::{Deref};
-use std::{
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use core::ops::{Deref};
+use core::{
     borrow::Borrow,
     cmp, hash,
-    io::Read,
-    io::Result,
     slice,
 };
+#[cfg(feature = "std")]
+use std::io::{self, Error, Read, Result};
 
-use crate::Binary;
+use crate::prelude::*;
+use crate::{Binary, BinaryMut};
 
-use super::Buf;
+use super::{Buf, MarkBuf};
 
 static EMPTY_ARRAY: &[u8] = &[];
 
@@ -101,15 +101,34 @@ impl<'a> BinaryRef<'a> {
         self.cursor += by;
     }
 
-    // #[inline]
-    // unsafe fn sub_start(&mut self, by: usize) {
-    //     // should already be asserted, but debug assert for tests
-    //     debug_assert!(self.cursor >= by, "internal: inc_start out of bounds");
-    //     self.len += by;
-    //     self.ptr = self.ptr.sub(by);
-    //     self.cursor -= by;
-    //     self.mark = std::cmp::min(self.mark, self.cursor);
-    // }
+    #[inline]
+    unsafe fn sub_start(&mut self, by: usize) {
+        // should already be asserted, but debug assert for tests
+        debug_assert!(self.cursor >= by, "internal: sub_start out of bounds");
+        self.len += by;
+        self.ptr = self.ptr.sub(by);
+        self.cursor -= by;
+        self.mark = core::cmp::min(self.mark, self.cursor);
+    }
+
+    /// 获取剩余数据的引用, 生命周期与原始数据一致
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryRef, Buf};
+    ///
+    /// let data = b"hello world".to_vec();
+    /// let mut b = BinaryRef::from(&data[..]);
+    /// b.advance(6);
+    /// let left: &[u8] = b.as_bytes();
+    /// drop(b);
+    /// assert_eq!(left, b"world");
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
 
     pub fn copy_from_slice(data: &'a [u8]) -> Self {
         data.into()
@@ -158,6 +177,30 @@ impl<'a> From<&'a [u8]> for BinaryRef<'a> {
     }
 }
 
+impl<'a> From<&'a Vec<u8>> for BinaryRef<'a> {
+    fn from(value: &'a Vec<u8>) -> Self {
+        BinaryRef::from(&value[..])
+    }
+}
+
+impl<'a> From<&'a Binary> for BinaryRef<'a> {
+    fn from(value: &'a Binary) -> Self {
+        BinaryRef::from(value.chunk())
+    }
+}
+
+impl<'a> From<&'a BinaryMut> for BinaryRef<'a> {
+    fn from(value: &'a BinaryMut) -> Self {
+        BinaryRef::from(value.chunk())
+    }
+}
+
+impl<'a> From<BinaryRef<'a>> for &'a [u8] {
+    fn from(value: BinaryRef<'a>) -> Self {
+        value.as_bytes()
+    }
+}
+
 impl<'a> Buf for BinaryRef<'a> {
     fn remaining(&self) -> usize {
         self.len
@@ -187,6 +230,30 @@ impl<'a> Buf for BinaryRef<'a> {
 }
 
 
+impl<'a> MarkBuf for BinaryRef<'a> {
+    fn mark_commit(&mut self) -> usize {
+        self.mark = self.cursor;
+        self.mark
+    }
+
+    fn mark_len(&self) -> usize {
+        self.cursor - self.mark
+    }
+
+    fn mark_slice_skip(&mut self, skip: usize) -> &[u8] {
+        debug_assert!(self.mark_len() >= skip);
+        let len = self.mark_len();
+        let ret = unsafe { slice::from_raw_parts(self.ptr.sub(len), len - skip) };
+        self.mark = self.cursor;
+        ret
+    }
+
+    fn mark_reset(&mut self) {
+        unsafe { self.sub_start(self.cursor - self.mark) }
+    }
+}
+
+#[cfg(feature = "std")]
 impl<'a> Read for BinaryRef<'a> {
     #[inline(always)]
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
@@ -194,9 +261,9 @@ impl<'a> Read for BinaryRef<'a> {
         if left == 0 || buf.len() == 0 {
             return Err(Error::new(io::ErrorKind::WouldBlock, ""));
         }
-        let read = std::cmp::min(left, buf.len());
+        let read = core::cmp::min(left, buf.len());
         unsafe {
-            std::ptr::copy(&self.chunk()[0], &mut buf[0], read);
+            core::ptr::copy(&self.chunk()[0], &mut buf[0], read);
         }
         self.advance(read);
         Ok(read)
@@ -221,7 +288,7 @@ impl<'a> Deref for BinaryRef<'a> {
 }
 
 impl<'a> Debug for BinaryRef<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Binary")
             .field("ptr", &self.ptr)
             .field("cursor", &self.cursor)
@@ -424,7 +491,7 @@ impl<'a> Default for BinaryRef<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{BinaryRef, Buf};
+    use crate::{BinaryRef, Buf, MarkBuf};
 
 
     #[test]
@@ -443,4 +510,19 @@ mod tests {
             drop(b1);
         }
     }
+
+    #[test]
+    fn binary_ref_mark() {
+        let data = b"GET / HTTP/1.1\r\n".to_vec();
+        let mut b = BinaryRef::from(&data);
+        b.advance(4);
+        assert_eq!(b.mark_len(), 4);
+        assert_eq!(b.mark_slice_skip(1), b"GET");
+        b.advance(2);
+        b.mark_reset();
+        assert_eq!(b.mark_len(), 0);
+        assert_eq!(b.chunk(), b"/ HTTP/1.1\r\n");
+        b.advance(2);
+        assert_eq!(b.mark_slice(), b"/ ");
+    }
 }
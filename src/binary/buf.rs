@@ -10,11 +10,20 @@
 // -----
 // Created Date: 2023/08/28 09:38:10
 
-use std::{mem, io::{self}};
+use core::mem;
+#[cfg(feature = "std")]
+use std::io::IoSlice;
 
-use crate::{Binary, try_advance};
+use crate::{io, try_advance, Binary};
+#[cfg(feature = "std")]
+use crate::{
+    http2::{decoder::IntegerDecodingError, DecoderError},
+    Http2Error, WebResult,
+};
 
 use super::panic_advance;
+#[cfg(feature = "std")]
+use super::Reader;
 
 macro_rules! buf_get_impl {
     ($this:ident, $typ:tt::$conv:tt) => {{
@@ -72,6 +81,29 @@ pub trait Buf {
     /// 将数据转成Binary
     fn into_binary(self) -> Binary;
 
+    /// 取出前`len`个字节为`Binary`, 默认拷贝数据, `Binary`中直接返回共享的切片
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Binary, Buf};
+    ///
+    /// let mut buf = Binary::from(b"hello world".to_vec());
+    /// let hello = buf.copy_to_binary(5);
+    /// assert_eq!(hello.chunk(), b"hello");
+    /// assert_eq!(buf.chunk(), b" world");
+    /// assert_eq!((&b"abc"[..]).copy_to_binary(2).chunk(), b"ab");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `len`大于剩余的数据时panic
+    fn copy_to_binary(&mut self, len: usize) -> Binary {
+        let data = Binary::from(self.chunk()[..len].to_vec());
+        self.advance(len);
+        data
+    }
+
     /// 消耗所有的字节
     fn advance_all(&mut self) {
         self.advance(self.remaining());
@@ -126,12 +158,162 @@ pub trait Buf {
         assert!(self.remaining() >= dst.len());
         unsafe {
             let src = self.chunk();
-            std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), dst.len());
+            core::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), dst.len());
             self.advance(dst.len())
         }
         dst.len()
     }
 
+    /// 将当前的数据填充到`dst`中, 用于`write_vectored`等批量写入, 返回填充的个数
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::IoSlice;
+    /// use webparse::Buf;
+    ///
+    /// let buf = &b"hello world"[..];
+    /// let mut dst = [IoSlice::new(&[]); 2];
+    /// assert_eq!(buf.chunks_vectored(&mut dst), 1);
+    /// assert_eq!(&dst[0][..], b"hello world");
+    /// ```
+    #[cfg(feature = "std")]
+    fn chunks_vectored<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        if dst.is_empty() {
+            return 0;
+        }
+        if self.has_remaining() {
+            dst[0] = IoSlice::new(self.chunk());
+            1
+        } else {
+            0
+        }
+    }
+
+    /// 读取3个字节的大端无符号整数, 如HTTP/2帧头的长度
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Buf;
+    ///
+    /// let mut buf = &b"\x01\x02\x03 hello"[..];
+    /// assert_eq!(0x010203, buf.get_u24());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is not enough remaining data in `self`.
+    fn get_u24(&mut self) -> u32 {
+        self.get_uint(3) as u32
+    }
+
+    fn try_get_u24(&mut self) -> io::Result<u32> {
+        try_advance!(self.remaining() >= 3);
+        Ok(self.get_u24())
+    }
+
+    /// 读取4个字节的大端无符号整数, 并忽略最高的保留位, 如HTTP/2的StreamId
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Buf;
+    ///
+    /// let mut buf = &b"\x80\x00\x00\x01 hello"[..];
+    /// assert_eq!(1, buf.get_u31_masked());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is not enough remaining data in `self`.
+    fn get_u31_masked(&mut self) -> u32 {
+        self.get_u32() & ((1u32 << 31) - 1)
+    }
+
+    fn try_get_u31_masked(&mut self) -> io::Result<u32> {
+        try_advance!(self.remaining() >= 4);
+        Ok(self.get_u31_masked())
+    }
+
+    /// 读取HPACK格式的整数(RFC7541 5.1), `prefix_bits`为首字节中可用的位数,
+    /// 数据不完整或格式错误时不消耗任何数据
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Buf;
+    ///
+    /// let mut buf = &[0x1f, 0x9a, 0x0a][..];
+    /// assert_eq!(1337, buf.get_hpack_int(5).unwrap());
+    /// assert!(!buf.has_remaining());
+    /// ```
+    #[cfg(feature = "std")]
+    fn get_hpack_int(&mut self, prefix_bits: u8) -> WebResult<usize> {
+        fn err(e: IntegerDecodingError) -> crate::WebError {
+            Http2Error::into(DecoderError::IntegerDecodingError(e))
+        }
+        if !(1..=8).contains(&prefix_bits) {
+            return Err(err(IntegerDecodingError::InvalidPrefix));
+        }
+        let buf = self.chunk();
+        if buf.is_empty() {
+            return Err(err(IntegerDecodingError::NotEnoughOctets));
+        }
+
+        let mask = if prefix_bits == 8 {
+            0xFF
+        } else {
+            (1u8 << prefix_bits) - 1
+        };
+        let mut value = (buf[0] & mask) as usize;
+        if value < (mask as usize) {
+            self.advance(1);
+            return Ok(value);
+        }
+
+        // 最多允许5个字节, 超出视为错误
+        let octet_limit = 5;
+        let mut m = 0;
+        for (idx, &b) in buf[1..].iter().enumerate() {
+            let total = idx + 2;
+            value += ((b & 127) as usize) << m;
+            m += 7;
+
+            if b & 128 != 128 {
+                self.advance(total);
+                return Ok(value);
+            }
+
+            if total == octet_limit {
+                return Err(err(IntegerDecodingError::TooManyOctets));
+            }
+        }
+        Err(err(IntegerDecodingError::NotEnoughOctets))
+    }
+
+    /// 生成实现`std::io::Read`的适配对象
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use webparse::Buf;
+    ///
+    /// let mut reader = (&b"hello world"[..]).reader();
+    /// let mut dst = [0; 5];
+    /// reader.read_exact(&mut dst).unwrap();
+    /// assert_eq!(&dst, b"hello");
+    /// assert_eq!(reader.get_ref().remaining(), 6);
+    /// ```
+    #[cfg(feature = "std")]
+    fn reader(self) -> Reader<Self>
+    where
+        Self: Sized,
+    {
+        Reader::new(self)
+    }
+
 
     fn get_u8(&mut self) -> u8 {
         assert!(self.remaining() >= 1);
@@ -1119,6 +1301,7 @@ impl Buf for &[u8] {
 }
 
 
+#[cfg(feature = "std")]
 impl<T: AsRef<[u8]>> Buf for std::io::Cursor<T> {
     #[inline]
     fn remaining(&self) -> usize {
@@ -1148,4 +1331,46 @@ impl<T: AsRef<[u8]>> Buf for std::io::Cursor<T> {
     fn into_binary(self) -> Binary {
         Binary::from(self.get_ref().as_ref()[(self.position() as usize)..].to_vec())
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{Buf, BufMut};
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hpack_int() {
+        // RFC 7541 C.1
+        let cases: [(usize, u8, &[u8]); 3] = [
+            (10, 5, &[0x0a]),
+            (1337, 5, &[0x1f, 0x9a, 0x0a]),
+            (42, 8, &[0x2a]),
+        ];
+        for (value, prefix, encoded) in cases {
+            let mut vec = vec![];
+            assert_eq!(vec.put_hpack_int(value, prefix, 0), encoded.len());
+            assert_eq!(&vec[..], encoded);
+            let mut buf = encoded;
+            assert_eq!(buf.get_hpack_int(prefix).unwrap(), value);
+            assert!(!buf.has_remaining());
+        }
+
+        let mut partial = &[0x1f, 0x9a][..];
+        assert!(partial.get_hpack_int(5).is_err());
+        assert_eq!(partial.remaining(), 2);
+        let mut too_long = &[0x1f, 0xff, 0xff, 0xff, 0xff, 0x01][..];
+        assert!(too_long.get_hpack_int(5).is_err());
+    }
+
+    #[test]
+    fn u24_u31() {
+        let mut vec = vec![];
+        assert_eq!(vec.put_u24(0x123456), 3);
+        vec.put_u32(0x8000_0005);
+        let mut buf = &vec[..];
+        assert_eq!(buf.get_u24(), 0x123456);
+        assert_eq!(buf.get_u31_masked(), 5);
+        assert!(buf.try_get_u24().is_err());
+    }
+}
@@ -10,13 +10,16 @@
 // -----
 // Created Date: 2023/08/28 09:38:10
 
-use std::{
+use core::{
     cmp,
     mem::{self, MaybeUninit},
     ptr, slice,
 };
 
+use crate::prelude::*;
 use super::panic_advance;
+#[cfg(feature = "std")]
+use super::Writer;
 
 pub unsafe trait BufMut {
     fn remaining_mut(&self) -> usize;
@@ -27,6 +30,26 @@ pub unsafe trait BufMut {
         self.remaining_mut() > 0
     }
 
+    /// 生成实现`std::io::Write`的适配对象
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use webparse::{BinaryMut, BufMut};
+    ///
+    /// let mut writer = BinaryMut::new().writer();
+    /// writer.write_all(b"hello world").unwrap();
+    /// assert_eq!(&writer.into_inner()[..], b"hello world");
+    /// ```
+    #[cfg(feature = "std")]
+    fn writer(self) -> Writer<Self>
+    where
+        Self: Sized,
+    {
+        Writer::new(self)
+    }
+
     fn put<T: super::Buf>(&mut self, src: &mut T) -> usize
     where
         Self: Sized,
@@ -250,6 +273,55 @@ pub unsafe trait BufMut {
         2
     }
 
+    /// 写入3个字节的大端无符号整数, 高位将被忽略
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::BufMut;
+    ///
+    /// let mut buf = vec![];
+    /// buf.put_u24(0x010203);
+    /// assert_eq!(buf, b"\x01\x02\x03");
+    /// ```
+    fn put_u24(&mut self, n: u32) -> usize {
+        self.put_slice(&n.to_be_bytes()[1..])
+    }
+
+    /// 写入HPACK格式的整数(RFC7541 5.1), `leading_bits`为首字节中前缀之外的标识位
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::BufMut;
+    ///
+    /// let mut buf = vec![];
+    /// buf.put_hpack_int(1337, 5, 0);
+    /// assert_eq!(buf, [0x1f, 0x9a, 0x0a]);
+    /// ```
+    #[cfg(feature = "std")]
+    fn put_hpack_int(&mut self, mut value: usize, prefix_bits: u8, leading_bits: u8) -> usize {
+        let mask = if prefix_bits >= 8 {
+            0xFF
+        } else {
+            (1u8 << prefix_bits) - 1
+        };
+        let leading_bits = leading_bits & (!mask);
+        let mask = mask as usize;
+        if value < mask {
+            return self.put_u8(leading_bits | value as u8);
+        }
+
+        let mut size = self.put_u8(leading_bits | mask as u8);
+        value -= mask;
+        while value >= 128 {
+            size += self.put_u8(((value % 128) + 128) as u8);
+            value /= 128;
+        }
+        size += self.put_u8(value as u8);
+        size
+    }
+
     /// Writes an unsigned 32 bit integer to `self` in big-endian byte order.
     ///
     /// The current position is advanced by 4.
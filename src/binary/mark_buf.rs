@@ -0,0 +1,37 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/14 12:21:02
+
+use super::Buf;
+
+/// 可标记的缓存, 在解析时记录起始位置, 解析完成后可取出标记到当前游标的数据,
+/// 数据不完整时可回退到标记处重新解析
+///
+/// `Binary`, `BinaryMut`, `BinaryRef`均已实现, `&[u8]`无法保存标记位置,
+/// 需通过`BinaryRef::from`包装后使用
+pub trait MarkBuf: Buf {
+    /// 将当前游标设置为标记位置, 返回当前的游标值
+    fn mark_commit(&mut self) -> usize;
+
+    /// 从上一次标记到当前游标已经消耗的字节数
+    fn mark_len(&self) -> usize;
+
+    /// 获取从标记处到当前游标之前`skip`个字节的数据, 并将当前游标设置为标记位置
+    fn mark_slice_skip(&mut self, skip: usize) -> &[u8];
+
+    /// 将游标回退到上一次的标记位置
+    fn mark_reset(&mut self);
+
+    /// 获取从标记处到当前游标的数据, 并将当前游标设置为标记位置
+    fn mark_slice(&mut self) -> &[u8] {
+        self.mark_slice_skip(0)
+    }
+}
@@ -12,17 +12,31 @@
 
 // copy a large content from bytes.
 
+//! 缓存相关的类型及trait, 本crate中的Request/Response/http2帧/websocket
+//! 解析与编码统一以`Buf`, `BufMut`, `MarkBuf`作为约束, 不依赖外部缓存trait,
+//! 泛型代码只需满足这一组trait即可
+
 mod binary;
 mod binary_mut;
 mod binary_ref;
 mod buf;
 mod buf_mut;
+mod mark_buf;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod writer;
 
 pub use binary::Binary;
 pub use binary_mut::BinaryMut;
 pub use binary_ref::BinaryRef;
 pub use buf::Buf;
 pub use buf_mut::BufMut;
+pub use mark_buf::MarkBuf;
+#[cfg(feature = "std")]
+pub use reader::Reader;
+#[cfg(feature = "std")]
+pub use writer::Writer;
 
 fn panic_advance(cnt: usize, left: usize) {
     panic!("当前只剩余:{},无法消耗:{}", left, cnt);
@@ -0,0 +1,57 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/14 12:21:02
+
+use std::{cmp, io};
+
+use super::Buf;
+
+/// 将`Buf`适配成`std::io::Read`, 通过`Buf::reader`生成
+#[derive(Debug)]
+pub struct Reader<B> {
+    buf: B,
+}
+
+impl<B: Buf> Reader<B> {
+    pub(crate) fn new(buf: B) -> Reader<B> {
+        Reader { buf }
+    }
+
+    pub fn get_ref(&self) -> &B {
+        &self.buf
+    }
+
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.buf
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<B: Buf> io::Read for Reader<B> {
+    fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+        let len = cmp::min(self.buf.remaining(), dst.len());
+        self.buf.copy_to_slice(&mut dst[0..len]);
+        Ok(len)
+    }
+}
+
+impl<B: Buf> io::BufRead for Reader<B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.buf.chunk())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buf.advance(amt)
+    }
+}
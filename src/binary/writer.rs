@@ -0,0 +1,50 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/14 12:21:02
+
+use std::{cmp, io};
+
+use super::BufMut;
+
+/// 将`BufMut`适配成`std::io::Write`, 通过`BufMut::writer`生成
+#[derive(Debug)]
+pub struct Writer<B> {
+    buf: B,
+}
+
+impl<B: BufMut> Writer<B> {
+    pub(crate) fn new(buf: B) -> Writer<B> {
+        Writer { buf }
+    }
+
+    pub fn get_ref(&self) -> &B {
+        &self.buf
+    }
+
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.buf
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<B: BufMut> io::Write for Writer<B> {
+    fn write(&mut self, src: &[u8]) -> io::Result<usize> {
+        let n = cmp::min(self.buf.remaining_mut(), src.len());
+        Ok(self.buf.put_slice(&src[0..n]))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
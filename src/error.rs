@@ -10,20 +10,127 @@
 // -----
 // Created Date: 2023/08/15 10:47:56
 
-use std::{fmt::{self}, result, convert::Infallible};
+use core::{fmt::{self}, result, convert::Infallible};
 
-use crate::{http::HttpError, url::UrlError, Http2Error, ws::WsError};
+use crate::{http::HttpError, io, url::UrlError, ws::WsError};
+#[cfg(feature = "std")]
+use crate::Http2Error;
+
+/// 解析失败时期望得到的内容类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    Method,
+    Token,
+    Version,
+    StatusCode,
+    HeaderName,
+    HeaderValue,
+    NewLine,
+    Other,
+}
+
+impl Expected {
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Expected::Method => "method",
+            Expected::Token => "token",
+            Expected::Version => "version",
+            Expected::StatusCode => "status code",
+            Expected::HeaderName => "header name",
+            Expected::HeaderValue => "header value",
+            Expected::NewLine => "new line",
+            Expected::Other => "other",
+        }
+    }
+}
+
+impl From<HttpError> for Expected {
+    fn from(e: HttpError) -> Self {
+        match e {
+            HttpError::Method => Expected::Method,
+            HttpError::Token => Expected::Token,
+            HttpError::Version => Expected::Version,
+            HttpError::Status | HttpError::InvalidStatusCode => Expected::StatusCode,
+            HttpError::HeaderName => Expected::HeaderName,
+            HttpError::HeaderValue => Expected::HeaderValue,
+            HttpError::NewLine => Expected::NewLine,
+            _ => Expected::Other,
+        }
+    }
+}
+
+/// 带位置信息的解析错误, 不产生内存分配
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{Expected, HttpError, Request, WebError};
+/// let data = b"GET / HTTP/1.1\r\nHo st: a\r\n\r\n";
+/// let mut req = Request::new();
+/// let err = req.parse_with_report(data).unwrap_err();
+/// assert_eq!(err.offset(), Some(20));
+/// assert_eq!(err.expected(), Some(Expected::HeaderName));
+///
+/// // `parse`保持原有的错误类型
+/// let err = req.parse(data).unwrap_err();
+/// assert!(matches!(err, WebError::Http(HttpError::HeaderName)));
+/// assert_eq!(err.expected(), Some(Expected::HeaderName));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    offset: usize,
+    kind: HttpError,
+}
+
+impl ParseError {
+    pub fn new(offset: usize, kind: HttpError) -> ParseError {
+        ParseError { offset, kind }
+    }
+
+    /// 出错时已读取的字节数
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[inline]
+    pub fn kind(&self) -> HttpError {
+        self.kind
+    }
+
+    #[inline]
+    pub fn expected(&self) -> Expected {
+        Expected::from(self.kind)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}, expected {}", self.kind, self.offset, self.expected().as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
 
 #[derive(Debug)]
 pub enum WebError {
     Http(HttpError),
+    /// 带位置信息的Http解析错误
+    Parse(ParseError),
+    #[cfg(feature = "std")]
     Http2(Http2Error),
     Ws(WsError),
     Url(UrlError),
     IntoError,
     Extension(&'static str),
     Serialize(&'static str),
-    Io(std::io::Error),
+    Io(io::Error),
 }
 
 impl WebError {
@@ -32,13 +139,14 @@ impl WebError {
         match self {
             WebError::Url(e) => e.description_str(),
             WebError::Http(e) => e.description_str(),
+            WebError::Parse(e) => e.kind.description_str(),
+            #[cfg(feature = "std")]
             WebError::Http2(e) => e.description_str(),
             WebError::Ws(e) => e.description_str(),
             WebError::IntoError => "into value error",
             WebError::Extension(_) => "std error",
             WebError::Serialize(_) => "serialize error",
             WebError::Io(_) => "io error",
-            
         }
     }
 
@@ -48,22 +156,79 @@ impl WebError {
             _ => false
         }
     }
+
+    /// 为Http解析错误附加出错的字节偏移, 转为`WebError::Parse`, 数据不足及其它错误原样返回
+    pub fn with_offset(self, offset: usize) -> WebError {
+        match self {
+            WebError::Http(HttpError::Partial) => self,
+            WebError::Http(e) => WebError::Parse(ParseError::new(offset, e)),
+            _ => self,
+        }
+    }
+
+    /// 出错时的字节偏移, 仅解析错误包含该信息
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            WebError::Parse(e) => Some(e.offset),
+            _ => None,
+        }
+    }
+
+    /// 出错位置期望的内容类别
+    pub fn expected(&self) -> Option<Expected> {
+        match self {
+            WebError::Parse(e) => Some(e.expected()),
+            WebError::Http(HttpError::Partial) => None,
+            WebError::Http(e) => Some(Expected::from(*e)),
+            _ => None,
+        }
+    }
+
+    /// 获取Http解析错误, 不论是否带有偏移信息
+    pub fn http_error(&self) -> Option<HttpError> {
+        match self {
+            WebError::Http(e) => Some(*e),
+            WebError::Parse(e) => Some(e.kind),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for WebError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.description_str())
+        match self {
+            WebError::Parse(e) => e.fmt(f),
+            WebError::Extension(s) => write!(f, "extension error: {}", s),
+            WebError::Serialize(s) => write!(f, "serialize error: {}", s),
+            WebError::Io(e) => write!(f, "io error: {}", e),
+            _ => f.write_str(self.description_str()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WebError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WebError::Http(e) => Some(e),
+            WebError::Parse(e) => Some(e),
+            WebError::Http2(e) => Some(e),
+            WebError::Ws(e) => Some(e),
+            WebError::Url(e) => Some(e),
+            WebError::Io(e) => Some(e),
+            _ => None,
+        }
     }
 }
 
-impl From<std::num::ParseIntError> for WebError {
-    fn from(_: std::num::ParseIntError) -> Self {
+impl From<core::num::ParseIntError> for WebError {
+    fn from(_: core::num::ParseIntError) -> Self {
         WebError::Extension("parse int error")
     }
 }
 
-impl From<std::io::Error> for WebError {
-    fn from(e: std::io::Error) -> Self {
+impl From<io::Error> for WebError {
+    fn from(e: io::Error) -> Self {
         WebError::Io(e)
     }
 }
@@ -10,19 +10,50 @@
 // -----
 // Created Date: 2023/08/18 02:18:00
 
-use std::any::{Any, TypeId};
+use core::any::{Any, TypeId};
+use core::fmt;
+#[cfg(feature = "std")]
+use core::hash::{BuildHasherDefault, Hasher};
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
-use std::hash::{BuildHasherDefault, Hasher};
+use crate::prelude::*;
 
-type AnyMap = HashMap<TypeId, Box<dyn Any + Send + Sync>, BuildHasherDefault<IdHasher>>;
+type AnyValue = Box<dyn Any + Send + Sync>;
+
+#[cfg(feature = "std")]
+type AnyMap = HashMap<TypeId, Entry, BuildHasherDefault<IdHasher>>;
+// 无std时使用BTreeMap, TypeId本身可比较
+#[cfg(not(feature = "std"))]
+type AnyMap = alloc::collections::BTreeMap<TypeId, Entry>;
+
+// 存储的值及可选的克隆函数, 只有通过`insert_cloneable`插入的值才能在克隆时保留
+struct Entry {
+    value: AnyValue,
+    clone: Option<fn(&AnyValue) -> AnyValue>,
+}
+
+fn clone_value<T: Clone + Send + Sync + 'static>(value: &AnyValue) -> AnyValue {
+    let value: &T = (&**value as &(dyn Any + 'static))
+        .downcast_ref()
+        .expect("extension type mismatch");
+    Box::new(value.clone())
+}
+
+fn downcast_owned<T: 'static>(entry: Entry) -> Option<T> {
+    (entry.value as Box<dyn Any + 'static>)
+        .downcast()
+        .ok()
+        .map(|boxed| *boxed)
+}
 
 // With TypeIds as keys, there's no need to hash them. They are already hashes
 // themselves, coming from the compiler. The IdHasher just holds the u64 of
 // the TypeId, and then returns it, instead of doing any bit fiddling.
+#[cfg(feature = "std")]
 #[derive(Default)]
 struct IdHasher(u64);
 
+#[cfg(feature = "std")]
 impl Hasher for IdHasher {
     fn write(&mut self, _: &[u8]) {
         unreachable!("TypeId calls write_u64");
@@ -72,15 +103,65 @@ impl Extensions {
     /// assert_eq!(ext.insert(9i32), Some(5i32));
     /// ```
     pub fn insert<T: Send + Sync + 'static>(&mut self, val: T) -> Option<T> {
+        self.insert_entry(val, None)
+    }
+
+    /// 插入可克隆的值, 在`Extensions::clone`及`Parts::clone`时会一同被克隆,
+    /// 而通过`insert`插入的值在克隆时将被丢弃
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use webparse::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert_cloneable(String::from("trace-id"));
+    /// ext.insert(5i32);
+    ///
+    /// let cloned = ext.clone();
+    /// assert_eq!(cloned.get::<String>().unwrap(), "trace-id");
+    /// assert!(cloned.get::<i32>().is_none());
+    /// ```
+    pub fn insert_cloneable<T: Clone + Send + Sync + 'static>(&mut self, val: T) -> Option<T> {
+        self.insert_entry(val, Some(clone_value::<T>))
+    }
+
+    fn insert_entry<T: Send + Sync + 'static>(
+        &mut self,
+        val: T,
+        clone: Option<fn(&AnyValue) -> AnyValue>,
+    ) -> Option<T> {
         self.map
-            .get_or_insert_with(|| Box::new(HashMap::default()))
-            .insert(TypeId::of::<T>(), Box::new(val))
-            .and_then(|boxed| {
-                (boxed as Box<dyn Any + 'static>)
-                    .downcast()
-                    .ok()
-                    .map(|boxed| *boxed)
-            })
+            .get_or_insert_with(|| Box::new(AnyMap::default()))
+            .insert(TypeId::of::<T>(), Entry { value: Box::new(val), clone })
+            .and_then(downcast_owned)
+    }
+
+    /// 获取指定类型的值, 若不存在则通过`f`生成并插入
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use webparse::Extensions;
+    /// let mut ext = Extensions::new();
+    /// *ext.get_or_insert_with(|| 0u32) += 1;
+    /// *ext.get_or_insert_with(|| 0u32) += 1;
+    /// assert_eq!(ext.get::<u32>(), Some(&2));
+    /// ```
+    pub fn get_or_insert_with<T: Send + Sync + 'static, F: FnOnce() -> T>(
+        &mut self,
+        f: F,
+    ) -> &mut T {
+        let entry = self
+            .map
+            .get_or_insert_with(|| Box::new(AnyMap::default()))
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Entry {
+                value: Box::new(f()),
+                clone: None,
+            });
+        (&mut *entry.value as &mut (dyn Any + 'static))
+            .downcast_mut()
+            .expect("extension type mismatch")
     }
 
     /// Get a reference to a type previously inserted on this `Extensions`.
@@ -99,7 +180,7 @@ impl Extensions {
         self.map
             .as_ref()
             .and_then(|map| map.get(&TypeId::of::<T>()))
-            .and_then(|boxed| (&**boxed as &(dyn Any + 'static)).downcast_ref())
+            .and_then(|entry| (&*entry.value as &(dyn Any + 'static)).downcast_ref())
     }
 
     /// Get a mutable reference to a type previously inserted on this `Extensions`.
@@ -118,7 +199,7 @@ impl Extensions {
         self.map
             .as_mut()
             .and_then(|map| map.get_mut(&TypeId::of::<T>()))
-            .and_then(|boxed| (&mut **boxed as &mut (dyn Any + 'static)).downcast_mut())
+            .and_then(|entry| (&mut *entry.value as &mut (dyn Any + 'static)).downcast_mut())
     }
 
     /// Remove a type from this `Extensions`.
@@ -138,12 +219,7 @@ impl Extensions {
         self.map
             .as_mut()
             .and_then(|map| map.remove(&TypeId::of::<T>()))
-            .and_then(|boxed| {
-                (boxed as Box<dyn Any + 'static>)
-                    .downcast()
-                    .ok()
-                    .map(|boxed| *boxed)
-            })
+            .and_then(downcast_owned)
     }
 
     /// Clear the `Extensions` of all inserted extensions.
@@ -235,6 +311,24 @@ impl Extensions {
     }
 }
 
+/// 仅克隆通过`insert_cloneable`插入的值
+impl Clone for Extensions {
+    fn clone(&self) -> Self {
+        let mut value = Extensions::new();
+        if let Some(map) = &self.map {
+            for (id, entry) in map.iter() {
+                if let Some(clone) = entry.clone {
+                    value
+                        .map
+                        .get_or_insert_with(|| Box::new(AnyMap::default()))
+                        .insert(*id, Entry { value: clone(&entry.value), clone: Some(clone) });
+                }
+            }
+        }
+        value
+    }
+}
+
 impl fmt::Debug for Extensions {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Extensions").finish()
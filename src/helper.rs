@@ -12,9 +12,25 @@
 
 
 
+use crate::prelude::*;
 use crate::{Buf, WebResult, WebError, byte_map, next, expect, peek, HttpError, StatusCode, BufMut, BinaryRef};
 use super::{Method, Version, HeaderMap, HeaderName, HeaderValue, Scheme};
 
+const BASE64_STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// base64解码表, 非字母表中的字符为0xFF
+const BASE64_STANDARD_DECODE: [u8; 256] = base64_decode_table(BASE64_STANDARD);
+const BASE64_URL_SAFE_DECODE: [u8; 256] = base64_decode_table(BASE64_URL_SAFE);
+
+const fn base64_decode_table(alphabet: &[u8; 64]) -> [u8; 256] {
+    let mut table = [0xFF; 256];
+    let mut i = 0;
+    while i < 64 {
+        table[alphabet[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
 
 pub struct Helper;
 
@@ -216,10 +232,57 @@ impl Helper {
     }
 
     pub(crate) fn parse_method<B:Buf>(buffer: &mut B) -> WebResult<Method> {
+        if let Some((method, len)) = Self::parse_method_fast(buffer.chunk()) {
+            buffer.advance(len);
+            return Ok(method);
+        }
         let token = Self::parse_token(buffer)?;
         TryFrom::try_from(token)
     }
 
+    /// 常用方法的快速匹配, 将方法名连同其后的空格按整数比较, 不产生分配,
+    /// 返回方法及方法名的长度(不含空格), 无法匹配时由parse_token处理
+    #[inline]
+    fn parse_method_fast(chunk: &[u8]) -> Option<(Method, usize)> {
+        const GET: u32 = u32::from_le_bytes(*b"GET ");
+        const PUT: u32 = u32::from_le_bytes(*b"PUT ");
+        const POST: u64 = u64::from_le_bytes(*b"POST \0\0\0");
+        const HEAD: u64 = u64::from_le_bytes(*b"HEAD \0\0\0");
+        const PATCH: u64 = u64::from_le_bytes(*b"PATCH \0\0");
+        const TRACE: u64 = u64::from_le_bytes(*b"TRACE \0\0");
+        const DELETE: u64 = u64::from_le_bytes(*b"DELETE \0");
+        const OPTIONS: u64 = u64::from_le_bytes(*b"OPTIONS ");
+        const CONNECT: u64 = u64::from_le_bytes(*b"CONNECT ");
+
+        if chunk.len() < 8 {
+            return None;
+        }
+        match u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) {
+            GET => return Some((Method::Get, 3)),
+            PUT => return Some((Method::Put, 3)),
+            _ => {}
+        }
+        let word = u64::from_le_bytes([
+            chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7],
+        ]);
+        match word & 0xFF_FFFF_FFFF {
+            POST => return Some((Method::Post, 4)),
+            HEAD => return Some((Method::Head, 4)),
+            _ => {}
+        }
+        match word & 0xFFFF_FFFF_FFFF {
+            PATCH => return Some((Method::Patch, 5)),
+            TRACE => return Some((Method::Trace, 5)),
+            _ => {}
+        }
+        match word {
+            OPTIONS => Some((Method::Options, 7)),
+            CONNECT => Some((Method::Connect, 7)),
+            _ if word & 0xFF_FFFF_FFFF_FFFF == DELETE => Some((Method::Delete, 6)),
+            _ => None,
+        }
+    }
+
     pub(crate) fn parse_status<B:Buf>(buffer: &mut B) -> WebResult<StatusCode> {
         let token = Self::parse_token(buffer)?;
         let status = StatusCode::try_from(token);
@@ -264,7 +327,7 @@ impl Helper {
             return Err(err);
         } else {
             let val = unsafe {
-                std::str::from_utf8_unchecked(&buffer.advance_chunk(position))
+                core::str::from_utf8_unchecked(&buffer.advance_chunk(position))
             };
             return Ok(val);
         }
@@ -291,6 +354,15 @@ impl Helper {
         Self::parse_token_by_func(buffer, Self::is_status_token, WebError::from(HttpError::Token))
     }
 
+    /// 解析状态行中的原因短语, 原因短语可以省略, 如`HTTP/1.1 200\r\n`, 此时返回空字符串
+    pub(crate) fn parse_reason_phrase<B:Buf>(buffer: &mut B) -> WebResult<&str> {
+        Self::skip_spaces(buffer)?;
+        if matches!(peek!(buffer)?, b'\r' | b'\n') {
+            return Ok("");
+        }
+        Self::parse_status_token(buffer)
+    }
+
     #[inline]
     pub(crate) fn parse_header_name<'a, B:Buf>(buffer: &'a mut B) -> WebResult<HeaderName> {
         let token = Self::parse_token_by_func(buffer, Self::is_header_name_token, WebError::from(HttpError::HeaderName))?;
@@ -300,12 +372,6 @@ impl Helper {
         }
     }
 
-    #[inline]
-    pub(crate) fn parse_header_value<'a, B:Buf>(buffer: &'a mut B) -> WebResult<HeaderValue> {
-        let token = Self::parse_token_by_func_empty(buffer, Self::is_header_value_token, WebError::from(HttpError::HeaderValue), true)?;
-        Ok(HeaderValue::Value(token.as_bytes().to_vec()))
-    }
-
     #[inline]
     pub(crate) fn parse_scheme<'a, B:Buf>(buffer: &'a mut B) -> WebResult<&'a str> {
         let token = Self::parse_token_by_func(buffer, Scheme::is_scheme_token, WebError::from(HttpError::HeaderValue))?;
@@ -363,29 +429,115 @@ impl Helper {
         }
     }
     
+    /// 跳过空格及制表符(OWS)
+    #[inline]
+    fn skip_ows<B:Buf>(buffer: &mut B) -> WebResult<()> {
+        loop {
+            match peek!(buffer)? {
+                b' ' | b'\t' => {
+                    next!(buffer)?;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// 去除值末尾的空格及制表符, 中间的空白保持不变
+    #[inline]
+    fn trim_ows_end(value: &[u8]) -> &[u8] {
+        let len = value.iter().rposition(|b| *b != b' ' && *b != b'\t').map_or(0, |p| p + 1);
+        &value[..len]
+    }
+
     #[inline]
     pub(crate) fn parse_header<B:Buf>(buffer: &mut B, header: &mut HeaderMap) -> WebResult<()> {
+        Self::parse_header_with(buffer, header, false, |_| ())?;
+        Ok(())
+    }
+
+    /// 解析头部, 值去除首尾的空白(OWS), 以空白开头的续行(obs-fold)以一个空格与上一行合并.
+    /// 严格模式下拒绝名字与`:`之间的空白及续行, 避免如`Host : a`的请求走私.
+    /// 每解析一个头调用一次`on_name`, 返回头的行数, 同名的头分别计数, 续行不单独计数
+    pub(crate) fn parse_header_with<B:Buf, F:FnMut(&HeaderName)>(buffer: &mut B, header: &mut HeaderMap, strict: bool, mut on_name: F) -> WebResult<usize> {
         header.clear();
+        let mut count = 0;
 
         loop {
             let b = peek!(buffer)?;
             if b == b'\r' {
                 buffer.get_next();
                 expect!(buffer.next() == b'\n' => Err(WebError::from(HttpError::NewLine)));
-                return Ok(());
+                return Ok(count);
             }
             if b == b'\n' {
                 buffer.get_next();
-                return Ok(());
+                return Ok(count);
             }
 
             let name = Helper::parse_header_name(buffer)?;
-            Self::skip_spaces(buffer)?;
+            on_name(&name);
+            count += 1;
+            if !strict {
+                Self::skip_spaces(buffer)?;
+            }
             expect!(buffer.next() == b':' => Err(WebError::from(HttpError::HeaderName)));
-            Self::skip_spaces(buffer)?;
-            let value = Helper::parse_header_value(buffer)?;
-            Self::skip_new_line(buffer)?;
-            header.insert(name, value);
+            let mut value = Self::parse_ows_value(buffer)?;
+            while matches!(peek!(buffer)?, b' ' | b'\t') {
+                if strict {
+                    return Err(WebError::from(HttpError::HeaderValue));
+                }
+                let more = Self::parse_ows_value(buffer)?;
+                if !more.is_empty() {
+                    if !value.is_empty() {
+                        value.push(b' ');
+                    }
+                    value.extend_from_slice(&more);
+                }
+            }
+            // 重复的Content-Length合并为列表, 由HeaderMap::content_length检查是否一致
+            if name == HeaderName::CONTENT_LENGTH {
+                if let Some(old) = header.get_option_value(&HeaderName::CONTENT_LENGTH) {
+                    let mut all = old.as_bytes().to_vec();
+                    all.extend_from_slice(b", ");
+                    all.extend_from_slice(&value);
+                    value = all;
+                }
+            }
+            header.insert(name, HeaderValue::Value(value));
+        }
+    }
+
+    /// 解析冒号后(或续行中)的值直到行尾, 去除首尾的空白
+    fn parse_ows_value<B:Buf>(buffer: &mut B) -> WebResult<Vec<u8>> {
+        Self::skip_ows(buffer)?;
+        // 值可能包含obs-text(0x80-0xFF), 按字节保存, 不做UTF-8的转换
+        let position = match buffer.chunk().iter().position(|b| !Self::is_header_value_token(*b)) {
+            Some(position) => position,
+            None => return Err(WebError::from(HttpError::Partial)),
+        };
+        let value = Self::trim_ows_end(&buffer.chunk()[..position]).to_vec();
+        buffer.advance(position);
+        Self::skip_new_line(buffer)?;
+        Ok(value)
+    }
+
+    /// 跳过chunk长度后的扩展(RFC 7230 §4.1.1, 如`5;name=val`)及换行, 扩展的内容被忽略
+    pub(crate) fn skip_chunk_ext<B:Buf>(buffer: &mut B) -> WebResult<()> {
+        Self::skip_ows(buffer)?;
+        if peek!(buffer)? == b';' {
+            loop {
+                match peek!(buffer)? {
+                    b'\r' | b'\n' => break,
+                    b'\t' => {}
+                    b if b < 0x20 || b == 0x7f => return Err(WebError::from(HttpError::Token)),
+                    _ => {}
+                }
+                next!(buffer)?;
+            }
+        }
+        match peek!(buffer)? {
+            b'\r' | b'\n' => Self::skip_new_line(buffer),
+            _ => Err(WebError::from(HttpError::Token)),
         }
     }
 
@@ -393,9 +545,9 @@ impl Helper {
         let len = buffer.remaining();
         let mut val = BinaryRef::from(buffer.chunk());
         let num = Helper::parse_hex(&mut val)?;
-        let num = usize::from_str_radix(num, 16).unwrap();
-        Helper::skip_new_line(&mut val)?;
-        if num + 2 > val.remaining() {
+        let num = usize::from_str_radix(num, 16).map_err(|_| WebError::from(HttpError::Token))?;
+        Helper::skip_chunk_ext(&mut val)?;
+        if num.saturating_add(2) > val.remaining() {
             return Err(WebError::Http(HttpError::Partial));
         }
         return Ok((len - val.remaining(), num));
@@ -406,7 +558,33 @@ impl Helper {
         // Ok((ret, buffer.mark_commit() - first, num == 0))
     }
 
-    pub fn encode_chunk_data<B:Buf+BufMut>(buffer: &mut B, data: &[u8]) -> std::io::Result<usize> {
+    /// 根据头信息计算body在数据中所占的字节数, chunked时为编码后的长度(包含trailer),
+    /// 数据不足时返回Partial错误, 用于流水线请求中确定单个消息的边界
+    pub fn body_frame_len(header: &HeaderMap, buf: &[u8]) -> WebResult<usize> {
+        // 请求中最后的传输编码不是chunked时无法确定body的长度, 见RFC 7230 §3.3.3
+        if header.contains(&HeaderName::TRANSFER_ENCODING) && !header.is_final_chunked() {
+            return Err(WebError::from(HttpError::TransferEncoding));
+        }
+        if !header.is_final_chunked() {
+            let len = header.content_length()?.unwrap_or(0);
+            if buf.len() < len {
+                return Err(WebError::from(HttpError::Partial));
+            }
+            return Ok(len);
+        }
+        let mut buffer = BinaryRef::from(buf);
+        loop {
+            let (head, num) = Self::parse_chunk_data(&mut buffer)?;
+            buffer.advance(head + num);
+            if num == 0 {
+                Self::parse_header(&mut buffer, &mut HeaderMap::new())?;
+                return Ok(buf.len() - buffer.remaining());
+            }
+            Self::skip_new_line(&mut buffer)?;
+        }
+    }
+
+    pub fn encode_chunk_data<B:Buf+BufMut>(buffer: &mut B, data: &[u8]) -> crate::io::Result<usize> {
         let len_str = format!("{:x}", data.len());
         let mut size = buffer.put_slice(len_str.as_bytes());
         size += buffer.put_slice("\r\n".as_bytes());
@@ -424,10 +602,10 @@ impl Helper {
         for b in bytes {
             if b != &b' ' {
                 if is_first {
-                    val = u8::from_str_radix(std::str::from_utf8(&[*b]).unwrap(), 16).unwrap();
+                    val = u8::from_str_radix(core::str::from_utf8(&[*b]).unwrap(), 16).unwrap();
                     is_first = false
                 } else {
-                    val = val * 16 + u8::from_str_radix(std::str::from_utf8(&[*b]).unwrap(), 16).unwrap();
+                    val = val * 16 + u8::from_str_radix(core::str::from_utf8(&[*b]).unwrap(), 16).unwrap();
                     result.push(val);
                     val = 0;
                     is_first = true;
@@ -437,6 +615,70 @@ impl Helper {
         result
     }
 
+    /// 转成十六进制字符串, 每`group`个字节以空格分隔, 为0时不分隔, 结果可由`hex_to_vec`还原
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Helper;
+    ///
+    /// let data = [0x82, 0x86, 0x84, 0x41, 0x0f];
+    /// assert_eq!(Helper::vec_to_hex(&data, 0), "828684410f");
+    /// assert_eq!(Helper::vec_to_hex(&data, 1), "82 86 84 41 0f");
+    /// assert_eq!(Helper::vec_to_hex(&data, 2), "8286 8441 0f");
+    /// assert_eq!(Helper::hex_to_vec(&Helper::vec_to_hex(&data, 2)), data);
+    /// ```
+    pub fn vec_to_hex(data: &[u8], group: usize) -> String {
+        let mut result = String::with_capacity(data.len() * 3);
+        for (i, v) in data.iter().enumerate() {
+            if group != 0 && i != 0 && i % group == 0 {
+                result.push(' ');
+            }
+            result.push(Self::to_hex(v / 16).to_ascii_lowercase() as char);
+            result.push(Self::to_hex(v % 16).to_ascii_lowercase() as char);
+        }
+        result
+    }
+
+    /// 类似`hexdump -C`的输出, 每行16个字节, 包含偏移量、十六进制及可见的ASCII字符
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Helper;
+    ///
+    /// let dump = Helper::hex_dump(b"HTTP/1.1 200 OK\r\n\r\n");
+    /// assert_eq!(dump, "\
+    /// 00000000  48 54 54 50 2f 31 2e 31  20 32 30 30 20 4f 4b 0d  |HTTP/1.1 200 OK.|
+    /// 00000010  0a 0d 0a                                          |...|");
+    /// ```
+    pub fn hex_dump(data: &[u8]) -> String {
+        let mut lines = Vec::new();
+        for (i, chunk) in data.chunks(16).enumerate() {
+            let mut line = format!("{:08x} ", i * 16);
+            for j in 0..16 {
+                if j == 8 {
+                    line.push(' ');
+                }
+                match chunk.get(j) {
+                    Some(v) => {
+                        line.push(' ');
+                        line.push(Self::to_hex(v / 16).to_ascii_lowercase() as char);
+                        line.push(Self::to_hex(v % 16).to_ascii_lowercase() as char);
+                    }
+                    None => line.push_str("   "),
+                }
+            }
+            line.push_str("  |");
+            for v in chunk {
+                line.push(if v.is_ascii_graphic() || *v == b' ' { *v as char } else { '.' });
+            }
+            line.push('|');
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
 
     pub fn eq_bytes_ignore_ascii_case(a: &[u8], b: &[u8]) -> bool {
         if a.len() != b.len() {
@@ -472,4 +714,265 @@ impl Helper {
         }
         false
     }
+
+    /// 标准base64编码, 带`=`填充, 如websocket握手中的Sec-WebSocket-Key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Helper;
+    /// assert_eq!(Helper::base64_encode(b"foob"), "Zm9vYg==");
+    /// assert_eq!(Helper::base64_decode(b"Zm9vYg==").unwrap(), b"foob");
+    /// ```
+    pub fn base64_encode(data: &[u8]) -> String {
+        let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+        Self::base64_encode_into(data, &mut out, false);
+        out
+    }
+
+    /// url安全的base64编码, 不带填充, 如HTTP2-Settings头
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Helper;
+    /// assert_eq!(Helper::base64url_encode(&[0xfb, 0xff]), "-_8");
+    /// assert_eq!(Helper::base64url_decode(b"-_8").unwrap(), [0xfb, 0xff]);
+    /// ```
+    pub fn base64url_encode(data: &[u8]) -> String {
+        let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+        Self::base64_encode_into(data, &mut out, true);
+        out
+    }
+
+    /// 编码并追加到已有的字符串中, 可复用内存, url_safe时不带填充
+    pub fn base64_encode_into(data: &[u8], out: &mut String, url_safe: bool) {
+        let (alphabet, pad) = if url_safe { (BASE64_URL_SAFE, false) } else { (BASE64_STANDARD, true) };
+        out.reserve(data.len().div_ceil(3) * 4);
+        for chunk in data.chunks(3) {
+            let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(alphabet[(n >> (18 - i * 6)) as usize & 0x3F] as char);
+                } else if pad {
+                    out.push('=');
+                }
+            }
+        }
+    }
+
+    /// 标准base64解码, 填充可省略
+    pub fn base64_decode(data: &[u8]) -> WebResult<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() / 4 * 3 + 2);
+        Self::base64_decode_into(data, &mut out, false)?;
+        Ok(out)
+    }
+
+    /// url安全的base64解码, 填充可省略
+    pub fn base64url_decode(data: &[u8]) -> WebResult<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() / 4 * 3 + 2);
+        Self::base64_decode_into(data, &mut out, true)?;
+        Ok(out)
+    }
+
+    /// 解码并追加到已有的数组中, 返回解码出的字节数, 失败时不修改out
+    pub fn base64_decode_into(data: &[u8], out: &mut Vec<u8>, url_safe: bool) -> WebResult<usize> {
+        let table = if url_safe { &BASE64_URL_SAFE_DECODE } else { &BASE64_STANDARD_DECODE };
+        let err = || WebError::Extension("invalid base64");
+        let data = match data.iter().position(|b| *b == b'=') {
+            Some(pos) => {
+                // 填充只能出现在末尾, 且补齐到4的倍数
+                if !data.len().is_multiple_of(4) || data.len() - pos > 2 || data[pos..].iter().any(|b| *b != b'=') {
+                    return Err(err());
+                }
+                &data[..pos]
+            }
+            None => data,
+        };
+        if data.len() % 4 == 1 {
+            return Err(err());
+        }
+        let start = out.len();
+        for chunk in data.chunks(4) {
+            let mut n = 0u32;
+            for (i, b) in chunk.iter().enumerate() {
+                let v = table[*b as usize];
+                if v == 0xFF {
+                    out.truncate(start);
+                    return Err(err());
+                }
+                n |= (v as u32) << (18 - i * 6);
+            }
+            let bytes = n.to_be_bytes();
+            out.extend_from_slice(&bytes[1..chunk.len()]);
+        }
+        Ok(out.len() - start)
+    }
+
+    /// 计算SHA-1摘要, 用于websocket握手中Sec-WebSocket-Accept的生成
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Helper;
+    /// let digest = Helper::sha1(b"abc");
+    /// assert_eq!(digest[..4], [0xa9, 0x99, 0x3e, 0x36]);
+    /// ```
+    pub fn sha1(data: &[u8]) -> [u8; 20] {
+        let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+        let bit_len = (data.len() as u64).wrapping_mul(8);
+        // 末尾补0x80及0, 最后8字节为原始数据的位长度
+        let mut tail = [0u8; 128];
+        let rest = data.chunks_exact(64).remainder();
+        tail[..rest.len()].copy_from_slice(rest);
+        tail[rest.len()] = 0x80;
+        let tail_len = if rest.len() < 56 { 64 } else { 128 };
+        tail[tail_len - 8..tail_len].copy_from_slice(&bit_len.to_be_bytes());
+
+        for block in data.chunks_exact(64).chain(tail[..tail_len].chunks_exact(64)) {
+            let mut w = [0u32; 80];
+            for i in 0..16 {
+                w[i] = u32::from_be_bytes([block[i * 4], block[i * 4 + 1], block[i * 4 + 2], block[i * 4 + 3]]);
+            }
+            for i in 16..80 {
+                w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+            }
+            let [mut a, mut b, mut c, mut d, mut e] = h;
+            for (i, wi) in w.iter().enumerate() {
+                let (f, k) = match i {
+                    0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                    20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                    40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                    _ => (b ^ c ^ d, 0xCA62C1D6),
+                };
+                let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*wi);
+                e = d;
+                d = c;
+                c = b.rotate_left(30);
+                b = a;
+                a = temp;
+            }
+            for (v, n) in h.iter_mut().zip([a, b, c, d, e]) {
+                *v = v.wrapping_add(n);
+            }
+        }
+
+        let mut digest = [0u8; 20];
+        for (i, v) in h.iter().enumerate() {
+            digest[i * 4..i * 4 + 4].copy_from_slice(&v.to_be_bytes());
+        }
+        digest
+    }
+
+    /// 解析以`"`开头的quoted-string, 返回去掉`\`转义后的值及剩余部分, 未闭合时返回None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Helper;
+    /// assert_eq!(Helper::parse_quoted(r#""a\"b"; x"#), Some(("a\"b".to_string(), "; x")));
+    /// assert_eq!(Helper::parse_quoted(r#""abc"#), None);
+    /// ```
+    pub fn parse_quoted(value: &str) -> Option<(String, &str)> {
+        let quoted = value.strip_prefix('"')?;
+        let mut result = String::new();
+        let mut escape = false;
+        for (i, c) in quoted.char_indices() {
+            if escape {
+                result.push(c);
+                escape = false;
+            } else if c == '\\' {
+                escape = true;
+            } else if c == '"' {
+                return Some((result, &quoted[i + 1..]));
+            } else {
+                result.push(c);
+            }
+        }
+        None
+    }
+
+    /// 以quoted-string形式输出, 转义其中的`"`及`\`
+    pub fn write_quoted<W: core::fmt::Write>(f: &mut W, value: &str) -> core::fmt::Result {
+        f.write_char('"')?;
+        for c in value.chars() {
+            if c == '"' || c == '\\' {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Helper;
+    #[cfg(not(feature = "std"))]
+    use crate::prelude::*;
+    use crate::{BinaryRef, Buf, Method};
+
+    #[test]
+    fn method_fast_path() {
+        let methods = [
+            Method::GET, Method::PUT, Method::POST, Method::HEAD, Method::PATCH,
+            Method::TRACE, Method::DELETE, Method::OPTIONS, Method::CONNECT,
+        ];
+        for method in methods {
+            let line = format!("{} / HTTP/1.1\r\n", method);
+            let mut buffer = BinaryRef::from(line.as_bytes());
+            assert_eq!(Helper::parse_method(&mut buffer).unwrap(), method);
+            assert_eq!(buffer.chunk(), b" / HTTP/1.1\r\n");
+            // 数据过短时走原有的解析
+            let mut short = BinaryRef::from(&line.as_bytes()[..method.as_str().len() + 1]);
+            assert_eq!(Helper::parse_method(&mut short).unwrap(), method);
+        }
+        assert!(Helper::parse_method(&mut BinaryRef::from(&b"GETS / HTTP/1.1"[..])).is_err());
+        assert!(Helper::parse_method(&mut BinaryRef::from(&b"POSTED / HTTP/1.1"[..])).is_err());
+    }
+
+    #[test]
+    fn base64_rfc4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (raw, encoded) in vectors {
+            assert_eq!(Helper::base64_encode(raw.as_bytes()), encoded);
+            assert_eq!(Helper::base64_decode(encoded.as_bytes()).unwrap(), raw.as_bytes());
+            let url = encoded.trim_end_matches('=');
+            assert_eq!(Helper::base64url_encode(raw.as_bytes()), url);
+            assert_eq!(Helper::base64url_decode(url.as_bytes()).unwrap(), raw.as_bytes());
+        }
+        let mut out = b"x".to_vec();
+        assert!(Helper::base64_decode_into(b"Zm9v!A==", &mut out, false).is_err());
+        assert!(Helper::base64_decode(b"Zg=").is_err());
+        assert!(Helper::base64_decode(b"Z=g=").is_err());
+        assert_eq!(out, b"x");
+    }
+
+    #[test]
+    fn sha1_vectors() {
+        let vectors: [(&[u8], &str); 3] = [
+            (b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            ),
+        ];
+        for (data, hex) in vectors {
+            let digest = Helper::sha1(data);
+            let digest: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(digest, hex);
+        }
+        let million = vec![b'a'; 1_000_000];
+        assert_eq!(Helper::sha1(&million)[..4], [0x34, 0xaa, 0x97, 0x3c]);
+    }
 }
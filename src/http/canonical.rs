@@ -0,0 +1,202 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/17 11:05:43
+
+use crate::prelude::*;
+use crate::{HeaderName, Helper, Request, Serialize, Url};
+
+/// 用于请求签名的规范请求, 规则同AWS SigV4
+///
+/// 由方法, 转码后的path, 排序后的参数, 小写并排序的头, 签名的头列表及body的摘要以换行连接,
+/// 签名算法由调用方完成
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{CanonicalRequest, Request};
+///
+/// let req = Request::builder()
+///     .method("GET")
+///     .url("http://example.amazonaws.com/a%20b/?Param2=value2&Param1=value%201")
+///     .header("Host", "example.amazonaws.com")
+///     .header("X-Amz-Date", "20150830T123600Z")
+///     .header("My-Header", "  a   b  ")
+///     .body(())
+///     .unwrap();
+/// let canonical = CanonicalRequest::new(&req, &["x-amz-date", "host", "my-header"], "UNSIGNED-PAYLOAD");
+/// assert_eq!(canonical.signed_headers(), "host;my-header;x-amz-date");
+/// assert_eq!(
+///     canonical.as_str(),
+///     "GET\n/a%20b/\nParam1=value%201&Param2=value2\n\
+///      host:example.amazonaws.com\nmy-header:a b\nx-amz-date:20150830T123600Z\n\n\
+///      host;my-header;x-amz-date\nUNSIGNED-PAYLOAD"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalRequest {
+    canonical: String,
+    signed_headers: String,
+}
+
+impl CanonicalRequest {
+    /// 按`signed`中的头名字(不区分大小写)生成, 为空时签名所有的头, 同名的头合并为一行,
+    /// 不存在Host头时以Url中的地址作为host, `payload_hash`为body摘要的十六进制字符串
+    pub fn new<T: Serialize>(req: &Request<T>, signed: &[&str], payload_hash: &str) -> Self {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for (name, value) in req.headers().iter() {
+            let name = name.to_string().to_ascii_lowercase();
+            if !signed.is_empty() && !signed.iter().any(|s| s.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+            let value = Self::trim_value(value.as_bytes());
+            // 同名的头只列出一次, 值按出现的顺序以`,`连接
+            match headers.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, old)) => {
+                    old.push(',');
+                    old.push_str(&value);
+                }
+                None => headers.push((name, value)),
+            }
+        }
+        let want_host = signed.is_empty() || signed.iter().any(|s| s.eq_ignore_ascii_case("host"));
+        if want_host && !req.headers().contains(&HeaderName::HOST) {
+            let authority = req.url().get_authority();
+            if !authority.is_empty() {
+                headers.push(("host".to_string(), authority));
+            }
+        }
+        headers.sort();
+
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let mut canonical = String::new();
+        canonical.push_str(req.method().as_str());
+        canonical.push('\n');
+        canonical.push_str(&Self::canonical_path(&req.url().path));
+        canonical.push('\n');
+        canonical.push_str(&Self::canonical_query(req.url()));
+        canonical.push('\n');
+        for (name, value) in &headers {
+            canonical.push_str(name);
+            canonical.push(':');
+            canonical.push_str(value);
+            canonical.push('\n');
+        }
+        canonical.push('\n');
+        canonical.push_str(&signed_headers);
+        canonical.push('\n');
+        canonical.push_str(payload_hash);
+        CanonicalRequest {
+            canonical,
+            signed_headers,
+        }
+    }
+
+    /// 规范请求的完整内容, 用于计算摘要
+    pub fn as_str(&self) -> &str {
+        &self.canonical
+    }
+
+    /// 以`;`分隔的签名头列表, 用于Authorization头中的SignedHeaders
+    pub fn signed_headers(&self) -> &str {
+        &self.signed_headers
+    }
+
+    /// 除`A-Za-z0-9-_.~`外均以大写的十六进制转码
+    pub fn uri_encode(val: &[u8], keep_slash: bool) -> String {
+        let mut result = String::with_capacity(val.len());
+        for &b in val {
+            if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) || (keep_slash && b == b'/') {
+                result.push(b as char);
+            } else {
+                result.push('%');
+                result.push(Helper::to_hex(b / 16) as char);
+                result.push(Helper::to_hex(b % 16) as char);
+            }
+        }
+        result
+    }
+
+    fn canonical_path(path: &str) -> String {
+        if path.is_empty() {
+            return "/".to_string();
+        }
+        Self::uri_encode(path.as_bytes(), true)
+    }
+
+    /// 参数按转码后的名字及值排序, 没有值的参数以空值输出
+    fn canonical_query(url: &Url) -> String {
+        let query = match &url.query {
+            Some(query) => query,
+            None => return String::new(),
+        };
+        let decode = |v: &str| Url::url_decode(v).unwrap_or(v.to_string());
+        let mut pairs: Vec<(String, String)> = query
+            .split('&')
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let (name, value) = v.split_once('=').unwrap_or((v, ""));
+                (
+                    Self::uri_encode(decode(name).as_bytes(), false),
+                    Self::uri_encode(decode(value).as_bytes(), false),
+                )
+            })
+            .collect();
+        pairs.sort();
+        pairs
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// 去除首尾的空白, 并将连续的空白合并为一个空格
+    fn trim_value(value: &[u8]) -> String {
+        let value = String::from_utf8_lossy(value);
+        value.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl<T: Serialize> Request<T> {
+    /// 生成用于请求签名的规范请求, 见`CanonicalRequest`
+    pub fn canonical_request(&self, signed: &[&str], payload_hash: &str) -> CanonicalRequest {
+        CanonicalRequest::new(self, signed, payload_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HeaderName, Request};
+
+    #[test]
+    fn repeated_header_signed_once() {
+        let mut req = Request::builder()
+            .url("http://example.com/")
+            .header("X-Value", "a")
+            .header("X-Other", " b ")
+            .body(())
+            .unwrap();
+        for (name, _) in req.headers_mut().iter_mut() {
+            if *name == "x-other" {
+                *name = HeaderName::try_from("x-value").unwrap();
+            }
+        }
+        let canonical = req.canonical_request(&["x-value"], "UNSIGNED-PAYLOAD");
+        assert_eq!(canonical.signed_headers(), "x-value");
+        assert_eq!(
+            canonical.as_str(),
+            "GET\n/\n\nx-value:a,b\n\nx-value\nUNSIGNED-PAYLOAD"
+        );
+    }
+}
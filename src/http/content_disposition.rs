@@ -0,0 +1,227 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/22 16:40:08
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{Helper, HttpError, WebError, WebResult};
+
+use super::media_type::{fmt_param, is_token, parse_params};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispositionType {
+    Inline,
+    Attachment,
+    /// multipart/form-data中的字段
+    FormData,
+    Other(String),
+}
+
+impl DispositionType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DispositionType::Inline => "inline",
+            DispositionType::Attachment => "attachment",
+            DispositionType::FormData => "form-data",
+            DispositionType::Other(s) => s,
+        }
+    }
+}
+
+/// Content-Disposition头, 见RFC 6266, 用于下载及multipart的各部分
+///
+/// `filename*`按RFC 5987解码, 支持UTF-8及ISO-8859-1, 优先于`filename`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{ContentDisposition, DispositionType};
+///
+/// let cd: ContentDisposition =
+///     "attachment; filename=\"EURO rates\"; filename*=utf-8''%e2%82%ac%20rates".parse().unwrap();
+/// assert_eq!(cd.disposition(), &DispositionType::Attachment);
+/// assert_eq!(cd.filename().as_deref(), Some("€ rates"));
+///
+/// let cd = ContentDisposition::attachment("报告.pdf");
+/// assert_eq!(cd.to_string(), "attachment; filename=__.pdf; filename*=UTF-8''%E6%8A%A5%E5%91%8A.pdf");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDisposition {
+    disposition: DispositionType,
+    params: Vec<(String, String)>,
+}
+
+impl ContentDisposition {
+    pub fn new(disposition: DispositionType) -> ContentDisposition {
+        ContentDisposition {
+            disposition,
+            params: Vec::new(),
+        }
+    }
+
+    /// 附件下载, 文件名含非ascii字符时同时生成`filename`的兼容值及`filename*`
+    pub fn attachment(filename: &str) -> ContentDisposition {
+        let mut cd = ContentDisposition::new(DispositionType::Attachment);
+        cd.set_filename(filename);
+        cd
+    }
+
+    /// multipart/form-data中的字段
+    pub fn form_data(name: &str, filename: Option<&str>) -> ContentDisposition {
+        let mut cd = ContentDisposition::new(DispositionType::FormData);
+        cd.set_param("name", name.to_string());
+        if let Some(filename) = filename {
+            cd.set_filename(filename);
+        }
+        cd
+    }
+
+    pub fn parse(value: &str) -> WebResult<ContentDisposition> {
+        let (kind, rest) = match value.find(';') {
+            Some(pos) => (value[..pos].trim(), &value[pos + 1..]),
+            None => (value.trim(), ""),
+        };
+        if !is_token(kind) {
+            return Err(WebError::from(HttpError::HeaderValue));
+        }
+        let disposition = if kind.eq_ignore_ascii_case("inline") {
+            DispositionType::Inline
+        } else if kind.eq_ignore_ascii_case("attachment") {
+            DispositionType::Attachment
+        } else if kind.eq_ignore_ascii_case("form-data") {
+            DispositionType::FormData
+        } else {
+            DispositionType::Other(kind.to_ascii_lowercase())
+        };
+        Ok(ContentDisposition {
+            disposition,
+            params: parse_params(rest)?,
+        })
+    }
+
+    pub fn disposition(&self) -> &DispositionType {
+        &self.disposition
+    }
+
+    pub fn is_attachment(&self) -> bool {
+        self.disposition == DispositionType::Attachment
+    }
+
+    /// 获取原始的参数值, 参数名不区分大小写
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 设置参数, 已存在时替换
+    pub fn set_param(&mut self, name: &str, value: String) {
+        let name = name.to_ascii_lowercase();
+        match self.params.iter_mut().find(|(n, _)| *n == name) {
+            Some(param) => param.1 = value,
+            None => self.params.push((name, value)),
+        }
+    }
+
+    /// form-data中字段的名字
+    pub fn name(&self) -> Option<&str> {
+        self.param("name")
+    }
+
+    /// 文件名, 优先使用可解码的`filename*`, 否则使用`filename`
+    pub fn filename(&self) -> Option<String> {
+        if let Some(ext) = self.param("filename*") {
+            if let Some(name) = Self::decode_ext_value(ext) {
+                return Some(name);
+            }
+        }
+        self.param("filename").map(|s| s.to_string())
+    }
+
+    /// 设置文件名, 非ascii时`filename`中以`_`替代, 并附加UTF-8编码的`filename*`
+    pub fn set_filename(&mut self, filename: &str) {
+        self.params.retain(|(n, _)| n != "filename" && n != "filename*");
+        if filename.is_ascii() {
+            self.set_param("filename", filename.to_string());
+            return;
+        }
+        let fallback: String = filename
+            .chars()
+            .map(|c| if c.is_ascii() { c } else { '_' })
+            .collect();
+        self.set_param("filename", fallback);
+        self.set_param("filename*", Self::encode_ext_value(filename));
+    }
+
+    /// 解码`charset'[language]'percent-encoded`
+    fn decode_ext_value(value: &str) -> Option<String> {
+        let mut parts = value.splitn(3, '\'');
+        let charset = parts.next()?;
+        let _language = parts.next()?;
+        let encoded = parts.next()?.as_bytes();
+        let mut bytes = Vec::with_capacity(encoded.len());
+        let mut i = 0;
+        while i < encoded.len() {
+            if encoded[i] == b'%' {
+                let hi = Helper::convert_hex(*encoded.get(i + 1)?)?;
+                let lo = Helper::convert_hex(*encoded.get(i + 2)?)?;
+                bytes.push(hi * 16 + lo);
+                i += 3;
+            } else {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+        if charset.eq_ignore_ascii_case("utf-8") {
+            String::from_utf8(bytes).ok()
+        } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+            Some(bytes.iter().map(|b| *b as char).collect())
+        } else {
+            None
+        }
+    }
+
+    fn encode_ext_value(value: &str) -> String {
+        let mut out = String::from("UTF-8''");
+        for b in value.bytes() {
+            // attr-char, 见RFC 5987 3.2.1
+            if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+                out.push(b as char);
+            } else {
+                out.push('%');
+                out.push(Helper::to_hex(b >> 4) as char);
+                out.push(Helper::to_hex(b & 0x0F) as char);
+            }
+        }
+        out
+    }
+}
+
+impl FromStr for ContentDisposition {
+    type Err = WebError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ContentDisposition::parse(s)
+    }
+}
+
+impl fmt::Display for ContentDisposition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.disposition.as_str())?;
+        for (name, value) in &self.params {
+            fmt_param(f, name, value)?;
+        }
+        Ok(())
+    }
+}
@@ -10,11 +10,11 @@
 // -----
 // Created Date: 2023/08/21 06:03:19
 
-use std::fmt;
+use core::fmt;
 
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpError {
     /// 数据太小不足以支持读
     BufTooShort,
@@ -38,6 +38,20 @@ pub enum HttpError {
     InvalidStatusCode,
     /// Scheme 太长了
     SchemeTooLong,
+    /// 无效的PROXY协议头
+    ProxyProtocol,
+    /// 缺少Host头
+    HostMissing,
+    /// 存在多个Host头
+    HostMultiple,
+    /// Host头与请求目标或:authority不一致
+    HostMismatch,
+    /// body超过限制的大小, 服务端应返回413
+    PayloadTooLarge,
+    /// 请求中最后的传输编码不是chunked, 服务端应返回400
+    TransferEncoding,
+    /// Content-Length不是数字或多个值不一致, 服务端应返回400
+    ContentLength,
 
 }
 
@@ -56,6 +70,13 @@ impl HttpError {
             HttpError::Partial => "invalid HTTP length",
             HttpError::InvalidStatusCode => "invalid status code",
             HttpError::SchemeTooLong => "scheme too long",
+            HttpError::ProxyProtocol => "invalid proxy protocol header",
+            HttpError::HostMissing => "missing host header",
+            HttpError::HostMultiple => "multiple host headers",
+            HttpError::HostMismatch => "host header mismatch",
+            HttpError::PayloadTooLarge => "payload too large",
+            HttpError::TransferEncoding => "invalid transfer encoding",
+            HttpError::ContentLength => "invalid content length",
         }
     }
 }
@@ -65,3 +86,6 @@ impl fmt::Display for HttpError {
         f.write_str(self.description_str())
     }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for HttpError {}
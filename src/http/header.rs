@@ -10,13 +10,27 @@
 // -----
 // Created Date: 2023/08/14 05:20:35
 
-use std::{
-    ops::{Index, IndexMut}, fmt::Display, collections::HashMap, borrow::Borrow, hash::Hash
+use core::{
+    ops::{Index, IndexMut}, fmt::Display, borrow::Borrow,
 };
-use crate::{HeaderName, HeaderValue, WebError, WebResult, Buf, BufMut};
-
-
-
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use crate::prelude::*;
+use crate::{http::{ViaHop, Warning}, ContentDisposition, HeaderName, HeaderValue, MediaType, HttpError, WebError, WebResult, Buf, BufMut};
+
+
+/// 消息的trailer头, 保存在请求或响应的扩展中
+#[derive(Debug, Clone)]
+pub(crate) struct Trailers(pub HeaderMap);
+
+/// 头部集合
+///
+/// 头按首次插入的顺序保存, 同名的头在`push`时合并到首次出现的位置,
+/// `iter`/`keys`/`values`/`drain`及编码时均按此顺序输出
 #[derive(Debug, PartialEq, Eq)]
 pub struct HeaderMap {
     headers: Vec<(HeaderName, HeaderValue)>,
@@ -31,14 +45,86 @@ impl HeaderMap {
         }
     }
 
-    pub fn iter(&self) ->  std::slice::Iter<(HeaderName, HeaderValue)> {
+    pub fn iter(&self) ->  core::slice::Iter<(HeaderName, HeaderValue)> {
         self.headers.iter()
     }
 
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<(HeaderName, HeaderValue)> {
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<(HeaderName, HeaderValue)> {
         self.headers.iter_mut()
     }
 
+    /// 按插入顺序遍历所有的头名
+    pub fn keys(&self) -> impl Iterator<Item = &HeaderName> {
+        self.headers.iter().map(|(name, _)| name)
+    }
+
+    /// 按插入顺序遍历所有的头值
+    pub fn values(&self) -> impl Iterator<Item = &HeaderValue> {
+        self.headers.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut HeaderValue> {
+        self.headers.iter_mut().map(|(_, value)| value)
+    }
+
+    /// 按插入顺序取出所有的头, 保留已分配的空间
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// let mut header = HeaderMap::new();
+    /// header.insert("host", "example.com");
+    /// header.insert("accept", "*/*");
+    /// let names: Vec<String> = header.drain().map(|(name, _)| name.to_string()).collect();
+    /// assert_eq!(names, vec!["host", "accept"]);
+    /// assert!(header.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> alloc::vec::Drain<'_, (HeaderName, HeaderValue)> {
+        self.headers.drain(..)
+    }
+
+    /// 只保留`f`返回true的头, 保持原有的顺序
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// let mut header = HeaderMap::new();
+    /// header.insert("host", "example.com");
+    /// header.insert("x-trace", "1");
+    /// header.insert("accept", "*/*");
+    /// header.retain(|name, _| !name.as_bytes().starts_with(b"x-"));
+    /// assert_eq!(header.keys().map(|k| k.to_string()).collect::<Vec<_>>(), vec!["host", "accept"]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&HeaderName, &mut HeaderValue) -> bool,
+    {
+        self.headers.retain_mut(|(name, value)| f(name, value))
+    }
+
+    /// 获取指定头的入口, 不存在时可插入到最后
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{HeaderMap, HeaderName, HeaderValue};
+    /// let mut header = HeaderMap::new();
+    /// header.entry(HeaderName::VARY).or_insert(HeaderValue::from_static("accept"));
+    /// let value = header.entry(HeaderName::VARY).or_insert(HeaderValue::from_static("origin"));
+    /// assert_eq!(value.as_bytes(), b"accept");
+    /// assert_eq!(header.len(), 1);
+    /// ```
+    pub fn entry(&mut self, name: HeaderName) -> HeaderEntry<'_> {
+        let index = self.headers.iter().position(|(n, _)| n == &name);
+        HeaderEntry {
+            headers: &mut self.headers,
+            name,
+            index,
+        }
+    }
+
     pub fn push<T, V>(&mut self, name: T, value: V) -> Option<HeaderValue>
     where
         HeaderName: TryFrom<T>,
@@ -224,13 +310,53 @@ impl HeaderMap {
         //     value.try_into().unwrap_or(0)
         // } else
 
-        if let Some(value) = self.get_option_value(&HeaderName::CONTENT_LENGTH) {
-            value.try_into().unwrap_or(0)
-        } else {
-            0
+        match self.content_length() {
+            Ok(Some(len)) => isize::try_from(len).unwrap_or(0),
+            _ => 0,
         }
     }
 
+    /// 解析Content-Length, 不存在时返回None, 值不是数字或多个值不一致时返回错误,
+    /// 解析时重复的Content-Length头会合并为逗号分隔的列表, 见RFC 7230 §3.3.2
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    ///
+    /// let mut header = HeaderMap::new();
+    /// assert_eq!(header.content_length().unwrap(), None);
+    /// header.insert("Content-Length", "5, 5");
+    /// assert_eq!(header.content_length().unwrap(), Some(5));
+    /// header.insert("Content-Length", "5, 0");
+    /// assert!(header.content_length().is_err());
+    /// header.insert("Content-Length", "1x");
+    /// assert!(header.content_length().is_err());
+    /// ```
+    pub fn content_length(&self) -> WebResult<Option<usize>> {
+        let value = match self.get_option_value(&HeaderName::CONTENT_LENGTH) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let err = || WebError::from(HttpError::ContentLength);
+        let mut len = None;
+        for item in value.as_bytes().split(|b| *b == b',') {
+            let item = item.trim_ascii();
+            if item.is_empty() || !item.iter().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+            let now = core::str::from_utf8(item)
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .ok_or_else(err)?;
+            if len.is_some_and(|len| len != now) {
+                return Err(err());
+            }
+            len = Some(now);
+        }
+        Ok(len)
+    }
+
     pub fn is_keep_alive(&self) -> bool {
 
         if let Some(value) = self.get_option_value(&HeaderName::CONNECTION) {
@@ -240,6 +366,31 @@ impl HeaderMap {
         }
     }
     
+    /// Transfer-Encoding中最后的传输编码是否为chunked, 如`gzip, chunked`,
+    /// 不存在Transfer-Encoding时返回false
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// let mut header = HeaderMap::new();
+    /// header.insert("Transfer-Encoding", "gzip, Chunked");
+    /// assert!(header.is_final_chunked());
+    /// header.insert("Transfer-Encoding", "chunked, gzip");
+    /// assert!(!header.is_final_chunked());
+    /// ```
+    pub fn is_final_chunked(&self) -> bool {
+        match self.get_option_value(&HeaderName::TRANSFER_ENCODING) {
+            Some(value) => value
+                .as_bytes()
+                .rsplit(|b| *b == b',')
+                .map(|v| v.trim_ascii())
+                .find(|v| !v.is_empty())
+                .is_some_and(|v| v.eq_ignore_ascii_case(b"chunked")),
+            None => false,
+        }
+    }
+
     pub fn is_chunked(&self) -> bool {
         if let Some(value) = self.get_option_value(&HeaderName::TRANSFER_ENCODING) {
             Self::contains_bytes(value.as_bytes(), b"chunked")
@@ -248,6 +399,128 @@ impl HeaderMap {
         }
     }
 
+    /// 解析Content-Type头, 不存在或格式错误时返回None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// let mut header = HeaderMap::new();
+    /// header.insert("Content-Type", "text/html; charset=UTF-8");
+    /// let mt = header.content_type().unwrap();
+    /// assert!(mt.is("text/html"));
+    /// assert_eq!(mt.charset(), Some("utf-8"));
+    /// ```
+    pub fn content_type(&self) -> Option<MediaType> {
+        let value = self.get_option_value(&HeaderName::CONTENT_TYPE)?;
+        MediaType::parse(core::str::from_utf8(value.as_bytes()).ok()?).ok()
+    }
+
+    /// 解析Content-Disposition头, 也可用于multipart中各部分的头
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// let mut header = HeaderMap::new();
+    /// header.insert("Content-Disposition", "form-data; name=\"file\"; filename=\"a.txt\"");
+    /// let cd = header.content_disposition().unwrap();
+    /// assert_eq!(cd.name(), Some("file"));
+    /// assert_eq!(cd.filename().as_deref(), Some("a.txt"));
+    /// ```
+    pub fn content_disposition(&self) -> Option<ContentDisposition> {
+        let value = self.get_option_value(&HeaderName::CONTENT_DISPOSITION)?;
+        ContentDisposition::parse(core::str::from_utf8(value.as_bytes()).ok()?).ok()
+    }
+
+    /// 解析所有Via头中的各跳, 按经过的顺序排列, 格式错误的头将被忽略
+    pub fn via(&self) -> Vec<ViaHop> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name == &HeaderName::VIA)
+            .filter_map(|(_, value)| core::str::from_utf8(value.as_bytes()).ok())
+            .filter_map(|value| ViaHop::parse_list(value).ok())
+            .flatten()
+            .collect()
+    }
+
+    /// 代理转发时追加本跳, 追加到最后一个Via头中
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// use webparse::http::ViaHop;
+    ///
+    /// let mut header = HeaderMap::new();
+    /// header.insert("Via", "1.1 edge");
+    /// assert!(!header.is_via_loop("gateway"));
+    /// header.push_via(ViaHop::new("1.1", "gateway"));
+    /// assert_eq!(header.get_str_value(&"Via").unwrap(), "1.1 edge, 1.1 gateway");
+    /// assert!(header.is_via_loop("Gateway"));
+    /// ```
+    pub fn push_via(&mut self, hop: ViaHop) {
+        let hop = hop.to_string();
+        match self.headers.iter_mut().rev().find(|(name, _)| name == &HeaderName::VIA) {
+            Some((_, value)) => {
+                let mut combined = value.as_bytes().to_vec();
+                combined.extend_from_slice(b", ");
+                combined.extend_from_slice(hop.as_bytes());
+                *value = HeaderValue::from_bytes(&combined);
+            }
+            None => {
+                self.push(HeaderName::VIA, hop);
+            }
+        }
+    }
+
+    /// 请求中是否已经经过了指定别名的代理, 即出现了转发环路
+    pub fn is_via_loop(&self, pseudonym: &str) -> bool {
+        self.via()
+            .iter()
+            .any(|hop| hop.received_by.eq_ignore_ascii_case(pseudonym))
+    }
+
+    /// 解析所有Warning头中的警告, 格式错误的头将被忽略
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name == &HeaderName::WARNING)
+            .filter_map(|(_, value)| core::str::from_utf8(value.as_bytes()).ok())
+            .filter_map(|value| Warning::parse_list(value).ok())
+            .flatten()
+            .collect()
+    }
+
+    /// 追加一条警告, 追加到最后一个Warning头中
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::HeaderMap;
+    /// use webparse::http::Warning;
+    ///
+    /// let mut header = HeaderMap::new();
+    /// header.insert("Warning", "110 - \"Response is stale\"");
+    /// header.push_warning(Warning::new(214, "gateway", "Transformation applied"));
+    /// assert_eq!(header.get_str_value(&"Warning").unwrap(), "110 - \"Response is stale\", 214 gateway \"Transformation applied\"");
+    /// assert_eq!(header.warnings().len(), 2);
+    /// ```
+    pub fn push_warning(&mut self, warning: Warning) {
+        let warning = warning.to_string();
+        match self.headers.iter_mut().rev().find(|(name, _)| name == &HeaderName::WARNING) {
+            Some((_, value)) => {
+                let mut combined = value.as_bytes().to_vec();
+                combined.extend_from_slice(b", ");
+                combined.extend_from_slice(warning.as_bytes());
+                *value = HeaderValue::from_bytes(&combined);
+            }
+            None => {
+                self.push(HeaderName::WARNING, warning);
+            }
+        }
+    }
+
     pub fn get_upgrade_protocol(&self) -> Option<String> {
 
         if let Some(value) = self.get_option_value(&HeaderName::CONNECTION) {
@@ -277,12 +550,21 @@ impl HeaderMap {
         self.systems.insert(key, value);
     }
 
+    #[cfg(feature = "std")]
     pub fn system_get<Q: ?Sized>(&self, key: &Q) -> Option<&String>
     where
     String: Borrow<Q>,
     Q: Hash + Eq, {
         self.systems.get(key)
     }
+
+    #[cfg(not(feature = "std"))]
+    pub fn system_get<Q: ?Sized>(&self, key: &Q) -> Option<&String>
+    where
+    String: Borrow<Q>,
+    Q: Ord, {
+        self.systems.get(key)
+    }
     
     pub fn encode<B: Buf+BufMut>(&self, buffer: &mut B) -> WebResult<usize> {
         let mut size = 0;
@@ -309,6 +591,40 @@ impl HeaderMap {
     }
 }
 
+/// `HeaderMap::entry`返回的入口
+#[derive(Debug)]
+pub struct HeaderEntry<'a> {
+    headers: &'a mut Vec<(HeaderName, HeaderValue)>,
+    name: HeaderName,
+    index: Option<usize>,
+}
+
+impl<'a> HeaderEntry<'a> {
+    pub fn is_occupied(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn name(&self) -> &HeaderName {
+        &self.name
+    }
+
+    /// 不存在时插入`value`, 返回该头值的引用
+    pub fn or_insert(self, value: HeaderValue) -> &'a mut HeaderValue {
+        self.or_insert_with(|| value)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> HeaderValue>(self, f: F) -> &'a mut HeaderValue {
+        let index = match self.index {
+            Some(index) => index,
+            None => {
+                self.headers.push((self.name, f()));
+                self.headers.len() - 1
+            }
+        };
+        &mut self.headers[index].1
+    }
+}
+
 impl Index<&'static str> for HeaderMap {
     type Output = HeaderValue;
 
@@ -347,7 +663,7 @@ impl IndexMut<&'static str> for HeaderMap {
 
 impl IntoIterator for HeaderMap {
     type Item = (HeaderName, HeaderValue);
-    type IntoIter = std::vec::IntoIter<(HeaderName, HeaderValue)>;
+    type IntoIter = alloc::vec::IntoIter<(HeaderName, HeaderValue)>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.headers.into_iter()
@@ -364,7 +680,7 @@ impl Clone for HeaderMap {
 }
 
 impl Display for HeaderMap {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for v in &self.headers {
             v.0.fmt(f)?;
             f.write_str(": ")?;
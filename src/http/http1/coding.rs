@@ -0,0 +1,210 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/16 09:52:18
+
+use crate::prelude::*;
+use crate::{Buf, BufMut, HeaderMap, HeaderName, Helper, WebError, WebResult};
+
+/// 内容编码(Content-Encoding), 如gzip/deflate/br
+///
+/// 本库不包含压缩算法, 可以包装如flate2的编码器实现该trait后交给`ChunkedEncoder`
+pub trait ContentCoding {
+    /// Content-Encoding中的名字, 如`gzip`, 不做编码时为None
+    fn name(&self) -> Option<&'static str>;
+
+    /// 编码一段数据追加到`out`中, 数据可以暂存在编码器中而不立即输出
+    fn encode(&mut self, data: &[u8], out: &mut Vec<u8>) -> WebResult<()>;
+
+    /// 数据已全部写入, 输出剩余的数据, 如gzip的尾部
+    fn finish(&mut self, out: &mut Vec<u8>) -> WebResult<()>;
+}
+
+/// 不做任何编码
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Identity;
+
+impl ContentCoding for Identity {
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn encode(&mut self, data: &[u8], out: &mut Vec<u8>) -> WebResult<()> {
+        out.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn finish(&mut self, _out: &mut Vec<u8>) -> WebResult<()> {
+        Ok(())
+    }
+}
+
+/// 流式输出body, 先做内容编码再做chunked传输编码(RFC 7230 §3.3.1, 如gzip在chunked之内)
+///
+/// 每段数据经过`ContentCoding`编码后作为一个chunk写入, 编码器暂未输出时不写入空chunk,
+/// `finish`时写入编码器剩余的数据及结束的`0\r\n\r\n`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http::http1::{ChunkedEncoder, Identity};
+/// use webparse::{BinaryMut, Buf, HeaderMap};
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert("Content-Length", "10");
+/// let mut encoder = ChunkedEncoder::new(Identity);
+/// encoder.prepare_headers(&mut headers);
+/// assert!(!headers.contains(&"content-length"));
+/// assert_eq!(headers.get_str_value(&"transfer-encoding").as_deref(), Some("chunked"));
+///
+/// let mut buffer = BinaryMut::new();
+/// encoder.encode_all(["hello", "", " world"], &mut buffer).unwrap();
+/// assert_eq!(buffer.chunk(), &b"5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n"[..]);
+/// assert!(encoder.is_finished());
+/// ```
+#[derive(Debug)]
+pub struct ChunkedEncoder<C: ContentCoding> {
+    coding: C,
+    scratch: Vec<u8>,
+    finished: bool,
+}
+
+impl<C: ContentCoding> ChunkedEncoder<C> {
+    pub fn new(coding: C) -> ChunkedEncoder<C> {
+        ChunkedEncoder {
+            coding,
+            scratch: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// 设置对应的头: 移除Content-Length, 设置`Transfer-Encoding: chunked`,
+    /// 编码名追加到Content-Encoding的最后, 表示在已有的编码之后再做编码
+    pub fn prepare_headers(&self, headers: &mut HeaderMap) {
+        headers.remove(&HeaderName::CONTENT_LENGTH);
+        if let Some(name) = self.coding.name() {
+            let value = match headers.get_str_value(&HeaderName::CONTENT_ENCODING) {
+                Some(old) if !old.trim().is_empty() => format!("{}, {}", old.trim(), name),
+                _ => name.to_string(),
+            };
+            headers.insert(HeaderName::CONTENT_ENCODING, value);
+        }
+        headers.insert(HeaderName::TRANSFER_ENCODING, "chunked");
+    }
+
+    /// 编码一段数据, 返回写入的字节数, 已调用`finish`后返回错误
+    pub fn encode<B: Buf + BufMut>(&mut self, data: &[u8], buffer: &mut B) -> WebResult<usize> {
+        if self.finished {
+            return Err(WebError::Serialize("chunked body already finished"));
+        }
+        self.scratch.clear();
+        self.coding.encode(data, &mut self.scratch)?;
+        self.flush(buffer)
+    }
+
+    /// 结束body, 写入编码器剩余的数据及最后一个空chunk, 重复调用不再写入
+    pub fn finish<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        if self.finished {
+            return Ok(0);
+        }
+        self.scratch.clear();
+        self.coding.finish(&mut self.scratch)?;
+        let size = self.flush(buffer)?;
+        self.finished = true;
+        Ok(size + Helper::encode_chunk_data(buffer, &[])?)
+    }
+
+    /// 编码所有的数据并结束body
+    pub fn encode_all<I, T, B>(&mut self, chunks: I, buffer: &mut B) -> WebResult<usize>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+        B: Buf + BufMut,
+    {
+        let mut size = 0;
+        for data in chunks {
+            size += self.encode(data.as_ref(), buffer)?;
+        }
+        Ok(size + self.finish(buffer)?)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn coding(&self) -> &C {
+        &self.coding
+    }
+
+    pub fn into_inner(self) -> C {
+        self.coding
+    }
+
+    fn flush<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        // 空chunk表示body结束, 不能在中间写入
+        if self.scratch.is_empty() {
+            return Ok(0);
+        }
+        Ok(Helper::encode_chunk_data(buffer, &self.scratch)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkedEncoder, ContentCoding};
+    use crate::prelude::*;
+    use crate::{BinaryMut, Buf, HeaderMap, WebResult};
+
+    /// 缓存数据直到超过4字节, 结束时追加尾部, 模拟gzip的行为
+    struct Buffered(Vec<u8>);
+
+    impl ContentCoding for Buffered {
+        fn name(&self) -> Option<&'static str> {
+            Some("x-test")
+        }
+
+        fn encode(&mut self, data: &[u8], out: &mut Vec<u8>) -> WebResult<()> {
+            self.0.extend_from_slice(data);
+            if self.0.len() > 4 {
+                out.extend(self.0.drain(..).map(|b| b.to_ascii_uppercase()));
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self, out: &mut Vec<u8>) -> WebResult<()> {
+            out.extend(self.0.drain(..).map(|b| b.to_ascii_uppercase()));
+            out.extend_from_slice(b"!");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn coding_inside_chunked() {
+        let mut headers = HeaderMap::new();
+        let mut encoder = ChunkedEncoder::new(Buffered(vec![]));
+        encoder.prepare_headers(&mut headers);
+        assert_eq!(
+            headers.get_str_value(&"content-encoding").as_deref(),
+            Some("x-test")
+        );
+        headers.insert("Content-Encoding", "gzip");
+        encoder.prepare_headers(&mut headers);
+        assert_eq!(
+            headers.get_str_value(&"content-encoding").as_deref(),
+            Some("gzip, x-test")
+        );
+
+        let mut buffer = BinaryMut::new();
+        encoder.encode_all(["ab", "cde", "f"], &mut buffer).unwrap();
+        assert_eq!(buffer.chunk(), &b"5\r\nABCDE\r\n2\r\nF!\r\n0\r\n\r\n"[..]);
+        assert!(encoder.encode(b"more", &mut buffer).is_err());
+        assert_eq!(encoder.finish(&mut buffer).unwrap(), 0);
+    }
+}
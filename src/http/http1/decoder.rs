@@ -0,0 +1,377 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/26 10:35:18
+
+use crate::{Binary, BinaryMut, BinaryRef, Buf, HeaderMap, Helper, HttpError, WebError, WebResult};
+
+/// body的分帧方式, 见RFC 7230 §3.3.3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFraming {
+    /// 没有body, 如1xx/204/304的响应
+    Empty,
+    /// 由Content-Length指定长度
+    Length(usize),
+    /// Transfer-Encoding: chunked
+    Chunked,
+    /// 读取直到连接关闭
+    Eof,
+}
+
+#[derive(Debug)]
+enum State {
+    /// 剩余的body长度
+    Length(usize),
+    /// 等待chunk的长度行
+    ChunkSize,
+    /// 当前chunk剩余的长度
+    ChunkData(usize),
+    /// chunk数据后的换行
+    ChunkEnd,
+    /// 最后一个chunk后的trailer
+    Trailers,
+    Eof,
+    Done,
+}
+
+/// 流式的body解码器, 按`BodyFraming`从缓存中取出body数据
+///
+/// 每次`decode`返回一段body数据, 数据不足或已完成时返回None,
+/// 读到连接关闭时需调用`finish_eof`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{BinaryMut, Buf, BufMut, Response};
+/// use webparse::http::http1::{BodyDecoder, BodyFraming};
+///
+/// let mut res = Response::new(());
+/// res.parse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n").unwrap();
+/// assert_eq!(res.body_framing().unwrap(), BodyFraming::Chunked);
+///
+/// let mut decoder = BodyDecoder::new(res.body_framing().unwrap());
+/// let mut buffer = BinaryMut::new();
+/// buffer.put_slice(b"5\r\nhel");
+/// assert_eq!(decoder.decode(&mut buffer).unwrap().unwrap().chunk(), b"hel");
+/// assert!(decoder.decode(&mut buffer).unwrap().is_none());
+/// buffer.put_slice(b"lo\r\n0\r\nExpires: 0\r\n\r\n");
+/// assert_eq!(decoder.decode(&mut buffer).unwrap().unwrap().chunk(), b"lo");
+/// assert!(decoder.decode(&mut buffer).unwrap().is_none());
+/// assert!(decoder.is_complete());
+/// assert!(decoder.trailers().unwrap().contains(&"expires"));
+/// ```
+#[derive(Debug)]
+pub struct BodyDecoder {
+    state: State,
+    trailers: Option<HeaderMap>,
+}
+
+impl BodyDecoder {
+    pub fn new(framing: BodyFraming) -> BodyDecoder {
+        let state = match framing {
+            BodyFraming::Empty | BodyFraming::Length(0) => State::Done,
+            BodyFraming::Length(len) => State::Length(len),
+            BodyFraming::Chunked => State::ChunkSize,
+            BodyFraming::Eof => State::Eof,
+        };
+        BodyDecoder {
+            state,
+            trailers: None,
+        }
+    }
+
+    /// body是否已完整读取
+    pub fn is_complete(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// chunked时最后一个chunk后的trailer, 没有trailer时返回None
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.trailers.as_ref()
+    }
+
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
+
+    /// 连接已关闭, 读到结束的body视为完成, 其它方式未完成时返回错误
+    pub fn finish_eof(&mut self) -> WebResult<()> {
+        match self.state {
+            State::Eof | State::Done => {
+                self.state = State::Done;
+                Ok(())
+            }
+            _ => Err(WebError::from(HttpError::Partial)),
+        }
+    }
+
+    /// 从缓存中取出一段body数据, 数据不足或body已完成时返回None,
+    /// 不会消耗属于下一个消息的数据
+    pub fn decode<B: Buf>(&mut self, buffer: &mut B) -> WebResult<Option<Binary>> {
+        loop {
+            match self.state {
+                State::Done => return Ok(None),
+                State::Length(len) => {
+                    let data = match Self::take(buffer, len) {
+                        Some(data) => data,
+                        None => return Ok(None),
+                    };
+                    self.state = match len - data.remaining() {
+                        0 => State::Done,
+                        left => State::Length(left),
+                    };
+                    return Ok(Some(data));
+                }
+                State::Eof => return Ok(Self::take(buffer, usize::MAX)),
+                State::ChunkSize => {
+                    let (used, num) = {
+                        let mut line = BinaryRef::from(buffer.chunk());
+                        let len = line.remaining();
+                        let num = match Self::partial(Helper::parse_hex(&mut line))? {
+                            Some(num) => usize::from_str_radix(num, 16)
+                                .map_err(|_| WebError::from(HttpError::Token))?,
+                            None => return Ok(None),
+                        };
+                        if Self::partial(Helper::skip_chunk_ext(&mut line))?.is_none() {
+                            return Ok(None);
+                        }
+                        (len - line.remaining(), num)
+                    };
+                    buffer.advance(used);
+                    crate::web_trace!("HTTP1: 解析chunk长度; len={}", num);
+                    self.state = if num == 0 {
+                        State::Trailers
+                    } else {
+                        State::ChunkData(num)
+                    };
+                }
+                State::ChunkData(len) => {
+                    let data = match Self::take(buffer, len) {
+                        Some(data) => data,
+                        None => return Ok(None),
+                    };
+                    self.state = match len - data.remaining() {
+                        0 => State::ChunkEnd,
+                        left => State::ChunkData(left),
+                    };
+                    return Ok(Some(data));
+                }
+                State::ChunkEnd => {
+                    let used = {
+                        let mut line = BinaryRef::from(buffer.chunk());
+                        let len = line.remaining();
+                        if Self::partial(Helper::skip_new_line(&mut line))?.is_none() {
+                            return Ok(None);
+                        }
+                        len - line.remaining()
+                    };
+                    buffer.advance(used);
+                    self.state = State::ChunkSize;
+                }
+                State::Trailers => {
+                    let mut trailers = HeaderMap::new();
+                    let used = {
+                        let mut block = BinaryRef::from(buffer.chunk());
+                        let len = block.remaining();
+                        if Self::partial(Helper::parse_header(&mut block, &mut trailers))?
+                            .is_none()
+                        {
+                            return Ok(None);
+                        }
+                        len - block.remaining()
+                    };
+                    buffer.advance(used);
+                    crate::web_trace!("HTTP1: chunked body结束; trailers={}", trailers.len());
+                    if !trailers.is_empty() {
+                        self.trailers = Some(trailers);
+                    }
+                    self.state = State::Done;
+                }
+            }
+        }
+    }
+
+    /// 取出最多max字节的数据, 缓存为空时返回None, 缓存为`Binary`时不产生拷贝
+    fn take<B: Buf>(buffer: &mut B, max: usize) -> Option<Binary> {
+        let len = core::cmp::min(buffer.chunk().len(), max);
+        if len == 0 {
+            return None;
+        }
+        Some(buffer.copy_to_binary(len))
+    }
+
+    /// 将数据不足的错误转为None
+    fn partial<T>(result: WebResult<T>) -> WebResult<Option<T>> {
+        match result {
+            Ok(val) => Ok(Some(val)),
+            Err(e) if e.is_partial() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 将流式的body收集为一个完整的`Binary`, 超过限制的大小时返回`HttpError::PayloadTooLarge`,
+/// 服务端可以据此返回413, 适用于较小的body, 如JSON接口
+///
+/// 由Content-Length指定的长度超过限制时在读取数据之前即返回错误
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{BinaryMut, Buf, BufMut, HttpError, WebError};
+/// use webparse::http::http1::{BodyAccumulator, BodyFraming};
+///
+/// let mut accumulator = BodyAccumulator::new(BodyFraming::Chunked, 16);
+/// let mut buffer = BinaryMut::new();
+/// buffer.put_slice(b"5\r\nhello\r\n");
+/// assert!(accumulator.feed(&mut buffer).unwrap().is_none());
+/// buffer.put_slice(b"1\r\n!\r\n0\r\n\r\n");
+/// let body = accumulator.feed(&mut buffer).unwrap().unwrap();
+/// assert_eq!(body.chunk(), b"hello!");
+///
+/// let mut accumulator = BodyAccumulator::new(BodyFraming::Length(17), 16);
+/// let err = accumulator.feed(&mut buffer).unwrap_err();
+/// assert!(matches!(err, WebError::Http(HttpError::PayloadTooLarge)));
+/// ```
+#[derive(Debug)]
+pub struct BodyAccumulator {
+    decoder: BodyDecoder,
+    framing: BodyFraming,
+    limit: usize,
+    body: BinaryMut,
+}
+
+impl BodyAccumulator {
+    pub fn new(framing: BodyFraming, limit: usize) -> BodyAccumulator {
+        BodyAccumulator {
+            decoder: BodyDecoder::new(framing),
+            framing,
+            limit,
+            body: BinaryMut::new(),
+        }
+    }
+
+    /// 已收集的body长度
+    pub fn len(&self) -> usize {
+        self.body.remaining()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn decoder(&self) -> &BodyDecoder {
+        &self.decoder
+    }
+
+    /// 从缓存中读取body数据, body完整时返回收集的数据, 之后再调用返回空的数据
+    pub fn feed<B: Buf>(&mut self, buffer: &mut B) -> WebResult<Option<Binary>> {
+        if let BodyFraming::Length(len) = self.framing {
+            if len > self.limit {
+                return Err(WebError::from(HttpError::PayloadTooLarge));
+            }
+        }
+        while let Some(data) = self.decoder.decode(buffer)? {
+            if self.body.remaining() + data.remaining() > self.limit {
+                return Err(WebError::from(HttpError::PayloadTooLarge));
+            }
+            self.body.put_slice(data.chunk());
+        }
+        if self.decoder.is_complete() {
+            return Ok(Some(core::mem::take(&mut self.body).freeze()));
+        }
+        Ok(None)
+    }
+
+    /// 连接已关闭, 返回以连接关闭结束的body, body未完整时返回错误
+    pub fn finish_eof(&mut self) -> WebResult<Binary> {
+        self.decoder.finish_eof()?;
+        Ok(core::mem::take(&mut self.body).freeze())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BodyDecoder, BodyFraming};
+    use crate::{Binary, BinaryMut, Buf, HttpError, Request, Response, WebError};
+
+    #[test]
+    fn chunked_any_boundary() {
+        let data = b"5\r\nhello\r\n6\r\n world\r\n0\r\nExpires: 0\r\n\r\nHTTP/1.1";
+        for step in 1..data.len() {
+            let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+            let mut buffer = BinaryMut::new();
+            let mut body = vec![];
+            for part in data.chunks(step) {
+                buffer.put_slice(part);
+                while let Some(chunk) = decoder.decode(&mut buffer).unwrap() {
+                    body.extend_from_slice(chunk.chunk());
+                }
+            }
+            assert_eq!(body, b"hello world");
+            assert!(decoder.is_complete());
+            assert!(decoder.trailers().unwrap().contains(&"expires"));
+            assert_eq!(buffer.chunk(), b"HTTP/1.1");
+        }
+    }
+
+    #[test]
+    fn length_and_eof() {
+        let mut buffer = BinaryMut::new();
+        buffer.put_slice(b"abcdef");
+        let mut decoder = BodyDecoder::new(BodyFraming::Length(4));
+        assert_eq!(decoder.decode(&mut buffer).unwrap().unwrap().chunk(), b"abcd");
+        assert!(decoder.is_complete());
+        assert!(decoder.decode(&mut buffer).unwrap().is_none());
+        assert!(decoder.finish_eof().is_ok());
+
+        let mut decoder = BodyDecoder::new(BodyFraming::Eof);
+        assert_eq!(decoder.decode(&mut buffer).unwrap().unwrap().chunk(), b"ef");
+        assert!(!decoder.is_complete());
+        decoder.finish_eof().unwrap();
+        assert!(decoder.is_complete());
+
+        let mut decoder = BodyDecoder::new(BodyFraming::Length(4));
+        assert!(decoder.finish_eof().is_err());
+    }
+
+    #[test]
+    fn chunk_extensions_and_framing() {
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        let mut buffer = Binary::from(b"5;name=val\r\nhello\r\n1 ; a=\"b;c\"\r\n!\r\n0;end\r\n\r\n".to_vec());
+        let start = buffer.chunk().as_ptr();
+        let hello = decoder.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(hello.chunk(), b"hello");
+        // 从Binary中取出的数据共享同一块内存
+        assert_eq!(hello.chunk().as_ptr(), unsafe { start.add(12) });
+        assert_eq!(decoder.decode(&mut buffer).unwrap().unwrap().chunk(), b"!");
+        assert!(decoder.decode(&mut buffer).unwrap().is_none());
+        assert!(decoder.is_complete());
+
+        let mut decoder = BodyDecoder::new(BodyFraming::Chunked);
+        let mut buffer = BinaryMut::new();
+        buffer.put_slice(b"5;a\x01\r\nhello\r\n");
+        assert!(decoder.decode(&mut buffer).is_err());
+
+        let mut res = Response::new(());
+        res.parse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked, gzip\r\nContent-Length: 5\r\n\r\n").unwrap();
+        assert_eq!(res.body_framing().unwrap(), BodyFraming::Eof);
+        res.parse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n").unwrap();
+        assert_eq!(res.body_framing().unwrap(), BodyFraming::Chunked);
+        res.parse(b"HTTP/1.1 200 OK\r\nContent-Length: 1x\r\n\r\n").unwrap();
+        assert!(res.body_framing().is_err());
+        res.parse(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: 0\r\n\r\n").unwrap();
+        assert!(matches!(res.body_framing(), Err(WebError::Http(HttpError::ContentLength))));
+
+        let mut req = Request::new();
+        let err = req.parse_message(b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\nContent-Length: 1\r\n\r\na").unwrap_err();
+        assert!(matches!(err, WebError::Http(HttpError::TransferEncoding)));
+    }
+}
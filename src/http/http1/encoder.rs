@@ -0,0 +1,400 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/18 09:42:05
+
+use core::cmp::Ordering;
+
+use super::BodyFraming;
+use crate::prelude::*;
+use crate::{
+    Buf, BufMut, HeaderMap, HeaderName, HeaderValue, Method, Request, Response,
+    Serialize, Version, WebError, WebResult,
+};
+use crate::http::RawHeaders;
+
+/// 输出头名字时的大小写策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderCase {
+    /// 保持原样输出
+    Preserve,
+    /// 单词首字母大写, 如`Content-Length`
+    Title,
+    /// 全部小写, 如`content-length`
+    Lower,
+}
+
+/// 行尾的换行符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\r\n`, 标准的换行
+    CrLf,
+    /// `\n`, 仅用于兼容部分宽松的实现
+    Lf,
+}
+
+impl LineEnding {
+    #[inline]
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Lf => b"\n",
+        }
+    }
+}
+
+/// Http/1.x 的编码器, 默认输出与收到的数据一致:
+/// 头名字保持原样, 按插入顺序输出, 不添加任何额外的头
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{Request, BinaryMut, Buf};
+/// use webparse::http::http1::{Encoder, HeaderCase};
+///
+/// let req = Request::builder()
+///     .url("/index")
+///     .header("x-b", "2")
+///     .header("content-length", "0")
+///     .body(())
+///     .unwrap();
+/// let mut buffer = BinaryMut::new();
+/// Encoder::new()
+///     .header_case(HeaderCase::Title)
+///     .sorted(true)
+///     .encode_request_head(&req, &mut buffer)
+///     .unwrap();
+/// assert_eq!(
+///     buffer.chunk(),
+///     &b"GET /index HTTP/1.1\r\nContent-Length: 0\r\nUser-Agent: wenmeng\r\nX-B: 2\r\n\r\n"[..]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Encoder {
+    header_case: HeaderCase,
+    line_ending: LineEnding,
+    default_headers: bool,
+    sorted: bool,
+    body_rules: bool,
+    raw_headers: bool,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self {
+            header_case: HeaderCase::Preserve,
+            line_ending: LineEnding::CrLf,
+            default_headers: false,
+            sorted: false,
+            body_rules: false,
+            raw_headers: false,
+        }
+    }
+}
+
+impl Encoder {
+    pub fn new() -> Encoder {
+        Encoder::default()
+    }
+
+    /// 设置头名字的大小写策略
+    pub fn header_case(mut self, case: HeaderCase) -> Self {
+        self.header_case = case;
+        self
+    }
+
+    /// 设置行尾的换行符
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// 是否补充默认的头, 请求为`User-Agent`, 响应为`Server`, 已存在时不处理
+    pub fn default_headers(mut self, default_headers: bool) -> Self {
+        self.default_headers = default_headers;
+        self
+    }
+
+    /// 是否按头名字(不区分大小写)排序输出, 同名的头保持原有顺序
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// 是否按RFC 7230 §3.3校验body相关的规则, 违反时返回错误:
+    /// GET/HEAD请求带有body, 1xx/204及CONNECT的2xx响应带有Content-Length或Transfer-Encoding
+    pub fn body_rules(mut self, body_rules: bool) -> Self {
+        self.body_rules = body_rules;
+        self
+    }
+
+    /// 是否按原始的字节输出解析后未修改的头, 需在解析时开启`preserve_raw_headers`,
+    /// 这些头按收到的顺序排在最前, 不受大小写策略及排序的影响, 见`RawHeaders`
+    pub fn raw_headers(mut self, raw_headers: bool) -> Self {
+        self.raw_headers = raw_headers;
+        self
+    }
+
+    /// 编码请求行及头信息
+    pub fn encode_request_head<T: Serialize, B: Buf + BufMut>(
+        &self,
+        req: &Request<T>,
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        let mut size = 0;
+        size += self.encode_method(req.method(), buffer)?;
+        size += buffer.put_u8(b' ');
+        size += buffer.put_slice(req.path().as_bytes());
+        size += buffer.put_u8(b' ');
+        size += self.encode_version(req.version(), buffer)?;
+        size += buffer.put_slice(self.line_ending.as_bytes());
+        let extra = (HeaderName::from_static("User-Agent"), HeaderValue::Stand("wenmeng"));
+        let raw = req.raw_headers();
+        size += self.encode_headers(req.headers(), raw, extra, buffer)?;
+        Ok(size)
+    }
+
+    /// 编码完整的请求, 包括body
+    pub fn encode_request<T: Serialize, B: Buf + BufMut>(
+        &self,
+        req: &mut Request<T>,
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        if self.body_rules && req.has_unexpected_body() {
+            return Err(WebError::Serialize("body not allowed for GET/HEAD"));
+        }
+        let size = self.encode_request_head(req, buffer)?;
+        Ok(size + req.body_mut().serialize(buffer)?)
+    }
+
+    /// 编码状态行及头信息
+    pub fn encode_response_head<T: Serialize, B: Buf + BufMut>(
+        &self,
+        res: &Response<T>,
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        let mut size = 0;
+        size += self.encode_version(res.version(), buffer)?;
+        size += buffer.put_u8(b' ');
+        let status = res.status();
+        // 未登记原因短语的状态码(如599)输出空的原因短语
+        let reason = status.canonical_reason().unwrap_or("");
+        size += buffer.put_slice(status.as_str().as_bytes());
+        size += buffer.put_u8(b' ');
+        size += buffer.put_slice(reason.as_bytes());
+        size += buffer.put_slice(self.line_ending.as_bytes());
+        let extra = (HeaderName::from_static("Server"), HeaderValue::Stand("wenmeng"));
+        let raw = res.raw_headers();
+        size += self.encode_headers(res.headers(), raw, extra, buffer)?;
+        Ok(size)
+    }
+
+    /// 编码完整的响应, 1xx/204/304的响应即使带有body也不会输出
+    pub fn encode_response<T: Serialize, B: Buf + BufMut>(
+        &self,
+        res: &mut Response<T>,
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        self.encode_response_with(None, res, buffer)
+    }
+
+    /// 编码对method请求的响应, HEAD请求及CONNECT成功的响应不输出body
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Method, Response, BinaryMut, Buf};
+    /// use webparse::http::http1::Encoder;
+    ///
+    /// let mut res = Response::builder()
+    ///     .no_default_headers()
+    ///     .header("Content-Length", "2")
+    ///     .body("ok")
+    ///     .unwrap();
+    /// let mut buffer = BinaryMut::new();
+    /// Encoder::new()
+    ///     .encode_response_for(&Method::HEAD, &mut res, &mut buffer)
+    ///     .unwrap();
+    /// assert_eq!(buffer.chunk(), &b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n"[..]);
+    ///
+    /// let mut buffer = BinaryMut::new();
+    /// let err = Encoder::new()
+    ///     .body_rules(true)
+    ///     .encode_response_for(&Method::CONNECT, &mut res, &mut buffer);
+    /// assert!(err.is_err());
+    /// ```
+    pub fn encode_response_for<T: Serialize, B: Buf + BufMut>(
+        &self,
+        method: &Method,
+        res: &mut Response<T>,
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        self.encode_response_with(Some(method), res, buffer)
+    }
+
+    fn encode_response_with<T: Serialize, B: Buf + BufMut>(
+        &self,
+        method: Option<&Method>,
+        res: &mut Response<T>,
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        if self.body_rules {
+            res.check_body_rules(method)?;
+        }
+        let framing = match method {
+            Some(method) => res.body_framing_for(method)?,
+            None => res.body_framing()?,
+        };
+        let size = self.encode_response_head(res, buffer)?;
+        if framing == BodyFraming::Empty {
+            return Ok(size);
+        }
+        Ok(size + res.body_mut().serialize(buffer)?)
+    }
+
+    fn encode_method<B: Buf + BufMut>(&self, method: &Method, buffer: &mut B) -> WebResult<usize> {
+        match method {
+            Method::None => Err(WebError::Serialize("method")),
+            _ => Ok(buffer.put_slice(method.as_str().as_bytes())),
+        }
+    }
+
+    fn encode_version<B: Buf + BufMut>(&self, version: Version, buffer: &mut B) -> WebResult<usize> {
+        match version {
+            Version::Http10 | Version::Http11 => Ok(buffer.put_slice(version.as_str().as_bytes())),
+            _ => Err(WebError::Serialize("version")),
+        }
+    }
+
+    fn encode_headers<B: Buf + BufMut>(
+        &self,
+        header: &HeaderMap,
+        raw: Option<&RawHeaders>,
+        extra: (HeaderName, HeaderValue),
+        buffer: &mut B,
+    ) -> WebResult<usize> {
+        let mut size = 0;
+        let mut list: Vec<(&HeaderName, &HeaderValue)> = header.iter().map(|(n, v)| (n, v)).collect();
+        if let Some(raw) = raw.filter(|_| self.raw_headers) {
+            list.retain(|(n, v)| !raw.is_unmodified(n, v));
+            for line in raw.iter() {
+                if !list.iter().any(|(n, _)| **n == line.name()) && header.contains(&line.name()) {
+                    size += buffer.put_slice(line.line());
+                }
+            }
+        }
+        if self.default_headers && !header.contains(&extra.0) {
+            list.push((&extra.0, &extra.1));
+        }
+        if self.sorted {
+            list.sort_by(|a, b| Self::cmp_name(a.0, b.0));
+        }
+        for (name, value) in list {
+            size += self.encode_name(name, buffer);
+            size += buffer.put_slice(b": ");
+            size += value.encode(buffer)?;
+            size += buffer.put_slice(self.line_ending.as_bytes());
+        }
+        size += buffer.put_slice(self.line_ending.as_bytes());
+        Ok(size)
+    }
+
+    fn encode_name<B: Buf + BufMut>(&self, name: &HeaderName, buffer: &mut B) -> usize {
+        let bytes = name.as_bytes();
+        match self.header_case {
+            HeaderCase::Preserve => buffer.put_slice(bytes),
+            HeaderCase::Lower => {
+                for b in bytes {
+                    buffer.put_u8(b.to_ascii_lowercase());
+                }
+                bytes.len()
+            }
+            HeaderCase::Title => {
+                let mut upper = true;
+                for b in bytes {
+                    if upper {
+                        buffer.put_u8(b.to_ascii_uppercase());
+                    } else {
+                        buffer.put_u8(b.to_ascii_lowercase());
+                    }
+                    upper = *b == b'-';
+                }
+                bytes.len()
+            }
+        }
+    }
+
+    fn cmp_name(a: &HeaderName, b: &HeaderName) -> Ordering {
+        let a = a.as_bytes().iter().map(|v| v.to_ascii_lowercase());
+        let b = b.as_bytes().iter().map(|v| v.to_ascii_lowercase());
+        a.cmp(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoder, HeaderCase, LineEnding};
+    use crate::{BinaryMut, Buf, Response};
+
+    #[test]
+    fn encode_response_options() {
+        let mut res = Response::builder()
+            .header("Content-Length", "2")
+            .body("ok")
+            .unwrap();
+        res.headers_mut().remove(&"Server");
+        let mut buffer = BinaryMut::new();
+        Encoder::new()
+            .header_case(HeaderCase::Lower)
+            .line_ending(LineEnding::Lf)
+            .encode_response(&mut res, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer.chunk(), &b"HTTP/1.1 200 OK\ncontent-length: 2\n\nok"[..]);
+
+        let mut buffer = BinaryMut::new();
+        Encoder::new()
+            .default_headers(true)
+            .encode_response(&mut res, &mut buffer)
+            .unwrap();
+        assert_eq!(
+            buffer.chunk(),
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nServer: wenmeng\r\n\r\nok"[..]
+        );
+    }
+
+    #[test]
+    fn encode_bodyless_response() {
+        let mut res = Response::builder()
+            .no_default_headers()
+            .status(204)
+            .body("ignored")
+            .unwrap();
+        let mut buffer = BinaryMut::new();
+        Encoder::new().encode_response(&mut res, &mut buffer).unwrap();
+        assert_eq!(buffer.chunk(), &b"HTTP/1.1 204 No Content\r\n\r\n"[..]);
+
+        res.headers_mut().insert("Content-Length", "7");
+        let mut buffer = BinaryMut::new();
+        assert!(Encoder::new().body_rules(true).encode_response(&mut res, &mut buffer).is_err());
+        assert!(!buffer.has_remaining());
+    }
+
+    #[test]
+    fn encode_unregistered_status() {
+        let mut res = Response::builder()
+            .no_default_headers()
+            .status(599)
+            .body(())
+            .unwrap();
+        let mut buffer = BinaryMut::new();
+        Encoder::new().encode_response(&mut res, &mut buffer).unwrap();
+        assert_eq!(buffer.chunk(), &b"HTTP/1.1 599 \r\n\r\n"[..]);
+    }
+}
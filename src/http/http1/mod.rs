@@ -0,0 +1,23 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/18 09:41:27
+
+//! Http/1.x 的编码相关
+
+mod coding;
+mod decoder;
+mod encoder;
+mod parser;
+
+pub use coding::{ChunkedEncoder, ContentCoding, Identity};
+pub use decoder::{BodyAccumulator, BodyDecoder, BodyFraming};
+pub use encoder::{Encoder, HeaderCase, LineEnding};
+pub use parser::RequestParser;
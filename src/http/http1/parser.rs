@@ -0,0 +1,241 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/19 14:26:08
+
+use crate::{
+    Binary, BinaryMut, BinaryRef, Buf, HeaderMap, HeaderName, Helper, HttpError, ParseStatus, Request,
+    WebError, WebResult,
+};
+
+#[derive(Debug)]
+enum State {
+    /// 等待完整的请求头
+    Head,
+    /// 按Content-Length读取body
+    Length(Request<()>, usize),
+    /// 按chunked读取body, 已读取的数据保存在BinaryMut中
+    Chunked(Request<()>, BinaryMut),
+}
+
+/// 自带缓存的请求解析器, 可按任意边界传入数据
+///
+/// 通过`feed`追加读到的数据, 通过`poll`取出已完整接收的请求,
+/// body支持Content-Length及chunked两种方式
+///
+/// # Examples
+///
+/// ```
+/// use webparse::Buf;
+/// use webparse::http::http1::RequestParser;
+///
+/// let mut parser = RequestParser::new();
+/// parser.feed(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Le").unwrap();
+/// assert!(parser.poll().unwrap().is_none());
+/// parser.feed(b"ngth: 5\r\n\r\nhel").unwrap();
+/// assert!(parser.poll().unwrap().is_none());
+/// parser.feed(b"loGET /next HTTP/1.1\r\n\r\n").unwrap();
+/// let req = parser.poll().unwrap().unwrap();
+/// assert_eq!(req.body().chunk(), b"hello");
+/// let req = parser.poll().unwrap().unwrap();
+/// assert_eq!(req.path(), "/next");
+/// assert!(parser.poll().unwrap().is_none());
+/// ```
+#[derive(Debug)]
+pub struct RequestParser {
+    buffer: BinaryMut,
+    state: State,
+    /// 缓存及chunked的body允许的最大长度
+    max_buffer: usize,
+}
+
+impl RequestParser {
+    pub fn new() -> RequestParser {
+        RequestParser::with_max_buffer(usize::MAX)
+    }
+
+    /// 限制未解析数据及chunked的body的最大长度, 防止对端无限制的增长缓存,
+    /// 超出时`feed`或`poll`返回错误
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http::http1::RequestParser;
+    ///
+    /// let mut parser = RequestParser::with_max_buffer(64);
+    /// assert!(parser.feed(&[b'a'; 65]).is_err());
+    /// assert_eq!(parser.buffered(), 0);
+    ///
+    /// parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n").unwrap();
+    /// assert!(parser.poll().unwrap().is_none());
+    /// for _ in 0..2 {
+    ///     parser.feed(b"20\r\naaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\r\n").unwrap();
+    ///     assert!(parser.poll().unwrap().is_none());
+    /// }
+    /// parser.feed(b"1\r\na\r\n").unwrap();
+    /// assert!(parser.poll().is_err());
+    /// ```
+    pub fn with_max_buffer(max_buffer: usize) -> RequestParser {
+        RequestParser {
+            buffer: BinaryMut::with_max_capacity(max_buffer),
+            state: State::Head,
+            max_buffer,
+        }
+    }
+
+    /// 追加收到的数据, 超出最大长度时不写入任何数据并返回错误
+    pub fn feed(&mut self, data: &[u8]) -> WebResult<usize> {
+        self.buffer.try_put_slice(data)
+    }
+
+    /// 缓存中尚未被解析的字节数
+    pub fn buffered(&self) -> usize {
+        self.buffer.remaining()
+    }
+
+    /// 是否正处于两个请求之间, 即没有解析到一半的请求
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Head) && !self.buffer.has_remaining()
+    }
+
+    /// 尝试取出一个完整的请求, 数据不足时返回None, 格式错误时返回Err
+    pub fn poll(&mut self) -> WebResult<Option<Request<Binary>>> {
+        loop {
+            match core::mem::replace(&mut self.state, State::Head) {
+                State::Head => {
+                    let mut request = Request::new();
+                    let len = match request.parse_status(self.buffer.chunk())? {
+                        ParseStatus::Complete(len) => len,
+                        ParseStatus::Partial => return Ok(None),
+                    };
+                    self.buffer.advance(len);
+                    let headers = request.headers();
+                    if headers.contains(&HeaderName::TRANSFER_ENCODING) && !headers.is_final_chunked() {
+                        return Err(WebError::from(HttpError::TransferEncoding));
+                    }
+                    if headers.is_final_chunked() {
+                        self.state = State::Chunked(request, BinaryMut::with_max_capacity(self.max_buffer));
+                    } else {
+                        let body_len = headers.content_length()?.unwrap_or(0);
+                        if body_len == 0 {
+                            return Ok(Some(request.into(Binary::new()).0));
+                        }
+                        self.state = State::Length(request, body_len);
+                    }
+                }
+                State::Length(request, len) => {
+                    if self.buffer.remaining() < len {
+                        self.state = State::Length(request, len);
+                        return Ok(None);
+                    }
+                    let body = Binary::from(self.buffer.chunk()[..len].to_vec());
+                    self.buffer.advance(len);
+                    return Ok(Some(request.into(body).0));
+                }
+                State::Chunked(mut request, mut body) => {
+                    let finish = match self.parse_chunk(&mut body) {
+                        Ok(finish) => finish,
+                        Err(e) if e.is_partial() => {
+                            self.state = State::Chunked(request, body);
+                            return Ok(None);
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    if let Some(trailers) = finish {
+                        if !trailers.is_empty() {
+                            request.set_trailers(trailers);
+                        }
+                        return Ok(Some(request.into(body.freeze()).0));
+                    }
+                    self.state = State::Chunked(request, body);
+                }
+            }
+        }
+    }
+
+    /// 解析一个chunk, 为最后一个chunk时返回其后的trailer, 数据不足时不消耗缓存
+    fn parse_chunk(&mut self, body: &mut BinaryMut) -> WebResult<Option<HeaderMap>> {
+        let (head, num) = Helper::parse_chunk_data(&mut self.buffer)?;
+        if num == 0 {
+            let mut trailers = HeaderMap::new();
+            let used = {
+                let mut trailer = BinaryRef::from(&self.buffer.chunk()[head..]);
+                let len = trailer.remaining();
+                Helper::parse_header(&mut trailer, &mut trailers)?;
+                len - trailer.remaining()
+            };
+            self.buffer.advance(head + used);
+            return Ok(Some(trailers));
+        }
+        // parse_chunk_data已保证数据后至少还有两个字节, 先检查换行再消耗缓存
+        if &self.buffer.chunk()[head + num..head + num + 2] != b"\r\n" {
+            return Err(WebError::from(HttpError::NewLine));
+        }
+        body.try_put_slice(&self.buffer.chunk()[head..head + num])?;
+        self.buffer.advance(head + num + 2);
+        Ok(None)
+    }
+}
+
+impl Default for RequestParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RequestParser;
+    use crate::Buf;
+
+    #[test]
+    fn chunked_any_boundary() {
+        let data = b"POST /up HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\nExpires: 0\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        for step in 1..data.len() {
+            let mut parser = RequestParser::new();
+            let mut reqs = vec![];
+            for part in data.chunks(step) {
+                parser.feed(part).unwrap();
+                while let Some(req) = parser.poll().unwrap() {
+                    reqs.push(req);
+                }
+            }
+            assert_eq!(reqs.len(), 2);
+            assert_eq!(reqs[0].body().chunk(), b"hello world");
+            assert!(reqs[0].trailers().unwrap().contains(&"expires"));
+            assert_eq!(reqs[1].path(), "/");
+            assert!(parser.is_idle());
+        }
+    }
+
+    #[test]
+    fn content_length_smuggling() {
+        for data in [
+            &b"POST / HTTP/1.1\r\nContent-Length: 1x\r\n\r\nGET /admin HTTP/1.1\r\n\r\n"[..],
+            &b"POST / HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 0\r\n\r\nhello"[..],
+        ] {
+            let mut parser = RequestParser::new();
+            parser.feed(data).unwrap();
+            assert!(parser.poll().is_err());
+        }
+
+        let mut parser = RequestParser::new();
+        parser.feed(b"POST / HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+        assert_eq!(parser.poll().unwrap().unwrap().body().chunk(), b"hello");
+    }
+
+    #[test]
+    fn chunk_missing_crlf() {
+        let mut parser = RequestParser::new();
+        parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhelloXX0\r\n\r\n").unwrap();
+        let err = parser.poll().unwrap_err();
+        assert!(!err.is_partial());
+    }
+}
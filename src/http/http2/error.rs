@@ -13,7 +13,10 @@
 use std::fmt;
 use crate::{WebError};
 
-use super::{DecoderError, HuffmanDecoderError};
+use super::{
+    frame::{ErrorCode, FrameViolation, StreamIdentifier},
+    DecoderError, HuffmanDecoderError,
+};
 
 
 #[derive(Debug)]
@@ -62,6 +65,10 @@ pub enum Http2Error {
     MalformedMessage,
     /// 请求的头信息不全
     InvalidRequesetUrl,
+    /// 违反RFC 7540第6节的帧
+    Frame(FrameViolation),
+    /// 编码时单个头超出了设置的最大大小
+    HeaderTooLarge,
 }
 
 
@@ -69,9 +76,85 @@ impl Http2Error {
     #[inline]
     pub fn description_str(&self) -> &'static str {
         match *self {
-            Self::Decoder(_) => "",
-            Self::Huffman(_) => "",
-            _ => "",
+            Self::Decoder(_) => "hpack decoder error",
+            Self::Huffman(_) => "huffman decoder error",
+            Self::Short => "frame header too short",
+            Self::BadFlag(_) => "invalid frame flag",
+            Self::BadKind(_) => "invalid frame kind",
+            Self::TooMuchPadding(_) => "too much padding",
+            Self::PayloadLengthTooShort => "payload length too short",
+            Self::PartialSettingLength => "partial setting length",
+            Self::InvalidPayloadLength => "invalid payload length",
+            Self::InvalidStreamId => "invalid stream id",
+            Self::InvalidSettingValue => "invalid setting value",
+            Self::BadFrameSize => "bad frame size",
+            Self::InvalidWindowUpdateValue => "invalid window update value",
+            Self::InvalidDependencyId => "invalid dependency id",
+            Self::MalformedMessage => "malformed message",
+            Self::InvalidRequesetUrl => "invalid request url",
+            Self::Frame(_) => "frame violation",
+            Self::HeaderTooLarge => "header too large",
+        }
+    }
+
+    /// 对应的RFC 7540错误码, 用于RST_STREAM或GOAWAY帧
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::{frame::ErrorCode, DecoderError, Http2Error};
+    ///
+    /// let err = Http2Error::Decoder(DecoderError::HeaderIndexOutOfBounds);
+    /// assert_eq!(err.error_code(), ErrorCode::CompressionError);
+    /// assert!(err.is_connection_error());
+    ///
+    /// let err = Http2Error::MalformedMessage;
+    /// assert_eq!(err.error_code(), ErrorCode::ProtocolError);
+    /// assert!(!err.is_connection_error());
+    /// assert_eq!(Http2Error::BadFrameSize.error_code(), ErrorCode::FrameSizeError);
+    /// ```
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::Decoder(e) => e.error_code(),
+            Self::Huffman(_) => ErrorCode::CompressionError,
+            Self::Short
+            | Self::PartialSettingLength
+            | Self::InvalidPayloadLength
+            | Self::BadFrameSize => ErrorCode::FrameSizeError,
+            Self::BadFlag(_)
+            | Self::BadKind(_)
+            | Self::TooMuchPadding(_)
+            | Self::PayloadLengthTooShort
+            | Self::InvalidStreamId
+            | Self::InvalidSettingValue
+            | Self::InvalidWindowUpdateValue
+            | Self::InvalidDependencyId
+            | Self::MalformedMessage
+            | Self::InvalidRequesetUrl => ErrorCode::ProtocolError,
+            Self::Frame(v) => v.error_code(),
+            Self::HeaderTooLarge => ErrorCode::InternalError,
+        }
+    }
+
+    /// 是否为连接级错误, 是则发送GOAWAY并关闭连接, 否则只需对出错的流发送RST_STREAM
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Self::Decoder(e) => e.is_connection_error(),
+            Self::Frame(v) => v.is_connection_error(),
+            // 只影响单个流, 不影响连接状态
+            Self::InvalidDependencyId
+            | Self::MalformedMessage
+            | Self::InvalidRequesetUrl
+            | Self::HeaderTooLarge => false,
+            _ => true,
+        }
+    }
+
+    /// 流级错误时出错的流id, 无法确定时返回None
+    pub fn stream_id(&self) -> Option<StreamIdentifier> {
+        match self {
+            Self::Frame(FrameViolation::Stream(id, _, _)) => Some(*id),
+            _ => None,
         }
     }
 
@@ -82,7 +165,18 @@ impl Http2Error {
 
 impl fmt::Display for Http2Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.description_str())
+        match self {
+            Self::Frame(v) => v.fmt(f),
+            _ => f.write_str(self.description_str()),
+        }
+    }
+}
+
+impl std::error::Error for Http2Error {}
+
+impl From<FrameViolation> for Http2Error {
+    fn from(e: FrameViolation) -> Self {
+        Http2Error::Frame(e)
     }
 }
 
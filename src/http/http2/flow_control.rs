@@ -0,0 +1,108 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/10 09:12:45
+
+use crate::{
+    http2::{
+        frame::{ErrorCode, FrameViolation, StreamIdentifier, WindowUpdate},
+        MAX_WINDOW_SIZE,
+    },
+    Http2Error, WebResult,
+};
+
+/// 单个流或连接(流0)的流量控制窗口
+///
+/// 窗口可能因SETTINGS_INITIAL_WINDOW_SIZE变小而为负, 此时需等待WINDOW_UPDATE恢复后才能发送
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::{FlowControl, frame::StreamIdentifier};
+///
+/// let mut flow = FlowControl::new(StreamIdentifier(1), 100);
+/// flow.consume(80).unwrap();
+/// assert_eq!(flow.available(), 20);
+/// assert!(flow.consume(21).is_err());
+/// flow.apply_initial_delta(100, 50).unwrap();
+/// assert_eq!(flow.window(), -30);
+/// assert_eq!(flow.available(), 0);
+/// flow.increase(40).unwrap();
+/// assert_eq!(flow.available(), 10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowControl {
+    stream_id: StreamIdentifier,
+    window: i32,
+}
+
+impl FlowControl {
+    pub fn new(stream_id: StreamIdentifier, initial: u32) -> FlowControl {
+        FlowControl {
+            stream_id,
+            window: initial.min(MAX_WINDOW_SIZE) as i32,
+        }
+    }
+
+    pub fn stream_id(&self) -> StreamIdentifier {
+        self.stream_id
+    }
+
+    /// 当前窗口大小, 可能为负
+    pub fn window(&self) -> i32 {
+        self.window
+    }
+
+    /// 当前可发送的字节数
+    pub fn available(&self) -> u32 {
+        self.window.max(0) as u32
+    }
+
+    /// 发送或接收数据后减少窗口, 超出可用窗口时返回FLOW_CONTROL_ERROR
+    pub fn consume(&mut self, len: u32) -> WebResult<()> {
+        if len > self.available() {
+            return Err(self.violation("data exceeds flow-control window"));
+        }
+        self.window -= len as i32;
+        Ok(())
+    }
+
+    /// 增加窗口, 超过2^31-1时返回FLOW_CONTROL_ERROR
+    pub fn increase(&mut self, inc: u32) -> WebResult<()> {
+        self.window = WindowUpdate::new(self.stream_id, inc)
+            .apply_to(self.window)
+            .map_err(Http2Error::into)?;
+        Ok(())
+    }
+
+    /// SETTINGS_INITIAL_WINDOW_SIZE由`old`变为`new`时按差值调整窗口, 结果可能为负,
+    /// 超过2^31-1时为连接级的FLOW_CONTROL_ERROR
+    pub fn apply_initial_delta(&mut self, old: u32, new: u32) -> WebResult<()> {
+        let value = self.window as i64 + new as i64 - old as i64;
+        if value > MAX_WINDOW_SIZE as i64 || value < i32::MIN as i64 {
+            return Err(Http2Error::Frame(FrameViolation::Connection(
+                ErrorCode::FlowControlError,
+                "initial window size change overflows window",
+            ))
+            .into());
+        }
+        self.window = value as i32;
+        Ok(())
+    }
+
+    fn violation(&self, msg: &'static str) -> crate::WebError {
+        let violation = if self.stream_id.is_zero() {
+            FrameViolation::Connection(ErrorCode::FlowControlError, msg)
+        } else {
+            FrameViolation::Stream(self.stream_id, ErrorCode::FlowControlError, msg)
+        };
+        Http2Error::Frame(violation).into()
+    }
+}
@@ -13,7 +13,9 @@
 
 use crate::{Binary, Serialize, Buf, BufMut, WebResult, http2::encoder::Encoder};
 
-use super::{Flag, FrameHeader, Kind, StreamIdentifier};
+use std::fmt;
+
+use super::{frame::write_head, Flag, Frame, FrameHeader, Kind, StreamIdentifier};
 
 #[derive(Eq, PartialEq, Debug)]
 pub struct Data<T = Binary> {
@@ -88,7 +90,72 @@ impl<T> Data<T> {
     }
 }
 
+impl<T> From<Data<T>> for Frame<T> {
+    fn from(src: Data<T>) -> Self {
+        Frame::Data(src)
+    }
+}
+
+impl<T: Buf> fmt::Display for Data<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::Data, self.stream_id, self.flags.bits())?;
+        write!(f, " len={}", self.data.remaining())
+    }
+}
+
 impl Data<Binary> {
+    /// 按max_frame_size及可用的流量窗口拆分为多个DATA帧,
+    /// 返回可立即发送的帧及受窗口限制未能发送的剩余数据,
+    /// END_STREAM仅在数据全部发送完时设置在最后一帧上
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Data, Flag, FrameHeader, Kind, StreamIdentifier};
+    /// use webparse::{Binary, Buf};
+    ///
+    /// let head = FrameHeader::new(Kind::Data, Flag::end_stream(), StreamIdentifier(1));
+    /// let data = Data::new(head, Binary::from(vec![0u8; 10]));
+    /// let (frames, rest) = data.split(4, 6);
+    /// assert_eq!(frames.len(), 2);
+    /// assert_eq!(frames[1].payload().remaining(), 2);
+    /// assert!(frames.iter().all(|f| !f.is_end_stream()));
+    ///
+    /// let (frames, rest) = rest.unwrap().split(4, 65535);
+    /// assert_eq!(frames.len(), 1);
+    /// assert!(frames[0].is_end_stream());
+    /// assert!(rest.is_none());
+    /// ```
+    pub fn split(mut self, max_frame_size: usize, window_available: usize) -> (Vec<Data>, Option<Data>) {
+        let max_frame_size = std::cmp::max(max_frame_size, 1);
+        let end_stream = self.is_end_stream();
+        let mut window = window_available;
+        let mut frames = vec![];
+        loop {
+            let remaining = self.data.remaining();
+            let now_len = std::cmp::min(std::cmp::min(remaining, max_frame_size), window);
+            // 空的DATA帧不占用窗口, 仍可用来发送END_STREAM
+            if now_len == 0 && remaining != 0 {
+                return (frames, Some(self));
+            }
+            let is_last = now_len == remaining;
+            let mut flags = Flag::zero();
+            if is_last && end_stream {
+                flags.set_end_stream();
+            }
+            frames.push(Data {
+                stream_id: self.stream_id,
+                data: self.data.copy_to_binary(now_len),
+                flags,
+                pad_len: None,
+            });
+            if is_last {
+                return (frames, None);
+            }
+            window -= now_len;
+        }
+    }
+
     pub fn encode<B: Buf+BufMut>(&mut self,
         encoder: &mut Encoder, dst: &mut B) -> WebResult<usize> {
         let mut size = 0;
@@ -9,3 +9,134 @@
 // Author: tickbh
 // -----
 // Created Date: 2023/09/01 04:15:54
+
+use std::fmt;
+
+use crate::{Buf, BufMut, Serialize, WebResult};
+
+use super::Reason;
+
+/// RST_STREAM及GOAWAY中的错误码 (RFC 7540 7), 未定义的值保存在`Unknown`中
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    NoError,
+    ProtocolError,
+    InternalError,
+    FlowControlError,
+    SettingsTimeout,
+    StreamClosed,
+    FrameSizeError,
+    RefusedStream,
+    Cancel,
+    CompressionError,
+    ConnectError,
+    EnhanceYourCalm,
+    InadequateSecurity,
+    Http11Required,
+    Unknown(u32),
+}
+
+impl ErrorCode {
+    pub fn parse<T: Buf>(buf: &mut T) -> ErrorCode {
+        if buf.remaining() < 4 {
+            return ErrorCode::NoError;
+        }
+        ErrorCode::from(buf.get_u32())
+    }
+
+    pub fn as_u32(&self) -> u32 {
+        match self {
+            ErrorCode::NoError => 0,
+            ErrorCode::ProtocolError => 1,
+            ErrorCode::InternalError => 2,
+            ErrorCode::FlowControlError => 3,
+            ErrorCode::SettingsTimeout => 4,
+            ErrorCode::StreamClosed => 5,
+            ErrorCode::FrameSizeError => 6,
+            ErrorCode::RefusedStream => 7,
+            ErrorCode::Cancel => 8,
+            ErrorCode::CompressionError => 9,
+            ErrorCode::ConnectError => 10,
+            ErrorCode::EnhanceYourCalm => 11,
+            ErrorCode::InadequateSecurity => 12,
+            ErrorCode::Http11Required => 13,
+            ErrorCode::Unknown(v) => *v,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::NoError => "NO_ERROR",
+            ErrorCode::ProtocolError => "PROTOCOL_ERROR",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+            ErrorCode::FlowControlError => "FLOW_CONTROL_ERROR",
+            ErrorCode::SettingsTimeout => "SETTINGS_TIMEOUT",
+            ErrorCode::StreamClosed => "STREAM_CLOSED",
+            ErrorCode::FrameSizeError => "FRAME_SIZE_ERROR",
+            ErrorCode::RefusedStream => "REFUSED_STREAM",
+            ErrorCode::Cancel => "CANCEL",
+            ErrorCode::CompressionError => "COMPRESSION_ERROR",
+            ErrorCode::ConnectError => "CONNECT_ERROR",
+            ErrorCode::EnhanceYourCalm => "ENHANCE_YOUR_CALM",
+            ErrorCode::InadequateSecurity => "INADEQUATE_SECURITY",
+            ErrorCode::Http11Required => "HTTP_1_1_REQUIRED",
+            ErrorCode::Unknown(_) => "UNKNOWN",
+        }
+    }
+}
+
+impl From<u32> for ErrorCode {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => ErrorCode::NoError,
+            1 => ErrorCode::ProtocolError,
+            2 => ErrorCode::InternalError,
+            3 => ErrorCode::FlowControlError,
+            4 => ErrorCode::SettingsTimeout,
+            5 => ErrorCode::StreamClosed,
+            6 => ErrorCode::FrameSizeError,
+            7 => ErrorCode::RefusedStream,
+            8 => ErrorCode::Cancel,
+            9 => ErrorCode::CompressionError,
+            10 => ErrorCode::ConnectError,
+            11 => ErrorCode::EnhanceYourCalm,
+            12 => ErrorCode::InadequateSecurity,
+            13 => ErrorCode::Http11Required,
+            v => ErrorCode::Unknown(v),
+        }
+    }
+}
+
+impl From<ErrorCode> for u32 {
+    fn from(value: ErrorCode) -> Self {
+        value.as_u32()
+    }
+}
+
+impl From<Reason> for ErrorCode {
+    fn from(value: Reason) -> Self {
+        ErrorCode::from(u32::from(value))
+    }
+}
+
+impl From<ErrorCode> for Reason {
+    fn from(value: ErrorCode) -> Self {
+        Reason::from(value.as_u32())
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::Unknown(v) => write!(f, "UNKNOWN({:#x})", v),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        buffer.put_u32(self.as_u32());
+        Ok(4)
+    }
+}
@@ -10,6 +10,8 @@
 // -----
 // Created Date: 2023/08/21 11:03:20
 
+use super::Kind;
+
 bitflags! {
     #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
     pub struct Flag: u8 {
@@ -85,6 +87,51 @@ impl Flag {
     pub fn unset_end_stream(&mut self) {
         self.set(Flag::END_STREAM, false)
     }
+
+    /// 按帧类型输出标志位的名字, 如`END_HEADERS|END_STREAM`, 0x1在SETTINGS及PING中为ACK
+    ///
+    /// 该类型未定义的标志位以十六进制输出, 没有标志位时输出`0`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Flag, Kind};
+    ///
+    /// assert_eq!(Flag::describe(&Kind::Headers, 0x5), "END_HEADERS|END_STREAM");
+    /// assert_eq!(Flag::describe(&Kind::Ping, 0x1), "ACK");
+    /// assert_eq!(Flag::describe(&Kind::Settings, 0x9), "ACK|0x8");
+    /// assert_eq!(Flag::describe(&Kind::Data, 0), "0");
+    /// ```
+    pub fn describe(kind: &Kind, bits: u8) -> String {
+        let known: &[(u8, &str)] = match kind {
+            Kind::Data => &[(0x1, "END_STREAM"), (0x8, "PADDED")],
+            Kind::Headers => &[
+                (0x4, "END_HEADERS"),
+                (0x1, "END_STREAM"),
+                (0x8, "PADDED"),
+                (0x20, "PRIORITY"),
+            ],
+            Kind::PushPromise => &[(0x4, "END_HEADERS"), (0x8, "PADDED")],
+            Kind::Continuation => &[(0x4, "END_HEADERS")],
+            Kind::Settings | Kind::Ping => &[(0x1, "ACK")],
+            _ => &[],
+        };
+        if bits == 0 {
+            return "0".to_string();
+        }
+        let mut names = vec![];
+        let mut rest = bits;
+        for (bit, name) in known {
+            if bits & bit != 0 {
+                names.push(name.to_string());
+                rest &= !bit;
+            }
+        }
+        if rest != 0 {
+            names.push(format!("{:#x}", rest));
+        }
+        names.join("|")
+    }
 }
 
 impl Default for Flag {
@@ -10,17 +10,17 @@
 // -----
 // Created Date: 2023/08/21 11:20:39
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Write};
 
 use crate::{
-    http::http2::{encoder::Encoder, Decoder},
+    http::http2::{encoder::Encoder, Decoder, MAX_MAX_FRAME_SIZE},
     Binary, Buf, BufMut, HeaderMap, Http2Error, Serialize, WebResult,
 };
 
 use super::{
-    encode_u24,
     headers::{PushPromise},
-    read_u24, Data, Flag, GoAway, Headers, Kind, Ping, Priority, Reset, Settings, StreamIdentifier,
+    validate, Data, ErrorCode, Flag, GoAway, Headers, Kind, Ping, Priority, Reset, Settings, StreamIdentifier,
+    ValidateContext,
     WindowUpdate,
 };
 
@@ -47,6 +47,9 @@ pub enum Frame<T = Binary> {
     Reset(Reset),
 }
 
+/// 数据为`Binary`的帧, 解析及编码时使用的类型
+pub type BinaryFrame = Frame<Binary>;
+
 impl Frame<Binary> {
     #[inline]
     pub fn trim_padding<B: Buf>(header: &FrameHeader, buf: &mut B) -> WebResult<()> {
@@ -61,7 +64,9 @@ impl Frame<Binary> {
         }
         Ok(())
     }
-    
+}
+
+impl<T> Frame<T> {
     pub fn display_name(&self) -> String {
         match self {
             Frame::Data(f) => format!("Data({})", f.stream_id()),
@@ -136,7 +141,58 @@ impl Frame<Binary> {
         }
     }
 
-    
+    /// 转换DATA帧中数据的类型, 其它帧不受影响
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{BinaryFrame, Data, Flag, Frame, FrameHeader, Kind, StreamIdentifier};
+    /// use webparse::Binary;
+    ///
+    /// let head = FrameHeader::new(Kind::Data, Flag::end_stream(), StreamIdentifier(1));
+    /// let frame: BinaryFrame = Data::new(head, Binary::from_static(b"hello")).into();
+    /// let frame: Frame<Vec<u8>> = frame.map(|b| b.to_vec());
+    /// assert!(frame.is_end_stream());
+    /// let frame = frame.into_headers().unwrap_err();
+    /// assert_eq!(frame.into_data().unwrap().into_payload(), b"hello".to_vec());
+    /// ```
+    pub fn map<F, U>(self, f: F) -> Frame<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        match self {
+            Frame::Data(v) => Frame::Data(v.map(f)),
+            Frame::Headers(v) => Frame::Headers(v),
+            Frame::Priority(v) => Frame::Priority(v),
+            Frame::PushPromise(v) => Frame::PushPromise(v),
+            Frame::Settings(v) => Frame::Settings(v),
+            Frame::Ping(v) => Frame::Ping(v),
+            Frame::GoAway(v) => Frame::GoAway(v),
+            Frame::WindowUpdate(v) => Frame::WindowUpdate(v),
+            Frame::Reset(v) => Frame::Reset(v),
+        }
+    }
+
+    /// 取出DATA帧, 其它类型的帧原样返回
+    #[allow(clippy::result_large_err)]
+    pub fn into_data(self) -> Result<Data<T>, Self> {
+        match self {
+            Frame::Data(v) => Ok(v),
+            frame => Err(frame),
+        }
+    }
+
+    /// 取出HEADERS帧, 其它类型的帧原样返回
+    #[allow(clippy::result_large_err)]
+    pub fn into_headers(self) -> Result<Headers, Self> {
+        match self {
+            Frame::Headers(v) => Ok(v),
+            frame => Err(frame),
+        }
+    }
+}
+
+impl Frame<Binary> {
     pub fn encode<B: Buf + BufMut>(
         self,
         buf: &mut B,
@@ -154,7 +210,7 @@ impl Frame<Binary> {
             Frame::WindowUpdate(v) => v.encode(buf)?,
             Frame::Reset(v) => v.encode(buf)?,
         };
-        log::trace!("编码http2二进制Frame({}) 大小 {}", name, size);
+        crate::web_trace!("编码http2二进制Frame({}) 大小 {}", name, size);
         Ok(size)
     }
 }
@@ -166,6 +222,14 @@ impl<T: Buf> Frame<T> {
         decoder: &mut Decoder,
         max_header_list_size: usize,
     ) -> WebResult<Frame<T>> {
+        crate::web_trace!(
+            "HTTP2: 解码Frame; kind={:?} stream={} flag={:?} len={}",
+            header.kind(),
+            header.stream_id().0,
+            header.flag(),
+            header.length
+        );
+        validate(&header, &ValidateContext::default()).map_err(Http2Error::into)?;
         Frame::trim_padding(&header, &mut buf)?;
         match header.kind() {
             Kind::Data => Ok(Frame::Data(Data::new(header, buf))),
@@ -223,6 +287,9 @@ impl<T: Buf> Serialize for Frame<T> {
 }
 
 impl FrameHeader {
+    /// 帧头的长度, 固定为9个字节
+    pub const LENGTH: usize = FRAME_HEADER_BYTES;
+
     pub fn new(kind: Kind, flag: Flag, id: StreamIdentifier) -> FrameHeader {
         FrameHeader {
             length: 0,
@@ -231,22 +298,80 @@ impl FrameHeader {
             id,
         }
     }
+    /// 解析帧头, 数据不足9个字节时返回None且不消耗数据, 流id的保留位被忽略, 需要时见`parse_reserved`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Flag, FrameHeader, Kind, StreamIdentifier};
+    /// use webparse::{BinaryMut, Buf};
+    ///
+    /// let data = [0, 0, 8, 6, 1, 0x80, 0, 0, 0];
+    /// assert!(FrameHeader::parse(&mut &data[..8]).unwrap().is_none());
+    /// let head = FrameHeader::parse(&mut &data[..]).unwrap().unwrap();
+    /// assert_eq!((head.length, *head.kind()), (8, Kind::Ping));
+    /// assert_eq!(head.stream_id(), StreamIdentifier(0));
+    ///
+    /// let mut buffer = BinaryMut::new();
+    /// assert_eq!(head.encode(&mut buffer).unwrap(), FrameHeader::LENGTH);
+    /// assert_eq!(buffer.chunk(), &[0, 0, 8, 6, 1, 0, 0, 0, 0]);
+    /// ```
     #[inline]
-    pub fn parse<T: Buf>(buffer: &mut T) -> WebResult<FrameHeader> {
+    pub fn parse<T: Buf>(buffer: &mut T) -> WebResult<Option<FrameHeader>> {
+        Ok(Self::parse_reserved(buffer)?.map(|(head, _)| head))
+    }
+
+    /// 同`parse`, 同时返回流id的保留位是否被设置, 编码时该位总是写入0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{FrameHeader, StreamIdentifier};
+    ///
+    /// let data = [0, 0, 0, 4, 0, 0x80, 0, 0, 0];
+    /// let (head, reserved) = FrameHeader::parse_reserved(&mut &data[..]).unwrap().unwrap();
+    /// assert!(reserved);
+    /// assert_eq!(head, FrameHeader::parse(&mut &data[..]).unwrap().unwrap());
+    /// assert_eq!(head.stream_id(), StreamIdentifier(0));
+    /// ```
+    pub fn parse_reserved<T: Buf>(buffer: &mut T) -> WebResult<Option<(FrameHeader, bool)>> {
         if buffer.remaining() < FRAME_HEADER_BYTES {
-            return Err(Http2Error::into(Http2Error::Short));
+            return Ok(None);
         }
-        let length = read_u24(buffer);
+        let length = buffer.get_u24();
         let kind = Kind::new(buffer.get_u8());
         let flag = buffer.get_u8();
         let flag = Flag::new(flag).map_err(|()| Http2Error::into(Http2Error::BadFlag(flag)))?;
-        let id = StreamIdentifier::parse(buffer);
-        Ok(FrameHeader {
-            length,
-            kind,
-            flag,
-            id,
-        })
+        let (id, reserved) = StreamIdentifier::parse_reserved(buffer);
+        Ok(Some((
+            FrameHeader {
+                length,
+                kind,
+                flag,
+                id,
+            },
+            reserved,
+        )))
+    }
+
+    /// 严格模式的解析, 流id的保留位被设置时返回`Http2Error::InvalidStreamId`,
+    /// 用于检查不符合规范的发送方, 正常接收时应使用`parse`忽略该位
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::FrameHeader;
+    ///
+    /// let data = [0, 0, 0, 4, 0, 0x80, 0, 0, 0];
+    /// assert!(FrameHeader::parse(&mut &data[..]).unwrap().is_some());
+    /// assert!(FrameHeader::parse_strict(&mut &data[..]).is_err());
+    /// assert!(FrameHeader::parse_strict(&mut &data[..8]).unwrap().is_none());
+    /// ```
+    pub fn parse_strict<T: Buf>(buffer: &mut T) -> WebResult<Option<FrameHeader>> {
+        match Self::parse_reserved(buffer)? {
+            Some((_, true)) => Err(Http2Error::into(Http2Error::InvalidStreamId)),
+            head => Ok(head.map(|(head, _)| head)),
+        }
     }
 
     pub fn kind(&self) -> &Kind {
@@ -265,9 +390,13 @@ impl FrameHeader {
         &mut self.flag
     }
 
+    /// 编码帧头, 长度超出24位时返回错误, 流id的保留位总是写入0
     pub fn encode<B: Buf + BufMut>(&self, buffer: &mut B) -> WebResult<usize> {
+        if self.length > MAX_MAX_FRAME_SIZE {
+            return Err(Http2Error::into(Http2Error::BadFrameSize));
+        }
         let mut size = 0;
-        size += encode_u24(buffer, self.length);
+        size += buffer.put_u24(self.length);
         size += buffer.put_u8(self.kind.encode());
         size += buffer.put_u8(self.flag.bits());
         size += self.id.encode(buffer)?;
@@ -275,6 +404,136 @@ impl FrameHeader {
     }
 }
 
+impl fmt::Display for FrameHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &self.kind, self.id, self.flag.bits())?;
+        write!(f, " len={}", self.length)
+    }
+}
+
+/// 帧的简要信息, 用于日志输出, HEADERS只输出头的数量
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::{Frame, Ping, Reason, Reset, StreamIdentifier, WindowUpdate};
+/// use webparse::Binary;
+///
+/// let frame: Frame<Binary> = Reset::new(StreamIdentifier(3), Reason::CANCEL).into();
+/// assert_eq!(frame.to_string(), "RST_STREAM sid=3 flags=0 error_code=CANCEL");
+/// let frame: Frame<Binary> = WindowUpdate::new(StreamIdentifier(0), 1024).into();
+/// assert_eq!(frame.to_string(), "WINDOW_UPDATE sid=0 flags=0 increment=1024");
+/// let frame: Frame<Binary> = Ping::pong([0, 0, 0, 0, 0, 0, 0, 1]).into();
+/// assert_eq!(frame.to_string(), "PING sid=0 flags=ACK opaque=0x0000000000000001");
+/// ```
+impl<T: Buf> fmt::Display for Frame<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frame::Data(v) => fmt::Display::fmt(v, f),
+            Frame::Headers(v) => fmt::Display::fmt(v, f),
+            Frame::Priority(v) => fmt::Display::fmt(v, f),
+            Frame::PushPromise(v) => fmt::Display::fmt(v, f),
+            Frame::Settings(v) => fmt::Display::fmt(v, f),
+            Frame::Ping(v) => fmt::Display::fmt(v, f),
+            Frame::GoAway(v) => fmt::Display::fmt(v, f),
+            Frame::WindowUpdate(v) => fmt::Display::fmt(v, f),
+            Frame::Reset(v) => fmt::Display::fmt(v, f),
+        }
+    }
+}
+
+/// 各帧Display的公共头部, 如`HEADERS sid=1 flags=END_HEADERS|END_STREAM`
+pub(super) fn write_head<W: Write>(
+    w: &mut W,
+    kind: &Kind,
+    id: StreamIdentifier,
+    bits: u8,
+) -> fmt::Result {
+    write!(w, "{} sid={} flags={}", kind, id, Flag::describe(kind, bits))
+}
+
+/// 逐帧输出二进制数据的摘要, 每帧一行, 用于日志及调试
+///
+/// 只解析帧头及固定长度的负载, 不做HPACK解码, 也不校验帧的合法性,
+/// 数据不完整时在最后一行注明截断
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::inspect;
+///
+/// let data = b"\x00\x00\x00\x04\x01\x00\x00\x00\x00\
+///     \x00\x00\x04\x08\x00\x00\x00\x00\x01\x00\x00\x03\xe8\
+///     \x00\x00\x02\x01\x05\x00\x00\x00\x01\x82\x84\
+///     \x00\x00\x05\x00\x01\x00\x00\x00\x01ab";
+/// assert_eq!(inspect(data), "SETTINGS sid=0 flags=ACK len=0
+/// WINDOW_UPDATE sid=1 flags=0 len=4 increment=1000
+/// HEADERS sid=1 flags=END_HEADERS|END_STREAM len=2
+/// DATA sid=1 flags=END_STREAM len=5 truncated=2");
+/// ```
+pub fn inspect(buf: &[u8]) -> String {
+    let mut out = String::new();
+    let mut rest = buf;
+    while !rest.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        if rest.len() < FRAME_HEADER_BYTES {
+            let _ = write!(out, "truncated header len={}", rest.len());
+            break;
+        }
+        let length = u32::from_be_bytes([0, rest[0], rest[1], rest[2]]) as usize;
+        let kind = Kind::new(rest[3]);
+        let id = StreamIdentifier(
+            u32::from_be_bytes([rest[5], rest[6], rest[7], rest[8]]) & super::MASK_U31,
+        );
+        let _ = write_head(&mut out, &kind, id, rest[4]);
+        if let Kind::Unregistered(byte) = kind {
+            let _ = write!(out, " type={:#x}", byte);
+        }
+        let _ = write!(out, " len={}", length);
+        rest = &rest[FRAME_HEADER_BYTES..];
+        if rest.len() < length {
+            let _ = write!(out, " truncated={}", rest.len());
+            break;
+        }
+        let (payload, next) = rest.split_at(length);
+        let _ = inspect_payload(&mut out, &kind, payload);
+        rest = next;
+    }
+    out
+}
+
+fn inspect_payload(out: &mut String, kind: &Kind, payload: &[u8]) -> fmt::Result {
+    let u32_at = |i: usize| u32::from_be_bytes([payload[i], payload[i + 1], payload[i + 2], payload[i + 3]]);
+    match (kind, payload.len()) {
+        (Kind::Priority, 5) => write!(
+            out,
+            " dep={} weight={} exclusive={}",
+            u32_at(0) & super::MASK_U31,
+            payload[4],
+            payload[0] & 0x80 != 0
+        ),
+        (Kind::Reset, 4) => write!(out, " error_code={}", ErrorCode::from(u32_at(0))),
+        (Kind::Settings, n) if n > 0 && n % 6 == 0 => {
+            for item in payload.chunks(6) {
+                let id = u16::from_be_bytes([item[0], item[1]]);
+                let value = u32::from_be_bytes([item[2], item[3], item[4], item[5]]);
+                write!(out, " {:#x}={}", id, value)?;
+            }
+            Ok(())
+        }
+        (Kind::Ping, 8) => write!(out, " opaque={:#018x}", u64::from_be_bytes(payload.try_into().unwrap())),
+        (Kind::GoAway, n) if n >= 8 => write!(
+            out,
+            " last_stream_id={} error_code={}",
+            u32_at(0) & super::MASK_U31,
+            ErrorCode::from(u32_at(4))
+        ),
+        (Kind::WindowUpdate, 4) => write!(out, " increment={}", u32_at(0) & super::MASK_U31),
+        _ => Ok(()),
+    }
+}
 
 // impl<T: Buf> Debug for Frame<T> {
 //     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -14,7 +14,7 @@ use std::fmt;
 
 use crate::{Binary, WebResult, Http2Error, Buf, BufMut};
 
-use super::{StreamIdentifier, Reason, frame, Kind, FrameHeader, Flag};
+use super::{StreamIdentifier, Reason, ErrorCode, frame, Kind, FrameHeader, Flag, Ping};
 
 
 
@@ -26,11 +26,25 @@ pub struct GoAway {
 }
 
 impl GoAway {
-    pub fn new(last_stream_id: StreamIdentifier, reason: Reason) -> Self {
+    /// 创建GOAWAY帧, `code`可为`Reason`或`ErrorCode`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{ErrorCode, GoAway, StreamIdentifier};
+    /// let go_away = GoAway::new(StreamIdentifier(7), ErrorCode::EnhanceYourCalm, "too many streams");
+    /// assert_eq!(go_away.error_code(), ErrorCode::EnhanceYourCalm);
+    /// assert_eq!(go_away.debug_data(), b"too many streams");
+    /// assert_eq!(
+    ///     go_away.to_string(),
+    ///     "GOAWAY last_stream_id=7 error_code=ENHANCE_YOUR_CALM debug_data=\"too many streams\""
+    /// );
+    /// ```
+    pub fn new(last_stream_id: StreamIdentifier, code: impl Into<Reason>, debug_data: impl Into<Binary>) -> Self {
         GoAway {
             last_stream_id,
-            error_code: reason,
-            debug_data: Binary::new(),
+            error_code: code.into(),
+            debug_data: debug_data.into(),
         }
     }
 
@@ -50,8 +64,12 @@ impl GoAway {
         self.error_code
     }
 
-    pub fn debug_data(&self) -> &Binary {
-        &self.debug_data
+    pub fn error_code(&self) -> ErrorCode {
+        ErrorCode::from(self.error_code)
+    }
+
+    pub fn debug_data(&self) -> &[u8] {
+        self.debug_data.chunk()
     }
 
     pub fn parse<B: Buf>(payload: &mut B) -> WebResult<GoAway> {
@@ -107,3 +125,127 @@ impl fmt::Debug for GoAway {
         builder.finish()
     }
 }
+
+impl fmt::Display for GoAway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GOAWAY last_stream_id={} error_code={}", self.last_stream_id.0, self.error_code())?;
+        if !self.debug_data.is_empty() {
+            write!(f, " debug_data={:?}", String::from_utf8_lossy(self.debug_data.chunk()))?;
+        }
+        Ok(())
+    }
+}
+
+/// 优雅关闭时第一个GOAWAY中的流id, 即2^31-1
+const DRAIN_STREAM_ID: StreamIdentifier = StreamIdentifier((1 << 31) - 1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShutdownState {
+    Open,
+    Draining,
+    Closed,
+}
+
+/// 按RFC 7540 §6.8推荐的两步方式生成GOAWAY帧
+///
+/// 先发送last_stream_id为2^31-1的GOAWAY通知对端停止创建新流,
+/// 等待至少一个往返(可发送`Ping::SHUTDOWN`并等待其ACK)后,
+/// 再发送带有实际处理过的最大流id的GOAWAY
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::{ErrorCode, GoAwayPlanner, Ping, StreamIdentifier};
+///
+/// let mut planner = GoAwayPlanner::new();
+/// planner.record_stream(StreamIdentifier(5));
+/// let first = planner.start_graceful_shutdown().unwrap();
+/// assert_eq!(first.last_stream_id(), StreamIdentifier((1 << 31) - 1));
+/// assert_eq!(first.error_code(), ErrorCode::NoError);
+/// assert!(planner.start_graceful_shutdown().is_none());
+///
+/// // 第一个GOAWAY到达前对端创建的流依然需要处理
+/// assert!(planner.accepts_stream(StreamIdentifier(7)));
+/// planner.record_stream(StreamIdentifier(7));
+///
+/// let last = planner.recv_ping_ack(&Ping::ack_of(&Ping::new(Ping::SHUTDOWN))).unwrap();
+/// assert_eq!(last.last_stream_id(), StreamIdentifier(7));
+/// assert!(planner.is_closed());
+/// assert!(!planner.accepts_stream(StreamIdentifier(9)));
+/// ```
+#[derive(Debug)]
+pub struct GoAwayPlanner {
+    state: ShutdownState,
+    last_processed: StreamIdentifier,
+}
+
+impl Default for GoAwayPlanner {
+    fn default() -> Self {
+        GoAwayPlanner {
+            state: ShutdownState::Open,
+            last_processed: StreamIdentifier::zero(),
+        }
+    }
+}
+
+impl GoAwayPlanner {
+    pub fn new() -> GoAwayPlanner {
+        GoAwayPlanner::default()
+    }
+
+    /// 记录开始处理的对端流, 最终的GOAWAY将带上其中最大的id
+    pub fn record_stream(&mut self, id: StreamIdentifier) {
+        if self.state != ShutdownState::Closed && id > self.last_processed {
+            self.last_processed = id;
+        }
+    }
+
+    pub fn last_processed(&self) -> StreamIdentifier {
+        self.last_processed
+    }
+
+    /// 是否已发送第一个GOAWAY, 等待发送最终的GOAWAY
+    pub fn is_draining(&self) -> bool {
+        self.state == ShutdownState::Draining
+    }
+
+    /// 是否已发送最终的GOAWAY
+    pub fn is_closed(&self) -> bool {
+        self.state == ShutdownState::Closed
+    }
+
+    /// 是否还应处理对端新建的流, 发送最终的GOAWAY后只处理不超过last_stream_id的流
+    pub fn accepts_stream(&self, id: StreamIdentifier) -> bool {
+        match self.state {
+            ShutdownState::Closed => id <= self.last_processed,
+            _ => true,
+        }
+    }
+
+    /// 开始优雅关闭, 返回第一个GOAWAY, 已开始关闭时返回None
+    pub fn start_graceful_shutdown(&mut self) -> Option<GoAway> {
+        if self.state != ShutdownState::Open {
+            return None;
+        }
+        self.state = ShutdownState::Draining;
+        Some(GoAway::new(DRAIN_STREAM_ID, ErrorCode::NoError, Binary::new()))
+    }
+
+    /// 由调用方在等待一段时间后调用, 返回带有实际最大流id的最终GOAWAY,
+    /// 未开始优雅关闭时直接关闭, 已关闭时返回None
+    pub fn finish(&mut self) -> Option<GoAway> {
+        if self.state == ShutdownState::Closed {
+            return None;
+        }
+        self.state = ShutdownState::Closed;
+        Some(GoAway::new(self.last_processed, ErrorCode::NoError, Binary::new()))
+    }
+
+    /// 收到`Ping::SHUTDOWN`的ACK时说明对端已收到第一个GOAWAY, 返回最终的GOAWAY
+    pub fn recv_ping_ack(&mut self, ping: &Ping) -> Option<GoAway> {
+        if !self.is_draining() || !ping.is_ack() || ping.payload() != &Ping::SHUTDOWN {
+            return None;
+        }
+        self.finish()
+    }
+}
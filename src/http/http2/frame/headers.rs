@@ -10,7 +10,7 @@
 // -----
 // Created Date: 2023/09/01 04:34:25
 
-use crate::{http::{request, response}, http2::DecoderError, BufMut, HeaderName, Request, Serialize};
+use crate::{http::{request, response}, BufMut, HeaderName, Request, Response, Serialize};
 use std::fmt;
 
 use crate::{
@@ -18,10 +18,10 @@ use crate::{
         http2::{encoder::Encoder, Decoder},
         StatusCode,
     },
-    BinaryMut, Buf, HeaderMap, Http2Error, Method, Scheme, Url, WebResult,
+    BinaryMut, Buf, HeaderMap, HeaderValue, Http2Error, Method, Scheme, Url, WebResult,
 };
 
-use super::{frame::Frame, Flag, FrameHeader, Kind, StreamDependency, StreamIdentifier};
+use super::{frame::{write_head, Frame}, ErrorCode, Flag, FrameHeader, FrameViolation, Kind, StreamDependency, StreamIdentifier};
 
 ///
 /// This could be either a request or a response.
@@ -63,6 +63,8 @@ pub struct Parts {
     pub scheme: Option<Scheme>,
     pub authority: Option<String>,
     pub path: Option<String>,
+    /// RFC 8441 扩展CONNECT中的`:protocol`, 如`websocket`
+    pub protocol: Option<String>,
 
     // Response
     pub status: Option<StatusCode>,
@@ -133,39 +135,8 @@ impl Headers {
         }
 
         let len = buffer.remaining();
-        let headers = decoder.decode(&mut buffer)?;
-        let mut header_size = 0;
-        for h in headers {
-            header_size += h.0.as_bytes().len() + h.1.as_bytes().len() + 32;
-            if header_size > max_header_list_size {
-                return Err(Http2Error::Decoder(DecoderError::HeaderIndexOutOfBounds).into());
-            }
-            if h.0.is_spec() {
-                let value: String = (&h.1).try_into()?;
-                match h.0.name() {
-                    ":authority" => {
-                        self.header_block.parts.authority = Some(value);
-                    }
-                    ":method" => {
-                        self.header_block.parts.method = Some(Method::try_from(&*value)?);
-                    }
-                    ":path" => {
-                        self.header_block.parts.path = Some(value);
-                    }
-                    ":scheme" => {
-                        self.header_block.parts.scheme = Some(Scheme::try_from(&*value)?);
-                    }
-                    ":status" => {
-                        self.header_block.parts.status = Some(StatusCode::try_from(&*value)?);
-                    }
-                    _ => {
-                        self.header_block.fields.insert(h.0, h.1);
-                    }
-                }
-            } else {
-                self.header_block.fields.insert(h.0, h.1);
-            }
-        }
+        self.header_block
+            .parse(&mut buffer, decoder, max_header_list_size, self.stream_id)?;
         Ok(len - buffer.remaining())
     }
 
@@ -237,6 +208,51 @@ impl Headers {
         &self.header_block.parts.status
     }
 
+    pub fn set_protocol(&mut self, protocol: String) {
+        self.header_block.parts.protocol = Some(protocol);
+    }
+
+    pub fn protocol(&self) -> Option<&str> {
+        self.header_block.parts.protocol.as_deref()
+    }
+
+    /// 是否为扩展的CONNECT请求, 即带有`:protocol`的CONNECT
+    pub fn is_extended_connect(&self) -> bool {
+        self.header_block.parts.method == Some(Method::Connect)
+            && self.header_block.parts.protocol.is_some()
+    }
+
+    /// 构建扩展的CONNECT请求头, 见`Parts::extended_connect`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Headers, StreamIdentifier};
+    /// use webparse::http2::{encoder::Encoder, Decoder};
+    /// use webparse::{BinaryMut, Buf, HeaderMap, Url};
+    ///
+    /// let url = Url::try_from("https://example.com/chat").unwrap();
+    /// let headers = Headers::extended_connect(StreamIdentifier(1), "websocket", url, HeaderMap::new());
+    /// let mut dst = BinaryMut::new();
+    /// headers.encode(&mut Encoder::new(), &mut dst).unwrap();
+    ///
+    /// dst.advance(9);
+    /// let decoded = Decoder::new().decode(&mut dst).unwrap();
+    /// let names: Vec<&str> = decoded.iter().map(|h| h.0.name()).collect();
+    /// assert_eq!(names, [":method", ":authority", ":scheme", ":path", ":protocol"]);
+    /// ```
+    pub fn extended_connect(
+        stream_id: StreamIdentifier,
+        protocol: &str,
+        uri: Url,
+        fields: HeaderMap,
+    ) -> Self {
+        let parts = Parts::extended_connect(protocol, uri);
+        let mut headers = Headers::trailers(stream_id, parts, fields);
+        headers.set_end_headers();
+        headers
+    }
+
     pub fn is_over_size(&self) -> bool {
         self.header_block.is_over_size
     }
@@ -274,6 +290,9 @@ impl Headers {
             builder = builder.method(m);
         }
         builder = builder.headers(header);
+        if let Some(protocol) = parts.protocol {
+            builder = builder.header(":protocol", protocol);
+        }
         Ok(builder)
     }
 
@@ -289,13 +308,131 @@ impl Headers {
         Ok(builder)
     }
 
+    /// 由请求构建HEADERS帧, 与`into_request`相对, Host转为`:authority`,
+    /// 并去除连接相关的头, end_stream为true时表示请求不带body
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Headers, StreamIdentifier};
+    /// use webparse::Request;
+    ///
+    /// let req = Request::builder()
+    ///     .method("GET")
+    ///     .url("/index")
+    ///     .header("Host", "example.com:8080")
+    ///     .header("Connection", "keep-alive")
+    ///     .header("Accept", "*/*")
+    ///     .body(())
+    ///     .unwrap();
+    /// let mut headers = Headers::from_request(StreamIdentifier(1), &req, true).unwrap();
+    /// assert!(headers.is_end_stream());
+    /// assert_eq!(headers.authority().as_deref(), Some("example.com:8080"));
+    /// assert_eq!(headers.path().as_deref(), Some("/index"));
+    /// assert!(!headers.fields().contains(&"connection"));
+    /// assert!(!headers.fields().contains(&"host"));
+    /// assert!(headers.fields().contains(&"accept"));
+    /// ```
+    pub fn from_request<T: Serialize>(
+        stream_id: StreamIdentifier,
+        req: &Request<T>,
+        end_stream: bool,
+    ) -> WebResult<Headers> {
+        let parts = request_parts(req)?;
+        Ok(Self::build(stream_id, parts, strip_fields(req.headers()), end_stream))
+    }
+
+    /// 由响应构建HEADERS帧, 与`into_response`相对
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Headers, StreamIdentifier};
+    /// use webparse::{Response, StatusCode};
+    ///
+    /// let res = Response::builder()
+    ///     .status(404)
+    ///     .header("Transfer-Encoding", "chunked")
+    ///     .header("Content-Type", "text/plain")
+    ///     .body(())
+    ///     .unwrap();
+    /// let mut headers = Headers::from_response(StreamIdentifier(1), &res, false).unwrap();
+    /// assert!(!headers.is_end_stream());
+    /// assert_eq!(headers.status(), &Some(StatusCode::NOT_FOUND));
+    /// assert!(!headers.fields().contains(&"transfer-encoding"));
+    /// ```
+    pub fn from_response<T: Serialize>(
+        stream_id: StreamIdentifier,
+        res: &Response<T>,
+        end_stream: bool,
+    ) -> WebResult<Headers> {
+        let parts = Parts::response(res.status());
+        Ok(Self::build(stream_id, parts, strip_fields(res.headers()), end_stream))
+    }
+
+    /// 构建trailer头块, 在最后的DATA帧之后发送并带有END_STREAM,
+    /// 此时DATA帧不能再设置END_STREAM, trailer中不允许出现伪头部
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Headers, StreamIdentifier};
+    /// use webparse::HeaderMap;
+    ///
+    /// let mut fields = HeaderMap::new();
+    /// fields.insert("grpc-status", "0");
+    /// let headers = Headers::from_trailers(StreamIdentifier(1), fields).unwrap();
+    /// assert!(headers.is_trailers());
+    /// assert!(headers.into_trailers().unwrap().contains(&"grpc-status"));
+    ///
+    /// let mut fields = HeaderMap::new();
+    /// fields.insert(":status", "200");
+    /// assert!(Headers::from_trailers(StreamIdentifier(1), fields).is_err());
+    /// ```
+    pub fn from_trailers(stream_id: StreamIdentifier, fields: HeaderMap) -> WebResult<Headers> {
+        if fields.iter().any(|(name, _)| name.is_spec()) {
+            return Err(Http2Error::MalformedMessage.into());
+        }
+        Ok(Self::build(stream_id, Parts::default(), fields, true))
+    }
+
+    /// 是否为trailer头块, 即带有END_STREAM且不含伪头部
+    pub fn is_trailers(&self) -> bool {
+        self.is_end_stream() && self.header_block.parts == Parts::default()
+    }
+
+    /// 将收到的trailer头块转为HeaderMap, 可通过`Request::set_trailers`附加到请求上,
+    /// 含有伪头部或未带END_STREAM时返回流级错误
+    pub fn into_trailers(self) -> WebResult<HeaderMap> {
+        if self.header_block.parts != Parts::default() {
+            return Err(self.malformed("pseudo-header in trailers"));
+        }
+        if !self.is_end_stream() {
+            return Err(self.malformed("trailers without END_STREAM"));
+        }
+        Ok(self.header_block.fields)
+    }
+
+    fn malformed(&self, reason: &'static str) -> crate::WebError {
+        Http2Error::Frame(FrameViolation::Stream(self.stream_id, ErrorCode::ProtocolError, reason)).into()
+    }
+
+    fn build(stream_id: StreamIdentifier, parts: Parts, fields: HeaderMap, end_stream: bool) -> Headers {
+        let mut headers = Headers::trailers(stream_id, parts, fields);
+        headers.set_end_headers();
+        if end_stream {
+            headers.set_end_stream();
+        }
+        headers
+    }
+
     pub fn encode<B: Buf + BufMut>(
         mut self,
         encoder: &mut Encoder,
         dst: &mut B,
     ) -> WebResult<usize> {
-        let size = self.header_block.encode(encoder, dst, self.flags, self.stream_id)?;
-        log::trace!("HTTP2: 编码头信息; len={}", size);
+        let size = self.header_block.encode(encoder, dst, Kind::Headers, self.flags, self.stream_id, None)?;
+        crate::web_trace!("HTTP2: 编码头信息; len={}", size);
         Ok(size)
     }
 }
@@ -322,6 +459,16 @@ impl fmt::Debug for Headers {
     }
 }
 
+impl fmt::Display for Headers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::Headers, self.stream_id, self.flags.bits())?;
+        if let Some(ref dep) = self.stream_dep {
+            write!(f, " dep={} weight={}", dep.dependency_id(), dep.weight())?;
+        }
+        write!(f, " fields={}", self.header_block.fields.len())
+    }
+}
+
 // ===== impl PushPromise =====
 
 impl PushPromise {
@@ -406,19 +553,53 @@ impl PushPromise {
         (self.header_block.parts, self.header_block.fields)
     }
 
+    /// 根据请求构建推送帧, 填充:method/:scheme/:authority/:path,
+    /// 编码时按`Encoder::max_frame_size`拆分为PUSH_PROMISE及CONTINUATION帧
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::{frame::{PushPromise, StreamIdentifier}, encoder::Encoder};
+    /// use webparse::{BinaryMut, Request};
+    ///
+    /// let req = Request::builder()
+    ///     .url("https://www.example.com/style.css")
+    ///     .body(())
+    ///     .unwrap();
+    /// let push = PushPromise::from_request(StreamIdentifier(1), StreamIdentifier(2), &req).unwrap();
+    /// assert_eq!(push.promised_id(), StreamIdentifier(2));
+    /// let mut buffer = BinaryMut::new();
+    /// assert!(push.encode(&mut Encoder::new(), &mut buffer).unwrap() > 13);
+    /// ```
+    pub fn from_request(
+        stream_id: StreamIdentifier,
+        promised_id: StreamIdentifier,
+        req: &Request<()>,
+    ) -> WebResult<PushPromise> {
+        Self::validate_request(req)?;
+        if promised_id.is_zero() || !promised_id.0.is_multiple_of(2) {
+            return Err(Http2Error::InvalidStreamId.into());
+        }
+        let parts = request_parts(req)?;
+        let mut head = FrameHeader::new(Kind::PushPromise, Flag::end_headers(), stream_id);
+        head.length = 0;
+        let mut push = PushPromise::new(head, promised_id, strip_fields(req.headers()));
+        push.header_block.parts = parts;
+        Ok(push)
+    }
+
     pub fn validate_request(req: &Request<()>) -> WebResult<()> {
-        // use PushPromiseHeaderHttp2Error::*;
         // The spec has some requirements for promised request headers
         // [https://httpwg.org/specs/rfc7540.html#PushRequests]
 
-        if req.get_body_len() == 0 {
-            return Err(Http2Error::PayloadLengthTooShort.into());
+        // 推送的请求不能携带body
+        if req.get_body_len() != 0 {
+            return Err(Http2Error::MalformedMessage.into());
         }
         // "The server MUST include a method in the :method parts-header field
         // that is safe and cacheable"
         if !Self::safe_and_cacheable(req.method()) {
-            // return Err(NotSafeAndCacheable);
-            return Err(Http2Error::PayloadLengthTooShort.into());
+            return Err(Http2Error::MalformedMessage.into());
         }
 
         Ok(())
@@ -441,13 +622,16 @@ impl PushPromise {
     pub fn parse<B: Buf>(
         head: FrameHeader,
         mut src: B,
-        _decoder: &mut Decoder,
-        _max_header_list_size: usize,
+        decoder: &mut Decoder,
+        max_header_list_size: usize,
     ) -> WebResult<Self> {
+        if src.remaining() < 4 {
+            return Err(Http2Error::PayloadLengthTooShort.into());
+        }
         let promised_id = StreamIdentifier::parse(&mut src);
-        let push = PushPromise::new(head, promised_id, HeaderMap::new());
-        // push.header_block
-        //     .parse(&mut src, max_header_list_size, decoder)?;
+        let mut push = PushPromise::new(head, promised_id, HeaderMap::new());
+        push.header_block
+            .parse(&mut src, decoder, max_header_list_size, promised_id)?;
         Ok(push)
     }
 
@@ -460,38 +644,15 @@ impl PushPromise {
         encoder: &mut Encoder,
         dst: &mut B,
     ) -> WebResult<usize> {
-        let mut binary = BinaryMut::new();
-        self.header_block
-            .parts
-            .encode_header(&mut self.header_block.fields);
-
-        if let Some(v) = self.header_block.fields.remove(&":method") {
-            let _ =
-                encoder.encode_header_into((&HeaderName::from_static(":method"), &v), &mut binary);
-        }
-        if let Some(v) = self.header_block.fields.remove(&":authority") {
-            let _ = encoder
-                .encode_header_into((&HeaderName::from_static(":authority"), &v), &mut binary);
-        }
-        if let Some(v) = self.header_block.fields.remove(&":scheme") {
-            let _ =
-                encoder.encode_header_into((&HeaderName::from_static(":scheme"), &v), &mut binary);
-        }
-        if let Some(v) = self.header_block.fields.remove(&":path") {
-            let _ =
-                encoder.encode_header_into((&HeaderName::from_static(":path"), &v), &mut binary);
-        }
-
-        let mut size = 0;
-        let mut head = FrameHeader::new(Kind::PushPromise, self.flags.into(), self.stream_id);
-        head.flag.set_end_headers();
-        head.length = binary.remaining() as u32 + 4;
-        size += head.encode(dst).unwrap();
-        size += self.promised_id.encode(dst).unwrap();
-        size += binary.serialize(dst).unwrap();
-
-        size += self.header_block.encode(encoder, dst, self.flags, self.promised_id)?;
-        log::trace!("HTTP2: 编码推送信息; len={}", size);
+        let size = self.header_block.encode(
+            encoder,
+            dst,
+            Kind::PushPromise,
+            self.flags,
+            self.stream_id,
+            Some(self.promised_id),
+        )?;
+        crate::web_trace!("HTTP2: 编码推送信息; len={}", size);
         Ok(size)
     }
 
@@ -503,6 +664,13 @@ impl<T> From<PushPromise> for Frame<T> {
     }
 }
 
+impl fmt::Display for PushPromise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::PushPromise, self.stream_id, self.flags.bits())?;
+        write!(f, " promised_id={} fields={}", self.promised_id, self.header_block.fields.len())
+    }
+}
+
 impl fmt::Debug for PushPromise {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("PushPromise")
@@ -514,6 +682,59 @@ impl fmt::Debug for PushPromise {
     }
 }
 
+/// 由请求生成伪头部, 普通的CONNECT请求只含`:method`及`:authority`
+fn request_parts<T: Serialize>(req: &Request<T>) -> WebResult<Parts> {
+    let url = req.url();
+    let mut parts = Parts::request(req.method().clone(), url.clone(), None);
+    if url.domain.is_some() {
+        parts.set_authority(url.get_authority());
+    } else {
+        match req.get_host() {
+            Some(host) => parts.set_authority(host),
+            None => return Err(Http2Error::InvalidRequesetUrl.into()),
+        }
+    }
+    if let Some(protocol) = req.headers().get_str_value(&":protocol") {
+        parts.set_protocol(protocol);
+    }
+    if req.method() == &Method::Connect && parts.protocol.is_none() {
+        parts.scheme = None;
+        parts.path = None;
+        return Ok(parts);
+    }
+    if parts.scheme.is_none() {
+        parts.set_scheme(Scheme::Https);
+    }
+    if parts.path.is_none() {
+        parts.path = Some(req.path().clone());
+    }
+    Ok(parts)
+}
+
+/// 复制普通的头, 去除伪头部, Host及连接相关的头
+fn strip_fields(headers: &HeaderMap) -> HeaderMap {
+    let mut fields = HeaderMap::new();
+    for (name, value) in headers.iter() {
+        if name.is_spec() || name == &HeaderName::HOST || is_connection_header(name, value) {
+            continue;
+        }
+        fields.push(name.clone(), value.clone());
+    }
+    fields
+}
+
+/// 连接相关的头不能出现在http2中, TE仅允许取值`trailers`
+fn is_connection_header(name: &HeaderName, value: &HeaderValue) -> bool {
+    if name == &HeaderName::TE {
+        return !value.as_bytes().eq_ignore_ascii_case(b"trailers");
+    }
+    name == &HeaderName::CONNECTION
+        || name == &"keep-alive"
+        || name == &"proxy-connection"
+        || name == &HeaderName::TRANSFER_ENCODING
+        || name == &HeaderName::UPGRADE
+}
+
 // ===== impl parts =====
 
 impl Parts {
@@ -525,6 +746,7 @@ impl Parts {
             scheme: protocol,
             authority: None,
             path: Some(path).filter(|p| !p.is_empty()),
+            protocol: None,
             status: None,
         };
 
@@ -545,10 +767,36 @@ impl Parts {
             scheme: None,
             authority: None,
             path: None,
+            protocol: None,
             status: Some(status),
         }
     }
 
+    /// 扩展的CONNECT请求(RFC 8441), 如基于http2的websocket,
+    /// 需对端在SETTINGS中开启`SETTINGS_ENABLE_CONNECT_PROTOCOL`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::Parts;
+    /// use webparse::{Method, Url};
+    ///
+    /// let url = Url::try_from("https://example.com/chat").unwrap();
+    /// let parts = Parts::extended_connect("websocket", url);
+    /// assert_eq!(parts.method, Some(Method::Connect));
+    /// assert_eq!(parts.protocol.as_deref(), Some("websocket"));
+    /// assert_eq!(parts.path.as_deref(), Some("/chat"));
+    /// ```
+    pub fn extended_connect(protocol: &str, uri: Url) -> Self {
+        let mut parts = Parts::request(Method::Connect, uri, Some(Scheme::Https));
+        parts.protocol = Some(protocol.to_string());
+        parts
+    }
+
+    pub fn set_protocol(&mut self, protocol: String) {
+        self.protocol = Some(protocol);
+    }
+
     pub fn set_status(&mut self, value: StatusCode) {
         self.status = Some(value);
     }
@@ -579,6 +827,9 @@ impl Parts {
         if let Some(path) = self.path.take() {
             header.insert(":path", path);
         }
+        if let Some(protocol) = self.protocol.take() {
+            header.insert(":protocol", protocol);
+        }
         if let Some(status) = self.status.take() {
             header.insert(":status", status.as_str());
         }
@@ -596,54 +847,130 @@ impl Parts {
 }
 
 impl HeaderBlock {
+    /// 解析头块, 未开启宽松模式时按RFC 7540 8.1.2校验, 违反时返回流级错误
+    fn parse<B: Buf>(
+        &mut self,
+        buffer: &mut B,
+        decoder: &mut Decoder,
+        max_header_list_size: usize,
+        stream_id: StreamIdentifier,
+    ) -> WebResult<()> {
+        let strict = !decoder.is_lenient_headers();
+        let malformed = |reason: &'static str| -> WebResult<()> {
+            Err(Http2Error::Frame(FrameViolation::Stream(
+                stream_id,
+                ErrorCode::ProtocolError,
+                reason,
+            ))
+            .into())
+        };
+        let headers = decoder.decode_with_limit(buffer, max_header_list_size)?;
+        let mut seen_regular = false;
+        for h in headers {
+            if h.0.is_spec() {
+                if strict && seen_regular {
+                    return malformed("pseudo-header after regular header");
+                }
+                let value: String = (&h.1).try_into()?;
+                let duplicated = match h.0.name() {
+                    ":authority" => self.parts.authority.replace(value).is_some(),
+                    ":method" => self
+                        .parts
+                        .method
+                        .replace(Method::try_from(&*value)?)
+                        .is_some(),
+                    ":path" => self.parts.path.replace(value).is_some(),
+                    ":scheme" => self
+                        .parts
+                        .scheme
+                        .replace(Scheme::try_from(&*value)?)
+                        .is_some(),
+                    ":protocol" => self.parts.protocol.replace(value).is_some(),
+                    ":status" => self
+                        .parts
+                        .status
+                        .replace(StatusCode::try_from(&*value)?)
+                        .is_some(),
+                    _ => {
+                        if strict {
+                            return malformed("unknown pseudo-header");
+                        }
+                        self.fields.insert(h.0, h.1);
+                        false
+                    }
+                };
+                if strict && duplicated {
+                    return malformed("duplicated pseudo-header");
+                }
+            } else {
+                seen_regular = true;
+                if strict && is_connection_header(&h.0, &h.1) {
+                    return malformed("connection-specific header");
+                }
+                self.fields.insert(h.0, h.1);
+            }
+        }
+        if strict && self.parts.status.is_some() && self.parts.method.is_some() {
+            return malformed("request and response pseudo-headers mixed");
+        }
+        Ok(())
+    }
+
+    /// 编码头块, 伪头部在前, 超出max_frame_size时拆分为CONTINUATION帧,
+    /// END_HEADERS只设置在最后一帧上, 单个头同样可以跨越多个帧.
+    /// 有头超出`Encoder::max_header_size`时在修改动态表前返回错误
     pub fn encode<B: Buf + BufMut>(
         &mut self,
         encoder: &mut Encoder,
         dst: &mut B,
+        kind: Kind,
         mut flags: Flag,
         stream_id: StreamIdentifier,
+        promised_id: Option<StreamIdentifier>,
     ) -> WebResult<usize> {
-        let mut result = vec![];
+        self.parts.encode_header(&mut self.fields);
+        for (name, value) in self.fields.iter() {
+            encoder.check_header_size(name, value)?;
+        }
         let mut binary = BinaryMut::new();
-        
-        for value in self.fields.iter() {
-            if value.0.bytes_len() + value.1.bytes_len() + binary.remaining()
-                > encoder.max_frame_size as usize
-            {
-                result.push(binary);
-                binary = BinaryMut::new();
-            }
-            let _ = encoder.encode_header_into((&value.0, &value.1), &mut binary);
+        for value in self.fields.iter().filter(|v| v.0.is_spec()) {
+            encoder.encode_header_into((&value.0, &value.1), &mut binary)?;
+        }
+        for value in self.fields.iter().filter(|v| !v.0.is_spec()) {
+            encoder.encode_header_into((&value.0, &value.1), &mut binary)?;
         }
 
-        result.push(binary);
+        let max_frame_size = encoder.max_frame_size;
+        let prefix = if promised_id.is_some() { 4 } else { 0 };
+        let block = binary.chunk();
+        let first_len = core::cmp::min(block.len(), max_frame_size.saturating_sub(prefix));
+        let (first, mut rest) = block.split_at(first_len);
+
         let mut size = 0;
-        if result.len() == 1 {
+        flags.unset_end_headers();
+        if rest.is_empty() {
             flags.set_end_headers();
-            let mut head = FrameHeader::new(Kind::Headers, flags, stream_id);
-            head.length = result[0].remaining() as u32;
-            size += head.encode(dst).unwrap();
-            size += result[0].serialize(dst).unwrap();
-        } else {
-            let mut head = FrameHeader::new(Kind::Headers, Flag::zero(), stream_id);
-            head.length = result[0].remaining() as u32;
-            size += head.encode(dst).unwrap();
-            size += result[0].serialize(dst).unwrap();
-
-            for idx in 1..result.len() {
-                let mut head = FrameHeader::new(Kind::Continuation, Flag::zero(), stream_id);
-                if idx == result.len() - 1 {
-                    flags.set_end_headers();
-                    head.flag = flags;
-                }
-                head.length = result[idx].remaining() as u32;
-                size += head.encode(dst).unwrap();
-                size += result[idx].serialize(dst).unwrap();
-            }
+        }
+        let mut head = FrameHeader::new(kind, flags, stream_id);
+        head.length = (first.len() + prefix) as u32;
+        size += head.encode(dst)?;
+        if let Some(promised_id) = promised_id {
+            size += promised_id.encode(dst)?;
+        }
+        size += dst.put_slice(first);
+
+        while !rest.is_empty() {
+            let len = core::cmp::min(rest.len(), max_frame_size);
+            let (now, left) = rest.split_at(len);
+            let flag = if left.is_empty() { Flag::end_headers() } else { Flag::zero() };
+            let mut head = FrameHeader::new(Kind::Continuation, flag, stream_id);
+            head.length = now.len() as u32;
+            size += head.encode(dst)?;
+            size += dst.put_slice(now);
+            rest = left;
         }
         Ok(size)
     }
-
 }
 
 // #[cfg(test)]
@@ -716,3 +1043,81 @@ impl HeaderBlock {
 //         huffman::decode(src, &mut buf).unwrap()
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::http2::{encoder::Encoder, Decoder};
+    use crate::{BinaryMut, Buf, HeaderMap, Request};
+
+    use super::{Flag, FrameHeader, Headers, Kind, PushPromise, StreamIdentifier};
+
+    #[test]
+    fn push_promise_split() {
+        let req = Request::builder()
+            .url("https://www.example.com/static/style.css")
+            .header("accept", "text/css")
+            .header("Connection", "keep-alive")
+            .body(())
+            .unwrap();
+        let push = PushPromise::from_request(StreamIdentifier(1), StreamIdentifier(2), &req).unwrap();
+        let mut encoder = Encoder::new();
+        encoder.max_frame_size = 16;
+        let mut buffer = BinaryMut::new();
+        push.encode(&mut encoder, &mut buffer).unwrap();
+
+        let mut block = vec![];
+        let mut kinds = vec![];
+        while buffer.has_remaining() {
+            let head = FrameHeader::parse(&mut buffer).unwrap().unwrap();
+            assert!(head.length <= 16);
+            let mut len = head.length as usize;
+            if head.kind() == &Kind::PushPromise {
+                assert_eq!(StreamIdentifier::parse(&mut buffer), StreamIdentifier(2));
+                len -= 4;
+            }
+            kinds.push((*head.kind(), head.flag().is_end_headers()));
+            block.extend_from_slice(&buffer.chunk()[..len]);
+            buffer.advance(len);
+        }
+        assert_eq!(kinds[0], (Kind::PushPromise, false));
+        assert_eq!(kinds.last(), Some(&(Kind::Continuation, true)));
+
+        let headers = Decoder::new().decode(&mut &block[..]).unwrap();
+        let names: Vec<&str> = headers.iter().map(|h| h.0.name()).collect();
+        assert_eq!(names[..4], [":method", ":authority", ":scheme", ":path"]);
+        assert_eq!(headers[3].1.as_bytes(), b"/static/style.css");
+        assert!(!names.iter().any(|n| n.eq_ignore_ascii_case("connection")));
+    }
+
+    #[test]
+    fn huge_header_split() {
+        let value = "v".repeat(100);
+        let mut fields = HeaderMap::new();
+        fields.insert("x-huge", value.clone());
+        let headers = Headers::new(FrameHeader::new(Kind::Headers, Flag::end_stream(), StreamIdentifier(1)), fields);
+        let mut encoder = Encoder::new();
+        encoder.max_frame_size = 16;
+        let mut buffer = BinaryMut::new();
+        headers.encode(&mut encoder, &mut buffer).unwrap();
+
+        let mut block = vec![];
+        let mut frames = 0;
+        while buffer.has_remaining() {
+            let head = FrameHeader::parse(&mut buffer).unwrap().unwrap();
+            assert!(head.length <= 16);
+            let len = head.length as usize;
+            block.extend_from_slice(&buffer.chunk()[..len]);
+            buffer.advance(len);
+            frames += 1;
+        }
+        assert!(frames > 1);
+        let decoded = Decoder::new().decode(&mut &block[..]).unwrap();
+        assert_eq!(decoded[0].1.as_bytes(), value.as_bytes());
+
+        let mut fields = HeaderMap::new();
+        fields.insert("x-huge", value);
+        let headers = Headers::new(FrameHeader::new(Kind::Headers, Flag::zero(), StreamIdentifier(3)), fields);
+        encoder.set_max_header_size(Some(64));
+        assert!(headers.encode(&mut encoder, &mut BinaryMut::new()).is_err());
+    }
+}
@@ -10,20 +10,33 @@
 // -----
 // Created Date: 2023/08/21 11:07:39
 
-#[repr(u8)]
+use std::fmt;
+
+/// 帧的类型, 未知的类型保留原始的类型值, 以便原样转发或输出日志
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::Kind;
+///
+/// assert_eq!(Kind::new(0x4), Kind::Settings);
+/// let kind = Kind::new(0xfa);
+/// assert_eq!(kind, Kind::Unregistered(0xfa));
+/// assert_eq!(kind.encode(), 0xfa);
+/// ```
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Kind {
-    Data = 0,
-    Headers = 1,
-    Priority = 2,
-    Reset = 3,
-    Settings = 4,
-    PushPromise = 5,
-    Ping = 6,
-    GoAway = 7,
-    WindowUpdate = 8,
-    Continuation = 9,
-    Unregistered
+    Data,
+    Headers,
+    Priority,
+    Reset,
+    Settings,
+    PushPromise,
+    Ping,
+    GoAway,
+    WindowUpdate,
+    Continuation,
+    Unregistered(u8),
 }
 
 impl Kind {
@@ -39,7 +52,7 @@ impl Kind {
             7 => Kind::GoAway,
             8 => Kind::WindowUpdate,
             9 => Kind::Continuation,
-            _ => Kind::Unregistered
+            _ => Kind::Unregistered(byte),
         }
     }
 
@@ -55,14 +68,37 @@ impl Kind {
             Kind::GoAway => 7,
             Kind::WindowUpdate => 8,
             Kind::Continuation => 9,
-            Kind::Unregistered => 255
+            Kind::Unregistered(byte) => byte,
+        }
+    }
+
+    /// 帧类型在RFC 7540中的名字, 用于日志输出
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Kind::Data => "DATA",
+            Kind::Headers => "HEADERS",
+            Kind::Priority => "PRIORITY",
+            Kind::Reset => "RST_STREAM",
+            Kind::Settings => "SETTINGS",
+            Kind::PushPromise => "PUSH_PROMISE",
+            Kind::Ping => "PING",
+            Kind::GoAway => "GOAWAY",
+            Kind::WindowUpdate => "WINDOW_UPDATE",
+            Kind::Continuation => "CONTINUATION",
+            Kind::Unregistered(_) => "UNKNOWN",
         }
     }
 }
 
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[test]
 fn test_encode() {
-    for n in 0..10 {
-        assert_eq!(Kind::new(n), Kind::new(Kind::new(n).encode()));
+    for n in 0..=255 {
+        assert_eq!(Kind::new(n).encode(), n);
     }
 }
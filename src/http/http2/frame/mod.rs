@@ -11,6 +11,7 @@
 // Created Date: 2023/09/01 04:09:08
 
 mod data;
+mod error_code;
 mod flag;
 mod frame;
 mod go_away;
@@ -21,22 +22,29 @@ mod priority;
 mod reason;
 mod reset;
 mod settings;
+mod validate;
 mod window_update;
 
 use std::{cmp::Ordering, fmt::Display};
 
 pub use data::Data;
+pub use error_code::ErrorCode;
 pub use flag::Flag;
-pub use frame::{Frame, PriorityFrame};
-pub use headers::{Headers, PushPromise};
+pub use frame::{inspect, BinaryFrame, Frame, PriorityFrame};
+pub use headers::{Headers, Parts, PushPromise};
 pub use kind::Kind;
 pub use priority::{Priority, StreamDependency};
 
-pub use self::go_away::GoAway;
-pub use self::ping::Ping;
+pub use self::go_away::{GoAway, GoAwayPlanner};
+pub use self::ping::{Ping, PingTracker};
 pub use self::reason::Reason;
 pub use self::reset::Reset;
-pub use self::settings::Settings;
+pub use self::settings::{
+    Setting, Settings, SettingsState, SettingsSync, SETTINGS_ENABLE_CONNECT_PROTOCOL,
+    SETTINGS_ENABLE_PUSH, SETTINGS_HEADER_TABLE_SIZE, SETTINGS_INITIAL_WINDOW_SIZE,
+    SETTINGS_MAX_CONCURRENT_STREAMS, SETTINGS_MAX_FRAME_SIZE, SETTINGS_MAX_HEADER_LIST_SIZE,
+};
+pub use self::validate::{validate, FrameViolation, ValidateContext};
 pub use self::window_update::WindowUpdate;
 
 use crate::{Buf, BufMut, Serialize, WebResult};
@@ -47,11 +55,30 @@ pub use self::frame::FrameHeader;
 pub struct StreamIdentifier(pub u32);
 
 impl StreamIdentifier {
+    /// 最大的流id, 2^31-1
+    pub const MAX: StreamIdentifier = StreamIdentifier(MASK_U31);
+
     pub fn parse<T: Buf>(buf: &mut T) -> StreamIdentifier {
+        Self::parse_reserved(buf).0
+    }
+
+    /// 解析流id并返回保留位是否被设置, RFC 7540要求接收时忽略该位
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::StreamIdentifier;
+    ///
+    /// let data = [0x80, 0, 0, 3];
+    /// assert_eq!(StreamIdentifier::parse_reserved(&mut &data[..]), (StreamIdentifier(3), true));
+    /// assert_eq!(StreamIdentifier::parse_reserved(&mut &data[1..]), (StreamIdentifier(0), false));
+    /// ```
+    pub fn parse_reserved<T: Buf>(buf: &mut T) -> (StreamIdentifier, bool) {
         if buf.remaining() < 4 {
-            return StreamIdentifier(0);
+            return (StreamIdentifier(0), false);
         }
-        StreamIdentifier(read_u31(buf))
+        let reserved = buf.chunk()[0] & 0x80 != 0;
+        (StreamIdentifier(read_u31(buf)), reserved)
     }
 
     pub fn zero() -> StreamIdentifier {
@@ -66,18 +93,103 @@ impl StreamIdentifier {
         StreamIdentifier(2)
     }
 
+    /// 返回当前的id并前进2, 耗尽后返回的id将超出2^31-1, 需检查时使用`try_next_id`
     pub fn next_id(&mut self) -> StreamIdentifier {
         let now = self.0;
-        self.0 = self.0 + 2;
+        self.0 = self.0.saturating_add(2);
         StreamIdentifier(now)
     }
 
+    /// 分配下一个流id, 超出2^31-1时返回None, 此时需新建连接
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::StreamIdentifier;
+    ///
+    /// let mut next = StreamIdentifier(StreamIdentifier::MAX.0 - 2);
+    /// assert_eq!(next.remaining(), 2);
+    /// assert_eq!(next.try_next_id(), Some(StreamIdentifier((1 << 31) - 3)));
+    /// assert_eq!(next.try_next_id(), Some(StreamIdentifier::MAX));
+    /// assert!(next.is_exhausted());
+    /// assert_eq!(next.try_next_id(), None);
+    /// ```
+    pub fn try_next_id(&mut self) -> Option<StreamIdentifier> {
+        if self.is_exhausted() {
+            return None;
+        }
+        Some(self.next_id())
+    }
+
+    /// 作为下一个待分配的id时, 是否已超出可用的范围
+    pub fn is_exhausted(&self) -> bool {
+        self.0 > Self::MAX.0
+    }
+
+    /// 作为下一个待分配的id时, 剩余可分配的id数量, 可用于提前新建连接
+    pub fn remaining(&self) -> u32 {
+        if self.is_exhausted() {
+            0
+        } else {
+            (Self::MAX.0 - self.0) / 2 + 1
+        }
+    }
+
     pub fn is_zero(&self) -> bool {
         self.0 == 0
     }
 
+    /// 客户端创建的流, 为奇数
+    pub fn is_client_initiated(&self) -> bool {
+        !self.0.is_multiple_of(2)
+    }
+
+    /// 服务端创建的流, 为非0的偶数
+    pub fn is_server_initiated(&self) -> bool {
+        !self.is_zero() && self.0.is_multiple_of(2)
+    }
+
+    /// 校验对端新建的流id, 需符合创建方的奇偶性且大于该端之前使用过的id,
+    /// 否则为连接级的PROTOCOL_ERROR
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::StreamIdentifier;
+    ///
+    /// let last = StreamIdentifier(5);
+    /// assert!(StreamIdentifier(7).validate_new(last, true).is_ok());
+    /// assert!(StreamIdentifier(8).validate_new(last, true).is_err());
+    /// assert!(StreamIdentifier(3).validate_new(last, true).is_err());
+    /// assert!(StreamIdentifier(2).validate_new(StreamIdentifier::zero(), false).is_ok());
+    /// ```
+    pub fn validate_new(
+        &self,
+        last: StreamIdentifier,
+        by_client: bool,
+    ) -> Result<(), FrameViolation> {
+        let parity = if by_client {
+            self.is_client_initiated()
+        } else {
+            self.is_server_initiated()
+        };
+        if !parity || self.0 > Self::MAX.0 {
+            return Err(FrameViolation::Connection(
+                ErrorCode::ProtocolError,
+                "invalid stream id for initiator",
+            ));
+        }
+        if *self <= last {
+            return Err(FrameViolation::Connection(
+                ErrorCode::ProtocolError,
+                "stream id not increasing",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn encode<B: Buf + BufMut>(&self, buffer: &mut B) -> WebResult<usize> {
-        buffer.put_u32(self.0);
+        buffer.put_u32(self.0 & MASK_U31);
         Ok(4)
     }
 }
@@ -120,9 +232,7 @@ pub fn read_u31<T: Buf>(buf: &mut T) -> u32 {
     if buf.remaining() < 4 {
         return 0;
     }
-    let val = buf.get_u32();
-    val & MASK_U31
-    // (buf[0] as u32 & 0x7F) << 24 | (buf[1] as u32) << 16 | (buf[2] as u32) << 8 | buf[3] as u32
+    buf.get_u31_masked()
 }
 
 #[inline(always)]
@@ -130,15 +240,12 @@ pub fn read_u24<T: Buf>(buf: &mut T) -> u32 {
     if buf.remaining() < 3 {
         return 0;
     }
-    (buf.get_u8() as u32) << 16 | (buf.get_u8() as u32) << 8 | buf.get_u8() as u32
+    buf.get_u24()
 }
 
 #[inline(always)]
 pub fn encode_u24<B: Buf + BufMut>(buf: &mut B, val: u32) -> usize {
-    buf.put_u8((val >> 16) as u8);
-    buf.put_u8((val >> 8) as u8);
-    buf.put_u8((val >> 0) as u8);
-    3
+    buf.put_u24(val)
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -146,7 +253,11 @@ pub struct SizeIncrement(pub u32);
 
 impl SizeIncrement {
     pub fn parse<T: Buf>(buf: &mut T) -> SizeIncrement {
-        SizeIncrement(buf.get_u32())
+        if buf.remaining() < 4 {
+            return SizeIncrement(0);
+        }
+        // 最高位为保留位, 接收时必须忽略
+        SizeIncrement(buf.get_u32() & !(1 << 31))
     }
 
     pub fn encode<B: Buf + BufMut>(&self, buf: &mut B) -> usize {
@@ -167,19 +278,3 @@ pub struct ParserSettings {
     priority: bool,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct ErrorCode(pub u32);
-
-impl ErrorCode {
-    pub fn parse<T: Buf>(buf: &mut T) -> ErrorCode {
-        buf.advance(4);
-        ErrorCode(0)
-    }
-}
-
-impl Serialize for ErrorCode {
-    fn serialize<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
-        buffer.put_u32(self.0);
-        Ok(4)
-    }
-}
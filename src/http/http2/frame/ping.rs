@@ -10,9 +10,14 @@
 // -----
 // Created Date: 2023/09/01 04:38:29
 
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
 use crate::{WebResult, Buf, http::http2::frame::{Kind, Flag}, Http2Error, Serialize, BufMut};
 
-use super::{FrameHeader, Frame, StreamIdentifier};
+use std::fmt;
+
+use super::{frame::write_head, FrameHeader, Frame, StreamIdentifier};
 
 
 pub type Payload = [u8; 8];
@@ -48,6 +53,16 @@ impl Ping {
         Ping { ack: true, payload: self.payload }
     }
 
+    /// 生成对某个ping的ACK回复
+    pub fn ack_of(ping: &Ping) -> Ping {
+        ping.ret_pong()
+    }
+
+    /// 以大端序将8字节的数据转为u64
+    pub fn opaque(&self) -> u64 {
+        u64::from_be_bytes(self.payload)
+    }
+
     pub fn is_ack(&self) -> bool {
         self.ack
     }
@@ -105,7 +120,7 @@ impl Ping {
         let mut size = 0;
         size += head.encode(dst)?;
         size += dst.put_slice(&self.payload);
-        log::trace!("HTTP2: 编码ping信息; len={}", size);
+        crate::web_trace!("HTTP2: 编码ping信息; len={}", size);
         Ok(size)
     }
 
@@ -120,8 +135,97 @@ impl Serialize for Ping {
     }
 }
 
+impl fmt::Display for Ping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = if self.ack { Flag::ACK.bits() } else { 0 };
+        write_head(f, &Kind::Ping, StreamIdentifier::zero(), flags)?;
+        write!(f, " opaque={:#018x}", self.opaque())
+    }
+}
+
 impl<T> From<Ping> for Frame<T> {
     fn from(src: Ping) -> Frame<T> {
         Frame::Ping(src)
     }
 }
+
+/// 记录发出的ping, 收到ACK时计算往返时间, 用于保活及RTT测量
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use webparse::http2::frame::{Ping, PingTracker};
+///
+/// let mut tracker = PingTracker::new();
+/// let now = Instant::now();
+/// let ping = tracker.ping_at(now);
+/// assert_eq!(tracker.pending(), 1);
+/// assert_eq!(tracker.ack_at(&Ping::new(*ping.payload()), now), None);
+/// let rtt = tracker.ack_at(&Ping::ack_of(&ping), now + Duration::from_millis(30));
+/// assert_eq!(rtt, Some(Duration::from_millis(30)));
+/// assert_eq!(tracker.last_rtt(), rtt);
+/// assert_eq!(tracker.pending(), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct PingTracker {
+    next: u64,
+    pending: VecDeque<(u64, Instant)>,
+    last_rtt: Option<Duration>,
+}
+
+impl PingTracker {
+    pub fn new() -> PingTracker {
+        PingTracker::default()
+    }
+
+    /// 生成新的ping帧, 数据在当前连接中唯一
+    pub fn ping(&mut self) -> Ping {
+        self.ping_at(Instant::now())
+    }
+
+    pub fn ping_at(&mut self, now: Instant) -> Ping {
+        let mut opaque;
+        loop {
+            self.next = self.next.wrapping_add(1);
+            opaque = self.next;
+            let payload = opaque.to_be_bytes();
+            if payload != SHUTDOWN_PAYLOAD && payload != USER_PAYLOAD {
+                break;
+            }
+        }
+        self.pending.push_back((opaque, now));
+        Ping::new(opaque.to_be_bytes())
+    }
+
+    /// 收到ACK时调用, 匹配到发出的ping则返回往返时间
+    pub fn ack(&mut self, pong: &Ping) -> Option<Duration> {
+        self.ack_at(pong, Instant::now())
+    }
+
+    pub fn ack_at(&mut self, pong: &Ping, now: Instant) -> Option<Duration> {
+        if !pong.is_ack() {
+            return None;
+        }
+        let opaque = pong.opaque();
+        let idx = self.pending.iter().position(|(v, _)| *v == opaque)?;
+        let (_, sent) = self.pending.remove(idx)?;
+        let rtt = now.saturating_duration_since(sent);
+        self.last_rtt = Some(rtt);
+        Some(rtt)
+    }
+
+    /// 未收到ACK的ping数量
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 最早发出且未收到ACK的ping已等待的时间
+    pub fn oldest_elapsed(&self, now: Instant) -> Option<Duration> {
+        self.pending.front().map(|(_, sent)| now.saturating_duration_since(*sent))
+    }
+
+    pub fn last_rtt(&self) -> Option<Duration> {
+        self.last_rtt
+    }
+}
@@ -12,7 +12,9 @@
 
 use crate::{WebResult, Http2Error, Buf, BufMut};
 
-use super::{frame::Frame, Flag, FrameHeader, StreamIdentifier, MASK_U31};
+use std::fmt;
+
+use super::{frame::{write_head, Frame}, Flag, Kind, FrameHeader, StreamIdentifier, MASK_U31};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Priority {
@@ -65,16 +67,28 @@ impl Priority {
     }
 
     pub fn encode<B: Buf + BufMut>(&self, dst: &mut B) -> WebResult<usize> {
-        let head = FrameHeader::new(super::Kind::Priority, Flag::zero(), self.stream_id);
+        let mut head = FrameHeader::new(super::Kind::Priority, Flag::zero(), self.stream_id);
+        head.length = 5;
         let mut size = 0;
         size += head.encode(dst)?;
         size += self.dependency.encode(dst)?;
-        log::trace!("HTTP2: 编码优先级信息; len={}", size);
+        crate::web_trace!("HTTP2: 编码优先级信息; len={}", size);
         Ok(size)
     }
     
 }
 
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::Priority, self.stream_id, 0)?;
+        write!(
+            f,
+            " dep={} weight={} exclusive={}",
+            self.dependency.dependency_id, self.dependency.weight, self.dependency.is_exclusive
+        )
+    }
+}
+
 impl<B> From<Priority> for Frame<B> {
     fn from(src: Priority) -> Self {
         Frame::Priority(src)
@@ -109,9 +123,21 @@ impl StreamDependency {
     pub fn dependency_id(&self) -> StreamIdentifier {
         self.dependency_id
     }
+
+    pub fn weight(&self) -> u8 {
+        self.weight
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.is_exclusive
+    }
     
     fn encode<B: Buf + BufMut>(&self, dst: &mut B) -> WebResult<usize> {
-        self.dependency_id.encode(dst)?;
+        let mut value = self.dependency_id.0 & MASK_U31;
+        if self.is_exclusive {
+            value |= !MASK_U31;
+        }
+        dst.put_u32(value);
         dst.put_u8(self.weight);
         Ok(5)
     }
@@ -12,7 +12,9 @@
 
 use crate::{WebResult, Http2Error, Buf, BufMut};
 
-use super::{StreamIdentifier, Reason, FrameHeader, frame::Frame, Kind, Flag};
+use std::fmt;
+
+use super::{StreamIdentifier, Reason, FrameHeader, frame::{write_head, Frame}, Kind, Flag, ErrorCode};
 
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -60,13 +62,20 @@ impl Reset {
         let mut size = 0;
         size += self.head().encode(buffer)?;
         size += buffer.put_u32(self.error_code.into());
-        log::trace!("HTTP2: 编码Reset信息; len={}", size);
+        crate::web_trace!("HTTP2: 编码Reset信息; len={}", size);
         Ok(size)
     }
 }
 
 
 
+impl fmt::Display for Reset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::Reset, self.stream_id, 0)?;
+        write!(f, " error_code={}", ErrorCode::from(self.error_code))
+    }
+}
+
 impl<B> From<Reset> for Frame<B> {
     fn from(src: Reset) -> Self {
         Frame::Reset(src)
@@ -10,15 +10,20 @@
 // -----
 // Created Date: 2023/09/01 02:19:26
 
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
 use crate::{
     http::http2::{
         frame::{Kind, StreamIdentifier},
-        DEFAULT_MAX_FRAME_SIZE, MAX_INITIAL_WINDOW_SIZE, MAX_MAX_FRAME_SIZE,
+        DEFAULT_INITIAL_WINDOW_SIZE, DEFAULT_MAX_FRAME_SIZE, DEFAULT_SETTINGS_HEADER_TABLE_SIZE,
+        DEFAULT_SETTINGS_TIMEOUT_SECS, MAX_INITIAL_WINDOW_SIZE, MAX_MAX_FRAME_SIZE,
     },
-    Binary, BinaryMut, Buf, BufMut, Http2Error, WebResult,
+    Binary, BinaryMut, Buf, BufMut, HeaderValue, Helper, Http2Error, WebResult,
 };
 
-use super::{frame::FrameHeader, Flag};
+use super::{frame::{write_head, FrameHeader}, ErrorCode, Flag, FrameViolation};
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub struct Settings {
@@ -31,9 +36,20 @@ pub struct Settings {
     max_frame_size: Option<u32>,
     max_header_list_size: Option<u32>,
     enable_connect_protocol: Option<u32>,
+    // 未知的设置项, 按收到的顺序保留以便原样转发
+    unknown: Vec<(u16, u32)>,
 }
 
-#[derive(Debug)]
+pub const SETTINGS_HEADER_TABLE_SIZE: u16 = 0x1;
+pub const SETTINGS_ENABLE_PUSH: u16 = 0x2;
+pub const SETTINGS_MAX_CONCURRENT_STREAMS: u16 = 0x3;
+pub const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+pub const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+pub const SETTINGS_MAX_HEADER_LIST_SIZE: u16 = 0x6;
+/// RFC 8441 扩展的CONNECT协议
+pub const SETTINGS_ENABLE_CONNECT_PROTOCOL: u16 = 0x8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Setting {
     /// 允许发送者以八位字节的形式通知远程端点用于解码头块的头压缩表的最大尺寸。
     /// 编码器可以通过使用特定于头部块内头部压缩格式的信令来选择等于或小于此值的
@@ -67,7 +83,10 @@ pub enum Setting {
     /// 该值基于头字段的未压缩大小，包括名称和八位字节的值的长度，以及每个头字段的开销32个字节。
     /// 对于任何给定的请求，可能会强制实施一个比所宣传的更低的限制。
     MaxHeaderListSize(u32),
+    /// RFC 8441, 值为1时允许对端发起带`:protocol`的CONNECT请求
     EnableConnectProtocol(u32),
+    /// 未知的设置项, 按RFC 7540需忽略, 但保留以便原样转发
+    Unknown(u16, u32),
 }
 
 impl Default for Settings {
@@ -91,6 +110,7 @@ impl Default for Settings {
             max_frame_size: Default::default(),
             max_header_list_size: Default::default(),
             enable_connect_protocol: Default::default(),
+            unknown: Default::default(),
         }
     }
 }
@@ -100,44 +120,91 @@ impl Default for Settings {
 impl Setting {
     /// Creates a new `Setting` with the correct variant corresponding to the
     /// given setting id, based on the settings IDs defined in section
-    /// 6.5.2.
+    /// 6.5.2. Unknown ids return None, see `Setting::from_id_or_unknown`.
     pub fn from_id(id: u16, val: u32) -> Option<Setting> {
         use self::Setting::*;
 
         match id {
-            1 => Some(HeaderTableSize(val)),
-            2 => Some(EnablePush(val)),
-            3 => Some(MaxConcurrentStreams(val)),
-            4 => Some(InitialWindowSize(val)),
-            5 => Some(MaxFrameSize(val)),
-            6 => Some(MaxHeaderListSize(val)),
-            8 => Some(EnableConnectProtocol(val)),
+            SETTINGS_HEADER_TABLE_SIZE => Some(HeaderTableSize(val)),
+            SETTINGS_ENABLE_PUSH => Some(EnablePush(val)),
+            SETTINGS_MAX_CONCURRENT_STREAMS => Some(MaxConcurrentStreams(val)),
+            SETTINGS_INITIAL_WINDOW_SIZE => Some(InitialWindowSize(val)),
+            SETTINGS_MAX_FRAME_SIZE => Some(MaxFrameSize(val)),
+            SETTINGS_MAX_HEADER_LIST_SIZE => Some(MaxHeaderListSize(val)),
+            SETTINGS_ENABLE_CONNECT_PROTOCOL => Some(EnableConnectProtocol(val)),
             _ => None,
         }
     }
 
-    fn parse<T: Buf>(bytes: &mut T) -> Option<Setting> {
+    /// 同`from_id`, 未知的标识返回`Setting::Unknown`
+    pub fn from_id_or_unknown(id: u16, val: u32) -> Setting {
+        Setting::from_id(id, val).unwrap_or(Setting::Unknown(id, val))
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Setting::Unknown(..))
+    }
+
+    /// 设置项的标识, 见RFC 7540 6.5.2
+    pub fn id(&self) -> u16 {
+        use self::Setting::*;
+        match *self {
+            HeaderTableSize(_) => SETTINGS_HEADER_TABLE_SIZE,
+            EnablePush(_) => SETTINGS_ENABLE_PUSH,
+            MaxConcurrentStreams(_) => SETTINGS_MAX_CONCURRENT_STREAMS,
+            InitialWindowSize(_) => SETTINGS_INITIAL_WINDOW_SIZE,
+            MaxFrameSize(_) => SETTINGS_MAX_FRAME_SIZE,
+            MaxHeaderListSize(_) => SETTINGS_MAX_HEADER_LIST_SIZE,
+            EnableConnectProtocol(_) => SETTINGS_ENABLE_CONNECT_PROTOCOL,
+            Unknown(id, _) => id,
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        use self::Setting::*;
+        match *self {
+            HeaderTableSize(v)
+            | EnablePush(v)
+            | MaxConcurrentStreams(v)
+            | InitialWindowSize(v)
+            | MaxFrameSize(v)
+            | MaxHeaderListSize(v)
+            | EnableConnectProtocol(v)
+            | Unknown(_, v) => v,
+        }
+    }
+
+    /// 校验设置值是否合法, 窗口大小超出时为FLOW_CONTROL_ERROR, 其它为PROTOCOL_ERROR
+    pub fn validate(&self) -> WebResult<()> {
+        use self::Setting::*;
+        match *self {
+            EnablePush(v) | EnableConnectProtocol(v) if v > 1 => {
+                Err(Http2Error::InvalidSettingValue.into())
+            }
+            InitialWindowSize(v) if v as usize > MAX_INITIAL_WINDOW_SIZE => {
+                Err(Http2Error::Frame(FrameViolation::Connection(
+                    ErrorCode::FlowControlError,
+                    "initial window size above 2^31-1",
+                ))
+                .into())
+            }
+            MaxFrameSize(v) if !(DEFAULT_MAX_FRAME_SIZE..=MAX_MAX_FRAME_SIZE).contains(&v) => {
+                Err(Http2Error::InvalidSettingValue.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn parse<T: Buf>(bytes: &mut T) -> Setting {
         let id: u16 = bytes.get_u16();
         let val: u32 = bytes.get_u32();
 
-        Setting::from_id(id, val)
+        Setting::from_id_or_unknown(id, val)
     }
 
     fn encode<B: Buf + BufMut>(&self, dst: &mut B) -> WebResult<usize> {
-        use self::Setting::*;
-
-        let (kind, val) = match *self {
-            HeaderTableSize(v) => (1, v),
-            EnablePush(v) => (2, v),
-            MaxConcurrentStreams(v) => (3, v),
-            InitialWindowSize(v) => (4, v),
-            MaxFrameSize(v) => (5, v),
-            MaxHeaderListSize(v) => (6, v),
-            EnableConnectProtocol(v) => (8, v),
-        };
-
-        dst.put_u16(kind);
-        dst.put_u32(val);
+        dst.put_u16(self.id());
+        dst.put_u32(self.value());
         Ok(6)
     }
 }
@@ -220,8 +287,6 @@ impl Settings {
     */
 
     fn parse_setting<T: Buf>(payload: &mut T) -> WebResult<Settings> {
-        use self::Setting::*;
-
         // Ensure the payload length is correct, each setting is 6 bytes long.
         if payload.remaining() % 6 != 0 {
             return Err(Http2Error::into(Http2Error::InvalidPayloadLength));
@@ -232,48 +297,10 @@ impl Settings {
 
         let len = payload.remaining() / 6;
         for _ in 0..len {
-            match Setting::parse(payload) {
-                Some(HeaderTableSize(val)) => {
-                    settings.header_table_size = Some(val);
-                }
-                Some(EnablePush(val)) => match val {
-                    0 | 1 => {
-                        settings.enable_push = Some(val);
-                    }
-                    _ => {
-                        return Err(Http2Error::InvalidSettingValue.into());
-                    }
-                },
-                Some(MaxConcurrentStreams(val)) => {
-                    settings.max_concurrent_streams = Some(val);
-                }
-                Some(InitialWindowSize(val)) => {
-                    if val as usize > MAX_INITIAL_WINDOW_SIZE {
-                        return Err(Http2Error::InvalidSettingValue.into());
-                    } else {
-                        settings.initial_window_size = Some(val);
-                    }
-                }
-                Some(MaxFrameSize(val)) => {
-                    if DEFAULT_MAX_FRAME_SIZE <= val && val <= MAX_MAX_FRAME_SIZE {
-                        settings.max_frame_size = Some(val);
-                    } else {
-                        return Err(Http2Error::InvalidSettingValue.into());
-                    }
-                }
-                Some(MaxHeaderListSize(val)) => {
-                    settings.max_header_list_size = Some(val);
-                }
-                Some(EnableConnectProtocol(val)) => match val {
-                    0 | 1 => {
-                        settings.enable_connect_protocol = Some(val);
-                    }
-                    _ => {
-                        return Err(Http2Error::InvalidSettingValue.into());
-                    }
-                },
-                None => {}
-            }
+            // 未知的设置项不影响连接, 仅保留
+            let setting = Setting::parse(payload);
+            setting.validate()?;
+            settings.set(setting);
         }
         Ok(settings)
     }
@@ -308,26 +335,50 @@ impl Settings {
     }
 
     pub fn parse_http_settings(&self, value: &str) -> WebResult<Settings> {
-        use base64::Engine;
-        match base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(value.as_bytes()) {
-            Err(_e) => {
-                return Err(crate::WebError::Http2(Http2Error::InvalidSettingValue));
-            }
-            Ok(v) => {
-                let mut binary = Binary::from(v);
-                return Self::parse_setting(&mut binary);
-            }
+        Self::decode_http2_settings(value.as_bytes())
+    }
+
+    /// 解析h2c升级请求中的HTTP2-Settings头(RFC 7540 3.2.1), 与`encode_http2_settings_header`相对
+    ///
+    /// 非base64url编码时返回`InvalidSettingValue`, 解码后的内容按SETTINGS帧的负载校验
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::Settings;
+    /// use webparse::HeaderValue;
+    ///
+    /// let value = HeaderValue::from_static("AAMAAABkAAQAAP__");
+    /// let settings = Settings::decode_http2_settings_header(&value).unwrap();
+    /// assert_eq!(settings.max_concurrent_streams(), Some(100));
+    /// assert_eq!(settings.initial_window_size(), Some(65535));
+    /// assert_eq!(settings.encode_http2_settings_header(), value);
+    ///
+    /// assert!(Settings::decode_http2_settings_header(&HeaderValue::from_static("AAMAAABkAAQAAP//")).is_err());
+    /// ```
+    pub fn decode_http2_settings_header(value: &HeaderValue) -> WebResult<Settings> {
+        Self::decode_http2_settings(value.as_bytes())
+    }
+
+    fn decode_http2_settings(value: &[u8]) -> WebResult<Settings> {
+        match Helper::base64url_decode(value.trim_ascii()) {
+            Err(_e) => Err(crate::WebError::Http2(Http2Error::InvalidSettingValue)),
+            Ok(v) => Self::parse_setting(&mut Binary::from(v)),
         }
     }
 
+    /// 编码为h2c升级请求中的HTTP2-Settings头, 即SETTINGS帧负载的base64url编码, 不带填充
+    pub fn encode_http2_settings_header(&self) -> HeaderValue {
+        HeaderValue::Value(self.encode_http_settings().into_bytes())
+    }
+
     pub fn encode_http_settings(&self) -> String {
-        use base64::Engine;
         let mut dst = BinaryMut::new();
         self.for_each(|setting| {
-            log::trace!("HTTP2: 编码设置信息; val={:?}", setting);
+            crate::web_trace!("HTTP2: 编码设置信息; val={:?}", setting);
             setting.encode(&mut dst).unwrap();
         });
-        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(dst.chunk())
+        Helper::base64url_encode(dst.chunk())
     }
 
     pub fn encode<B: Buf + BufMut>(&self, dst: &mut B) -> WebResult<usize> {
@@ -343,10 +394,56 @@ impl Settings {
         self.for_each(|setting| {
             size += setting.encode(dst).unwrap()
         });
-        log::trace!("HTTP2: 编码设置信息; len={}", size);
+        crate::web_trace!("HTTP2: 编码设置信息; len={}", size);
         Ok(size)
     }
 
+    /// 帧中包含的所有设置项
+    pub fn settings(&self) -> Vec<Setting> {
+        let mut list = Vec::new();
+        self.for_each(|setting| list.push(setting));
+        list
+    }
+
+    /// 设置单个值, 与解析时的含义一致
+    pub fn set(&mut self, setting: Setting) {
+        use self::Setting::*;
+        match setting {
+            HeaderTableSize(v) => self.header_table_size = Some(v),
+            EnablePush(v) => self.enable_push = Some(v),
+            MaxConcurrentStreams(v) => self.max_concurrent_streams = Some(v),
+            InitialWindowSize(v) => self.initial_window_size = Some(v),
+            MaxFrameSize(v) => self.max_frame_size = Some(v),
+            MaxHeaderListSize(v) => self.max_header_list_size = Some(v),
+            EnableConnectProtocol(v) => self.enable_connect_protocol = Some(v),
+            Unknown(id, v) => match self.unknown.iter_mut().find(|(i, _)| *i == id) {
+                Some(item) => item.1 = v,
+                None => self.unknown.push((id, v)),
+            },
+        }
+    }
+
+    /// 收到的未知设置项
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Setting, Settings};
+    ///
+    /// let mut settings = Settings::default();
+    /// settings.set(Setting::Unknown(0x10, 7));
+    /// settings.set(Setting::EnableConnectProtocol(1));
+    ///
+    /// let value = settings.encode_http_settings();
+    /// let parsed = settings.parse_http_settings(&value).unwrap();
+    /// assert_eq!(parsed.unknown(), &[(0x10, 7)]);
+    /// assert_eq!(parsed.is_extended_connect_protocol_enabled(), Some(true));
+    /// assert_eq!(parsed, settings);
+    /// ```
+    pub fn unknown(&self) -> &[(u16, u32)] {
+        &self.unknown
+    }
+
     fn for_each<F: FnMut(Setting)>(&self, mut f: F) {
         use self::Setting::*;
 
@@ -377,5 +474,251 @@ impl Settings {
         if let Some(v) = self.enable_connect_protocol {
             f(EnableConnectProtocol(v));
         }
+
+        for &(id, v) in &self.unknown {
+            f(Unknown(id, v));
+        }
+    }
+}
+
+/// 连接当前生效的设置值, 未收到的设置项取RFC 7540中的初始值
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::{Setting, SettingsState};
+///
+/// let mut state = SettingsState::default();
+/// let changed = state
+///     .apply(&[Setting::InitialWindowSize(1 << 20), Setting::HeaderTableSize(4096)])
+///     .unwrap();
+/// assert_eq!(changed, vec![Setting::InitialWindowSize(1 << 20)]);
+/// assert!(state.apply(&[Setting::EnablePush(2)]).is_err());
+///
+/// let mut target = state;
+/// target.max_frame_size = 1 << 20;
+/// let frame = state.encode_diff(&target);
+/// assert_eq!(frame.settings(), vec![Setting::MaxFrameSize(1 << 20)]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingsState {
+    pub header_table_size: u32,
+    pub enable_push: bool,
+    /// None表示不限制
+    pub max_concurrent_streams: Option<u32>,
+    pub initial_window_size: u32,
+    pub max_frame_size: u32,
+    /// None表示不限制
+    pub max_header_list_size: Option<u32>,
+    pub enable_connect_protocol: bool,
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::Settings, StreamIdentifier::zero(), self.flags.bits())?;
+        for setting in self.settings() {
+            write!(f, " {:#x}={}", setting.id(), setting.value())?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SettingsState {
+    fn default() -> Self {
+        SettingsState {
+            header_table_size: DEFAULT_SETTINGS_HEADER_TABLE_SIZE as u32,
+            enable_push: true,
+            max_concurrent_streams: None,
+            initial_window_size: DEFAULT_INITIAL_WINDOW_SIZE,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            max_header_list_size: None,
+            enable_connect_protocol: false,
+        }
+    }
+}
+
+impl SettingsState {
+    /// 应用收到的设置项, 返回值发生变化的项. 任一项非法时返回错误且不做任何修改
+    pub fn apply(&mut self, settings: &[Setting]) -> WebResult<Vec<Setting>> {
+        for setting in settings {
+            setting.validate()?;
+        }
+        let mut changed: Vec<Setting> = Vec::new();
+        for setting in settings {
+            if self.apply_one(*setting) {
+                // 同一项多次出现时只保留最后的值
+                changed.retain(|s| s.id() != setting.id());
+                changed.push(*setting);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// 应用SETTINGS帧, ACK帧不做任何修改
+    pub fn apply_frame(&mut self, frame: &Settings) -> WebResult<Vec<Setting>> {
+        if frame.is_ack() {
+            return Ok(Vec::new());
+        }
+        self.apply(&frame.settings())
+    }
+
+    fn apply_one(&mut self, setting: Setting) -> bool {
+        use self::Setting::*;
+        let old = *self;
+        match setting {
+            HeaderTableSize(v) => self.header_table_size = v,
+            EnablePush(v) => self.enable_push = v != 0,
+            MaxConcurrentStreams(v) => self.max_concurrent_streams = Some(v),
+            InitialWindowSize(v) => self.initial_window_size = v,
+            MaxFrameSize(v) => self.max_frame_size = v,
+            MaxHeaderListSize(v) => self.max_header_list_size = Some(v),
+            EnableConnectProtocol(v) => self.enable_connect_protocol = v != 0,
+            Unknown(..) => return false,
+        }
+        old != *self
+    }
+
+    /// 生成从当前状态变为`other`所需的最少设置项的SETTINGS帧
+    ///
+    /// 不限制的项(None)无法通过SETTINGS恢复, 将被忽略
+    pub fn encode_diff(&self, other: &SettingsState) -> Settings {
+        use self::Setting::*;
+        let mut settings = Settings::default();
+        if self.header_table_size != other.header_table_size {
+            settings.set(HeaderTableSize(other.header_table_size));
+        }
+        if self.enable_push != other.enable_push {
+            settings.set(EnablePush(other.enable_push as u32));
+        }
+        if let Some(v) = other.max_concurrent_streams {
+            if self.max_concurrent_streams != Some(v) {
+                settings.set(MaxConcurrentStreams(v));
+            }
+        }
+        if self.initial_window_size != other.initial_window_size {
+            settings.set(InitialWindowSize(other.initial_window_size));
+        }
+        if self.max_frame_size != other.max_frame_size {
+            settings.set(MaxFrameSize(other.max_frame_size));
+        }
+        if let Some(v) = other.max_header_list_size {
+            if self.max_header_list_size != Some(v) {
+                settings.set(MaxHeaderListSize(v));
+            }
+        }
+        if self.enable_connect_protocol != other.enable_connect_protocol {
+            settings.set(EnableConnectProtocol(other.enable_connect_protocol as u32));
+        }
+        settings
+    }
+}
+
+/// 跟踪本端发出但未被确认的SETTINGS帧, 收到ACK后才应用本端的设置值
+///
+/// 超时未收到ACK时调用方应以SETTINGS_TIMEOUT关闭连接,
+/// ACK后INITIAL_WINDOW_SIZE发生变化时需按差值调整所有流的接收窗口
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use webparse::http2::frame::{ErrorCode, Setting, Settings, SettingsSync};
+///
+/// let mut sync = SettingsSync::new(Duration::from_secs(5));
+/// let now = Instant::now();
+/// let mut frame = Settings::default();
+/// frame.set_initial_window_size(Some(1 << 20));
+/// sync.sent_at(&frame, now).unwrap();
+/// assert_eq!(sync.local().initial_window_size, 65_535);
+/// assert!(!sync.deadline_expired(now + Duration::from_secs(4)));
+/// assert!(sync.deadline_expired(now + Duration::from_secs(5)));
+/// assert_eq!(sync.timeout_error().error_code(), ErrorCode::SettingsTimeout);
+///
+/// let changed = sync.recv_ack(&Settings::ack()).unwrap();
+/// assert_eq!(changed, vec![Setting::InitialWindowSize(1 << 20)]);
+/// assert_eq!(sync.local().initial_window_size, 1 << 20);
+/// assert_eq!(sync.pending(), 0);
+/// assert!(sync.recv_ack(&Settings::ack()).is_err());
+/// ```
+#[derive(Debug)]
+pub struct SettingsSync {
+    local: SettingsState,
+    pending: VecDeque<(Vec<Setting>, Instant)>,
+    timeout: Duration,
+}
+
+impl Default for SettingsSync {
+    fn default() -> Self {
+        SettingsSync::new(Duration::from_secs(DEFAULT_SETTINGS_TIMEOUT_SECS))
+    }
+}
+
+impl SettingsSync {
+    pub fn new(timeout: Duration) -> SettingsSync {
+        SettingsSync {
+            local: SettingsState::default(),
+            pending: VecDeque::new(),
+            timeout,
+        }
+    }
+
+    /// 已被对端确认的本端设置
+    pub fn local(&self) -> &SettingsState {
+        &self.local
+    }
+
+    /// 未收到ACK的SETTINGS帧数量
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 发出SETTINGS帧时调用, 设置值非法时返回错误且不做记录
+    pub fn sent(&mut self, frame: &Settings) -> WebResult<()> {
+        self.sent_at(frame, Instant::now())
+    }
+
+    pub fn sent_at(&mut self, frame: &Settings, now: Instant) -> WebResult<()> {
+        if frame.is_ack() {
+            return Ok(());
+        }
+        let settings = frame.settings();
+        for setting in &settings {
+            setting.validate()?;
+        }
+        self.pending.push_back((settings, now));
+        Ok(())
+    }
+
+    /// 收到ACK时按发送顺序应用最早未确认的设置, 返回值发生变化的项,
+    /// 没有待确认的设置时返回PROTOCOL_ERROR
+    pub fn recv_ack(&mut self, frame: &Settings) -> WebResult<Vec<Setting>> {
+        if !frame.is_ack() {
+            return Ok(Vec::new());
+        }
+        match self.pending.pop_front() {
+            Some((settings, _)) => self.local.apply(&settings),
+            None => Err(Http2Error::Frame(FrameViolation::Connection(
+                ErrorCode::ProtocolError,
+                "unexpected settings ack",
+            ))
+            .into()),
+        }
+    }
+
+    /// 最早未确认的SETTINGS帧的截止时间
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.front().map(|(_, sent)| *sent + self.timeout)
+    }
+
+    /// 是否已超时未收到ACK, 是则应以`timeout_error`关闭连接
+    pub fn deadline_expired(&self, now: Instant) -> bool {
+        self.deadline().is_some_and(|deadline| now >= deadline)
+    }
+
+    pub fn timeout_error(&self) -> Http2Error {
+        Http2Error::Frame(FrameViolation::Connection(
+            ErrorCode::SettingsTimeout,
+            "settings ack timeout",
+        ))
     }
 }
@@ -0,0 +1,200 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/19 10:12:36
+
+use std::fmt;
+
+use crate::http2::MAX_MAX_FRAME_SIZE;
+
+use super::{ErrorCode, FrameHeader, Kind, StreamIdentifier};
+
+/// 校验帧时需要的连接状态
+#[derive(Debug, Clone, Copy)]
+pub struct ValidateContext {
+    /// 本端通告的SETTINGS_MAX_FRAME_SIZE
+    pub max_frame_size: u32,
+    /// 正在接收的头块所在的流, 此时只能收到该流的CONTINUATION帧
+    pub continuation: Option<StreamIdentifier>,
+}
+
+impl Default for ValidateContext {
+    fn default() -> Self {
+        ValidateContext {
+            max_frame_size: MAX_MAX_FRAME_SIZE,
+            continuation: None,
+        }
+    }
+}
+
+/// 违反RFC 7540第6节的错误, 区分连接级及流级
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameViolation {
+    /// 连接级错误, 需要发送GOAWAY并关闭连接
+    Connection(ErrorCode, &'static str),
+    /// 流级错误, 仅需对该流发送RST_STREAM
+    Stream(StreamIdentifier, ErrorCode, &'static str),
+}
+
+impl FrameViolation {
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            FrameViolation::Connection(code, _) => *code,
+            FrameViolation::Stream(_, code, _) => *code,
+        }
+    }
+
+    pub fn is_connection_error(&self) -> bool {
+        matches!(self, FrameViolation::Connection(..))
+    }
+}
+
+impl fmt::Display for FrameViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameViolation::Connection(code, msg) => write!(f, "connection error {}: {}", code, msg),
+            FrameViolation::Stream(id, code, msg) => {
+                write!(f, "stream {} error {}: {}", id.0, code, msg)
+            }
+        }
+    }
+}
+
+fn connection(code: ErrorCode, msg: &'static str) -> Result<(), FrameViolation> {
+    Err(FrameViolation::Connection(code, msg))
+}
+
+/// 按RFC 7540第6节校验帧头的流id, 长度及标志位
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::{validate, ErrorCode, Flag, FrameHeader, Kind, StreamIdentifier, ValidateContext};
+/// let mut head = FrameHeader::new(Kind::Ping, Flag::zero(), StreamIdentifier(1));
+/// head.length = 8;
+/// let err = validate(&head, &ValidateContext::default()).unwrap_err();
+/// assert!(err.is_connection_error());
+/// assert_eq!(err.error_code(), ErrorCode::ProtocolError);
+///
+/// let mut head = FrameHeader::new(Kind::Priority, Flag::zero(), StreamIdentifier(3));
+/// head.length = 4;
+/// let err = validate(&head, &ValidateContext::default()).unwrap_err();
+/// assert!(!err.is_connection_error());
+/// ```
+pub fn validate(head: &FrameHeader, ctx: &ValidateContext) -> Result<(), FrameViolation> {
+    let id = head.stream_id();
+    let length = head.length;
+    let flag = head.flag();
+    let kind = *head.kind();
+
+    if let Some(expect) = ctx.continuation {
+        if kind != Kind::Continuation || id != expect {
+            return connection(ErrorCode::ProtocolError, "expected CONTINUATION frame");
+        }
+    }
+
+    if length > ctx.max_frame_size {
+        return connection(ErrorCode::FrameSizeError, "frame exceeds SETTINGS_MAX_FRAME_SIZE");
+    }
+
+    let mut min_len = 0;
+    if flag.is_padded() && matches!(kind, Kind::Data | Kind::Headers | Kind::PushPromise) {
+        min_len += 1;
+    }
+
+    match kind {
+        Kind::Data => {
+            if id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "DATA on stream 0");
+            }
+        }
+        Kind::Headers => {
+            if id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "HEADERS on stream 0");
+            }
+            if flag.is_priority() {
+                min_len += 5;
+            }
+        }
+        Kind::Priority => {
+            if id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "PRIORITY on stream 0");
+            }
+            if length != 5 {
+                return Err(FrameViolation::Stream(
+                    id,
+                    ErrorCode::FrameSizeError,
+                    "PRIORITY length must be 5",
+                ));
+            }
+        }
+        Kind::Reset => {
+            if id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "RST_STREAM on stream 0");
+            }
+            if length != 4 {
+                return connection(ErrorCode::FrameSizeError, "RST_STREAM length must be 4");
+            }
+        }
+        Kind::Settings => {
+            if !id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "SETTINGS must be on stream 0");
+            }
+            if flag.is_ack() && length != 0 {
+                return connection(ErrorCode::FrameSizeError, "SETTINGS ack must be empty");
+            }
+            if !length.is_multiple_of(6) {
+                return connection(ErrorCode::FrameSizeError, "SETTINGS length must be a multiple of 6");
+            }
+        }
+        Kind::PushPromise => {
+            if id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "PUSH_PROMISE on stream 0");
+            }
+            min_len += 4;
+        }
+        Kind::Ping => {
+            if !id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "PING must be on stream 0");
+            }
+            if length != 8 {
+                return connection(ErrorCode::FrameSizeError, "PING length must be 8");
+            }
+        }
+        Kind::GoAway => {
+            if !id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "GOAWAY must be on stream 0");
+            }
+            if length < 8 {
+                return connection(ErrorCode::FrameSizeError, "GOAWAY length must be at least 8");
+            }
+        }
+        Kind::WindowUpdate => {
+            if length != 4 {
+                return connection(ErrorCode::FrameSizeError, "WINDOW_UPDATE length must be 4");
+            }
+        }
+        Kind::Continuation => {
+            if id.is_zero() {
+                return connection(ErrorCode::ProtocolError, "CONTINUATION on stream 0");
+            }
+            if ctx.continuation.is_none() {
+                return connection(ErrorCode::ProtocolError, "unexpected CONTINUATION frame");
+            }
+        }
+        // 未知类型的帧必须忽略
+        Kind::Unregistered(_) => {}
+    }
+
+    if length < min_len {
+        return connection(ErrorCode::FrameSizeError, "frame too short for its flags");
+    }
+    Ok(())
+}
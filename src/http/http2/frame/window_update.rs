@@ -10,9 +10,11 @@
 // -----
 // Created Date: 2023/09/01 04:39:00
 
-use crate::{http::http2::frame::Kind, Http2Error, WebResult, Buf, BufMut};
+use crate::{http::http2::frame::Kind, http2::MAX_WINDOW_SIZE, Http2Error, WebResult, Buf, BufMut};
 
-use super::{StreamIdentifier, FrameHeader, frame::Frame, Flag};
+use std::fmt;
+
+use super::{StreamIdentifier, FrameHeader, frame::{write_head, Frame}, Flag, ErrorCode, FrameViolation};
 
 
 
@@ -40,6 +42,32 @@ impl WindowUpdate {
         self.size_increment
     }
 
+    /// 将增量加到当前窗口上, 窗口可能因SETTINGS_INITIAL_WINDOW_SIZE变化而为负,
+    /// 结果超过2^31-1时返回FLOW_CONTROL_ERROR
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{ErrorCode, StreamIdentifier, WindowUpdate};
+    /// let update = WindowUpdate::new(StreamIdentifier(1), 100);
+    /// assert_eq!(update.apply_to(-50).unwrap(), 50);
+    /// let err = update.apply_to(i32::MAX - 10).unwrap_err();
+    /// assert_eq!(err.error_code(), ErrorCode::FlowControlError);
+    /// assert!(!err.is_connection_error());
+    /// ```
+    pub fn apply_to(&self, window: i32) -> Result<i32, FrameViolation> {
+        let value = window as i64 + self.size_increment as i64;
+        if value > MAX_WINDOW_SIZE as i64 {
+            let msg = "window size overflow";
+            return Err(if self.stream_id.is_zero() {
+                FrameViolation::Connection(ErrorCode::FlowControlError, msg)
+            } else {
+                FrameViolation::Stream(self.stream_id, ErrorCode::FlowControlError, msg)
+            });
+        }
+        Ok(value as i32)
+    }
+
     /// Builds a `WindowUpdate` frame from a raw frame.
     pub fn parse<B: Buf>(head: FrameHeader, payload: &mut B) -> WebResult<WindowUpdate> {
         debug_assert_eq!(head.kind(), &Kind::WindowUpdate);
@@ -51,8 +79,14 @@ impl WindowUpdate {
         // when received.
         let size_increment = payload.get_u32() & !SIZE_INCREMENT_MASK;
 
+        // 增量为0时, 流0上为连接错误, 其它为流错误
         if size_increment == 0 {
-            return Err(Http2Error::InvalidWindowUpdateValue.into());
+            let violation = if head.stream_id().is_zero() {
+                FrameViolation::Connection(ErrorCode::ProtocolError, "WINDOW_UPDATE increment of 0")
+            } else {
+                FrameViolation::Stream(head.stream_id(), ErrorCode::ProtocolError, "WINDOW_UPDATE increment of 0")
+            };
+            return Err(Http2Error::Frame(violation).into());
         }
 
         Ok(WindowUpdate {
@@ -72,8 +106,7 @@ impl WindowUpdate {
         let mut size = 0;
         size += self.head().encode(buffer)?;
         size += buffer.put_u32(self.size_increment);
-        log::trace!("encoding WindowUpdate; len={}", size);
-        log::trace!("HTTP2: 编码窗口更新信息; len={}", size);
+        crate::web_trace!("HTTP2: 编码窗口更新信息; len={}", size);
         Ok(size)
     }
 
@@ -81,6 +114,13 @@ impl WindowUpdate {
 
 
 
+impl fmt::Display for WindowUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_head(f, &Kind::WindowUpdate, self.stream_id, 0)?;
+        write!(f, " increment={}", self.size_increment)
+    }
+}
+
 impl<B> From<WindowUpdate> for Frame<B> {
     fn from(src: WindowUpdate) -> Self {
         Frame::WindowUpdate(src)
@@ -0,0 +1,128 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/18 10:12:36
+
+use crate::{
+    http2::{
+        frame::{Data, Flag, Frame, FrameHeader, Headers, Kind, StreamIdentifier},
+        encoder::Encoder,
+    },
+    Binary, BinaryMut, HeaderMap, Request, Response, Serialize, WebResult,
+};
+
+impl<T: Serialize> Request<T> {
+    /// 将请求转换为http2的帧, 依次为HEADERS, DATA及trailer的HEADERS
+    ///
+    /// - 没有body及trailer时END_STREAM设置在HEADERS上, 否则设置在最后一帧上
+    /// - body按`max_frame_size`拆分为多个DATA帧, 不考虑流量窗口, 需要时可用`Data::split`再拆分
+    /// - `encoder`仅用于预先按`Encoder::max_header_size`校验头的大小,
+    ///   编码时超出`Encoder::max_frame_size`的头块拆分到CONTINUATION帧
+    /// - Host转为`:authority`, 连接相关的头被去除, 见`Headers::from_request`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::{encoder::Encoder, frame::{Frame, StreamIdentifier}};
+    /// use webparse::{Buf, HeaderMap, Request};
+    ///
+    /// let mut req = Request::builder()
+    ///     .method("POST")
+    ///     .url("http://example.com/upload")
+    ///     .body("0123456789")
+    ///     .unwrap();
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("x-checksum", "1");
+    /// req.set_trailers(trailers);
+    ///
+    /// let encoder = Encoder::new();
+    /// let frames = req.to_h2_frames(&encoder, StreamIdentifier(3), 4).unwrap();
+    /// assert_eq!(frames.len(), 5);
+    /// assert!(matches!(&frames[0], Frame::Headers(h) if !h.is_end_stream()));
+    /// assert!(matches!(&frames[3], Frame::Data(d) if d.payload().remaining() == 2 && !d.is_end_stream()));
+    /// assert!(matches!(&frames[4], Frame::Headers(h) if h.is_trailers()));
+    /// assert!(frames.iter().all(|f| f.stream_id() == StreamIdentifier(3)));
+    /// ```
+    pub fn to_h2_frames(
+        &mut self,
+        encoder: &Encoder,
+        stream_id: StreamIdentifier,
+        max_frame_size: usize,
+    ) -> WebResult<Vec<Frame<Binary>>> {
+        let body = serialize_body(self.body_mut())?;
+        let end_stream = body.is_empty() && self.trailers().is_none();
+        let headers = Headers::from_request(stream_id, self, end_stream)?;
+        to_frames(encoder, headers, body, self.trailers(), max_frame_size)
+    }
+}
+
+impl<T: Serialize> Response<T> {
+    /// 将响应转换为http2的帧, 规则同`Request::to_h2_frames`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::{encoder::Encoder, frame::{Frame, StreamIdentifier}};
+    /// use webparse::Response;
+    ///
+    /// let mut res = Response::builder().status(204).body(()).unwrap();
+    /// let frames = res.to_h2_frames(&Encoder::new(), StreamIdentifier(1), 16_384).unwrap();
+    /// assert_eq!(frames.len(), 1);
+    /// assert!(frames[0].is_end_stream());
+    /// ```
+    pub fn to_h2_frames(
+        &mut self,
+        encoder: &Encoder,
+        stream_id: StreamIdentifier,
+        max_frame_size: usize,
+    ) -> WebResult<Vec<Frame<Binary>>> {
+        let body = serialize_body(self.body_mut())?;
+        let end_stream = body.is_empty() && self.trailers().is_none();
+        let headers = Headers::from_response(stream_id, self, end_stream)?;
+        to_frames(encoder, headers, body, self.trailers(), max_frame_size)
+    }
+}
+
+fn serialize_body<T: Serialize>(body: &mut T) -> WebResult<Binary> {
+    let mut buffer = BinaryMut::new();
+    body.serialize(&mut buffer)?;
+    Ok(buffer.freeze())
+}
+
+fn to_frames(
+    encoder: &Encoder,
+    headers: Headers,
+    body: Binary,
+    trailers: Option<&HeaderMap>,
+    max_frame_size: usize,
+) -> WebResult<Vec<Frame<Binary>>> {
+    let stream_id = headers.stream_id();
+    for (name, value) in headers.fields().iter() {
+        encoder.check_header_size(name, value)?;
+    }
+
+    let mut frames = vec![Frame::Headers(headers)];
+    if !body.is_empty() {
+        let mut flags = Flag::zero();
+        if trailers.is_none() {
+            flags.set_end_stream();
+        }
+        let data = Data::new(FrameHeader::new(Kind::Data, flags, stream_id), body);
+        let (data, _) = data.split(max_frame_size.max(1), usize::MAX);
+        frames.extend(data.into_iter().map(Frame::Data));
+    }
+    if let Some(trailers) = trailers {
+        frames.push(Frame::Headers(Headers::from_trailers(
+            stream_id,
+            trailers.clone(),
+        )?));
+    }
+    Ok(frames)
+}
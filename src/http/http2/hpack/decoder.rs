@@ -12,16 +12,15 @@
 
 use std::borrow::Cow;
 
-use std::num::Wrapping;
-
 use std::sync::{Arc, RwLock};
 
 use crate::{
-    Buf, HeaderName, HeaderValue, Http2Error, WebResult,
+    Buf, HeaderName, HeaderValue, Http2Error, WebError, WebResult,
 };
 
 use super::huffman::{HuffmanDecoder, HuffmanDecoderError};
-use super::HeaderIndex;
+use crate::http2::{frame::ErrorCode, DEFAULT_SETTINGS_HEADER_TABLE_SIZE};
+use super::{HeaderIndex, ENTRY_OVERHEAD};
 
 enum FieldRepresentation {
     Indexed,
@@ -87,93 +86,367 @@ pub enum DecoderError {
     IntegerDecodingError(IntegerDecodingError),
     StringDecodingError(StringDecodingError),
     InvalidMaxDynamicSize,
+    /// 解码后的头列表超过了SETTINGS_MAX_HEADER_LIST_SIZE
+    HeaderListTooLarge,
+    /// 头块结束时仍有未完成的字段
+    IncompleteBlock,
+}
+
+impl DecoderError {
+    /// 对应的RFC 7540错误码, 头块解码失败后压缩上下文不再一致, 均为COMPRESSION_ERROR
+    pub fn error_code(&self) -> ErrorCode {
+        ErrorCode::CompressionError
+    }
+
+    /// 解码失败均为连接级错误, 需发送GOAWAY
+    pub fn is_connection_error(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
 pub struct Decoder {
     pub index: Arc<RwLock<HeaderIndex>>,
+    max_allowed_size: usize,
+    max_header_list_size: usize,
+    /// 流式解码时未完成的字段数据
+    pending: Vec<u8>,
+    /// 流式解码时当前头块已累计的大小
+    block_size: usize,
+    /// 为true时不校验HTTP/2头部规则, 用于宽松的代理模式
+    lenient_headers: bool,
 }
 
 impl Decoder {
     pub fn new() -> Decoder {
         Decoder {
             index: Arc::new(RwLock::new(HeaderIndex::new())),
+            max_allowed_size: DEFAULT_SETTINGS_HEADER_TABLE_SIZE,
+            max_header_list_size: usize::MAX,
+            pending: Vec::new(),
+            block_size: 0,
+            lenient_headers: false,
         }
     }
 
+    /// 使用外部提供的动态表, 可由`HeaderIndex::shared`创建并在多处共享
     pub fn new_index(index: Arc<RwLock<HeaderIndex>>) -> Decoder {
-        Decoder { index }
+        Decoder {
+            index,
+            max_allowed_size: DEFAULT_SETTINGS_HEADER_TABLE_SIZE,
+            max_header_list_size: usize::MAX,
+            pending: Vec::new(),
+            block_size: 0,
+            lenient_headers: false,
+        }
+    }
+
+    /// 设置本端通告的SETTINGS_HEADER_TABLE_SIZE, 对端的动态表大小更新不能超过该值
+    pub fn set_max_allowed_size(&mut self, size: usize) {
+        self.max_allowed_size = size;
+    }
+
+    /// 设置本端通告的SETTINGS_MAX_HEADER_LIST_SIZE, 默认不限制
+    pub fn set_max_header_list_size(&mut self, size: usize) {
+        self.max_header_list_size = size;
+    }
+
+    /// 设置是否跳过RFC 7540 8.1.2中的头部校验, 默认校验,
+    /// 如伪头部顺序, 未知或重复的伪头部, 及Connection等连接相关的头
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::frame::{Flag, FrameHeader, Headers, Kind, StreamIdentifier};
+    /// use webparse::http2::{encoder::Encoder, Decoder};
+    /// use webparse::{HeaderMap, HeaderName, HeaderValue};
+    ///
+    /// let fields = [("connection", "close"), (":method", "GET"), (":path", "/")];
+    /// let fields: Vec<(HeaderName, HeaderValue)> = fields
+    ///     .iter()
+    ///     .map(|(n, v)| (HeaderName::from_static(n), HeaderValue::from_static(v)))
+    ///     .collect();
+    /// let block = Encoder::new().encode(fields.iter().map(|(n, v)| (n, v)));
+    /// let head = FrameHeader::new(Kind::Headers, Flag::end_headers(), StreamIdentifier(1));
+    ///
+    /// let mut decoder = Decoder::new();
+    /// let mut headers = Headers::new(head, HeaderMap::new());
+    /// assert!(headers.parse(block.clone(), &mut decoder, usize::MAX).is_err());
+    ///
+    /// decoder.set_lenient_headers(true);
+    /// let mut headers = Headers::new(head, HeaderMap::new());
+    /// assert!(headers.parse(block, &mut decoder, usize::MAX).is_ok());
+    /// ```
+    pub fn set_lenient_headers(&mut self, lenient: bool) {
+        self.lenient_headers = lenient;
+    }
+
+    pub fn is_lenient_headers(&self) -> bool {
+        self.lenient_headers
+    }
+
+    /// 查看索引对应的头, 静态表及当前的动态表, 不改变解码状态, 不存在时返回None
+    ///
+    /// 如头块中的`0x82`为索引头字段, 索引为`0x82 & 0x7f`即2
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::Decoder;
+    ///
+    /// let mut decoder = Decoder::new();
+    /// let (name, value) = decoder.peek_indexed(0x82 & 0x7f).unwrap();
+    /// assert_eq!((name.as_bytes(), value.as_bytes()), (&b":method"[..], &b"GET"[..]));
+    /// assert!(decoder.peek_indexed(0).is_none());
+    /// assert!(decoder.peek_indexed(62).is_none());
+    ///
+    /// // 字面量并加入动态表: custom-key: custom-header
+    /// let mut block = &b"\x40\x0acustom-key\x0dcustom-header"[..];
+    /// decoder.decode(&mut block).unwrap();
+    /// let (name, value) = decoder.peek_indexed(62).unwrap();
+    /// assert_eq!((name.as_bytes(), value.as_bytes()), (&b"custom-key"[..], &b"custom-header"[..]));
+    /// ```
+    pub fn peek_indexed(&self, index: usize) -> Option<(HeaderName, HeaderValue)> {
+        let header = self.index.read().unwrap();
+        header
+            .get_from_index(index)
+            .map(|(name, value)| (name.clone(), value.clone()))
     }
 
     pub fn decode<B: Buf>(
         &mut self,
         buf: &mut B,
+    ) -> WebResult<Vec<(HeaderName, HeaderValue)>> {
+        let limit = self.max_header_list_size;
+        self.decode_with_limit(buf, limit)
+    }
+
+    /// 解码时限制头列表的大小(名字+值+32), 超出时返回`DecoderError::HeaderListTooLarge`,
+    /// 在分配内存之前检查, 防止压缩炸弹
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::{Decoder, DecoderError};
+    /// use webparse::{WebError, Http2Error};
+    /// let mut decoder = Decoder::new();
+    /// // 四次引用静态表中的 :method GET
+    /// let mut buf = &[0x82u8, 0x82, 0x82, 0x82][..];
+    /// let err = decoder.decode_with_limit(&mut buf, 120).unwrap_err();
+    /// assert!(matches!(err, WebError::Http2(Http2Error::Decoder(DecoderError::HeaderListTooLarge))));
+    /// ```
+    pub fn decode_with_limit<B: Buf>(
+        &mut self,
+        buf: &mut B,
+        limit: usize,
     ) -> WebResult<Vec<(HeaderName, HeaderValue)>> {
         let mut header_list = Vec::new();
-        self.decode_with_cb(buf, |n, v| {
+        self.decode_limit_cb(buf, limit, |n, v| {
             header_list.push((n.into_owned(), v.into_owned()))
         })?;
         Ok(header_list)
     }
 
-    pub fn decode_with_cb<F, B: Buf>(&mut self, buf: &mut B, mut cb: F) -> WebResult<()>
+    pub fn decode_with_cb<F, B: Buf>(&mut self, buf: &mut B, cb: F) -> WebResult<()>
+    where
+        F: FnMut(Cow<HeaderName>, Cow<HeaderValue>),
+    {
+        let limit = self.max_header_list_size;
+        self.decode_limit_cb(buf, limit, cb)
+    }
+
+    fn decode_limit_cb<F, B: Buf>(&mut self, buf: &mut B, limit: usize, mut cb: F) -> WebResult<()>
+    where
+        F: FnMut(Cow<HeaderName>, Cow<HeaderValue>),
+    {
+        let mut list_size = 0usize;
+        self.decode_fields(buf, limit, &mut list_size, false, &mut cb)
+    }
+
+    /// 流式解码头块, 头块跨越多个CONTINUATION帧时可逐帧调用而无需先拼接,
+    /// 不完整的字段会保存到下一次调用, `end_of_block`为true时表示头块结束,
+    /// 此时仍有未完成的字段则返回`DecoderError::IncompleteBlock`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::Decoder;
+    /// let mut decoder = Decoder::new();
+    /// let mut headers = vec![];
+    /// let mut first = &[0x00, 0x03, b'a', b'b'][..];
+    /// decoder.decode_partial_with_cb(&mut first, false, |n, v| headers.push((n.into_owned(), v.into_owned()))).unwrap();
+    /// assert!(headers.is_empty());
+    /// let mut second = &[b'c', 0x01, b'x', 0x82][..];
+    /// decoder.decode_partial_with_cb(&mut second, true, |n, v| headers.push((n.into_owned(), v.into_owned()))).unwrap();
+    /// assert_eq!(headers.len(), 2);
+    /// assert_eq!(headers[0].0.name(), "abc");
+    /// assert_eq!(headers[0].1.as_bytes(), b"x");
+    /// ```
+    pub fn decode_partial_with_cb<F, B: Buf>(
+        &mut self,
+        buf: &mut B,
+        end_of_block: bool,
+        mut cb: F,
+    ) -> WebResult<()>
+    where
+        F: FnMut(Cow<HeaderName>, Cow<HeaderValue>),
+    {
+        let limit = self.max_header_list_size;
+        let mut list_size = self.block_size;
+        let ret = if self.pending.is_empty() {
+            let ret = self.decode_fields(buf, limit, &mut list_size, true, &mut cb);
+            if ret.is_ok() {
+                Self::save_pending(&mut self.pending, buf);
+            }
+            ret
+        } else {
+            let mut pending = core::mem::take(&mut self.pending);
+            Self::save_pending(&mut pending, buf);
+            let mut data = &pending[..];
+            let ret = self.decode_fields(&mut data, limit, &mut list_size, true, &mut cb);
+            if ret.is_ok() {
+                let used = pending.len() - data.len();
+                pending.drain(..used);
+                self.pending = pending;
+            }
+            ret
+        };
+        self.block_size = list_size;
+        if ret.is_err() || end_of_block {
+            let incomplete = !self.pending.is_empty();
+            self.pending.clear();
+            self.block_size = 0;
+            ret?;
+            if incomplete {
+                return Err(Http2Error::into(DecoderError::IncompleteBlock));
+            }
+        }
+        Ok(())
+    }
+
+    fn save_pending<B: Buf>(pending: &mut Vec<u8>, buf: &mut B) {
+        while buf.has_remaining() {
+            let chunk = buf.chunk();
+            let len = chunk.len();
+            pending.extend_from_slice(chunk);
+            buf.advance(len);
+        }
+    }
+
+    /// 数据不足以解析完整字段时的错误
+    fn is_incomplete(err: &WebError) -> bool {
+        matches!(
+            err,
+            WebError::Http2(Http2Error::Decoder(
+                DecoderError::IntegerDecodingError(IntegerDecodingError::NotEnoughOctets)
+                    | DecoderError::StringDecodingError(StringDecodingError::NotEnoughOctets)
+            ))
+        )
+    }
+
+    /// 逐个解码字段, `partial`为true时遇到不完整的字段则停止, 剩余数据保留在`buf`中
+    fn decode_fields<F, B: Buf>(
+        &mut self,
+        buf: &mut B,
+        limit: usize,
+        list_size: &mut usize,
+        partial: bool,
+        cb: &mut F,
+    ) -> WebResult<()>
     where
         F: FnMut(Cow<HeaderName>, Cow<HeaderValue>),
     {
         while buf.has_remaining() {
-            let initial_octet = buf.peek().unwrap();
-            let buffer_leftover = buf.chunk();
-            let consumed = match FieldRepresentation::new(initial_octet) {
-                FieldRepresentation::Indexed => {
-                    let consumed = (self.decode_indexed(initial_octet, |name, value| {
+            let consumed = match self.decode_field(buf.chunk(), limit, list_size, cb) {
+                Ok(consumed) => consumed,
+                Err(e) if partial && Self::is_incomplete(&e) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            buf.advance(consumed);
+        }
+        Ok(())
+    }
+
+    fn decode_field<F>(
+        &mut self,
+        buffer_leftover: &[u8],
+        limit: usize,
+        list_size: &mut usize,
+        cb: &mut F,
+    ) -> WebResult<usize>
+    where
+        F: FnMut(Cow<HeaderName>, Cow<HeaderValue>),
+    {
+        let initial_octet = buffer_leftover[0];
+        // 剩余可用于名字及值的字节数
+        let budget = limit.saturating_sub(*list_size).saturating_sub(ENTRY_OVERHEAD);
+        let consumed = match FieldRepresentation::new(initial_octet) {
+            FieldRepresentation::Indexed => {
+                let mut ret = Ok(());
+                let consumed = (self.decode_indexed(buffer_leftover, |name, value| {
+                    ret = Self::check_list_size(list_size, limit, name, value);
+                    if ret.is_ok() {
                         cb(Cow::Borrowed(name), Cow::Borrowed(value));
-                    }))?;
-                    consumed
-                }
-                FieldRepresentation::LiteralWithIncrementalIndexing => {
-                    let ((name, value), consumed) = {
-                        let ((name, value), consumed) =
-                            self.decode_literal(buffer_leftover, true)?;
-                        cb(Cow::Borrowed(&name), Cow::Borrowed(&value));
-
-                        // Since we are to add the decoded header to the header table, we need to
-                        // convert them into owned buffers that the decoder can keep internally.
-                        let name = name.clone();
-                        let value = value.clone();
-                        ((name, value), consumed)
-                    };
-                    // This cannot be done in the same scope as the `decode_literal` call, since
-                    // Rust cannot figure out that the `into_owned` calls effectively drop the
-                    // borrow on `self` that the `decode_literal` return value had. Since adding
-                    // a header to the table requires a `&mut self`, it fails to compile.
-                    // Manually separating it out here works around it...
-                    self.index.write().unwrap().add_header(name, value);
-                    consumed
-                }
-                FieldRepresentation::LiteralWithoutIndexing => {
-                    let ((name, value), consumed) = (self.decode_literal(buffer_leftover, false))?;
-                    cb(Cow::Owned(name), Cow::Owned(value));
+                    }
+                }))?;
+                ret?;
+                consumed
+            }
+            FieldRepresentation::LiteralWithIncrementalIndexing => {
+                let ((name, value), consumed) = {
+                    let ((name, value), consumed) =
+                        self.decode_literal(buffer_leftover, true, budget)?;
+                    Self::check_list_size(list_size, limit, &name, &value)?;
+                    cb(Cow::Borrowed(&name), Cow::Borrowed(&value));
+
+                    // Since we are to add the decoded header to the header table, we need to
+                    // convert them into owned buffers that the decoder can keep internally.
+                    let name = name.clone();
+                    let value = value.clone();
+                    ((name, value), consumed)
+                };
+                // This cannot be done in the same scope as the `decode_literal` call, since
+                // Rust cannot figure out that the `into_owned` calls effectively drop the
+                // borrow on `self` that the `decode_literal` return value had. Since adding
+                // a header to the table requires a `&mut self`, it fails to compile.
+                // Manually separating it out here works around it...
+                self.index.write().unwrap().add_header(name, value);
+                consumed
+            }
+            FieldRepresentation::LiteralWithoutIndexing => {
+                let ((name, value), consumed) = (self.decode_literal(buffer_leftover, false, budget))?;
+                Self::check_list_size(list_size, limit, &name, &value)?;
+                cb(Cow::Owned(name), Cow::Owned(value));
 
-                    consumed
-                }
-                FieldRepresentation::LiteralNeverIndexed => {
-                    // Same as the previous one, except if we were also a proxy
-                    // we would need to make sure not to change the
-                    // representation received here. We don't care about this
-                    // for now.
-                    let ((name, value), consumed) = (self.decode_literal(buffer_leftover, false))?;
-                    cb(Cow::Owned(name), Cow::Owned(value));
-
-                    consumed
-                }
-                FieldRepresentation::SizeUpdate => {
-                    // Handle the dynamic table size update...
-                    // self.update_max_dynamic_size(buffer_leftover)
-                    0
-                }
-            };
+                consumed
+            }
+            FieldRepresentation::LiteralNeverIndexed => {
+                // Same as the previous one, except if we were also a proxy
+                // we would need to make sure not to change the
+                // representation received here. We don't care about this
+                // for now.
+                let ((name, value), consumed) = (self.decode_literal(buffer_leftover, false, budget))?;
+                Self::check_list_size(list_size, limit, &name, &value)?;
+                cb(Cow::Owned(name), Cow::Owned(value));
+
+                consumed
+            }
+            FieldRepresentation::SizeUpdate => {
+                self.update_max_dynamic_size(buffer_leftover)?
+            }
+        };
+        Ok(consumed)
+    }
 
-            buf.advance(consumed);
+    fn check_list_size(
+        list_size: &mut usize,
+        limit: usize,
+        name: &HeaderName,
+        value: &HeaderValue,
+    ) -> WebResult<()> {
+        *list_size = list_size.saturating_add(HeaderIndex::entry_size(name, value));
+        if *list_size > limit {
+            return Err(Http2Error::into(DecoderError::HeaderListTooLarge));
         }
         Ok(())
     }
@@ -186,61 +459,19 @@ impl Decoder {
     /// Returns a tuple representing the decoded integer and the number
     /// of bytes from the buffer that were used.
     fn decode_integer(buf: &[u8], prefix_size: u8) -> WebResult<(usize, usize)> {
-        if prefix_size < 1 || prefix_size > 8 {
-            return Err(Http2Error::into(DecoderError::IntegerDecodingError(
-                IntegerDecodingError::InvalidPrefix,
-            )));
-        }
-        if buf.len() < 1 {
-            return Err(Http2Error::into(DecoderError::IntegerDecodingError(
-                IntegerDecodingError::NotEnoughOctets,
-            )));
-        }
-
-        // Make sure there's no overflow in the shift operation
-        let Wrapping(mask) = if prefix_size == 8 {
-            Wrapping(0xFF)
-        } else {
-            Wrapping(1u8 << prefix_size) - Wrapping(1)
-        };
-        let mut value = (buf[0] & mask) as usize;
-        if value < (mask as usize) {
-            // Value fits in the prefix bits.
-            return Ok((value, 1));
-        }
-
-        let mut total = 1;
-        let mut m = 0;
-        let octet_limit = 5;
-
-        for &b in buf[1..].iter() {
-            total += 1;
-            value += ((b & 127) as usize) * (1 << m);
-            m += 7;
-
-            if b & 128 != 128 {
-                // Most significant bit is not set => no more continuation bytes
-                return Ok((value, total));
-            }
-
-            if total == octet_limit {
-                // The spec tells us that we MUST treat situations where the
-                // encoded representation is too long (in octets) as an error.
-                return Err(Http2Error::into(DecoderError::IntegerDecodingError(
-                    IntegerDecodingError::TooManyOctets,
-                )));
-            }
-        }
-
-        // If we have reached here, it means the buffer has been exhausted without
-        // hitting the termination condition.
-        Err(Http2Error::into(DecoderError::IntegerDecodingError(
-            IntegerDecodingError::NotEnoughOctets,
-        )))
+        let mut cur = buf;
+        let value = cur.get_hpack_int(prefix_size)?;
+        Ok((value, buf.len() - cur.len()))
     }
 
-    fn decode_string<'a>(buf: &'a [u8]) -> WebResult<(Cow<'a, [u8]>, usize)> {
+    /// 解码字符串, 结果超过`budget`时直接返回错误, 哈夫曼编码按最长30位的码字预先估算
+    fn decode_string(buf: &[u8], budget: usize) -> WebResult<(Cow<'_, [u8]>, usize)> {
         let (len, consumed) = Self::decode_integer(buf, 7)?;
+        let huffman = buf[0] & 128 == 128;
+        let min_len = if huffman { len.saturating_mul(8) / 30 } else { len };
+        if min_len > budget {
+            return Err(Http2Error::into(DecoderError::HeaderListTooLarge));
+        }
         // debug!("decode_string: Consumed = {}, len = {}", consumed, len);
         if consumed + len > buf.len() {
             return Err(Http2Error::into(DecoderError::StringDecodingError(
@@ -248,7 +479,7 @@ impl Decoder {
             )));
         }
         let raw_string = &buf[consumed..consumed + len];
-        if buf[0] & 128 == 128 {
+        if huffman {
             // debug!("decode_string: Using the Huffman code");
             // Huffman coding used: pass the raw octets to the Huffman decoder
             // and return its result.
@@ -259,6 +490,9 @@ impl Decoder {
                 }
                 Ok(res) => res,
             };
+            if decoded.len() > budget {
+                return Err(Http2Error::into(DecoderError::HeaderListTooLarge));
+            }
             Ok((Cow::Owned(decoded), consumed + len))
         } else {
             // The octets were transmitted raw
@@ -271,6 +505,7 @@ impl Decoder {
         &self,
         buf: &[u8],
         index: bool,
+        budget: usize,
     ) -> WebResult<((HeaderName, HeaderValue), usize)> {
         let prefix = if index { 6 } else { 4 };
         let (table_index, mut consumed) = Self::decode_integer(buf, prefix)?;
@@ -278,7 +513,7 @@ impl Decoder {
         // First read the name appropriately
         let name = if table_index == 0 {
             // Read name string as literal
-            let (name, name_len) = Self::decode_string(&buf[consumed..])?;
+            let (name, name_len) = Self::decode_string(&buf[consumed..], budget)?;
             consumed += name_len;
             HeaderName::from_bytes(&name).unwrap()
         } else {
@@ -294,23 +529,34 @@ impl Decoder {
         };
 
         // Now read the value as a literal...
-        let (value, value_len) = Self::decode_string(&buf[consumed..])?;
+        let budget = budget.saturating_sub(name.bytes_len());
+        let (value, value_len) = Self::decode_string(&buf[consumed..], budget)?;
         consumed += value_len;
 
         Ok(((name, HeaderValue::from_bytes(&value)), consumed))
     }
 
-    fn decode_indexed<F>(&self, index: u8, call: F) -> WebResult<usize>
+    /// 处理动态表大小的更新, 不能超过SETTINGS_HEADER_TABLE_SIZE协商的值
+    fn update_max_dynamic_size(&mut self, buf: &[u8]) -> WebResult<usize> {
+        let (new_size, consumed) = Self::decode_integer(buf, 5)?;
+        if new_size > self.max_allowed_size {
+            return Err(Http2Error::into(DecoderError::InvalidMaxDynamicSize));
+        }
+        self.index.write().unwrap().set_max_table_size(new_size);
+        Ok(consumed)
+    }
+
+    fn decode_indexed<F>(&self, buf: &[u8], call: F) -> WebResult<usize>
     where
         F: FnOnce(&HeaderName, &HeaderValue),
     {
-        let index = index & 0x7f;
+        let (index, consumed) = Self::decode_integer(buf, 7)?;
         let header = self.index.read().unwrap();
         let (name, value) = header
-            .get_from_index(index as usize)
+            .get_from_index(index)
             .ok_or(Http2Error::into(DecoderError::HeaderIndexOutOfBounds))?;
         call(name, value);
-        Ok(1)
+        Ok(consumed)
     }
 
     fn get_from_table<F>(&self, index: usize, call: F) -> WebResult<()>
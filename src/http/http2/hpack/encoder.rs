@@ -10,17 +10,46 @@
 // -----
 // Created Date: 2023/08/22 10:50:59
 
-use super::{HeaderIndex, huffman::HuffmanEncoder};
-use crate::{BinaryMut, Buf, BufMut, HeaderName, HeaderValue};
+use super::{HeaderIndex, ENTRY_OVERHEAD, huffman::HuffmanEncoder};
+use crate::http2::Http2Error;
+use crate::{BinaryMut, Buf, BufMut, HeaderName, HeaderValue, WebResult};
 use std::{
+    collections::HashMap,
     io,
-    num::Wrapping,
+    iter::Peekable,
     sync::{Arc, RwLock},
 };
 
+/// 头在未完全匹配索引表时的编码方式, 见RFC 7541 6.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Indexing {
+    /// 编码为字面量并加入动态表
+    Incremental,
+    /// 编码为字面量, 不加入动态表, 适合每次都不同的值
+    Without,
+    /// 同`Without`, 并要求中间节点也不能加入动态表, 适合敏感的值
+    Never,
+}
+
+/// `Encoder::encode_into_limited`的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeState {
+    /// 本次编码的头的数量
+    pub consumed: usize,
+    /// 本次写入的字节数
+    pub written: usize,
+    /// 是否所有的头都已编码
+    pub complete: bool,
+}
+
 pub struct Encoder {
     pub index: Arc<RwLock<HeaderIndex>>,
     pub max_frame_size: usize,
+    /// 单个头的最大大小, 按RFC 7541 §4.1计算为名字与值的长度加32
+    max_header_size: Option<usize>,
+    /// 按头名字设置的编码方式, 未设置的使用default_indexing
+    indexing: HashMap<HeaderName, Indexing>,
+    default_indexing: Indexing,
 }
 
 impl Encoder {
@@ -28,13 +57,104 @@ impl Encoder {
         Encoder {
             index: Arc::new(RwLock::new(HeaderIndex::new())),
             max_frame_size: 16_384,
+            max_header_size: None,
+            indexing: HashMap::new(),
+            default_indexing: Indexing::Incremental,
         }
     }
 
+    /// 使用外部提供的动态表, 可由`HeaderIndex::shared`创建并在多处共享
     pub fn new_index(index: Arc<RwLock<HeaderIndex>>, max_frame_size: usize) -> Encoder {
         Encoder {
             index,
             max_frame_size,
+            max_header_size: None,
+            indexing: HashMap::new(),
+            default_indexing: Indexing::Incremental,
+        }
+    }
+
+    /// 设置单个头的最大大小, 超出时编码头块返回`Http2Error::HeaderTooLarge`,
+    /// 未设置时不限制, 超出max_frame_size的头会被拆分到CONTINUATION帧中
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{HeaderName, HeaderValue};
+    /// use webparse::http2::encoder::Encoder;
+    ///
+    /// let mut encoder = Encoder::new();
+    /// encoder.set_max_header_size(Some(64));
+    /// let name = HeaderName::from_static("x-token");
+    /// assert!(encoder.check_header_size(&name, &HeaderValue::from_static("short")).is_ok());
+    /// let value = HeaderValue::Value(vec![b'a'; 64]);
+    /// assert!(encoder.check_header_size(&name, &value).is_err());
+    /// ```
+    pub fn set_max_header_size(&mut self, size: Option<usize>) {
+        self.max_header_size = size;
+    }
+
+    pub fn max_header_size(&self) -> Option<usize> {
+        self.max_header_size
+    }
+
+    /// 检查单个头是否超出设置的最大大小
+    pub fn check_header_size(&self, name: &HeaderName, value: &HeaderValue) -> WebResult<()> {
+        match self.max_header_size {
+            Some(max) if name.bytes_len() + value.bytes_len() + ENTRY_OVERHEAD > max => {
+                Err(Http2Error::HeaderTooLarge.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 设置该名字的头的编码方式, 如`:path`或`content-length`每次都不同, 不加入动态表
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{HeaderName, HeaderValue};
+    /// use webparse::http2::encoder::{Encoder, Indexing};
+    ///
+    /// let mut encoder = Encoder::new();
+    /// encoder.set_indexing(HeaderName::from_static(":path"), Indexing::Without);
+    /// let path = (HeaderName::from_static(":path"), HeaderValue::from_static("/api/1"));
+    /// let block = encoder.encode([(&path.0, &path.1)].into_iter());
+    /// // 使用静态表中:path的名字, 不加入动态表
+    /// assert_eq!(block[0], 0x04);
+    /// assert_eq!(encoder.index.read().unwrap().get_size(), 0);
+    /// ```
+    pub fn set_indexing(&mut self, name: HeaderName, indexing: Indexing) {
+        self.indexing.insert(name, indexing);
+    }
+
+    /// 设置未单独配置的头的编码方式, 默认为`Indexing::Incremental`
+    pub fn set_default_indexing(&mut self, indexing: Indexing) {
+        self.default_indexing = indexing;
+    }
+
+    pub fn indexing(&self, name: &HeaderName) -> Indexing {
+        self.indexing.get(name).copied().unwrap_or(self.default_indexing)
+    }
+
+    /// 常用的编码方式: 每次都变化的头不加入动态表, 认证信息不允许被索引
+    pub fn use_common_indexing(&mut self) {
+        for name in [
+            ":path",
+            "content-length",
+            "content-range",
+            "etag",
+            "age",
+            "date",
+            "last-modified",
+            "if-modified-since",
+            "if-none-match",
+            "location",
+        ] {
+            self.set_indexing(HeaderName::from_static(name), Indexing::Without);
+        }
+        for name in ["authorization", "proxy-authorization"] {
+            self.set_indexing(HeaderName::from_static(name), Indexing::Never);
         }
     }
 
@@ -61,42 +181,118 @@ impl Encoder {
         Ok(())
     }
 
+    /// 在`budget`字节内尽可能多的编码头, 放不下的头及之后的头保留在`headers`中,
+    /// 下次以新的预算继续调用即可, 只有写入的头才会加入动态表
+    ///
+    /// `consumed`为0且未完成时表示单个头就超出了预算
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf, HeaderName, HeaderValue};
+    /// use webparse::http2::{encoder::Encoder, Decoder};
+    ///
+    /// let list: Vec<(HeaderName, HeaderValue)> = (0..10)
+    ///     .map(|i| (HeaderName::from_static("x-key"), HeaderValue::Value(format!("value-{}", i).into_bytes())))
+    ///     .collect();
+    /// let mut encoder = Encoder::new();
+    /// let mut headers = list.iter().map(|(n, v)| (n, v)).peekable();
+    /// let mut blocks = vec![];
+    /// loop {
+    ///     let mut block = BinaryMut::new();
+    ///     let state = encoder.encode_into_limited(&mut headers, &mut block, 20).unwrap();
+    ///     assert!(state.written <= 20 && state.consumed > 0);
+    ///     blocks.extend_from_slice(block.chunk());
+    ///     if state.complete {
+    ///         break;
+    ///     }
+    /// }
+    /// let decoded = Decoder::new().decode(&mut &blocks[..]).unwrap();
+    /// assert_eq!(decoded, list);
+    /// ```
+    pub fn encode_into_limited<'b, I, B: BufMut + Buf>(
+        &mut self,
+        headers: &mut Peekable<I>,
+        writer: &mut B,
+        budget: usize,
+    ) -> io::Result<EncodeState>
+    where
+        I: Iterator<Item = (&'b HeaderName, &'b HeaderValue)>,
+    {
+        let mut state = EncodeState {
+            consumed: 0,
+            written: 0,
+            complete: false,
+        };
+        while let Some(&header) = headers.peek() {
+            let mut encoded = BinaryMut::new();
+            let should_index = self.encode_representation(header, &mut encoded)?;
+            if state.written + encoded.remaining() > budget {
+                return Ok(state);
+            }
+            state.written += writer.put_slice(encoded.chunk());
+            state.consumed += 1;
+            if should_index {
+                self.index
+                    .write()
+                    .unwrap()
+                    .add_header(header.0.clone(), header.1.clone());
+            }
+            headers.next();
+        }
+        state.complete = true;
+        Ok(state)
+    }
+
     pub fn encode_header_into<B: BufMut + Buf>(
         &mut self,
         header: (&HeaderName, &HeaderValue),
         writer: &mut B,
     ) -> io::Result<()> {
+        if self.encode_representation(header, writer)? {
+            self.index
+                .write()
+                .unwrap()
+                .add_header(header.0.clone(), header.1.clone());
+        }
+        Ok(())
+    }
+
+    /// 写入头的编码, 不修改动态表, 返回是否需要加入动态表
+    fn encode_representation<B: BufMut + Buf>(
+        &mut self,
+        header: (&HeaderName, &HeaderValue),
+        writer: &mut B,
+    ) -> io::Result<bool> {
         let value = { self.index.read().unwrap().find_header(header) };
-        
+        let indexing = self.indexing(header.0);
+
         match value {
             None => {
-                self.encode_literal(header, true, writer)?;
-                self.index
-                    .write()
-                    .unwrap()
-                    .add_header(header.0.clone(), header.1.clone());
+                self.encode_literal(header, indexing, writer)?;
             }
             Some((index, false)) => {
-                self.encode_indexed_name((index, &header.1), true, writer)?;
-                self.index
-                    .write()
-                    .unwrap()
-                    .add_header(header.0.clone(), header.1.clone());
+                self.encode_indexed_name((index, &header.1), indexing, writer)?;
             }
             Some((index, true)) => {
                 self.encode_indexed(index, writer)?;
+                return Ok(false);
             }
         };
-        Ok(())
+        Ok(indexing == Indexing::Incremental)
     }
 
     fn encode_literal<B: BufMut + Buf>(
         &mut self,
         header: (&HeaderName, &HeaderValue),
-        should_index: bool,
+        indexing: Indexing,
         buf: &mut B,
     ) -> io::Result<()> {
-        let mask = if should_index { 0x40 } else { 0x0 };
+        let mask = match indexing {
+            Indexing::Incremental => 0x40,
+            Indexing::Without => 0x0,
+            Indexing::Never => 0x10,
+        };
 
         buf.put_slice(&[mask]);
         self.encode_string_literal_lower(&header.0.as_bytes(), buf)?;
@@ -129,10 +325,14 @@ impl Encoder {
     fn encode_indexed_name<B: BufMut + Buf>(
         &mut self,
         header: (usize, &HeaderValue),
-        should_index: bool,
+        indexing: Indexing,
         buf: &mut B,
     ) -> io::Result<()> {
-        let (mask, prefix) = if should_index { (0x40, 6) } else { (0x0, 4) };
+        let (mask, prefix) = match indexing {
+            Indexing::Incremental => (0x40, 6),
+            Indexing::Without => (0x0, 4),
+            Indexing::Never => (0x10, 4),
+        };
 
         Self::encode_integer_into(header.0, prefix, mask, buf)?;
         // So far, we rely on just one strategy for encoding string literals.
@@ -150,30 +350,12 @@ impl Encoder {
     }
 
     pub fn encode_integer_into<B: BufMut + Buf>(
-        mut value: usize,
+        value: usize,
         prefix_size: u8,
         leading_bits: u8,
         writer: &mut B,
     ) -> io::Result<()> {
-        let Wrapping(mask) = if prefix_size >= 8 {
-            Wrapping(0xFF)
-        } else {
-            Wrapping(1u8 << prefix_size) - Wrapping(1)
-        };
-        let leading_bits = leading_bits & (!mask);
-        let mask = mask as usize;
-        if value < mask {
-            writer.put_slice(&[leading_bits | value as u8]);
-            return Ok(());
-        }
-
-        writer.put_slice(&[leading_bits | mask as u8]);
-        value -= mask;
-        while value >= 128 {
-            writer.put_slice(&[((value % 128) + 128) as u8]);
-            value = value / 128;
-        }
-        writer.put_slice(&[value as u8]);
+        writer.put_hpack_int(value, prefix_size, leading_bits);
         Ok(())
     }
 }
@@ -13,12 +13,42 @@
 use crate::{http2::DEFAULT_SETTINGS_HEADER_TABLE_SIZE, HeaderName, HeaderValue};
 use lazy_static::lazy_static;
 use std::collections::{vec_deque, HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+/// 每个动态表条目除名字和值外额外计算的字节数 (HPACK 4.1)
+pub const ENTRY_OVERHEAD: usize = 32;
+
+/// 动态表被淘汰条目时的回调
+pub type EvictCallback = fn(&HeaderName, &HeaderValue);
 
 #[derive(Debug, Clone)]
 pub struct HeaderIndex {
     table: VecDeque<(HeaderName, HeaderValue)>,
     size: usize,
     max_size: usize,
+    stats: HeaderIndexStats,
+    on_evict: Option<EvictCallback>,
+}
+
+/// 动态表的统计信息
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeaderIndexStats {
+    /// 累计加入的条目数
+    pub inserted: u64,
+    /// 累计淘汰的条目数
+    pub evicted: u64,
+    /// 累计淘汰的字节数, 含每条目32字节的额外开销
+    pub evicted_bytes: u64,
+}
+
+/// 动态表在某一时刻的状态, 用于调试
+#[derive(Debug, Clone)]
+pub struct HeaderIndexSnapshot {
+    pub size: usize,
+    pub max_size: usize,
+    pub stats: HeaderIndexStats,
+    /// 条目及其在HPACK中的索引, 从最新加入的开始
+    pub entries: Vec<(usize, HeaderName, HeaderValue)>,
 }
 
 /// An `Iterator` through elements of the `DynamicTable`.
@@ -51,6 +81,17 @@ impl HeaderIndex {
         HeaderIndex::with_size(DEFAULT_SETTINGS_HEADER_TABLE_SIZE as usize)
     }
 
+    /// 创建可共享的动态表, 用于传给`Decoder::new_index`或`Encoder::new_index`
+    pub fn shared(max_size: usize) -> Arc<RwLock<HeaderIndex>> {
+        Arc::new(RwLock::new(HeaderIndex::with_size(max_size)))
+    }
+
+    /// 条目在动态表中占用的大小
+    #[inline]
+    pub fn entry_size(name: &HeaderName, value: &HeaderValue) -> usize {
+        name.bytes_len() + value.bytes_len() + ENTRY_OVERHEAD
+    }
+
     pub fn get_from_index(&self, index: usize) -> Option<(&HeaderName, &HeaderValue)> {
         let real_index = if index > 0 { index - 1 } else { return None };
 
@@ -71,29 +112,73 @@ impl HeaderIndex {
         }
     }
 
+    /// 查找头在索引表中的位置, 返回索引及名字和值是否都匹配,
+    /// 优先完全匹配(静态表在前), 其次仅名字匹配
     pub fn find_header(&self, header: (&HeaderName, &HeaderValue)) -> Option<(usize, bool)> {
-        if STATIC_HASH.contains_key(header.0) {
-            let v = &STATIC_HASH[header.0];
-            if v.contains_key(header.1) {
-                return Some((v[header.1], true));
-            } else if v.contains_key(&EMPTY_HEADER_VALUE) {
-                return Some((v[&EMPTY_HEADER_VALUE], false));
-            }
-        } else {
-            for (idx, value) in self.iter().enumerate() {
-                if value.0 == header.0 && value.1 == header.1 {
+        let statics = STATIC_HASH.get(header.0);
+        if let Some(index) = statics.and_then(|v| v.get(header.1)) {
+            return Some((*index, true));
+        }
+        let mut name_index = statics.and_then(|v| v.values().min().copied());
+        for (idx, value) in self.iter().enumerate() {
+            if value.0 == header.0 {
+                if value.1 == header.1 {
                     return Some((idx + 1 + STATIC_TABLE.len(), true));
                 }
+                name_index.get_or_insert(idx + 1 + STATIC_TABLE.len());
             }
         }
-        None
+        name_index.map(|index| (index, false))
     }
 
-    fn with_size(max_size: usize) -> HeaderIndex {
+    pub fn with_size(max_size: usize) -> HeaderIndex {
         HeaderIndex {
             table: VecDeque::new(),
             size: 0,
             max_size,
+            stats: HeaderIndexStats::default(),
+            on_evict: None,
+        }
+    }
+
+    /// 设置条目被淘汰时的回调
+    pub fn set_evict_callback(&mut self, callback: Option<EvictCallback>) {
+        self.on_evict = callback;
+    }
+
+    pub fn stats(&self) -> HeaderIndexStats {
+        self.stats
+    }
+
+    /// 获取当前动态表的状态
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::http2::HeaderIndex;
+    /// use webparse::{HeaderName, HeaderValue};
+    /// let mut index = HeaderIndex::with_size(80);
+    /// index.add_header(HeaderName::from_static("a"), HeaderValue::from_static("1"));
+    /// index.add_header(HeaderName::from_static("b"), HeaderValue::from_static("2"));
+    /// index.add_header(HeaderName::from_static("c"), HeaderValue::from_static("3"));
+    /// let snapshot = index.snapshot();
+    /// assert_eq!(snapshot.size, 68);
+    /// assert_eq!(snapshot.entries[0].0, 62);
+    /// assert_eq!(snapshot.stats.inserted, 3);
+    /// assert_eq!(snapshot.stats.evicted, 1);
+    /// ```
+    pub fn snapshot(&self) -> HeaderIndexSnapshot {
+        let entries = self
+            .table
+            .iter()
+            .enumerate()
+            .map(|(idx, v)| (idx + 1 + STATIC_TABLE.len(), v.0.clone(), v.1.clone()))
+            .collect();
+        HeaderIndexSnapshot {
+            size: self.size,
+            max_size: self.max_size,
+            stats: self.stats,
+            entries,
         }
     }
 
@@ -110,6 +195,7 @@ impl HeaderIndex {
     }
 
     pub fn set_max_table_size(&mut self, new_max_size: usize) {
+        crate::web_trace!("HPACK: 动态表大小更新; {} -> {}", self.max_size, new_max_size);
         self.max_size = new_max_size;
         // Make the table size fit within the new constraints.
         self.consolidate_table();
@@ -121,13 +207,13 @@ impl HeaderIndex {
     }
 
     pub fn add_header(&mut self, name: HeaderName, value: HeaderValue) {
-        self.size += name.bytes_len() + value.bytes_len() + 32;
-        // debug!("New dynamic table size {}", self.size);
+        self.size += Self::entry_size(&name, &value);
+        self.stats.inserted += 1;
+        crate::web_trace!("HPACK: 动态表插入; name={} size={}", name, self.size);
         // Now add it to the internal buffer
         self.table.push_front((name, value));
         // ...and make sure we're not over the maximum size.
         self.consolidate_table();
-        // debug!("After consolidation dynamic table size {}", self.size);
     }
 
     /// Consolidates the table entries so that the table size is below the
@@ -144,7 +230,14 @@ impl HeaderIndex {
                         panic!("Size of table != 0, but no headers left!");
                     }
                 };
-                self.size -= last_header.0.bytes_len() + last_header.1.bytes_len() + 32;
+                let entry_size = Self::entry_size(&last_header.0, &last_header.1);
+                self.size -= entry_size;
+                self.stats.evicted += 1;
+                self.stats.evicted_bytes += entry_size as u64;
+                crate::web_trace!("HPACK: 动态表淘汰; name={} size={}", last_header.0, self.size);
+                if let Some(callback) = self.on_evict {
+                    callback(&last_header.0, &last_header.1);
+                }
             }
             self.table.pop_back();
         }
@@ -168,6 +261,21 @@ impl HeaderIndex {
     }
 }
 
+/// HPACK的静态表(RFC 7541 附录A), 索引从1开始, 即索引`n`对应`static_table()[n - 1]`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::static_table;
+///
+/// assert_eq!(static_table().len(), 61);
+/// assert_eq!(static_table()[2 - 1], (":method", "GET"));
+/// assert_eq!(static_table()[61 - 1], ("www-authenticate", ""));
+/// ```
+pub fn static_table() -> &'static [(&'static str, &'static str)] {
+    STATIC_TABLE_RAW
+}
+
 /// (HPACK, Appendix A)
 static STATIC_TABLE_RAW: &'static [(&'static str, &'static str)] = &[
     (":authority", ""),
@@ -184,7 +292,7 @@ static STATIC_TABLE_RAW: &'static [(&'static str, &'static str)] = &[
     (":status", "400"),
     (":status", "404"),
     (":status", "500"),
-    ("accept-", ""),
+    ("accept-charset", ""),
     ("accept-encoding", "gzip, deflate"),
     ("accept-language", ""),
     ("accept-ranges", ""),
@@ -261,5 +369,4 @@ lazy_static! {
         }
         h
     };
-    static ref EMPTY_HEADER_VALUE: HeaderValue = HeaderValue::Value(vec![]);
 }
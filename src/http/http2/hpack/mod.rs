@@ -18,6 +18,6 @@ pub mod decoder;
 pub mod huffman;
 pub mod header_index;
 
-pub use header_index::HeaderIndex;
+pub use header_index::{static_table, HeaderIndex, HeaderIndexStats, HeaderIndexSnapshot, EvictCallback, ENTRY_OVERHEAD};
 pub use decoder::{Decoder, DecoderError};
 pub use huffman::{HuffmanDecoder, HuffmanDecoderError, HuffmanEncoder};
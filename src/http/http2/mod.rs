@@ -18,6 +18,8 @@ pub const DEFAULT_REMOTE_RESET_STREAM_MAX: usize = 20;
 pub const DEFAULT_RESET_STREAM_MAX: usize = 10;
 pub const DEFAULT_RESET_STREAM_SECS: u64 = 30;
 pub const DEFAULT_MAX_SEND_BUFFER_SIZE: usize = 1024 * 400;
+/// 等待SETTINGS帧ACK的默认时长
+pub const DEFAULT_SETTINGS_TIMEOUT_SECS: u64 = 10;
 
 /// 默认的header最大长度值
 pub const DEFAULT_SETTINGS_HEADER_TABLE_SIZE: usize = 4_096;
@@ -35,10 +37,21 @@ pub const MAX_INITIAL_WINDOW_SIZE: usize = (1 << 31) - 1;
 pub const MAX_MAX_FRAME_SIZE: FrameSize = (1 << 24) - 1;
 
 mod error;
+mod flow_control;
 pub mod frame;
+mod framing;
 mod hpack;
+mod reset_guard;
+pub mod sender;
+mod stream_map;
+mod upgrade;
 
 pub use error::Http2Error;
+pub use flow_control::FlowControl;
+pub use reset_guard::ResetGuard;
+pub use stream_map::{StreamMap, StreamWindows};
+pub use upgrade::{H2cUpgrade, HTTP2_SETTINGS};
+pub use frame::SettingsState;
 pub use hpack::*;
 
 pub type FrameSize = u32;
@@ -0,0 +1,109 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/11 14:05:32
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::{
+    http2::{
+        frame::{ErrorCode, FrameViolation, GoAway, StreamIdentifier},
+        DEFAULT_REMOTE_RESET_STREAM_MAX, DEFAULT_RESET_STREAM_SECS,
+    },
+    Binary, Http2Error, WebResult,
+};
+
+/// 统计对端在滑动窗口内重置(RST_STREAM或取消)的流, 超出上限时应以ENHANCE_YOUR_CALM关闭连接,
+/// 用于防御Rapid Reset攻击(CVE-2023-44487)
+///
+/// 默认30秒内最多20次
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use webparse::http2::{frame::{ErrorCode, StreamIdentifier}, ResetGuard};
+///
+/// let mut guard = ResetGuard::new(2, Duration::from_secs(10));
+/// let now = Instant::now();
+/// guard.record_at(now).unwrap();
+/// guard.record_at(now).unwrap();
+/// assert!(guard.record_at(now).is_err());
+/// assert_eq!(guard.go_away(StreamIdentifier(9)).error_code(), ErrorCode::EnhanceYourCalm);
+///
+/// // 超出窗口的记录不再计数
+/// let later = now + Duration::from_secs(11);
+/// assert_eq!(guard.count_at(later), 0);
+/// guard.record_at(later).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ResetGuard {
+    max: usize,
+    window: Duration,
+    events: VecDeque<Instant>,
+}
+
+impl Default for ResetGuard {
+    fn default() -> Self {
+        ResetGuard::new(
+            DEFAULT_REMOTE_RESET_STREAM_MAX,
+            Duration::from_secs(DEFAULT_RESET_STREAM_SECS),
+        )
+    }
+}
+
+impl ResetGuard {
+    pub fn new(max: usize, window: Duration) -> ResetGuard {
+        ResetGuard {
+            max,
+            window,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// 收到对端的RST_STREAM或对端取消流时调用, 超出上限时返回ENHANCE_YOUR_CALM的连接错误
+    pub fn record(&mut self) -> WebResult<()> {
+        self.record_at(Instant::now())
+    }
+
+    pub fn record_at(&mut self, now: Instant) -> WebResult<()> {
+        self.expire(now);
+        self.events.push_back(now);
+        if self.events.len() > self.max {
+            return Err(Http2Error::Frame(FrameViolation::Connection(
+                ErrorCode::EnhanceYourCalm,
+                "too many stream resets",
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// 窗口内的重置次数
+    pub fn count_at(&mut self, now: Instant) -> usize {
+        self.expire(now);
+        self.events.len()
+    }
+
+    /// 超出上限时发送的GOAWAY帧
+    pub fn go_away(&self, last_stream_id: StreamIdentifier) -> GoAway {
+        GoAway::new(last_stream_id, ErrorCode::EnhanceYourCalm, Binary::new())
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(front) = self.events.front() {
+            if now.saturating_duration_since(*front) < self.window {
+                break;
+            }
+            self.events.pop_front();
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/12 15:08:41
+
+use std::collections::VecDeque;
+
+use crate::{
+    http2::{
+        frame::{Data, Flag, FrameHeader, Kind, StreamIdentifier},
+        StreamMap, DEFAULT_MAX_FRAME_SIZE,
+    },
+    Binary, BinaryMut, Buf,
+};
+
+/// 默认的权重, 对应PRIORITY帧中的15, 即RFC 7540中的16
+pub const DEFAULT_WEIGHT: u8 = 15;
+
+struct PendingData {
+    stream_id: StreamIdentifier,
+    /// 按加入顺序排队的数据, 不做合并以避免拷贝
+    data: VecDeque<Binary>,
+    /// 所有排队数据的总字节数
+    remaining: usize,
+    end_stream: bool,
+    weight: u8,
+    vtime: u64,
+}
+
+impl PendingData {
+    /// 取出前`len`个字节, 在单个数据块内时共享内存, 跨越多个数据块时才合并
+    fn take(&mut self, len: usize) -> Binary {
+        self.remaining -= len;
+        match self.data.front_mut() {
+            Some(front) if front.remaining() >= len => {
+                let payload = front.copy_to_binary(len);
+                if !front.has_remaining() {
+                    self.data.pop_front();
+                }
+                payload
+            }
+            Some(_) => {
+                let mut payload = BinaryMut::with_capacity(len);
+                let mut need = len;
+                while need > 0 {
+                    let front = self.data.front_mut().expect("remaining is tracked");
+                    let n = need.min(front.remaining());
+                    payload.put_slice(&front.chunk()[..n]);
+                    front.advance(n);
+                    if !front.has_remaining() {
+                        self.data.pop_front();
+                    }
+                    need -= n;
+                }
+                payload.freeze()
+            }
+            None => Binary::new(),
+        }
+    }
+}
+
+/// 多个流的DATA帧发送调度, 按权重公平的选出下一个要发送的DATA帧, 并遵守连接及流的发送窗口
+///
+/// 每个流按发送的字节数除以权重累计虚拟时间, 每次选择虚拟时间最小且有可用窗口的流,
+/// 权重越大的流获得的带宽越多, 窗口为0的流会被跳过直到收到WINDOW_UPDATE.
+/// 流需要先加入`StreamMap`, 未加入的流不会被调度
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::{frame::StreamIdentifier, sender::Prioritizer, StreamMap};
+/// use webparse::{Binary, Buf};
+///
+/// let mut windows = StreamMap::new();
+/// windows.insert(StreamIdentifier(1));
+/// windows.insert(StreamIdentifier(3));
+///
+/// let mut prioritizer = Prioritizer::new(100);
+/// prioritizer.push(StreamIdentifier(1), Binary::from(vec![0u8; 1000]), true);
+/// prioritizer.push(StreamIdentifier(3), Binary::from(vec![0u8; 1000]), true);
+/// prioritizer.set_weight(StreamIdentifier(3), 255);
+///
+/// let mut sent = [0usize; 2];
+/// loop {
+///     let frame = prioritizer.next(&mut windows).unwrap();
+///     sent[(frame.stream_id().0 / 2) as usize] += frame.payload().remaining();
+///     if frame.is_end_stream() {
+///         // 权重大的流先发送完成
+///         assert_eq!(frame.stream_id(), StreamIdentifier(3));
+///         assert_eq!(sent, [100, 1000]);
+///         break;
+///     }
+/// }
+/// assert_eq!(prioritizer.pending(StreamIdentifier(1)), Some(900));
+/// ```
+pub struct Prioritizer {
+    streams: Vec<PendingData>,
+    max_frame_size: usize,
+    /// 最近一次调度的虚拟时间, 新加入的流从该时间开始, 避免长时间占用
+    vtime: u64,
+}
+
+impl Default for Prioritizer {
+    fn default() -> Self {
+        Prioritizer::new(DEFAULT_MAX_FRAME_SIZE as usize)
+    }
+}
+
+impl Prioritizer {
+    pub fn new(max_frame_size: usize) -> Prioritizer {
+        Prioritizer {
+            streams: Vec::new(),
+            max_frame_size: max_frame_size.max(1),
+            vtime: 0,
+        }
+    }
+
+    /// 对端的SETTINGS_MAX_FRAME_SIZE变化时调用
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.max_frame_size = size.max(1);
+    }
+
+    /// 加入待发送的数据, 已有数据的流追加在后面, `end_stream`为true时发送完最后一帧带上END_STREAM
+    pub fn push(&mut self, id: StreamIdentifier, data: Binary, end_stream: bool) {
+        if let Some(stream) = self.streams.iter_mut().find(|s| s.stream_id == id) {
+            if data.has_remaining() {
+                stream.remaining += data.remaining();
+                stream.data.push_back(data);
+            }
+            stream.end_stream |= end_stream;
+            return;
+        }
+        if !data.has_remaining() && !end_stream {
+            return;
+        }
+        let mut queue = VecDeque::new();
+        let remaining = data.remaining();
+        if remaining != 0 {
+            queue.push_back(data);
+        }
+        self.streams.push(PendingData {
+            stream_id: id,
+            data: queue,
+            remaining,
+            end_stream,
+            weight: DEFAULT_WEIGHT,
+            vtime: self.vtime,
+        });
+    }
+
+    /// 设置流的权重, 同PRIORITY帧中的值, 实际权重为`weight + 1`
+    pub fn set_weight(&mut self, id: StreamIdentifier, weight: u8) {
+        if let Some(stream) = self.streams.iter_mut().find(|s| s.stream_id == id) {
+            stream.weight = weight;
+        }
+    }
+
+    /// 移除流中未发送的数据, 如流被重置时
+    pub fn remove(&mut self, id: StreamIdentifier) -> Option<Binary> {
+        let idx = self.streams.iter().position(|s| s.stream_id == id)?;
+        let mut stream = self.streams.remove(idx);
+        let remaining = stream.remaining;
+        Some(stream.take(remaining))
+    }
+
+    /// 流中还未发送的字节数
+    pub fn pending(&self, id: StreamIdentifier) -> Option<usize> {
+        self.streams
+            .iter()
+            .find(|s| s.stream_id == id)
+            .map(|s| s.remaining)
+    }
+
+    /// 有待发送数据的流的数量
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// 选出下一个可发送的DATA帧并扣减对应的窗口, 所有的流都受窗口限制或没有数据时返回None
+    pub fn next(&mut self, windows: &mut StreamMap) -> Option<Data> {
+        let connection = windows.connection_send().available() as usize;
+        let mut chosen: Option<(usize, usize)> = None;
+        for (idx, stream) in self.streams.iter().enumerate() {
+            let remaining = stream.remaining;
+            let len = match windows.get(stream.stream_id) {
+                Some(w) => remaining
+                    .min(self.max_frame_size)
+                    .min(w.send.available() as usize)
+                    .min(connection),
+                None => continue,
+            };
+            // 空的DATA帧不占用窗口, 仍可用来发送END_STREAM
+            if len == 0 && remaining != 0 {
+                continue;
+            }
+            if chosen.is_none_or(|(c, _)| stream.vtime < self.streams[c].vtime) {
+                chosen = Some((idx, len));
+            }
+        }
+
+        let (idx, len) = chosen?;
+        windows
+            .send_data(self.streams[idx].stream_id, len as u32)
+            .ok()?;
+        let stream = &mut self.streams[idx];
+        self.vtime = stream.vtime;
+        stream.vtime += (len.max(1) as u64 * 256) / (stream.weight as u64 + 1);
+
+        let payload = stream.take(len);
+        let mut flags = Flag::zero();
+        let finished = stream.remaining == 0;
+        if finished && stream.end_stream {
+            flags.set_end_stream();
+        }
+        let head = FrameHeader::new(Kind::Data, flags, stream.stream_id);
+        if finished {
+            self.streams.remove(idx);
+        }
+        Some(Data::new(head, payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prioritizer;
+    use crate::{
+        http2::{
+            frame::{StreamIdentifier, WindowUpdate},
+            StreamMap,
+        },
+        Binary, Buf,
+    };
+
+    #[test]
+    fn window_blocked() {
+        let (one, three) = (StreamIdentifier(1), StreamIdentifier(3));
+        let mut windows = StreamMap::new();
+        windows.set_remote_initial_window_size(10).unwrap();
+        windows.insert(one);
+        windows.insert(three);
+
+        let mut prioritizer = Prioritizer::new(8);
+        prioritizer.push(one, Binary::from(vec![1u8; 20]), true);
+        prioritizer.push(three, Binary::new(), true);
+
+        let lens: Vec<(u32, usize, bool)> = core::iter::from_fn(|| prioritizer.next(&mut windows))
+            .map(|f| (f.stream_id().0, f.payload().remaining(), f.is_end_stream()))
+            .collect();
+        assert_eq!(lens, vec![(1, 8, false), (3, 0, true), (1, 2, false)]);
+        assert_eq!(prioritizer.pending(one), Some(10));
+
+        windows
+            .recv_window_update(&WindowUpdate::new(one, 100))
+            .unwrap();
+        let frame = prioritizer.next(&mut windows).unwrap();
+        assert_eq!(frame.payload().remaining(), 8);
+        assert!(prioritizer.next(&mut windows).unwrap().is_end_stream());
+        assert!(prioritizer.is_empty());
+    }
+
+    #[test]
+    fn queued_bodies() {
+        let one = StreamIdentifier(1);
+        let mut windows = StreamMap::new();
+        windows.insert(one);
+
+        let mut prioritizer = Prioritizer::new(4);
+        prioritizer.push(one, Binary::from(vec![1u8; 3]), false);
+        prioritizer.push(one, Binary::from(vec![2u8; 3]), true);
+        assert_eq!(prioritizer.pending(one), Some(6));
+
+        let frame = prioritizer.next(&mut windows).unwrap();
+        assert_eq!(frame.payload().chunk(), &[1, 1, 1, 2]);
+        let frame = prioritizer.next(&mut windows).unwrap();
+        assert_eq!(frame.payload().chunk(), &[2, 2]);
+        assert!(frame.is_end_stream());
+        assert!(prioritizer.is_empty());
+    }
+}
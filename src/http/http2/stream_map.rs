@@ -0,0 +1,385 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/10 09:40:18
+
+use std::collections::HashMap;
+
+use crate::{
+    http2::{
+        frame::{ErrorCode, FrameViolation, StreamIdentifier, WindowUpdate},
+        FlowControl, DEFAULT_INITIAL_WINDOW_SIZE, MAX_WINDOW_SIZE,
+    },
+    Http2Error, WebResult,
+};
+
+/// 单个流的发送及接收窗口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamWindows {
+    pub send: FlowControl,
+    pub recv: FlowControl,
+}
+
+/// 连接中所有流的流量控制状态及并发数
+///
+/// 发送窗口的初始值取自对端的SETTINGS_INITIAL_WINDOW_SIZE, 接收窗口取自本端的设置,
+/// 设置变化时已存在的流按差值调整, 连接窗口不受影响
+///
+/// 对端新建的流受本端SETTINGS_MAX_CONCURRENT_STREAMS限制, 本端新建的流受对端的设置限制,
+/// `new`创建的为服务端, 客户端使用`client`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::{StreamMap, frame::{StreamIdentifier, WindowUpdate}};
+///
+/// let mut streams = StreamMap::new();
+/// let id = StreamIdentifier(1);
+/// streams.insert(id);
+/// streams.send_data(id, 60_000).unwrap();
+/// // 对端将初始窗口改小, 已有的流窗口变为负数
+/// streams.set_remote_initial_window_size(16_384).unwrap();
+/// assert_eq!(streams.get(id).unwrap().send.window(), 16_384 - 60_000);
+/// assert!(streams.send_data(id, 1).is_err());
+///
+/// streams.recv_window_update(&WindowUpdate::new(id, 50_000)).unwrap();
+/// assert_eq!(streams.get(id).unwrap().send.available(), 6_384);
+/// ```
+#[derive(Debug)]
+pub struct StreamMap {
+    streams: HashMap<StreamIdentifier, StreamWindows>,
+    local_initial_window_size: u32,
+    remote_initial_window_size: u32,
+    send: FlowControl,
+    recv: FlowControl,
+    is_server: bool,
+    /// 本端设置的最大并发数, 限制对端新建的流
+    local_max_concurrent_streams: Option<u32>,
+    /// 对端设置的最大并发数, 限制本端新建的流
+    remote_max_concurrent_streams: Option<u32>,
+}
+
+impl Default for StreamMap {
+    fn default() -> Self {
+        StreamMap {
+            streams: HashMap::new(),
+            local_initial_window_size: DEFAULT_INITIAL_WINDOW_SIZE,
+            remote_initial_window_size: DEFAULT_INITIAL_WINDOW_SIZE,
+            send: FlowControl::new(StreamIdentifier::zero(), DEFAULT_INITIAL_WINDOW_SIZE),
+            recv: FlowControl::new(StreamIdentifier::zero(), DEFAULT_INITIAL_WINDOW_SIZE),
+            is_server: true,
+            local_max_concurrent_streams: None,
+            remote_max_concurrent_streams: None,
+        }
+    }
+}
+
+impl StreamMap {
+    pub fn new() -> StreamMap {
+        StreamMap::default()
+    }
+
+    pub fn client() -> StreamMap {
+        StreamMap {
+            is_server: false,
+            ..StreamMap::default()
+        }
+    }
+
+    /// 流是否由本端创建, 客户端创建奇数id的流, 服务端创建偶数id的流
+    pub fn is_local_initiated(&self, id: StreamIdentifier) -> bool {
+        if self.is_server {
+            id.is_server_initiated()
+        } else {
+            id.is_client_initiated()
+        }
+    }
+
+    /// 本端的SETTINGS_MAX_CONCURRENT_STREAMS, 只影响之后新建的流
+    pub fn set_local_max_concurrent_streams(&mut self, max: Option<u32>) {
+        self.local_max_concurrent_streams = max;
+    }
+
+    /// 收到对端的SETTINGS_MAX_CONCURRENT_STREAMS
+    pub fn set_remote_max_concurrent_streams(&mut self, max: Option<u32>) {
+        self.remote_max_concurrent_streams = max;
+    }
+
+    /// 本端创建且未关闭的流数量
+    pub fn local_active(&self) -> usize {
+        self.streams
+            .keys()
+            .filter(|id| self.is_local_initiated(**id))
+            .count()
+    }
+
+    /// 对端创建且未关闭的流数量
+    pub fn remote_active(&self) -> usize {
+        self.streams.len() - self.local_active()
+    }
+
+    /// 本端是否还能新建流而不超出对端的限制
+    pub fn can_send_open(&self) -> bool {
+        Self::below(self.local_active(), self.remote_max_concurrent_streams)
+    }
+
+    /// 本端新建流, 超出对端的并发限制时返回REFUSED_STREAM的流错误且不创建
+    pub fn send_open(&mut self, id: StreamIdentifier) -> WebResult<&mut StreamWindows> {
+        if !self.contains(id) && !self.can_send_open() {
+            return Err(Self::refused(
+                id,
+                "would exceed peer max concurrent streams",
+            ));
+        }
+        Ok(self.insert(id))
+    }
+
+    /// 对端新建流, 超出本端的并发限制时返回REFUSED_STREAM的流错误, 应以RST_STREAM拒绝
+    pub fn recv_open(&mut self, id: StreamIdentifier) -> WebResult<&mut StreamWindows> {
+        if !self.contains(id)
+            && !Self::below(self.remote_active(), self.local_max_concurrent_streams)
+        {
+            return Err(Self::refused(id, "max concurrent streams exceeded"));
+        }
+        Ok(self.insert(id))
+    }
+
+    /// 新建流, 已存在时返回原有的窗口
+    pub fn insert(&mut self, id: StreamIdentifier) -> &mut StreamWindows {
+        let (send, recv) = (
+            self.remote_initial_window_size,
+            self.local_initial_window_size,
+        );
+        self.streams.entry(id).or_insert_with(|| StreamWindows {
+            send: FlowControl::new(id, send),
+            recv: FlowControl::new(id, recv),
+        })
+    }
+
+    pub fn remove(&mut self, id: StreamIdentifier) -> Option<StreamWindows> {
+        self.streams.remove(&id)
+    }
+
+    pub fn get(&self, id: StreamIdentifier) -> Option<&StreamWindows> {
+        self.streams.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: StreamIdentifier) -> Option<&mut StreamWindows> {
+        self.streams.get_mut(&id)
+    }
+
+    pub fn contains(&self, id: StreamIdentifier) -> bool {
+        self.streams.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// 连接的发送窗口
+    pub fn connection_send(&self) -> &FlowControl {
+        &self.send
+    }
+
+    /// 连接的接收窗口
+    pub fn connection_recv(&self) -> &FlowControl {
+        &self.recv
+    }
+
+    /// 收到对端的SETTINGS_INITIAL_WINDOW_SIZE, 调整所有流的发送窗口
+    pub fn set_remote_initial_window_size(&mut self, size: u32) -> WebResult<()> {
+        let old = self.remote_initial_window_size;
+        Self::apply_delta(self.streams.values_mut().map(|s| &mut s.send), old, size)?;
+        self.remote_initial_window_size = size;
+        Ok(())
+    }
+
+    /// 本端的SETTINGS_INITIAL_WINDOW_SIZE被确认后调用, 调整所有流的接收窗口
+    pub fn set_local_initial_window_size(&mut self, size: u32) -> WebResult<()> {
+        let old = self.local_initial_window_size;
+        Self::apply_delta(self.streams.values_mut().map(|s| &mut s.recv), old, size)?;
+        self.local_initial_window_size = size;
+        Ok(())
+    }
+
+    /// 收到WINDOW_UPDATE, 流0时增加连接窗口, 已关闭的流将被忽略
+    pub fn recv_window_update(&mut self, update: &WindowUpdate) -> WebResult<()> {
+        let id = update.stream_id();
+        if id.is_zero() {
+            return self.send.increase(update.size_increment());
+        }
+        match self.streams.get_mut(&id) {
+            Some(stream) => stream.send.increase(update.size_increment()),
+            None => Ok(()),
+        }
+    }
+
+    /// 发送数据前调用, 同时扣减连接及流的发送窗口, 窗口不足时不做任何修改
+    pub fn send_data(&mut self, id: StreamIdentifier, len: u32) -> WebResult<()> {
+        let stream = match self.streams.get_mut(&id) {
+            Some(stream) => stream,
+            None => return Err(Http2Error::InvalidStreamId.into()),
+        };
+        if len > stream.send.available() {
+            return stream.send.consume(len);
+        }
+        self.send.consume(len)?;
+        stream.send.consume(len)
+    }
+
+    /// 收到数据时调用, 对端超出接收窗口时返回FLOW_CONTROL_ERROR
+    pub fn recv_data(&mut self, id: StreamIdentifier, len: u32) -> WebResult<()> {
+        self.recv.consume(len)?;
+        match self.streams.get_mut(&id) {
+            Some(stream) => stream.recv.consume(len),
+            None => Ok(()),
+        }
+    }
+
+    /// 归还接收窗口, 返回需要发送的WINDOW_UPDATE帧
+    pub fn release_recv(&mut self, id: StreamIdentifier, len: u32) -> WebResult<Vec<WindowUpdate>> {
+        let mut updates = vec![];
+        if len == 0 {
+            return Ok(updates);
+        }
+        self.recv.increase(len)?;
+        updates.push(WindowUpdate::new(StreamIdentifier::zero(), len));
+        if let Some(stream) = self.streams.get_mut(&id) {
+            stream.recv.increase(len)?;
+            updates.push(WindowUpdate::new(id, len));
+        }
+        Ok(updates)
+    }
+
+    fn below(active: usize, max: Option<u32>) -> bool {
+        max.is_none_or(|max| active < max as usize)
+    }
+
+    fn refused(id: StreamIdentifier, msg: &'static str) -> crate::WebError {
+        Http2Error::Frame(FrameViolation::Stream(id, ErrorCode::RefusedStream, msg)).into()
+    }
+
+    fn apply_delta<'a>(
+        windows: impl Iterator<Item = &'a mut FlowControl>,
+        old: u32,
+        new: u32,
+    ) -> WebResult<()> {
+        if new > MAX_WINDOW_SIZE {
+            return Err(Http2Error::Frame(FrameViolation::Connection(
+                ErrorCode::FlowControlError,
+                "initial window size above 2^31-1",
+            ))
+            .into());
+        }
+        let mut windows: Vec<&mut FlowControl> = windows.collect();
+        // 先在副本上检查所有的流, 任一溢出时不做任何修改
+        for flow in windows.iter() {
+            let mut check = **flow;
+            check.apply_initial_delta(old, new)?;
+        }
+        for flow in windows.iter_mut() {
+            flow.apply_initial_delta(old, new)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamMap;
+    use crate::http2::frame::{ErrorCode, StreamIdentifier, WindowUpdate};
+    use crate::{Http2Error, WebError};
+
+    #[test]
+    fn initial_window_delta() {
+        let mut streams = StreamMap::new();
+        let (a, b) = (StreamIdentifier(1), StreamIdentifier(3));
+        streams.insert(a);
+        streams.send_data(a, 65_535).unwrap();
+        streams.set_remote_initial_window_size(1_000).unwrap();
+        streams.insert(b);
+        assert_eq!(streams.get(a).unwrap().send.window(), 1_000 - 65_535);
+        assert_eq!(streams.get(b).unwrap().send.window(), 1_000);
+        // 连接窗口不受影响, 已用尽
+        assert_eq!(streams.connection_send().available(), 0);
+        streams
+            .recv_window_update(&WindowUpdate::new(StreamIdentifier::zero(), 10_000))
+            .unwrap();
+
+        // 负的窗口需要WINDOW_UPDATE恢复到正数后才能发送
+        streams
+            .recv_window_update(&WindowUpdate::new(a, 64_535))
+            .unwrap();
+        assert_eq!(streams.get(a).unwrap().send.window(), 0);
+        assert!(streams.send_data(a, 1).is_err());
+        streams
+            .recv_window_update(&WindowUpdate::new(a, 10))
+            .unwrap();
+        streams.send_data(a, 10).unwrap();
+        streams.send_data(b, 1_000).unwrap();
+        assert_eq!(streams.connection_send().available(), 10_000 - 1_010);
+
+        // 增大后同样按差值调整
+        streams.set_remote_initial_window_size(2_000).unwrap();
+        assert_eq!(streams.get(a).unwrap().send.window(), 1_000);
+        assert_eq!(streams.get(b).unwrap().send.window(), 1_000);
+
+        // 调整后超出2^31-1为连接级的FLOW_CONTROL_ERROR, 且不做任何修改
+        streams
+            .recv_window_update(&WindowUpdate::new(b, (1 << 31) - 1 - 1_000))
+            .unwrap();
+        match streams.set_remote_initial_window_size(3_000) {
+            Err(WebError::Http2(e @ Http2Error::Frame(_))) => {
+                assert_eq!(e.error_code(), ErrorCode::FlowControlError);
+                assert!(e.is_connection_error());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(streams.get(a).unwrap().send.window(), 1_000);
+    }
+
+    #[test]
+    fn max_concurrent_streams() {
+        let mut streams = StreamMap::new();
+        streams.set_local_max_concurrent_streams(Some(2));
+        streams.set_remote_max_concurrent_streams(Some(1));
+        streams.recv_open(StreamIdentifier(1)).unwrap();
+        streams.recv_open(StreamIdentifier(3)).unwrap();
+        match streams.recv_open(StreamIdentifier(5)) {
+            Err(WebError::Http2(e)) => {
+                assert_eq!(e.error_code(), ErrorCode::RefusedStream);
+                assert_eq!(e.stream_id(), Some(StreamIdentifier(5)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(!streams.contains(StreamIdentifier(5)));
+
+        // 对端的流不占用本端新建流的配额
+        assert!(streams.can_send_open());
+        streams.send_open(StreamIdentifier(2)).unwrap();
+        assert!(!streams.can_send_open());
+        assert!(streams.send_open(StreamIdentifier(4)).is_err());
+        assert_eq!((streams.local_active(), streams.remote_active()), (1, 2));
+
+        streams.remove(StreamIdentifier(1));
+        streams.remove(StreamIdentifier(2));
+        streams.recv_open(StreamIdentifier(5)).unwrap();
+        streams.send_open(StreamIdentifier(4)).unwrap();
+
+        let mut client = StreamMap::client();
+        client.set_remote_max_concurrent_streams(Some(0));
+        assert!(client.send_open(StreamIdentifier(1)).is_err());
+        client.recv_open(StreamIdentifier(2)).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/15 10:26:52
+
+use crate::{
+    http2::{
+        frame::{Data, Flag, FrameHeader, Headers, Kind, Settings, StreamIdentifier},
+        Http2Error, StreamMap,
+    },
+    Binary, BinaryMut, HeaderName, Request, Scheme, Serialize, WebError, WebResult,
+};
+
+/// h2c升级(RFC 7540 3.2)中携带的设置头
+pub const HTTP2_SETTINGS: &str = "http2-settings";
+
+/// h2c升级后的原HTTP/1.1请求, 在http2连接中作为流1, 状态为half-closed (remote)
+///
+/// 请求被转换为流1上的HEADERS帧, 没有body时END_STREAM设置在HEADERS上,
+/// 否则body作为带有END_STREAM的DATA帧跟在其后, 连接层可以和其它流一样处理并在流1上响应
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::{frame::StreamIdentifier, H2cUpgrade, StreamMap};
+/// use webparse::{Buf, Request};
+///
+/// let mut req = Request::new();
+/// req.parse(b"GET /index HTTP/1.1\r\nHost: example.com\r\nConnection: Upgrade, HTTP2-Settings\r\n\
+///     Upgrade: h2c\r\nHTTP2-Settings: AAMAAABkAAQAAAPo\r\nAccept: */*\r\n\r\n").unwrap();
+/// assert!(H2cUpgrade::is_upgrade_request(&req));
+///
+/// let upgrade = H2cUpgrade::from_request(&mut req).unwrap();
+/// assert_eq!(upgrade.settings().max_concurrent_streams(), Some(100));
+/// let mut streams = StreamMap::new();
+/// upgrade.register(&mut streams).unwrap();
+/// assert!(streams.contains(StreamIdentifier(1)));
+/// assert_eq!(streams.get(StreamIdentifier(1)).unwrap().send.window(), 1000);
+///
+/// let (mut headers, data, _settings) = upgrade.into_parts();
+/// assert_eq!(headers.stream_id(), StreamIdentifier(1));
+/// assert!(headers.is_end_stream());
+/// assert!(data.is_none());
+/// assert_eq!(headers.path().as_deref(), Some("/index"));
+/// assert!(headers.fields().contains(&"accept"));
+/// assert!(!headers.fields().contains(&"http2-settings"));
+///
+/// let mut req = Request::builder()
+///     .method("POST")
+///     .url("http://example.com/upload")
+///     .header("Connection", "Upgrade, HTTP2-Settings")
+///     .header("Upgrade", "h2c")
+///     .header("HTTP2-Settings", "")
+///     .body("hello")
+///     .unwrap();
+/// let upgrade = H2cUpgrade::from_request(&mut req).unwrap();
+/// assert!(!upgrade.headers().is_end_stream());
+/// let data = upgrade.data().unwrap();
+/// assert!(data.is_end_stream());
+/// assert_eq!(data.stream_id(), StreamIdentifier(1));
+/// assert_eq!(data.payload().chunk(), b"hello");
+/// ```
+#[derive(Debug)]
+pub struct H2cUpgrade {
+    headers: Headers,
+    data: Option<Data<Binary>>,
+    settings: Settings,
+}
+
+impl H2cUpgrade {
+    /// 升级后原请求所在的流
+    pub const STREAM_ID: StreamIdentifier = StreamIdentifier(1);
+
+    /// 是否为h2c升级请求, 需要`Upgrade: h2c`, Connection中包含Upgrade及HTTP2-Settings,
+    /// 且有且仅有一个HTTP2-Settings头
+    pub fn is_upgrade_request<T: Serialize>(req: &Request<T>) -> bool {
+        let headers = req.headers();
+        let connection = headers
+            .get_str_value(&HeaderName::CONNECTION)
+            .unwrap_or_default();
+        let has_token = |token: &str| {
+            connection
+                .split(',')
+                .any(|v| v.trim().eq_ignore_ascii_case(token))
+        };
+        headers
+            .get_str_value(&HeaderName::UPGRADE)
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("h2c"))
+            && has_token("upgrade")
+            && has_token(HTTP2_SETTINGS)
+            && headers.iter().filter(|(n, _)| *n == HTTP2_SETTINGS).count() == 1
+    }
+
+    /// 由升级请求构建流1的HEADERS帧及DATA帧, 并解析HTTP2-Settings中对端的设置
+    ///
+    /// 带有body的请求需在升级前完整读取body(RFC 7540 3.2), body为空时没有DATA帧
+    pub fn from_request<T: Serialize>(req: &mut Request<T>) -> WebResult<H2cUpgrade> {
+        if !Self::is_upgrade_request(req) {
+            return Err(WebError::Http2(Http2Error::MalformedMessage));
+        }
+        let value = req
+            .headers()
+            .get_option_value(&HTTP2_SETTINGS)
+            .ok_or(WebError::Http2(Http2Error::InvalidSettingValue))?;
+        let settings = Settings::decode_http2_settings_header(value)?;
+
+        let mut body = BinaryMut::new();
+        req.body_mut().serialize(&mut body)?;
+        let body = body.freeze();
+        let mut headers = Headers::from_request(Self::STREAM_ID, req, body.is_empty())?;
+        headers.set_scheme(Scheme::Http);
+        headers.fields_mut().remove(&HTTP2_SETTINGS);
+        let data = if body.is_empty() {
+            None
+        } else {
+            let mut flags = Flag::zero();
+            flags.set_end_stream();
+            Some(Data::new(
+                FrameHeader::new(Kind::Data, flags, Self::STREAM_ID),
+                body,
+            ))
+        };
+        Ok(H2cUpgrade {
+            headers,
+            data,
+            settings,
+        })
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// 请求body转换的DATA帧, 超出对端的帧大小时可用`Data::split`拆分
+    pub fn data(&self) -> Option<&Data<Binary>> {
+        self.data.as_ref()
+    }
+
+    /// 对端在HTTP2-Settings中的设置, 视为连接建立时收到的第一个SETTINGS帧
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn into_parts(self) -> (Headers, Option<Data<Binary>>, Settings) {
+        (self.headers, self.data, self.settings)
+    }
+
+    /// 应用对端的设置并将流1加入`StreamMap`, 对端不能再在流1上发送数据
+    pub fn register(&self, streams: &mut StreamMap) -> WebResult<()> {
+        if let Some(size) = self.settings.initial_window_size() {
+            streams.set_remote_initial_window_size(size)?;
+        }
+        if let Some(max) = self.settings.max_concurrent_streams() {
+            streams.set_remote_max_concurrent_streams(Some(max));
+        }
+        streams.recv_open(Self::STREAM_ID)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/25 10:18:52
+
+//! Link头(RFC 8288), 常用于分页的`rel=next`及预加载的`rel=preload`
+//!
+//! # Examples
+//!
+//! ```
+//! use webparse::http::link::{self, Link};
+//! use webparse::Url;
+//!
+//! let base = Url::try_from("https://api.example.com/items?page=2").unwrap();
+//! let value = "</items?page=3>; rel=\"next\", </items?page=1>; rel=prev; title=\"Previous, page\"";
+//! let links = link::parse(value, Some(&base)).unwrap();
+//! assert_eq!(links.len(), 2);
+//! assert_eq!(links[0].target.to_string(), "https://api.example.com/items?page=3");
+//! assert!(links[0].has_rel("NEXT"));
+//! assert_eq!(links[1].title(), Some("Previous, page"));
+//!
+//! let preload = Link::new(Url::try_from("/style.css").unwrap())
+//!     .with_param("rel", "preload")
+//!     .with_param("as", "style");
+//! assert_eq!(link::to_value(&[preload]), "</style.css>; rel=preload; as=style");
+//! ```
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{HttpError, Url, WebError, WebResult};
+
+use super::media_type::{fmt_param, parse_params};
+
+/// Link头中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub target: Url,
+    /// 参数名为小写, 保持原有顺序
+    pub params: Vec<(String, String)>,
+}
+
+impl Link {
+    pub fn new(target: Url) -> Link {
+        Link {
+            target,
+            params: Vec::new(),
+        }
+    }
+
+    /// 追加参数, 用于链式构建
+    pub fn with_param(mut self, name: &str, value: &str) -> Link {
+        self.params.push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    /// 获取参数, 参数名不区分大小写, 多个同名参数时返回第一个
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// rel中以空格分隔的各个关系
+    pub fn rels(&self) -> impl Iterator<Item = &str> {
+        self.param("rel").unwrap_or("").split_ascii_whitespace()
+    }
+
+    /// 是否包含指定的关系, 不区分大小写
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rels().any(|r| r.eq_ignore_ascii_case(rel))
+    }
+
+    pub fn media_type(&self) -> Option<&str> {
+        self.param("type")
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.param("title")
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.target)?;
+        for (name, value) in &self.params {
+            fmt_param(f, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// 解析Link头, 目标为相对地址时以base为基准解析
+pub fn parse(value: &str, base: Option<&Url>) -> WebResult<Vec<Link>> {
+    let err = || WebError::from(HttpError::HeaderValue);
+    let mut links = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ',']);
+        if rest.is_empty() {
+            break;
+        }
+        let inner = rest.strip_prefix('<').ok_or_else(err)?;
+        let end = inner.find('>').ok_or_else(err)?;
+        let target = match base {
+            Some(base) => base.join(&inner[..end])?,
+            None => Url::try_from(&inner[..end])?,
+        };
+        rest = &inner[end + 1..];
+
+        // 参数到不在引号中的逗号为止
+        let mut quoted = false;
+        let mut escape = false;
+        let mut params_end = rest.len();
+        for (i, c) in rest.char_indices() {
+            if escape {
+                escape = false;
+            } else if quoted && c == '\\' {
+                escape = true;
+            } else if c == '"' {
+                quoted = !quoted;
+            } else if c == ',' && !quoted {
+                params_end = i;
+                break;
+            }
+        }
+        links.push(Link {
+            target,
+            params: parse_params(&rest[..params_end])?,
+        });
+        rest = &rest[params_end..];
+    }
+    Ok(links)
+}
+
+/// 将多个Link序列化为一个头的值, 以`, `分隔
+pub fn to_value(links: &[Link]) -> String {
+    links
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
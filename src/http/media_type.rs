@@ -0,0 +1,192 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/22 14:05:36
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{Helper, HttpError, WebError, WebResult};
+
+/// Content-Type等头中的媒体类型, 见RFC 7231 §3.1.1.1
+///
+/// 类型, 子类型及参数名均不区分大小写, 解析后统一转为小写, charset的值同样转为小写
+///
+/// # Examples
+///
+/// ```
+/// use webparse::MediaType;
+///
+/// let mt: MediaType = "Multipart/Form-Data; boundary=\"a b\"; Charset=UTF-8".parse().unwrap();
+/// assert_eq!(mt.essence_str(), "multipart/form-data");
+/// assert_eq!(mt.boundary(), Some("a b"));
+/// assert_eq!(mt.charset(), Some("utf-8"));
+/// assert!(mt.is("multipart/form-data"));
+/// assert_eq!(mt.to_string(), "multipart/form-data; boundary=\"a b\"; charset=utf-8");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    /// `type/subtype`
+    essence: String,
+    slash: usize,
+    params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    pub fn new(mime_type: &str, subtype: &str) -> WebResult<MediaType> {
+        if !is_token(mime_type) || !is_token(subtype) {
+            return Err(WebError::from(HttpError::HeaderValue));
+        }
+        Ok(MediaType {
+            essence: format!("{}/{}", mime_type, subtype).to_ascii_lowercase(),
+            slash: mime_type.len(),
+            params: Vec::new(),
+        })
+    }
+
+    pub fn parse(value: &str) -> WebResult<MediaType> {
+        let err = || WebError::from(HttpError::HeaderValue);
+        let (essence, rest) = match value.find(';') {
+            Some(pos) => (&value[..pos], &value[pos + 1..]),
+            None => (value, ""),
+        };
+        let (mime_type, subtype) = essence.trim().split_once('/').ok_or_else(err)?;
+        let mut media = MediaType::new(mime_type, subtype)?;
+        for (name, value) in parse_params(rest)? {
+            media.set_param(&name, value);
+        }
+        Ok(media)
+    }
+
+    /// 主类型, 如`text`
+    pub fn mime_type(&self) -> &str {
+        &self.essence[..self.slash]
+    }
+
+    /// 子类型, 如`html`
+    pub fn subtype(&self) -> &str {
+        &self.essence[self.slash + 1..]
+    }
+
+    /// 不带参数的`type/subtype`
+    pub fn essence_str(&self) -> &str {
+        &self.essence
+    }
+
+    /// 是否为指定的类型, 不区分大小写, 不比较参数
+    pub fn is(&self, essence: &str) -> bool {
+        self.essence.eq_ignore_ascii_case(essence.trim())
+    }
+
+    /// 获取参数, 参数名不区分大小写
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// 设置参数, 已存在时替换
+    pub fn set_param(&mut self, name: &str, value: String) {
+        let name = name.to_ascii_lowercase();
+        let value = if name == "charset" {
+            value.to_ascii_lowercase()
+        } else {
+            value
+        };
+        match self.params.iter_mut().find(|(n, _)| *n == name) {
+            Some(param) => param.1 = value,
+            None => self.params.push((name, value)),
+        }
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// multipart的分隔符
+    pub fn boundary(&self) -> Option<&str> {
+        self.param("boundary")
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = WebError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MediaType::parse(s)
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.essence)?;
+        for (name, value) in &self.params {
+            fmt_param(f, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(Helper::is_token)
+}
+
+/// 解析`; name=value`形式的参数列表, 值可为token或带引号的字符串, 参数名转为小写
+pub(crate) fn parse_params(mut rest: &str) -> WebResult<Vec<(String, String)>> {
+    let err = || WebError::from(HttpError::HeaderValue);
+    let mut params = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ';']);
+        if rest.is_empty() {
+            break;
+        }
+        let eq = rest.find('=').ok_or_else(err)?;
+        let name = rest[..eq].trim_end();
+        if !is_token(name) {
+            return Err(err());
+        }
+        rest = rest[eq + 1..].trim_start();
+        let value = if rest.starts_with('"') {
+            // 带引号的值, 支持`\`转义
+            let (value, next) = Helper::parse_quoted(rest).ok_or_else(err)?;
+            rest = next;
+            let next = rest.trim_start();
+            if !next.is_empty() && !next.starts_with(';') {
+                return Err(err());
+            }
+            value
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            let value = rest[..end].trim_end();
+            if !is_token(value) {
+                return Err(err());
+            }
+            rest = &rest[end..];
+            value.to_string()
+        };
+        params.push((name.to_ascii_lowercase(), value));
+    }
+    Ok(params)
+}
+
+/// 输出`; name=value`, 值不是token时加引号并转义
+pub(crate) fn fmt_param(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    if is_token(value) {
+        return write!(f, "; {}={}", name, value);
+    }
+    write!(f, "; {}=", name)?;
+    Helper::write_quoted(f, value)
+}
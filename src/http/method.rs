@@ -10,8 +10,9 @@
 // -----
 // Created Date: 2023/08/15 10:03:23
 
-use std::{fmt::Display, str::FromStr};
+use core::{fmt::Display, str::FromStr};
 
+use crate::prelude::*;
 use crate::{WebError, WebResult, Buf, BufMut};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -114,7 +115,7 @@ impl Method {
 
 impl Display for Method {
     
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(&self.as_str())
     }
 }
@@ -18,15 +18,37 @@ mod status;
 pub mod response;
 mod name;
 mod value;
+pub mod http1;
+#[cfg(feature = "std")]
 pub mod http2;
 mod error;
+mod policy;
+mod parse_status;
+mod target_form;
+mod media_type;
+mod content_disposition;
+pub mod link;
+mod via;
+mod raw_header;
+mod canonical;
 
 pub use version::Version;
 pub use method::Method;
-pub use header::HeaderMap;
+pub use header::{HeaderMap, HeaderEntry};
+pub(crate) use header::Trailers;
 pub use name::HeaderName;
 pub use value::HeaderValue;
 pub use error::HttpError;
+pub use policy::BuilderPolicy;
+pub use parse_status::{ParseReport, ParseStats, ParseStatus};
+pub use target_form::TargetForm;
+pub use media_type::MediaType;
+pub use content_disposition::{ContentDisposition, DispositionType};
+pub use link::Link;
+pub use via::{ViaHop, Warning};
+pub use raw_header::{RawHeader, RawHeaders};
+pub use canonical::CanonicalRequest;
+pub use crate::url::{Authority, Host};
 
 pub use request::Request;
 pub use response::Response;
@@ -10,8 +10,9 @@
 // -----
 // Created Date: 2023/08/18 10:06:42
 
-use std::{fmt::{self, Display}, hash::Hash};
+use core::{fmt::{self, Display}, hash::Hash};
 
+use crate::prelude::*;
 use crate::{WebError, WebResult, Buf, BufMut, Helper};
 
 /// 请求头的名字不区分大小写
@@ -51,7 +52,7 @@ impl PartialEq for HeaderName {
 impl Eq for HeaderName {}
 
 impl Hash for HeaderName {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         let bytes = match self {
             HeaderName::Stand(stand) => stand.as_bytes(),
             HeaderName::Value(val) => val.as_bytes(),
@@ -113,6 +114,83 @@ impl HeaderName {
         }
     }
 
+    /// 是否为已知的标准头, 不区分大小写
+    pub fn is_standard(&self) -> bool {
+        Self::standard_name(self.as_bytes()).is_some()
+    }
+
+    /// 常见头的首字母大写写法, 解析HTTP/1.1时可保留原始大小写且不产生内存分配
+    fn common_title_case(name_bytes: &[u8]) -> Option<&'static str> {
+        let name = match name_bytes {
+            b"Accept" => "Accept",
+            b"Accept-Charset" => "Accept-Charset",
+            b"Accept-Encoding" => "Accept-Encoding",
+            b"Accept-Language" => "Accept-Language",
+            b"Accept-Ranges" => "Accept-Ranges",
+            b"Age" => "Age",
+            b"Allow" => "Allow",
+            b"Authorization" => "Authorization",
+            b"Cache-Control" => "Cache-Control",
+            b"Connection" => "Connection",
+            b"Content-Disposition" => "Content-Disposition",
+            b"Content-Encoding" => "Content-Encoding",
+            b"Content-Language" => "Content-Language",
+            b"Content-Length" => "Content-Length",
+            b"Content-Location" => "Content-Location",
+            b"Content-Range" => "Content-Range",
+            b"Content-Type" => "Content-Type",
+            b"Cookie" => "Cookie",
+            b"Date" => "Date",
+            b"ETag" => "ETag",
+            b"Expect" => "Expect",
+            b"Expires" => "Expires",
+            b"Forwarded" => "Forwarded",
+            b"Host" => "Host",
+            b"If-Match" => "If-Match",
+            b"If-Modified-Since" => "If-Modified-Since",
+            b"If-None-Match" => "If-None-Match",
+            b"If-Range" => "If-Range",
+            b"If-Unmodified-Since" => "If-Unmodified-Since",
+            b"Keep-Alive" => "Keep-Alive",
+            b"Last-Modified" => "Last-Modified",
+            b"Link" => "Link",
+            b"Location" => "Location",
+            b"Origin" => "Origin",
+            b"Pragma" => "Pragma",
+            b"Proxy-Authorization" => "Proxy-Authorization",
+            b"Proxy-Connection" => "Proxy-Connection",
+            b"Range" => "Range",
+            b"Referer" => "Referer",
+            b"Sec-WebSocket-Accept" => "Sec-WebSocket-Accept",
+            b"Sec-WebSocket-Extensions" => "Sec-WebSocket-Extensions",
+            b"Sec-WebSocket-Key" => "Sec-WebSocket-Key",
+            b"Sec-WebSocket-Protocol" => "Sec-WebSocket-Protocol",
+            b"Sec-WebSocket-Version" => "Sec-WebSocket-Version",
+            b"Server" => "Server",
+            b"Set-Cookie" => "Set-Cookie",
+            b"Transfer-Encoding" => "Transfer-Encoding",
+            b"Upgrade" => "Upgrade",
+            b"Upgrade-Insecure-Requests" => "Upgrade-Insecure-Requests",
+            b"User-Agent" => "User-Agent",
+            b"Vary" => "Vary",
+            b"Via" => "Via",
+            b"WWW-Authenticate" => "WWW-Authenticate",
+            b"X-Forwarded-For" => "X-Forwarded-For",
+            b"X-Forwarded-Proto" => "X-Forwarded-Proto",
+            b"X-Real-IP" => "X-Real-IP",
+            b"X-Requested-With" => "X-Requested-With",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// 以小写比较`name`与`other`, `name`须为小写
+    fn cmp_lowercase(name: &[u8], other: &[u8]) -> core::cmp::Ordering {
+        name.iter()
+            .copied()
+            .cmp(other.iter().map(|b| b.to_ascii_lowercase()))
+    }
+
     pub fn name(&self) -> &str {
         match self {
             Self::Stand(s) => s,
@@ -157,25 +235,56 @@ macro_rules! standard_headers {
         impl HeaderName {
             $(
                 $(#[$docs])*
-                // pub const $konst: HeaderName = HeaderName::Stand(unsafe { std::str::from_utf8_unchecked( $name_bytes ) });
-                pub const $upcase: HeaderName = HeaderName::Stand(unsafe { std::str::from_utf8_unchecked( $name_bytes ) });
-                // pub const concat!("S", {$upcase}): String = String::new();
+                pub const $upcase: HeaderName = HeaderName::Stand(static_name($name_bytes));
             )+
 
+            /// 按字节排序的标准头名称, 用于二分查找
+            const STANDARD_NAMES: &'static [&'static str] = &[$(static_name($name_bytes),)+];
+
+            /// 从字节中构建, 标准头及常见的首字母大写写法直接引用静态字符串,
+            /// 不产生内存分配, 其余的保留原始大小写存为`Value`
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use webparse::HeaderName;
+            /// assert!(matches!(HeaderName::from_bytes(b"host"), Some(HeaderName::Stand(_))));
+            /// assert!(matches!(HeaderName::from_bytes(b"Content-Length"), Some(HeaderName::Stand(_))));
+            /// assert!(matches!(HeaderName::from_bytes(b"X-Custom"), Some(HeaderName::Value(_))));
+            /// assert!(HeaderName::from_bytes(b"CONTENT-TYPE").unwrap().is_standard());
+            /// assert!(HeaderName::from_bytes(b"X-Forwarded-For").unwrap().is_standard());
+            /// ```
             pub fn from_bytes(name_bytes: &[u8]) -> Option<HeaderName> {
-                match name_bytes {
-                    $(
-                        $name_bytes => Some(HeaderName::$upcase),
-                    )+
-                    _ => {
-                        Some(HeaderName::Value(std::string::String::from_utf8_lossy(name_bytes).to_string()))
-                    },
+                if let Some(name) = Self::standard_name(name_bytes) {
+                    if name.as_bytes() == name_bytes {
+                        return Some(HeaderName::Stand(name));
+                    }
+                    if let Some(name) = Self::common_title_case(name_bytes) {
+                        return Some(HeaderName::Stand(name));
+                    }
                 }
+                Some(HeaderName::Value(alloc::string::String::from_utf8_lossy(name_bytes).to_string()))
+            }
+
+            /// 忽略大小写查找对应的标准头名称
+            pub fn standard_name(name_bytes: &[u8]) -> Option<&'static str> {
+                Self::STANDARD_NAMES
+                    .binary_search_by(|n| Self::cmp_lowercase(n.as_bytes(), name_bytes))
+                    .ok()
+                    .map(|i| Self::STANDARD_NAMES[i])
             }
         }
     }
 }
 
+/// 在常量中将标准头的字节转为字符串
+const fn static_name(name: &'static [u8]) -> &'static str {
+    match core::str::from_utf8(name) {
+        Ok(name) => name,
+        Err(_) => panic!("header name must be utf8"),
+    }
+}
+
 standard_headers! {
     /// Advertises which content types the client is able to understand.
     ///
@@ -499,15 +608,15 @@ standard_headers! {
     /// the browser are set to block them, for example.
     (Cookie, COOKIE, b"cookie");
 
+    /// Contains the date and time at which the message was originated.
+    (Date, DATE, b"date");
+
     /// Indicates the client's tracking preference.
     ///
     /// This header lets users indicate whether they would prefer privacy rather
     /// than personalized content.
     (Dnt, DNT, b"dnt");
 
-    /// Contains the date and time at which the message was originated.
-    (Date, DATE, b"date");
-
     /// Identifier for a specific version of a resource.
     ///
     /// This header allows caches to be more efficient, and saves bandwidth, as
@@ -699,6 +808,10 @@ standard_headers! {
     /// document.
     (IfUnmodifiedSince, IF_UNMODIFIED_SINCE, b"if-unmodified-since");
 
+    /// Allows the sender to hint about how the connection may be used to set a
+    /// timeout and a maximum amount of requests.
+    (KeepAlive, KEEP_ALIVE, b"keep-alive");
+
     /// Content-Types that are acceptable for the response.
     (LastModified, LAST_MODIFIED, b"last-modified");
 
@@ -782,6 +895,10 @@ standard_headers! {
     /// header.
     (ProxyAuthorization, PROXY_AUTHORIZATION, b"proxy-authorization");
 
+    /// Non-standard variant of `connection` sent by some clients to proxies,
+    /// removed as a hop-by-hop header when forwarding.
+    (ProxyConnection, PROXY_CONNECTION, b"proxy-connection");
+
     /// Associates a specific cryptographic public key with a certain server.
     ///
     /// This decreases the risk of MITM attacks with forged certificates. If one
@@ -915,10 +1032,6 @@ standard_headers! {
     /// message.
     (TransferEncoding, TRANSFER_ENCODING, b"transfer-encoding");
 
-    /// Contains a string that allows identifying the requesting client's
-    /// software.
-    (UserAgent, USER_AGENT, b"user-agent");
-
     /// Used as part of the exchange to upgrade the protocol.
     (Upgrade, UPGRADE, b"upgrade");
 
@@ -926,6 +1039,10 @@ standard_headers! {
     /// encrypted and authenticated response.
     (UpgradeInsecureRequests, UPGRADE_INSECURE_REQUESTS, b"upgrade-insecure-requests");
 
+    /// Contains a string that allows identifying the requesting client's
+    /// software.
+    (UserAgent, USER_AGENT, b"user-agent");
+
     /// Determines how to match future requests with cached responses.
     ///
     /// The `vary` HTTP response header determines how to match future request
@@ -987,6 +1104,14 @@ standard_headers! {
     /// needed. This reduces latency when the user clicks a link.
     (XDnsPrefetchControl, X_DNS_PREFETCH_CONTROL, b"x-dns-prefetch-control");
 
+    /// Identifies the originating IP addresses of a client connecting through
+    /// an HTTP proxy or load balancer.
+    (XForwardedFor, X_FORWARDED_FOR, b"x-forwarded-for");
+
+    /// Identifies the protocol that a client used to connect to a proxy or
+    /// load balancer.
+    (XForwardedProto, X_FORWARDED_PROTO, b"x-forwarded-proto");
+
     /// Indicates whether or not a browser should be allowed to render a page in
     /// a frame.
     ///
@@ -997,6 +1122,13 @@ standard_headers! {
     /// is using a browser supporting `x-frame-options`.
     (XFrameOptions, X_FRAME_OPTIONS, b"x-frame-options");
 
+    /// The address of the client as seen by the first proxy.
+    (XRealIp, X_REAL_IP, b"x-real-ip");
+
+    /// Marks a request as made by a script, usually with the value
+    /// `XMLHttpRequest`.
+    (XRequestedWith, X_REQUESTED_WITH, b"x-requested-with");
+
     /// Stop pages from loading when an XSS attack is detected.
     ///
     /// The HTTP X-XSS-Protection response header is a feature of Internet
@@ -1008,3 +1140,24 @@ standard_headers! {
     /// for users of older web browsers that don't yet support CSP.
     (XXssProtection, X_XSS_PROTECTION, b"x-xss-protection");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderName;
+
+    #[test]
+    fn standard_names_sorted() {
+        let names = HeaderName::STANDARD_NAMES;
+        assert!(names.windows(2).all(|w| w[0] < w[1]));
+        for name in names {
+            assert_eq!(HeaderName::standard_name(name.to_ascii_uppercase().as_bytes()), Some(*name));
+            assert!(matches!(HeaderName::from_bytes(name.as_bytes()), Some(HeaderName::Stand(_))));
+        }
+        for title in [&b"Keep-Alive"[..], b"X-Real-IP", b"ETag", b"WWW-Authenticate"] {
+            let name = HeaderName::from_bytes(title).unwrap();
+            assert!(matches!(name, HeaderName::Stand(_)));
+            assert!(name.is_standard());
+        }
+        assert_eq!(HeaderName::standard_name(b"x-custom"), None);
+    }
+}
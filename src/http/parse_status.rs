@@ -0,0 +1,124 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/19 10:12:36
+
+/// 解析的结果, 区分已完成及数据不足, 数据格式错误则以`Err`返回
+///
+/// 解析是无状态的, 返回`Partial`时需在收到更多数据后从头部的首字节重新传入,
+/// 已解析的头信息会在下一次解析时被清除
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{ParseStatus, Request};
+///
+/// let mut req = Request::new();
+/// assert_eq!(req.parse_status(b"GET / HTTP/1.1\r\nHost: a").unwrap(), ParseStatus::Partial);
+/// let status = req.parse_status(b"GET / HTTP/1.1\r\nHost: a\r\n\r\nbody").unwrap();
+/// assert_eq!(status, ParseStatus::Complete(27));
+/// assert!(req.parse_status(b"GET / HTTP/1.1\r\nHo st: a\r\n").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStatus {
+    /// 头部已完整解析, 包含头部所占的字节数, 之后的数据为body
+    Complete(usize),
+    /// 数据不足, 需要更多的数据
+    Partial,
+}
+
+impl ParseStatus {
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        matches!(self, ParseStatus::Complete(_))
+    }
+
+    #[inline]
+    pub fn is_partial(&self) -> bool {
+        matches!(self, ParseStatus::Partial)
+    }
+
+    /// 头部所占的字节数, 未完成时返回None
+    #[inline]
+    pub fn complete_len(&self) -> Option<usize> {
+        match self {
+            ParseStatus::Complete(len) => Some(*len),
+            ParseStatus::Partial => None,
+        }
+    }
+}
+
+/// 头部各部分在原始数据中的位置, 用于零拷贝的转发原始数据
+///
+/// # Examples
+///
+/// ```
+/// use webparse::Request;
+///
+/// let data = b"\r\nGET / HTTP/1.1\r\nHost: a\r\n\r\nbody";
+/// let mut req = Request::new();
+/// let report = req.parse_with_report(data).unwrap();
+/// assert_eq!(report.start_line, 2);
+/// assert_eq!(&data[report.start_line..report.header_start()], b"GET / HTTP/1.1\r\n");
+/// assert_eq!(&data[report.header_start()..report.body_start], b"Host: a\r\n\r\n");
+/// assert_eq!(&data[report.body_start..], b"body");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    /// 起始行的偏移, 起始行之前的空行会被跳过
+    pub start_line: usize,
+    /// 起始行的长度, 包含换行符
+    pub start_line_len: usize,
+    /// 头部字段的长度, 包含结束的空行
+    pub header_len: usize,
+    /// body的起始位置, 即头部所占的总字节数
+    pub body_start: usize,
+}
+
+impl ParseReport {
+    /// 头部字段的起始位置
+    #[inline]
+    pub fn header_start(&self) -> usize {
+        self.start_line + self.start_line_len
+    }
+}
+
+/// 解析的计数信息, 可供反向代理导出每个请求的解析指标, 无需再次测量缓存
+///
+/// 头部解析完成时记录头的数量及头部所占的字节数(含起始行及结束的空行),
+/// body的字节数由`parse_message`或调用方通过`add_body_bytes`累加
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{ParseStats, Request, Response};
+///
+/// let data = b"POST /a HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\n\r\nok";
+/// let mut req = Request::new();
+/// req.parse_message(data).unwrap();
+/// assert_eq!(req.stats(), ParseStats { headers: 2, header_bytes: 48, body_bytes: 2 });
+/// req.add_body_bytes(3);
+/// assert_eq!(req.stats().body_bytes, 5);
+/// req.reset();
+/// assert_eq!(req.stats(), ParseStats::default());
+///
+/// let mut res = Response::new(());
+/// res.parse(b"HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nX: 1\r\n\r\n").unwrap();
+/// assert_eq!(res.stats().headers, 3);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// 头的数量, 同名的头分别计数
+    pub headers: usize,
+    /// 头部所占的总字节数, 即body的起始位置
+    pub header_bytes: usize,
+    /// 目前已读取的body字节数
+    pub body_bytes: usize,
+}
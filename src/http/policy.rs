@@ -0,0 +1,58 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/18 15:20:43
+
+/// 控制Builder在构建时自动补充的头信息, 均在`body`时生效且不覆盖已存在的头
+///
+/// 代理等需要原样转发的场景可使用`BuilderPolicy::none()`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{BuilderPolicy, Request};
+///
+/// let req = Request::builder()
+///     .policy(BuilderPolicy::none())
+///     .url("http://www.baidu.com/")
+///     .body(())
+///     .unwrap();
+/// assert!(req.headers().is_empty());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuilderPolicy {
+    /// 请求补充`User-Agent: wenmeng`
+    pub user_agent: bool,
+    /// 请求根据Url补充`Host`
+    pub host: bool,
+    /// 响应补充`Server: wenmeng`
+    pub server: bool,
+}
+
+impl BuilderPolicy {
+    /// 不补充任何头
+    pub const fn none() -> BuilderPolicy {
+        BuilderPolicy {
+            user_agent: false,
+            host: false,
+            server: false,
+        }
+    }
+}
+
+impl Default for BuilderPolicy {
+    fn default() -> Self {
+        BuilderPolicy {
+            user_agent: true,
+            host: true,
+            server: true,
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/16 14:37:05
+
+use crate::prelude::*;
+use crate::{Binary, BinaryRef, Buf, HeaderMap, HeaderName, HeaderValue, Helper, WebResult};
+
+/// 解析时收到的一行原始头, 保留原有的大小写及空白
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawHeader {
+    name: Binary,
+    value: Binary,
+    line: Binary,
+}
+
+impl RawHeader {
+    /// 原始的头名字
+    pub fn name(&self) -> &[u8] {
+        self.name.as_ref()
+    }
+
+    /// 头的值, 与解析得到的值相同, 不包含首尾的空白及换行符, 续行以一个空格合并
+    pub fn value(&self) -> &[u8] {
+        self.value.as_ref()
+    }
+
+    /// 完整的一行, 包括原有的换行符及续行
+    pub fn line(&self) -> &[u8] {
+        self.line.as_ref()
+    }
+}
+
+/// 解析时收到的原始头, 按收到的顺序保存, 用于透明代理时原样转发未修改的头
+///
+/// 在解析前通过`Request::preserve_raw_headers`或`Response::preserve_raw_headers`开启,
+/// 长连接复用时`reset`不会关闭, 编码时开启`Encoder::raw_headers`后未修改的头按原始的字节输出
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{BinaryMut, Buf, Request};
+/// use webparse::http::http1::Encoder;
+///
+/// let mut req = Request::new();
+/// req.preserve_raw_headers();
+/// req.parse(b"GET / HTTP/1.1\r\nhOST:  a\r\nx-ID:1 \r\nAccept: */*\r\n\r\n").unwrap();
+/// let raw = req.raw_headers().unwrap();
+/// assert_eq!(raw.len(), 3);
+/// assert_eq!(raw.iter().next().unwrap().line(), b"hOST:  a\r\n");
+///
+/// req.headers_mut().insert("Accept", "text/html");
+/// let mut buffer = BinaryMut::new();
+/// Encoder::new().raw_headers(true).encode_request_head(&req, &mut buffer).unwrap();
+/// assert_eq!(
+///     buffer.chunk(),
+///     &b"GET / HTTP/1.1\r\nhOST:  a\r\nx-ID:1 \r\nAccept: text/html\r\n\r\n"[..]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawHeaders {
+    headers: Vec<RawHeader>,
+}
+
+impl RawHeaders {
+    pub fn new() -> RawHeaders {
+        RawHeaders::default()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, RawHeader> {
+        self.headers.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// 该头在解析后是否未被修改, 即该名字的所有原始行的值以`, `合并后与`value`相同.
+    /// 决定body长度的Content-Length及Transfer-Encoding总是视为已修改, 不按原始的字节输出
+    pub fn is_unmodified(&self, name: &HeaderName, value: &HeaderValue) -> bool {
+        if *name == HeaderName::CONTENT_LENGTH || *name == HeaderName::TRANSFER_ENCODING {
+            return false;
+        }
+        let mut lines = self.headers.iter().filter(|h| *name == h.name());
+        let mut rest = match lines.next() {
+            Some(first) => match value.as_bytes().strip_prefix(first.value()) {
+                Some(rest) => rest,
+                None => return false,
+            },
+            None => return false,
+        };
+        for line in lines {
+            rest = match rest.strip_prefix(b", ").and_then(|r| r.strip_prefix(line.value())) {
+                Some(rest) => rest,
+                None => return false,
+            };
+        }
+        rest.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.headers.clear();
+    }
+
+    /// 解析头部并记录每一行的原始数据, 出错时与`Helper::parse_header`相同
+    pub(crate) fn parse_header<B: Buf, F: FnMut(&HeaderName)>(
+        &mut self,
+        buffer: &mut B,
+        header: &mut HeaderMap,
+        strict: bool,
+        on_name: F,
+    ) -> WebResult<usize> {
+        self.headers.clear();
+        let (used, result) = {
+            let chunk = buffer.chunk();
+            let mut block = BinaryRef::from(chunk);
+            let result = Helper::parse_header_with(&mut block, header, strict, on_name);
+            let used = chunk.len() - block.remaining();
+            if result.is_ok() {
+                self.record(Binary::from(chunk[..used].to_vec()));
+            }
+            (used, result)
+        };
+        // 出错时同样前进已读取的数据, 以便错误中的偏移与不记录时一致
+        buffer.advance(used);
+        result
+    }
+
+    /// 各个头均为`block`的切片, 仅有续行的头需要另外拼接值
+    fn record(&mut self, block: Binary) {
+        let data: &[u8] = block.as_ref();
+        // 当前头的起始位置及续行拼接后的值
+        let mut start = 0;
+        let mut folded: Option<Vec<u8>> = None;
+        let mut pos = 0;
+        while pos < data.len() {
+            let end = data[pos..]
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(data.len(), |p| pos + p + 1);
+            let line = &data[pos..end];
+            // 续行合并到上一个头中
+            if matches!(line.first(), Some(b' ' | b'\t')) {
+                if let Some(last) = self.headers.last_mut() {
+                    let more = line.trim_ascii();
+                    if !more.is_empty() {
+                        let value = folded.get_or_insert_with(|| last.value.to_vec());
+                        if !value.is_empty() {
+                            value.push(b' ');
+                        }
+                        value.extend_from_slice(more);
+                    }
+                    last.line = block.slice(start..end);
+                }
+                pos = end;
+                continue;
+            }
+            self.finish_folded(&mut folded);
+            if let Some(colon) = line.iter().position(|b| *b == b':') {
+                let value = trim_range(data, pos + colon + 1, end);
+                let name_end = pos + line[..colon].trim_ascii_end().len();
+                self.headers.push(RawHeader {
+                    name: block.slice(pos..name_end),
+                    value: block.slice(value),
+                    line: block.slice(pos..end),
+                });
+                start = pos;
+            }
+            pos = end;
+        }
+        self.finish_folded(&mut folded);
+    }
+
+    fn finish_folded(&mut self, folded: &mut Option<Vec<u8>>) {
+        if let (Some(value), Some(last)) = (folded.take(), self.headers.last_mut()) {
+            last.value = Binary::from(value);
+        }
+    }
+}
+
+/// `data[start..end]`去除首尾空白后的范围
+fn trim_range(data: &[u8], mut start: usize, mut end: usize) -> core::ops::Range<usize> {
+    while start < end && data[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while end > start && data[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::http1::Encoder;
+    use crate::{BinaryMut, Buf, Request, Response};
+
+    #[test]
+    fn replay_unmodified() {
+        let data = b"HTTP/1.1 200 OK\r\nset-COOKIE: a=1\r\nX-Drop: 1\r\nVia : 1.1 a\r\n\r\n";
+        let mut res = Response::new(());
+        res.preserve_raw_headers();
+        res.parse(data).unwrap();
+        assert_eq!(res.raw_headers().unwrap().len(), 3);
+
+        let mut buffer = BinaryMut::new();
+        Encoder::new()
+            .raw_headers(true)
+            .encode_response_head(&res, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer.chunk(), &data[..]);
+
+        res.headers_mut().remove(&"x-drop");
+        res.headers_mut().insert("Via", "1.1 b");
+        res.headers_mut().insert("Server", "proxy");
+        let mut buffer = BinaryMut::new();
+        Encoder::new()
+            .raw_headers(true)
+            .encode_response_head(&res, &mut buffer)
+            .unwrap();
+        assert_eq!(
+            buffer.chunk(),
+            &b"HTTP/1.1 200 OK\r\nset-COOKIE: a=1\r\nVia: 1.1 b\r\nServer: proxy\r\n\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn repeated_not_replayed() {
+        let data = b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nX-A: 1\r\nx-a: 2\r\nVia: 1.1 a\r\n\r\n";
+        let mut res = Response::new(());
+        res.preserve_raw_headers();
+        res.parse(data).unwrap();
+
+        // 重复的头在解析后只保留了最后的值, 按解析得到的值输出以保持一致
+        let mut buffer = BinaryMut::new();
+        Encoder::new()
+            .raw_headers(true)
+            .encode_response_head(&res, &mut buffer)
+            .unwrap();
+        assert_eq!(
+            buffer.chunk(),
+            &b"HTTP/1.1 200 OK\r\nVia: 1.1 a\r\ncontent-length: 5\r\nX-A: 2\r\n\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn kept_after_reset() {
+        let mut req = Request::new();
+        req.preserve_raw_headers();
+        req.parse(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+        req.reset();
+        assert!(req.raw_headers().unwrap().is_empty());
+
+        req.parse(b"GET / HTTP/1.1\r\nX-Fold: a\r\n b\r\n\tc\r\nHost: a\r\n\r\n").unwrap();
+        let raw = req.raw_headers().unwrap();
+        assert_eq!(raw.len(), 2);
+        let fold = raw.iter().next().unwrap();
+        assert_eq!(fold.name(), b"X-Fold");
+        assert_eq!(fold.value(), b"a b c");
+        assert_eq!(fold.line(), b"X-Fold: a\r\n b\r\n\tc\r\n");
+        assert_eq!(raw.iter().nth(1).unwrap().line(), b"Host: a\r\n");
+    }
+}
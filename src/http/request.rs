@@ -10,15 +10,19 @@
 // -----
 // Created Date: 2023/08/15 10:00:38
 
-use std::{
-    fmt::Display,
-    sync::{Arc, RwLock},
-};
-
-use super::{http2::HeaderIndex, HeaderMap, Method, Version};
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::sync::{Arc, RwLock};
+
+use crate::prelude::*;
+use super::{BuilderPolicy, HeaderMap, HttpError, Method, ParseReport, ParseStats, ParseStatus, RawHeaders, TargetForm, Trailers, Version};
+#[cfg(feature = "std")]
+use super::http2::HeaderIndex;
+#[cfg(feature = "std")]
+use crate::http2::frame::Settings;
 use crate::{
-    http2::frame::Settings, BinaryMut, Buf, BufMut, Extensions, HeaderName, HeaderValue, Helper,
-    Scheme, Serialize, Url, WebError, WebResult,
+    Authority, Binary, BinaryMut, BinaryRef, Buf, BufMut, Extensions, HeaderName, HeaderValue, Helper,
+    Scheme, Serialize, SizedSerialize, Url, WebError, WebResult,
 };
 
 #[derive(Debug)]
@@ -29,21 +33,31 @@ where
     parts: Parts,
     body: T,
     partial: bool,
+    strict: bool,
+    raw: Option<RawHeaders>,
+    stats: ParseStats,
+    multiple_host: bool,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parts {
     pub method: Method,
     pub header: HeaderMap,
     pub version: Version,
     pub url: Url,
     pub path: String,
+    /// 解析时收到的原始请求目标, 未经任何处理
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw_target: Binary,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub extensions: Extensions,
 }
 
 #[derive(Debug)]
 pub struct Builder {
     inner: WebResult<Parts>,
+    policy: BuilderPolicy,
 }
 
 impl Builder {
@@ -151,9 +165,6 @@ impl Builder {
     {
         self.and_then(move |mut head| {
             head.url = TryFrom::try_from(url).map_err(Into::into)?;
-            if let Some(connect) = &head.url.get_connect_url() {
-                head.header.insert("Host", connect.clone());
-            }
             Ok(head)
         })
     }
@@ -284,6 +295,29 @@ impl Builder {
         self.inner.as_mut().ok().map(|h| &mut h.header)
     }
 
+    /// 设置自动补充头信息的策略, 默认补充
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::*;
+    ///
+    /// let req = Request::builder()
+    ///     .policy(BuilderPolicy::none())
+    ///     .body(())
+    ///     .unwrap();
+    /// assert!(!req.headers().contains(&"User-Agent"));
+    /// ```
+    pub fn policy(mut self, policy: BuilderPolicy) -> Builder {
+        self.policy = policy;
+        self
+    }
+
+    /// 不自动补充任何头, 等同于`policy(BuilderPolicy::none())`
+    pub fn no_default_headers(self) -> Builder {
+        self.policy(BuilderPolicy::none())
+    }
+
     /// 传入Body信息,构建出Request的请求信息
     ///
     /// # Examples
@@ -299,20 +333,56 @@ impl Builder {
     where
         T: Serialize,
     {
+        let policy = self.policy;
         self.inner.map(move |mut head| {
             if head.path.len() == 0 {
                 head.path = head.url.path.clone();
             }
+            if policy.host && !head.header.contains(&HeaderName::HOST) {
+                if let Some(connect) = head.url.get_connect_url() {
+                    head.header.insert("Host", connect);
+                }
+            }
             let server = HeaderName::from_static("User-Agent");
-            if !head.header.contains(&server) {
+            if policy.user_agent && !head.header.contains(&server) {
                 head.header.insert(server, "wenmeng");
             }
             Request {
                 parts: head,
                 body,
                 partial: true,
+                strict: false,
+                raw: None,
+                stats: ParseStats::default(),
+                multiple_host: false,
+            }
+        })
+    }
+
+    /// 传入已知长度的Body信息, 未设置Content-Length且非chunked时自动设置
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::*;
+    ///
+    /// let request = Request::builder()
+    ///     .sized_body("hello")
+    ///     .unwrap();
+    /// assert_eq!(request.get_body_len(), 5);
+    /// ```
+    pub fn sized_body<T>(self, body: T) -> WebResult<Request<T>>
+    where
+        T: SizedSerialize,
+    {
+        let len = body.serialize_len();
+        self.and_then(move |mut head| {
+            if !head.header.contains(&HeaderName::CONTENT_LENGTH) && !head.header.is_chunked() {
+                head.header.insert(HeaderName::CONTENT_LENGTH, len);
             }
+            Ok(head)
         })
+        .body(body)
     }
 
     /// 获取请求的body长度, 如果为0则表示不存在长度信息,
@@ -325,6 +395,7 @@ impl Builder {
         }
     }
 
+    #[cfg(feature = "std")]
     pub fn upgrade_http2(self, settings: Settings) -> Self {
         self.and_then(move |mut head| {
             if head.path.len() == 0 {
@@ -333,7 +404,7 @@ impl Builder {
             head.header.insert("Connection", "Upgrade, HTTP2-Settings");
             head.header.insert("Upgrade", "h2c");
             head.header
-                .insert("HTTP2-Settings", settings.encode_http_settings());
+                .insert("HTTP2-Settings", settings.encode_http2_settings_header());
             Ok(head)
         })
     }
@@ -344,6 +415,7 @@ impl Builder {
     {
         Builder {
             inner: self.inner.and_then(func),
+            policy: self.policy,
         }
     }
 }
@@ -353,7 +425,10 @@ impl Default for Builder {
     fn default() -> Builder {
         let mut parts = Parts::new();
         parts.method = Method::Get;
-        Builder { inner: Ok(parts) }
+        Builder {
+            inner: Ok(parts),
+            policy: BuilderPolicy::default(),
+        }
     }
 }
 
@@ -362,6 +437,10 @@ impl Request<()> {
         Request {
             body: (),
             partial: false,
+            strict: false,
+            raw: None,
+            stats: ParseStats::default(),
+            multiple_host: false,
             parts: Parts::new(),
         }
     }
@@ -370,6 +449,10 @@ impl Request<()> {
         Request {
             body: (),
             partial: false,
+            strict: false,
+            raw: None,
+            stats: ParseStats::default(),
+            multiple_host: false,
             parts,
         }
     }
@@ -427,6 +510,55 @@ where
         self.parts.path = path;
     }
 
+    /// 请求行中原始的请求目标, 与`path()`不同, 不会因`set_path`等修改而变化,
+    /// 可用于签名校验或原样回显, 非解析得到的请求为空
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Request;
+    ///
+    /// let mut req = Request::new();
+    /// req.parse(b"GET /a%20b/../c?x=%41 HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+    /// req.set_path("/c".to_string());
+    /// assert_eq!(&req.raw_target()[..], b"/a%20b/../c?x=%41");
+    /// assert_eq!(req.path(), "/c");
+    /// ```
+    #[inline]
+    pub fn raw_target(&self) -> &Binary {
+        &self.parts.raw_target
+    }
+
+    /// 清空已解析的信息以便解析长连接上的下一个请求, 保留已分配的空间, body保持不变
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Method, Request};
+    ///
+    /// let mut req = Request::new();
+    /// req.parse(b"GET /first/long/path HTTP/1.1\r\nHost: a.com\r\nAccept: */*\r\n\r\n").unwrap();
+    /// let capacity = req.path().capacity();
+    /// req.reset();
+    /// assert_eq!(req.method(), &Method::NONE);
+    /// assert!(req.headers().is_empty());
+    /// assert_eq!(req.path().capacity(), capacity);
+    ///
+    /// req.parse(b"POST /b HTTP/1.1\r\nHost: b.com\r\n\r\n").unwrap();
+    /// assert_eq!(req.path(), "/b");
+    /// assert_eq!(req.path().capacity(), capacity);
+    /// assert_eq!(req.headers().len(), 1);
+    /// ```
+    pub fn reset(&mut self) {
+        self.parts.reset();
+        self.partial = false;
+        if let Some(raw) = &mut self.raw {
+            raw.clear();
+        }
+        self.stats = ParseStats::default();
+        self.multiple_host = false;
+    }
+
     pub fn scheme(&self) -> &Scheme {
         &self.parts.url.scheme
     }
@@ -489,6 +621,161 @@ where
         self.parts.url.get_authority()
     }
 
+    /// 获取请求的认证部分, 可区分域名/IPv4/IPv6
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Host, Request};
+    /// let mut req = Request::new();
+    /// req.parse(b"CONNECT [::1]:443 HTTP/1.1\r\n\r\n").unwrap();
+    /// let auth = req.authority().unwrap();
+    /// assert!(matches!(auth.host(), Host::Ipv6(_)));
+    /// assert_eq!(auth.port(), Some(443));
+    /// assert_eq!(req.get_connect_url().unwrap(), "[::1]:443");
+    ///
+    /// // Host头及CONNECT的目标中不允许出现用户信息
+    /// assert!(Request::new().parse(b"CONNECT user@a.com:443 HTTP/1.1\r\n\r\n").is_err());
+    /// assert!(Request::new().parse(b"GET / HTTP/1.1\r\nHost: user@a.com\r\n\r\n").is_err());
+    /// ```
+    pub fn authority(&self) -> Option<&Authority> {
+        self.parts.url.authority()
+    }
+
+    /// 请求行中目标的形式
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Request, TargetForm};
+    /// let mut req = Request::new();
+    /// req.parse(b"GET http://example.com/path HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+    /// assert_eq!(req.target_form(), TargetForm::Absolute);
+    /// assert_eq!(req.url().domain.as_deref(), Some("example.com"));
+    /// ```
+    pub fn target_form(&self) -> TargetForm {
+        let path = &self.parts.path;
+        if self.parts.method == Method::Connect {
+            TargetForm::Authority
+        } else if path == "*" {
+            TargetForm::Asterisk
+        } else if path.starts_with('/') {
+            TargetForm::Origin
+        } else {
+            TargetForm::Absolute
+        }
+    }
+
+    /// 绝对形式的请求中, Host头是否与目标中的地址一致, 见RFC 7230 §5.4,
+    /// 不一致时应以目标中的地址为准, 代理可据此拒绝请求.
+    /// 其它形式或不存在Host头时返回true
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Request;
+    /// let mut req = Request::new();
+    /// req.parse(b"GET http://example.com/ HTTP/1.1\r\nHost: Example.com:80\r\n\r\n").unwrap();
+    /// assert!(req.host_matches_target());
+    /// req.parse(b"GET http://example.com/ HTTP/1.1\r\nHost: evil.com\r\n\r\n").unwrap();
+    /// assert!(!req.host_matches_target());
+    /// ```
+    pub fn host_matches_target(&self) -> bool {
+        if self.target_form() != TargetForm::Absolute {
+            return true;
+        }
+        let host = match self.parts.header.get_option_value(&HeaderName::HOST) {
+            Some(host) => host.to_string(),
+            None => return true,
+        };
+        let (host, target) = match (Authority::parse(&host), self.authority()) {
+            (Ok(host), Some(target)) => (host, target),
+            _ => return false,
+        };
+        let default = self.parts.url.scheme.default_port().unwrap_or(0);
+        host.host() == target.host() && host.port_or(default) == target.port_or(default)
+    }
+
+    /// 按RFC 7230 §5.4及RFC 7540 §8.1.2.3校验Host头, 失败时返回对应的`HttpError`,
+    /// 服务端可据此统一返回400
+    ///
+    /// - HTTP/1.1必须有且只有一个Host头
+    /// - 绝对形式的请求目标须与Host一致
+    /// - HTTP/2中Host与:authority同时存在时须一致
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{HttpError, Request, WebError};
+    /// let mut req = Request::new();
+    /// req.parse(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+    /// assert!(req.validate_host().is_ok());
+    ///
+    /// let mut req = Request::new();
+    /// req.parse(b"GET / HTTP/1.1\r\nAccept: */*\r\n\r\n").unwrap();
+    /// assert!(matches!(req.validate_host(), Err(WebError::Http(HttpError::HostMissing))));
+    ///
+    /// let mut req = Request::new();
+    /// req.parse(b"GET / HTTP/1.1\r\nHost: a.com\r\nHost: b.com\r\n\r\n").unwrap();
+    /// assert!(matches!(req.validate_host(), Err(WebError::Http(HttpError::HostMultiple))));
+    ///
+    /// let mut req = Request::new();
+    /// req.parse(b"GET http://a.com/ HTTP/1.1\r\nHost: b.com\r\n\r\n").unwrap();
+    /// assert!(matches!(req.validate_host(), Err(WebError::Http(HttpError::HostMismatch))));
+    /// ```
+    pub fn validate_host(&self) -> WebResult<()> {
+        if self.multiple_host {
+            return Err(WebError::from(HttpError::HostMultiple));
+        }
+        let host = match self.parts.header.get_option_value(&HeaderName::HOST) {
+            Some(value) => {
+                let value = value.to_string();
+                if !value.is_empty() && Authority::parse(&value).is_err() {
+                    return Err(WebError::from(HttpError::HeaderValue));
+                }
+                Some(value)
+            }
+            None => None,
+        };
+        match self.parts.version {
+            Version::Http11 if host.is_none() => {
+                return Err(WebError::from(HttpError::HostMissing));
+            }
+            Version::Http2 | Version::Http3 => {
+                if let (Some(host), Some(target)) = (&host, self.authority()) {
+                    let host = Authority::parse(host).map_err(|_| HttpError::HeaderValue)?;
+                    let default = self.parts.url.scheme.default_port().unwrap_or(0);
+                    if host.host() != target.host()
+                        || host.port_or(default) != target.port_or(default)
+                    {
+                        return Err(WebError::from(HttpError::HostMismatch));
+                    }
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        if !self.host_matches_target() {
+            return Err(WebError::from(HttpError::HostMismatch));
+        }
+        Ok(())
+    }
+
+    /// GET/HEAD请求是否带有body, 这类body没有定义的语义, 服务端可据此拒绝
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Request;
+    /// let mut req = Request::new();
+    /// req.parse(b"GET / HTTP/1.1\r\nHost: a.com\r\nContent-Length: 3\r\n\r\n").unwrap();
+    /// assert!(req.has_unexpected_body());
+    /// ```
+    pub fn has_unexpected_body(&self) -> bool {
+        matches!(self.parts.method, Method::Get | Method::Head)
+            && (self.parts.header.get_body_len() > 0 || self.parts.header.is_chunked())
+    }
+
     /// 获取请求的scheme
     pub fn get_scheme(&self) -> String {
         self.parts.url.get_scheme()
@@ -512,6 +799,10 @@ where
             body,
             parts: self.parts,
             partial: self.partial,
+            strict: self.strict,
+            raw: self.raw,
+            stats: self.stats,
+            multiple_host: self.multiple_host,
         };
         (new, self.body)
     }
@@ -521,53 +812,113 @@ where
             body: From::from(self.body),
             parts: self.parts,
             partial: self.partial,
+            strict: self.strict,
+            raw: self.raw,
+            stats: self.stats,
+            multiple_host: self.multiple_host,
         };
         new
     }
 
+    /// 存在多个Host头时不用于确定地址, 由`validate_host`拒绝
+    fn single_host(&self) -> Option<String> {
+        if self.multiple_host {
+            return None;
+        }
+        self.parts.header.get_host()
+    }
+
     fn parse_connect_by_host(url: &mut Url, h: &String) -> WebResult<()> {
-        // Host中存在端口号, 则直接取端口号
-        let vec: Vec<&str> = h.split(":").collect();
-        if vec.len() == 1 {
-            url.domain = Some(vec[0].to_string());
+        // 空的Host头依然合法
+        if h.is_empty() {
+            url.domain = Some(String::new());
             url.port = Some(80);
-        } else if vec.len() == 2 {
-            url.domain = Some(vec[0].to_string());
-            url.port = Some(vec[1].parse().map_err(WebError::from)?);
-        } else {
+            url.update_authority();
+            return Ok(());
+        }
+        // Host中存在端口号, 则直接取端口号, 支持[::1]:8080形式的IPv6
+        let mut authority = Authority::parse(h).map_err(|_| WebError::IntoError)?;
+        // Host头及CONNECT的目标中不允许出现用户信息, 见RFC 7230 §5.4及§5.3.3
+        if authority.userinfo().is_some() {
             return Err(WebError::IntoError);
         }
-
+        authority.set_port(Some(authority.port_or(80)));
+        url.set_authority(authority);
         Ok(())
     }
 
+    /// 从Buf中解析, 返回读取的字节数, 格式错误以`WebError::Http`返回,
+    /// 需要出错的偏移时使用`parse_with_report`
     pub fn parse_buffer<B: Buf>(&mut self, buffer: &mut B) -> WebResult<usize> {
         let len = buffer.remaining();
+        Ok(self.parse_head(buffer, len)?.body_start)
+    }
+
+    fn parse_buffer_report<B: Buf>(&mut self, buffer: &mut B) -> WebResult<ParseReport> {
+        let len = buffer.remaining();
+        self.parse_head(buffer, len)
+            .map_err(|e| e.with_offset(len - buffer.remaining()))
+    }
+
+    fn parse_head<B: Buf>(&mut self, buffer: &mut B, len: usize) -> WebResult<ParseReport> {
         self.partial = true;
         Helper::skip_empty_lines(buffer)?;
+        let start_line = len - buffer.remaining();
         self.parts.method = Helper::parse_method(buffer)?;
         Helper::skip_spaces(buffer)?;
-        self.parts.path = Helper::parse_token(buffer)?.to_string();
+        // 请求目标取为缓存的切片, 缓存为Binary时不拷贝数据
+        let target_len = match buffer.chunk().iter().position(|b| !Helper::is_token(*b)) {
+            Some(0) => return Err(WebError::from(HttpError::Token)),
+            Some(len) => len,
+            None => return Err(WebError::from(HttpError::Partial)),
+        };
+        self.parts.raw_target = buffer.copy_to_binary(target_len);
+        let target = core::str::from_utf8(self.parts.raw_target.chunk())
+            .map_err(|_| WebError::from(HttpError::Token))?;
+        self.parts.path.clear();
+        self.parts.path.push_str(target);
         Helper::skip_spaces(buffer)?;
         self.parts.version = Helper::parse_version(buffer)?;
         Helper::skip_new_line(buffer)?;
-        Helper::parse_header(buffer, &mut self.parts.header)?;
+        let header_start = len - buffer.remaining();
+        let strict = self.is_strict_headers();
+        let mut hosts = 0;
+        let on_name = |name: &HeaderName| {
+            if *name == HeaderName::HOST {
+                hosts += 1;
+            }
+        };
+        let headers = match &mut self.raw {
+            Some(raw) => raw.parse_header(buffer, &mut self.parts.header, strict, on_name)?,
+            None => Helper::parse_header_with(buffer, &mut self.parts.header, strict, on_name)?,
+        };
+        self.multiple_host = hosts > 1;
         self.partial = false;
-        self.parts.url = match self.parts.method {
+        let body_start = len - buffer.remaining();
+        // 解析到已有的Url中, 长连接中复用已分配的空间
+        let host = self.single_host();
+        let url = &mut self.parts.url;
+        match self.parts.method {
             // Connect 协议, Path则为连接地址,
             Method::Connect => {
-                let mut url = Url::new();
-                Self::parse_connect_by_host(&mut url, &self.parts.path)?;
-                url
+                url.clear();
+                Self::parse_connect_by_host(url, &self.parts.path)?;
+            }
+            // OPTIONS * 请求, 地址取自Host头
+            _ if self.parts.path == "*" => {
+                url.clear();
+                url.path.clear();
+                url.path.push('*');
+                if let Some(h) = host {
+                    Self::parse_connect_by_host(url, &h)?;
+                }
+                url.scheme = Scheme::Http;
             }
             _ => {
-                let mut url = Url::try_from(self.parts.path.to_string())?;
+                url.parse_into(&mut BinaryRef::from(self.parts.path.as_bytes()))?;
                 if url.domain.is_none() {
-                    match self.parts.header.get_host() {
-                        Some(h) => {
-                            Self::parse_connect_by_host(&mut url, &h)?;
-                        }
-                        _ => (),
+                    if let Some(h) = host {
+                        Self::parse_connect_by_host(url, &h)?;
                     }
                 }
 
@@ -583,18 +934,91 @@ where
                         }
                     }
                 }
-                url
             }
+        }
+        crate::web_trace!(
+            "HTTP1: 解析请求头完成; method={} path={} headers={} body_start={}",
+            self.parts.method,
+            self.parts.path,
+            self.parts.header.len(),
+            body_start
+        );
+        self.stats = ParseStats {
+            headers,
+            header_bytes: body_start,
+            body_bytes: 0,
         };
-        Ok(len - buffer.remaining())
+        Ok(ParseReport {
+            start_line,
+            start_line_len: header_start - start_line,
+            header_len: body_start - header_start,
+            body_start,
+        })
     }
 
     pub fn parse(&mut self, buf: &[u8]) -> WebResult<usize> {
         self.partial = true;
-        let mut buffer = BinaryMut::from(buf);
+        let mut buffer = BinaryRef::from(buf);
         self.parse_buffer(&mut buffer)
     }
 
+    /// 同`parse`, 返回起始行/头部/body在`buf`中的位置,
+    /// 格式错误以`WebError::Parse`返回, 带有出错的偏移
+    pub fn parse_with_report(&mut self, buf: &[u8]) -> WebResult<ParseReport> {
+        self.partial = true;
+        let mut buffer = BinaryRef::from(buf);
+        self.parse_buffer_report(&mut buffer)
+    }
+
+    /// 解析一个完整的消息(头部及body), 不会越过当前消息读取后续的流水线请求,
+    /// `Complete`中为该消息所占的总字节数, 余下的数据属于下一个请求
+    ///
+    /// body的数据位于`buf[head_len..len]`, chunked时为未解码的原始数据
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{ParseStatus, Request};
+    ///
+    /// let data = b"POST /a HTTP/1.1\r\nContent-Length: 2\r\n\r\nokGET /b HTTP/1.1\r\n\r\n";
+    /// let mut req = Request::new();
+    /// assert_eq!(req.parse_message(data).unwrap(), ParseStatus::Complete(41));
+    /// assert_eq!(req.parse_message(&data[41..]).unwrap(), ParseStatus::Complete(19));
+    /// assert_eq!(req.path(), "/b");
+    ///
+    /// // 无效或不一致的Content-Length无法确定消息的边界
+    /// let data = b"POST /a HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 0\r\n\r\nok";
+    /// assert!(Request::new().parse_message(data).is_err());
+    /// ```
+    pub fn parse_message(&mut self, buf: &[u8]) -> WebResult<ParseStatus> {
+        let head = match self.parse_status(buf)? {
+            ParseStatus::Complete(head) => head,
+            ParseStatus::Partial => return Ok(ParseStatus::Partial),
+        };
+        match Helper::body_frame_len(&self.parts.header, &buf[head..]) {
+            Ok(len) => {
+                self.add_body_bytes(len);
+                Ok(ParseStatus::Complete(head + len))
+            }
+            Err(e) if e.is_partial() => {
+                self.partial = true;
+                Ok(ParseStatus::Partial)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 解析头部, 以`ParseStatus`区分数据不足及解析完成, 格式错误返回`Err`
+    ///
+    /// 返回`Partial`时需带上之前的数据从头重新解析
+    pub fn parse_status(&mut self, buf: &[u8]) -> WebResult<ParseStatus> {
+        match self.parse(buf) {
+            Ok(len) => Ok(ParseStatus::Complete(len)),
+            Err(e) if e.is_partial() => Ok(ParseStatus::Partial),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Returns a reference to the associated extensions.
     ///
     /// # Examples
@@ -614,11 +1038,108 @@ where
         &mut self.parts.extensions
     }
 
+    /// 获取请求的trailer头, 如http2中在DATA之后收到的HEADERS帧
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{HeaderMap, Request};
+    /// let mut req = Request::new();
+    /// assert!(req.trailers().is_none());
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("grpc-status", "0");
+    /// req.set_trailers(trailers);
+    /// assert!(req.trailers().unwrap().contains(&"grpc-status"));
+    /// ```
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.parts.extensions.get::<Trailers>().map(|t| &t.0)
+    }
+
+    /// 解析时记录每个头的原始数据, 需在解析前调用, 见`RawHeaders`
+    pub fn preserve_raw_headers(&mut self) {
+        if self.raw.is_none() {
+            self.raw = Some(RawHeaders::new());
+        }
+    }
+
+    /// 严格解析头部, 拒绝名字与`:`之间的空白(如`Host : a`)及以空白开头的续行,
+    /// 默认关闭, 此时名字后的空白被忽略, 续行以一个空格与上一行合并
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Request;
+    ///
+    /// let data = b"GET / HTTP/1.1\r\nHost : a\r\nX-Fold: a\r\n  b\r\n\r\n";
+    /// let mut req = Request::new();
+    /// req.parse(data).unwrap();
+    /// assert_eq!(req.headers().get_str_value(&"x-fold").as_deref(), Some("a b"));
+    ///
+    /// let mut req = Request::new();
+    /// req.set_strict_headers(true);
+    /// assert!(req.parse(data).is_err());
+    /// assert!(req.parse(b"GET / HTTP/1.1\r\nHost: a\r\nX-Val:\t a  b \r\n\r\n").is_ok());
+    /// assert_eq!(req.headers().get_str_value(&"x-val").as_deref(), Some("a  b"));
+    /// ```
+    pub fn set_strict_headers(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict_headers(&self) -> bool {
+        self.strict
+    }
+
+    /// 解析的计数信息, 见`ParseStats`, 未解析时各项均为0
+    pub fn stats(&self) -> ParseStats {
+        self.stats
+    }
+
+    /// 累加已读取的body字节数, 供自行读取body的调用方记录
+    pub fn add_body_bytes(&mut self, len: usize) {
+        self.stats.body_bytes += len;
+    }
+
+    /// 解析时收到的原始头, 未开启`preserve_raw_headers`时返回None
+    pub fn raw_headers(&self) -> Option<&RawHeaders> {
+        self.raw.as_ref()
+    }
+
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.parts.extensions.insert_cloneable(Trailers(trailers));
+    }
+
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.parts.extensions.remove::<Trailers>().map(|t| t.0)
+    }
+
     pub fn http1_data(&mut self) -> WebResult<Vec<u8>> {
+        Ok(self.to_binary()?.to_vec())
+    }
+
+    /// 将请求头及body以http1格式直接写入调用方的缓存, 返回写入的字节数
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf, Request};
+    ///
+    /// let mut req = Request::builder().url("/index").header("Host", "a").body("hi").unwrap();
+    /// let mut buffer = BinaryMut::new();
+    /// let size = req.encode_to(&mut buffer).unwrap();
+    /// assert_eq!(size, buffer.remaining());
+    /// assert_eq!(req.to_binary().unwrap().chunk(), buffer.chunk());
+    /// ```
+    pub fn encode_to<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        let mut size = self.encode_header_only(buffer)?;
+        size += self.encode_body(buffer)?;
+        Ok(size)
+    }
+
+    /// 以http1格式编码为共享的`Binary`, 相比`http1_data`少一次拷贝
+    pub fn to_binary(&mut self) -> WebResult<Binary> {
         let mut buffer = BinaryMut::new();
-        self.encode_header(&mut buffer)?;
-        self.body.serialize(&mut buffer)?;
-        return Ok(buffer.into_slice_all());
+        self.encode_to(&mut buffer)?;
+        Ok(buffer.freeze())
     }
 
     pub fn body(&self) -> &T {
@@ -629,6 +1150,50 @@ where
         &mut self.body
     }
 
+    /// 仅编码请求行及头部, body由`encode_body`单独编码, 用于`Expect: 100-continue`时
+    /// 先发送头部, 等待服务端返回100 Continue后再发送body
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf, Request};
+    ///
+    /// let mut req = Request::builder()
+    ///     .method("POST")
+    ///     .url("/upload")
+    ///     .header("Host", "a")
+    ///     .header("Content-Length", "4")
+    ///     .header("Expect", "100-continue")
+    ///     .body("data")
+    ///     .unwrap();
+    /// assert!(req.expects_continue());
+    /// let mut buffer = BinaryMut::new();
+    /// req.encode_header_only(&mut buffer).unwrap();
+    /// assert!(buffer.chunk().ends_with(b"\r\n\r\n"));
+    ///
+    /// let mut body = BinaryMut::new();
+    /// req.encode_body(&mut body).unwrap();
+    /// assert_eq!(body.chunk(), b"data");
+    /// buffer.put_slice(body.chunk());
+    /// assert_eq!(buffer.chunk(), &req.http1_data().unwrap()[..]);
+    /// ```
+    pub fn encode_header_only<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        self.encode_header(buffer)
+    }
+
+    /// 仅编码body, 与`encode_header_only`配合使用
+    pub fn encode_body<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        self.body.serialize(buffer)
+    }
+
+    /// 是否带有`Expect: 100-continue`, 此时应等待服务端的100 Continue后再发送body
+    pub fn expects_continue(&self) -> bool {
+        self.parts
+            .header
+            .get_option_value(&HeaderName::EXPECT)
+            .is_some_and(|v| v.as_bytes().trim_ascii().eq_ignore_ascii_case(b"100-continue"))
+    }
+
     pub fn encode_header<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
         let mut size = 0;
         size += self.parts.method.encode(buffer)?;
@@ -644,11 +1209,15 @@ where
     pub fn replace_clone(&mut self, mut body: T) -> Request<T> {
         let parts = self.parts.clone();
         let partial = self.partial;
-        std::mem::swap(&mut self.body, &mut body);
+        core::mem::swap(&mut self.body, &mut body);
         Request {
             parts,
             body,
             partial,
+            strict: self.strict,
+            raw: self.raw.clone(),
+            stats: self.stats,
+            multiple_host: self.multiple_host,
         }
     }
 }
@@ -658,6 +1227,17 @@ impl Parts {
         Parts::default()
     }
 
+    /// 恢复为默认状态, 保留头部及路径等已分配的空间, 用于长连接中复用
+    pub fn reset(&mut self) {
+        self.method = Method::NONE;
+        self.header.clear();
+        self.version = Version::Http11;
+        self.url.clear();
+        self.path.clear();
+        self.raw_target = Binary::new();
+        self.extensions.clear();
+    }
+
     pub fn get_host(&self) -> Option<String> {
         if self.url.domain.is_some() {
             return self.url.domain.clone();
@@ -689,6 +1269,10 @@ impl Default for Request<()> {
             parts: Default::default(),
             body: Default::default(),
             partial: Default::default(),
+            strict: Default::default(),
+            raw: Default::default(),
+            stats: Default::default(),
+            multiple_host: Default::default(),
         }
     }
 }
@@ -697,7 +1281,7 @@ impl<T> Display for Request<T>
 where
     T: Serialize + Display,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.parts.method.fmt(f)?;
         f.write_str(" ")?;
         self.parts.path.fmt(f)?;
@@ -717,6 +1301,7 @@ impl Default for Parts {
             version: Version::Http11,
             url: Url::new(),
             path: String::new(),
+            raw_target: Binary::new(),
             extensions: Extensions::new(),
         }
     }
@@ -724,20 +1309,23 @@ impl Default for Parts {
 
 impl Clone for Parts {
     fn clone(&self) -> Self {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
         let mut value = Self {
             method: self.method.clone(),
             header: self.header.clone(),
             version: self.version.clone(),
             url: self.url.clone(),
             path: self.path.clone(),
-            extensions: Extensions::new(),
+            raw_target: self.raw_target.clone(),
+            extensions: self.extensions.clone(),
         };
 
-        match self.extensions.get::<Arc<RwLock<HeaderIndex>>>() {
-            Some(index) => {
-                value.extensions.insert(index.clone());
+        // 共享的头部索引即使未以可克隆的方式插入也需保留
+        #[cfg(feature = "std")]
+        if value.extensions.get::<Arc<RwLock<HeaderIndex>>>().is_none() {
+            if let Some(index) = self.extensions.get::<Arc<RwLock<HeaderIndex>>>() {
+                value.extensions.insert_cloneable(index.clone());
             }
-            _ => (),
         }
         value
     }
@@ -745,6 +1333,7 @@ impl Clone for Parts {
 
 #[cfg(test)]
 mod tests {
+    use crate::prelude::*;
 
     macro_rules! req {
         ($name:ident, $buf:expr, |$arg:ident| $body:expr) => {
@@ -782,6 +1371,7 @@ mod tests {
         |req| {
             assert_eq!(req.method(), &crate::Method::Get);
             assert_eq!(req.path(), "//://///");
+            assert_eq!(&req.raw_target()[..], b"//://///");
             assert_eq!(&req.url().path, "//://///");
             assert_eq!(req.url().query, None);
             assert_eq!(req.version(), crate::Version::Http11);
@@ -878,4 +1468,61 @@ mod tests {
     //     assert_eq!(&req.headers()[":authority"], "www.example.com");
     //     assert_eq!(&req.headers()["custom-key"], "custom-value");
     // }
+
+    #[test]
+    fn pipelined_messages() {
+        let data: &[u8] = b"GET /1 HTTP/1.1\r\nHost: a\r\n\r\nGET /2 HTTP/1.1\r\nHost: a\r\n\r\nGET /3 HTTP/1.1\r\nHost: a\r\n\r\nPOST /4 HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello";
+        let mut rest = data;
+        let mut paths = vec![];
+        while !rest.is_empty() {
+            let mut req = crate::Request::new();
+            let len = req.parse_message(rest).unwrap().complete_len().unwrap();
+            paths.push(req.path().clone());
+            if req.method() == &crate::Method::Post {
+                assert_eq!(&rest[len - 5..len], b"hello");
+            }
+            rest = &rest[len..];
+        }
+        assert_eq!(paths, vec!["/1", "/2", "/3", "/4"]);
+
+        // body不完整时不能被当作完成
+        let mut req = crate::Request::new();
+        let post = &data[84..data.len() - 1];
+        assert!(post.starts_with(b"POST"));
+        let status = req.parse_message(post).unwrap();
+        assert_eq!(status, crate::ParseStatus::Partial);
+    }
+
+    #[test]
+    fn target_forms() {
+        let mut req = crate::Request::new();
+        req.parse(b"OPTIONS * HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert_eq!(req.target_form(), crate::TargetForm::Asterisk);
+        assert_eq!(req.path(), "*");
+        assert_eq!(req.url().domain.as_deref(), Some("example.com"));
+
+        req.parse(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n").unwrap();
+        assert_eq!(req.target_form(), crate::TargetForm::Authority);
+        assert_eq!(req.get_connect_url().unwrap(), "example.com:443");
+
+        req.parse(b"GET /index HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(req.target_form(), crate::TargetForm::Origin);
+        assert_eq!(req.version(), crate::Version::Http10);
+        assert!(req.host_matches_target());
+
+        req.parse(b"GET http://example.com:8080/a HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert_eq!(req.target_form(), crate::TargetForm::Absolute);
+        assert_eq!(req.url().port, Some(8080));
+        assert!(!req.host_matches_target());
+    }
+
+    #[test]
+    fn strict_headers_survive_reset() {
+        let mut req = crate::Request::new();
+        req.set_strict_headers(true);
+        req.parse(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+        req.reset();
+        assert!(req.is_strict_headers());
+        assert!(req.parse(b"GET / HTTP/1.1\r\nHost : a\r\n\r\n").is_err());
+    }
 }
@@ -10,19 +10,22 @@
 // -----
 // Created Date: 2023/08/17 04:39:49
 
-use std::{
+use core::{
     any::{Any},
-    sync::{Arc, RwLock}, fmt::Display,
+    fmt::Display,
 };
+#[cfg(feature = "std")]
+use std::sync::{Arc, RwLock};
 
+use crate::prelude::*;
 use crate::{
-    Binary, BinaryMut, Buf, BufMut, Extensions, HeaderMap, HeaderName, HeaderValue, Serialize, Version, WebError, WebResult, Helper,
+    Binary, BinaryMut, BinaryRef, Buf, BufMut, Extensions, HeaderMap, HeaderName, HeaderValue, Serialize, SizedSerialize, Version, WebError, WebResult, Helper,
 };
 
-use super::{
-    http2::{HeaderIndex},
-    StatusCode,
-};
+use super::http1::BodyFraming;
+use super::{BuilderPolicy, Method, ParseReport, ParseStats, ParseStatus, RawHeaders, StatusCode, Trailers};
+#[cfg(feature = "std")]
+use super::http2::HeaderIndex;
 
 #[derive(Debug)]
 pub struct Response<T>
@@ -32,19 +35,71 @@ where
     parts: Parts,
     body: T,
     partial: bool,
+    strict: bool,
+    raw: Option<RawHeaders>,
+    stats: ParseStats,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parts {
     pub status: StatusCode,
     pub header: HeaderMap,
     pub version: Version,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub extensions: Extensions,
 }
 
+impl Parts {
+    /// 根据状态码及头信息确定body的分帧方式, chunked优先于Content-Length,
+    /// 两者都没有时读取直到连接关闭. 对HEAD或CONNECT的响应使用`body_framing_for`.
+    /// Content-Length不是数字或多个值不一致时无法确定body的长度, 返回错误
+    pub fn body_framing(&self) -> WebResult<BodyFraming> {
+        let code = self.status.as_u16();
+        if self.status.is_informational() || code == 204 || code == 304 {
+            return Ok(BodyFraming::Empty);
+        }
+        // 存在Transfer-Encoding时忽略Content-Length, 最后的编码不是chunked时读取直到连接关闭
+        if self.header.contains(&HeaderName::TRANSFER_ENCODING) {
+            if self.header.is_final_chunked() {
+                return Ok(BodyFraming::Chunked);
+            }
+            return Ok(BodyFraming::Eof);
+        }
+        match self.header.content_length()? {
+            Some(len) => Ok(BodyFraming::Length(len)),
+            None => Ok(BodyFraming::Eof),
+        }
+    }
+
+    /// 作为对method请求的响应时body的分帧方式, HEAD请求及CONNECT成功时没有body
+    pub fn body_framing_for(&self, method: &Method) -> WebResult<BodyFraming> {
+        if *method == Method::Head || (*method == Method::Connect && self.status.is_success()) {
+            return Ok(BodyFraming::Empty);
+        }
+        self.body_framing()
+    }
+
+    /// 按RFC 7230 §3.3校验body相关的头, 1xx/204的响应及CONNECT的2xx响应
+    /// 不能带有Content-Length或Transfer-Encoding
+    pub fn check_body_rules(&self, method: Option<&Method>) -> WebResult<()> {
+        let tunnel = method == Some(&Method::Connect) && self.status.is_success();
+        if self.status.is_informational() || self.status.as_u16() == 204 || tunnel {
+            if self.header.contains(&HeaderName::CONTENT_LENGTH) {
+                return Err(WebError::Serialize("content-length not allowed"));
+            }
+            if self.header.contains(&HeaderName::TRANSFER_ENCODING) {
+                return Err(WebError::Serialize("transfer-encoding not allowed"));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct Builder {
     inner: WebResult<Parts>,
+    policy: BuilderPolicy,
 }
 
 impl Builder {
@@ -261,6 +316,29 @@ impl Builder {
     //     self.inner.as_mut().ok().map(|h| &mut h.extensions)
     // }
 
+    /// 设置自动补充头信息的策略, 默认补充
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::*;
+    ///
+    /// let res = Response::builder()
+    ///     .policy(BuilderPolicy::none())
+    ///     .body(())
+    ///     .unwrap();
+    /// assert!(!res.headers().contains(&"Server"));
+    /// ```
+    pub fn policy(mut self, policy: BuilderPolicy) -> Builder {
+        self.policy = policy;
+        self
+    }
+
+    /// 不自动补充任何头, 等同于`policy(BuilderPolicy::none())`
+    pub fn no_default_headers(self) -> Builder {
+        self.policy(BuilderPolicy::none())
+    }
+
     /// "Consumes" this builder, using the provided `body` to return a
     /// constructed `Response`.
     ///
@@ -282,21 +360,48 @@ impl Builder {
     ///     .unwrap();
     /// ```
     pub fn body<T: Serialize>(self, body: T) -> WebResult<Response<T>> {
+        let policy = self.policy;
         self.inner.map(move |mut parts: Parts| {
             let server = HeaderName::from_static("Server");
-            if !parts.header.contains(&server) {
+            if policy.server && !parts.header.contains(&server) {
                 parts.header.insert(server, "wenmeng");
             };
             Response {
                 parts,
                 body,
                 partial: false,
+                strict: false,
+                raw: None,
+                stats: ParseStats::default(),
             }
         }
         )
     }
 
 
+    /// 传入已知长度的Body信息, 未设置Content-Length且非chunked时自动设置
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::*;
+    ///
+    /// let response = Response::builder()
+    ///     .sized_body("hello")
+    ///     .unwrap();
+    /// assert_eq!(response.get_body_len(), 5);
+    /// ```
+    pub fn sized_body<T: SizedSerialize>(self, body: T) -> WebResult<Response<T>> {
+        let len = body.serialize_len();
+        self.and_then(move |mut parts| {
+            if !parts.header.contains(&HeaderName::CONTENT_LENGTH) && !parts.header.is_chunked() {
+                parts.header.insert(HeaderName::CONTENT_LENGTH, len);
+            }
+            Ok(parts)
+        })
+        .body(body)
+    }
+
     /// 获取返回的body长度, 如果为0则表示未写入信息
     pub fn get_body_len(&self) -> isize {
         if let Ok(inner) = &self.inner {
@@ -314,6 +419,7 @@ impl Builder {
     {
         Builder {
             inner: self.inner.and_then(func),
+            policy: self.policy,
         }
     }
 }
@@ -323,6 +429,7 @@ impl Default for Builder {
     fn default() -> Builder {
         Builder {
             inner: Ok(Parts::default()),
+            policy: BuilderPolicy::default(),
         }
     }
 }
@@ -406,6 +513,9 @@ impl<T: Serialize> Response<T> {
             parts: Parts::default(),
             body: body,
             partial: false,
+            strict: false,
+            raw: None,
+            stats: ParseStats::default(),
         }
     }
 
@@ -430,6 +540,9 @@ impl<T: Serialize> Response<T> {
             parts: parts,
             body: body,
             partial: false,
+            strict: false,
+            raw: None,
+            stats: ParseStats::default(),
         }
     }
 
@@ -543,6 +656,80 @@ impl<T: Serialize> Response<T> {
         &mut self.parts.extensions
     }
 
+    /// 获取响应的trailer头, 如http2中在DATA之后收到的HEADERS帧
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{HeaderMap, Response};
+    /// let mut res = Response::new(());
+    /// assert!(res.trailers().is_none());
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("grpc-status", "0");
+    /// res.set_trailers(trailers);
+    /// assert!(res.trailers().unwrap().contains(&"grpc-status"));
+    /// ```
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.parts.extensions.get::<Trailers>().map(|t| &t.0)
+    }
+
+    /// 解析时记录每个头的原始数据, 需在解析前调用, 见`RawHeaders`
+    pub fn preserve_raw_headers(&mut self) {
+        if self.raw.is_none() {
+            self.raw = Some(RawHeaders::new());
+        }
+    }
+
+    /// 严格解析头部, 拒绝名字与`:`之间的空白(如`Host : a`)及以空白开头的续行,
+    /// 默认关闭, 此时名字后的空白被忽略, 续行以一个空格与上一行合并
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Response;
+    ///
+    /// let data = b"HTTP/1.1 200 OK\r\nHost : a\r\nX-Fold: a\r\n  b\r\n\r\n";
+    /// let mut res = Response::new(());
+    /// res.parse(data).unwrap();
+    /// assert_eq!(res.headers().get_str_value(&"x-fold").as_deref(), Some("a b"));
+    ///
+    /// let mut res = Response::new(());
+    /// res.set_strict_headers(true);
+    /// assert!(res.parse(data).is_err());
+    /// assert!(res.parse(b"HTTP/1.1 200 OK\r\nHost: a\r\nX-Val:\t a  b \r\n\r\n").is_ok());
+    /// assert_eq!(res.headers().get_str_value(&"x-val").as_deref(), Some("a  b"));
+    /// ```
+    pub fn set_strict_headers(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict_headers(&self) -> bool {
+        self.strict
+    }
+
+    /// 解析的计数信息, 见`ParseStats`, 未解析时各项均为0
+    pub fn stats(&self) -> ParseStats {
+        self.stats
+    }
+
+    /// 累加已读取的body字节数, 供自行读取body的调用方记录
+    pub fn add_body_bytes(&mut self, len: usize) {
+        self.stats.body_bytes += len;
+    }
+
+    /// 解析时收到的原始头, 未开启`preserve_raw_headers`时返回None
+    pub fn raw_headers(&self) -> Option<&RawHeaders> {
+        self.raw.as_ref()
+    }
+
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.parts.extensions.insert_cloneable(Trailers(trailers));
+    }
+
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.parts.extensions.remove::<Trailers>().map(|t| t.0)
+    }
+
     // /// Returns a mutable reference to the associated extensions.
     // ///
     // /// # Examples
@@ -649,13 +836,39 @@ impl<T: Serialize> Response<T> {
             body: f(self.body),
             parts: self.parts,
             partial: self.partial,
+            strict: self.strict,
+            raw: self.raw,
+            stats: self.stats,
         }
     }
 
     pub fn httpdata(&mut self) -> WebResult<Vec<u8>> {
+        Ok(self.to_binary()?.to_vec())
+    }
+
+    /// 将响应头及body以http1格式直接写入调用方的缓存, 返回写入的字节数
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf, Response};
+    ///
+    /// let mut res = Response::builder().header("Content-Length", "2").body("ok").unwrap();
+    /// let mut buffer = BinaryMut::new();
+    /// let size = res.encode_to(&mut buffer).unwrap();
+    /// assert_eq!(size, buffer.remaining());
+    /// assert!(buffer.chunk().ends_with(b"\r\n\r\nok"));
+    /// assert_eq!(res.to_binary().unwrap().chunk(), buffer.chunk());
+    /// ```
+    pub fn encode_to<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        self.serialize(buffer)
+    }
+
+    /// 以http1格式编码为共享的`Binary`, 相比`httpdata`少一次拷贝
+    pub fn to_binary(&mut self) -> WebResult<Binary> {
         let mut buffer = BinaryMut::new();
-        self.serialize(&mut buffer)?;
-        return Ok(buffer.into_slice_all());
+        self.encode_to(&mut buffer)?;
+        Ok(buffer.freeze())
     }
 
     pub fn into<B: Serialize>(self, body: B) -> (Response<B>, T) {
@@ -663,6 +876,9 @@ impl<T: Serialize> Response<T> {
             body,
             parts: self.parts,
             partial: self.partial,
+            strict: self.strict,
+            raw: self.raw,
+            stats: self.stats,
         };
         (new, self.body)
     }
@@ -672,6 +888,9 @@ impl<T: Serialize> Response<T> {
             body: From::from(self.body),
             parts: self.parts,
             partial: self.partial,
+            strict: self.strict,
+            raw: self.raw,
+            stats: self.stats,
         };
         new
     }
@@ -683,6 +902,9 @@ impl<T: Serialize> Response<T> {
             body: binary.freeze(),
             parts: self.parts,
             partial: self.partial,
+            strict: self.strict,
+            raw: self.raw,
+            stats: self.stats,
         };
         new
     }
@@ -693,6 +915,21 @@ impl<T: Serialize> Response<T> {
         self.parts.header.get_body_len()
     }
 
+    /// body的分帧方式, 见`Parts::body_framing`
+    pub fn body_framing(&self) -> WebResult<BodyFraming> {
+        self.parts.body_framing()
+    }
+
+    /// 作为对method请求的响应时body的分帧方式, 见`Parts::body_framing_for`
+    pub fn body_framing_for(&self, method: &Method) -> WebResult<BodyFraming> {
+        self.parts.body_framing_for(method)
+    }
+
+    /// 校验body相关的头, 见`Parts::check_body_rules`
+    pub fn check_body_rules(&self, method: Option<&Method>) -> WebResult<()> {
+        self.parts.check_body_rules(method)
+    }
+
     pub fn encode_header<B: Buf + BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
         let mut size = 0;
         size += self.parts.version.encode(buffer)?;
@@ -703,34 +940,108 @@ impl<T: Serialize> Response<T> {
     }
 
 
+    /// 从Buf中解析, 返回读取的字节数, 格式错误以`WebError::Http`返回,
+    /// 需要出错的偏移时使用`parse_with_report`
     pub fn parse_buffer<B: Buf>(&mut self, buffer: &mut B) -> WebResult<usize> {
         let len = buffer.remaining();
+        Ok(self.parse_head(buffer, len)?.body_start)
+    }
+
+    fn parse_buffer_report<B: Buf>(&mut self, buffer: &mut B) -> WebResult<ParseReport> {
+        let len = buffer.remaining();
+        self.parse_head(buffer, len)
+            .map_err(|e| e.with_offset(len - buffer.remaining()))
+    }
+
+    fn parse_head<B: Buf>(&mut self, buffer: &mut B, len: usize) -> WebResult<ParseReport> {
         self.partial = true;
-        // println!("===={:?}", String::from_utf8_lossy(buffer.chunk()));
         Helper::skip_empty_lines(buffer)?;
+        let start_line = len - buffer.remaining();
         self.parts.version = Helper::parse_version(buffer)?;
         Helper::skip_spaces(buffer)?;
         self.parts.status = Helper::parse_status(buffer)?;
-        Helper::skip_spaces(buffer)?;
-        let _reason = Helper::parse_status_token(buffer)?;
+        let _reason = Helper::parse_reason_phrase(buffer)?;
         Helper::skip_new_line(buffer)?;
-        Helper::parse_header(buffer, &mut self.parts.header)?;
+        let header_start = len - buffer.remaining();
+        let strict = self.is_strict_headers();
+        let headers = match &mut self.raw {
+            Some(raw) => raw.parse_header(buffer, &mut self.parts.header, strict, |_| ())?,
+            None => Helper::parse_header_with(buffer, &mut self.parts.header, strict, |_| ())?,
+        };
         self.partial = false;
-        Ok(len - buffer.remaining())
+        let body_start = len - buffer.remaining();
+        crate::web_trace!(
+            "HTTP1: 解析响应头完成; status={} headers={} body_start={}",
+            self.parts.status,
+            self.parts.header.len(),
+            body_start
+        );
+        self.stats = ParseStats {
+            headers,
+            header_bytes: body_start,
+            body_bytes: 0,
+        };
+        Ok(ParseReport {
+            start_line,
+            start_line_len: header_start - start_line,
+            header_len: body_start - header_start,
+            body_start,
+        })
+    }
+
+    /// 直接从借用的数据中解析, 不产生额外的拷贝, 状态行中的原因短语可以省略
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{Response, StatusCode};
+    ///
+    /// let mut res = Response::new(());
+    /// assert_eq!(res.parse(b"HTTP/1.1 204\r\nServer: lb\r\n\r\n").unwrap(), 28);
+    /// assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    /// assert!(res.parse(b"HTTP/1.1 200 \r\n\r\n").is_ok());
+    /// assert!(res.parse(b"HTTP/1.1 200").unwrap_err().is_partial());
+    /// ```
+    pub fn parse(&mut self, buf: &[u8]) -> WebResult<usize> {
+        self.partial = true;
+        let mut buffer = BinaryRef::from(buf);
+        self.parse_buffer(&mut buffer)
+    }
+
+    /// 同`parse`, 返回起始行/头部/body在`buf`中的位置,
+    /// 格式错误以`WebError::Parse`返回, 带有出错的偏移
+    pub fn parse_with_report(&mut self, buf: &[u8]) -> WebResult<ParseReport> {
+        self.partial = true;
+        let mut buffer = BinaryRef::from(buf);
+        self.parse_buffer_report(&mut buffer)
+    }
+
+    /// 解析头部, 以`ParseStatus`区分数据不足及解析完成, 格式错误返回`Err`
+    ///
+    /// 返回`Partial`时需带上之前的数据从头重新解析
+    pub fn parse_status(&mut self, buf: &[u8]) -> WebResult<ParseStatus> {
+        match self.parse(buf) {
+            Ok(len) => Ok(ParseStatus::Complete(len)),
+            Err(e) if e.is_partial() => Ok(ParseStatus::Partial),
+            Err(e) => Err(e),
+        }
     }
     
     pub fn replace_body(&mut self, mut body: T) {
-        std::mem::swap(&mut self.body, &mut body);
+        core::mem::swap(&mut self.body, &mut body);
     }
 
     pub fn replace_clone(&mut self, mut body: T) -> Response<T> {
         let parts = self.parts.clone();
         let partial = self.partial;
-        std::mem::swap(&mut self.body, &mut body);
+        core::mem::swap(&mut self.body, &mut body);
         Response {
             parts,
             body,
             partial,
+            strict: self.strict,
+            raw: self.raw.clone(),
+            stats: self.stats,
         }
     }
 }
@@ -741,6 +1052,9 @@ impl<T: Default + Serialize> Default for Response<T> {
             parts: Default::default(),
             body: Default::default(),
             partial: Default::default(),
+            strict: Default::default(),
+            raw: Default::default(),
+            stats: Default::default(),
         }
     }
 }
@@ -758,18 +1072,20 @@ impl Default for Parts {
 
 impl Clone for Parts {
     fn clone(&self) -> Self {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
         let mut value = Self {
             status: self.status.clone(),
             header: self.header.clone(),
             version: self.version.clone(),
-            extensions: Extensions::new(),
+            extensions: self.extensions.clone(),
         };
 
-        match self.extensions.get::<Arc<RwLock<HeaderIndex>>>() {
-            Some(index) => {
-                value.extensions.insert(index.clone());
+        // 共享的头部索引即使未以可克隆的方式插入也需保留
+        #[cfg(feature = "std")]
+        if value.extensions.get::<Arc<RwLock<HeaderIndex>>>().is_none() {
+            if let Some(index) = self.extensions.get::<Arc<RwLock<HeaderIndex>>>() {
+                value.extensions.insert_cloneable(index.clone());
             }
-            _ => (),
         }
         value
     }
@@ -792,7 +1108,7 @@ where
 
 impl<T> Display for Response<T>
 where T: Serialize + Display {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.parts.version.fmt(f)?;
         f.write_str(" ")?;
         self.parts.status.fmt(f)?;
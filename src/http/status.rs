@@ -10,10 +10,10 @@
 // -----
 // Created Date: 2023/08/17 04:42:58
 
-use std::fmt;
-use std::num::NonZeroU16;
-use std::str::FromStr;
-use std::{convert::TryFrom};
+use core::fmt;
+use core::num::NonZeroU16;
+use core::str::FromStr;
+use core::convert::TryFrom;
 
 use crate::{HttpError, WebError, WebResult, HeaderName, HeaderValue, Buf, BufMut};
 
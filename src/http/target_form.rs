@@ -0,0 +1,24 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/20 15:03:48
+
+/// 请求行中目标的形式, 见RFC 7230 §5.3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetForm {
+    /// `/path?query`, 最常见的形式
+    Origin,
+    /// `http://example.com/path`, 发往代理的请求
+    Absolute,
+    /// `example.com:443`, 仅用于CONNECT
+    Authority,
+    /// `*`, 仅用于服务器级别的OPTIONS
+    Asterisk,
+}
@@ -10,10 +10,12 @@
 // -----
 // Created Date: 2023/08/18 10:06:47
 
-use std::hash::Hash;
-use std::{borrow::Cow, fmt};
+use core::hash::Hash;
+use core::fmt;
+use alloc::borrow::Cow;
 
-use crate::{Helper, WebError, WebResult, Buf, BufMut};
+use crate::prelude::*;
+use crate::{Helper, HttpError, WebError, WebResult, Buf, BufMut};
 
 #[derive(Clone, Debug)]
 pub enum HeaderValue {
@@ -48,6 +50,35 @@ impl HeaderValue {
         }
     }
 
+    /// 转为字符串, 非UTF-8的字节(如latin-1的obs-text)替换为U+FFFD, 仅用于显示
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::{BinaryMut, Buf, HeaderValue, Response};
+    ///
+    /// let data = b"HTTP/1.1 200 OK\r\nX-Name: caf\xe9\r\n\r\n";
+    /// let mut res = Response::new(());
+    /// res.parse(data).unwrap();
+    /// let value = res.headers().get_option_value(&"x-name").unwrap();
+    /// assert_eq!(value.as_bytes(), b"caf\xe9");
+    /// assert_eq!(value.to_str_lossy(), "caf\u{fffd}");
+    /// assert!(value.to_str_strict().is_err());
+    /// assert_eq!(HeaderValue::from_static("café").to_str_strict().unwrap(), "café");
+    ///
+    /// let mut buffer = BinaryMut::new();
+    /// res.encode_header(&mut buffer).unwrap();
+    /// assert_eq!(buffer.chunk(), &data[..]);
+    /// ```
+    pub fn to_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
+
+    /// 转为字符串, 不是合法的UTF-8时返回错误
+    pub fn to_str_strict(&self) -> WebResult<&str> {
+        core::str::from_utf8(self.as_bytes()).map_err(|_| WebError::from(HttpError::HeaderValue))
+    }
+
     pub fn as_string(&self) -> Option<String> {
         match self {
             Self::Stand(s) => Some(s.to_string()),
@@ -82,7 +113,7 @@ impl HeaderValue {
 }
 
 impl Hash for HeaderValue {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         match self {
             HeaderValue::Stand(stand) => {
                 (*stand.as_bytes()).hash(state);
@@ -10,7 +10,7 @@
 // -----
 // Created Date: 2023/08/15 10:11:50
 
-use std::{fmt::Display};
+use core::{fmt::Display, str::FromStr};
 
 use crate::{WebError, WebResult, Buf, BufMut};
 
@@ -74,7 +74,21 @@ impl Version {
 }
 
 impl Display for Version {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(&self.as_str())
     }
 }
+
+impl FromStr for Version {
+    type Err = WebError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            Version::SHTTP10 => Ok(Version::Http10),
+            Version::SHTTP11 => Ok(Version::Http11),
+            Version::SHTTP2 => Ok(Version::Http2),
+            Version::SHTTP3 => Ok(Version::Http3),
+            _ => Err(WebError::Http(crate::HttpError::Version)),
+        }
+    }
+}
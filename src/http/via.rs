@@ -0,0 +1,222 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/25 15:22:40
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{Helper, HttpError, WebError, WebResult};
+
+use super::media_type::is_token;
+
+/// Via头中的一跳, 见RFC 7230 §5.7.1
+///
+/// `Via: 1.0 fred, HTTP/1.1 p.example.net (Apache/1.1)`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http::ViaHop;
+///
+/// let hops = ViaHop::parse_list("1.0 fred, HTTP/1.1 p.example.net:8080 (Apache/1.1)").unwrap();
+/// assert_eq!(hops.len(), 2);
+/// assert_eq!(hops[0].protocol, None);
+/// assert_eq!(hops[1].protocol.as_deref(), Some("HTTP"));
+/// assert_eq!(hops[1].received_by, "p.example.net:8080");
+/// assert_eq!(hops[1].comment.as_deref(), Some("Apache/1.1"));
+/// assert_eq!(hops[1].to_string(), "HTTP/1.1 p.example.net:8080 (Apache/1.1)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViaHop {
+    /// 协议名, 为HTTP时通常省略
+    pub protocol: Option<String>,
+    pub version: String,
+    /// 接收方的`host[:port]`或代理的别名
+    pub received_by: String,
+    pub comment: Option<String>,
+}
+
+impl ViaHop {
+    pub fn new(version: &str, received_by: &str) -> ViaHop {
+        ViaHop {
+            protocol: None,
+            version: version.to_string(),
+            received_by: received_by.to_string(),
+            comment: None,
+        }
+    }
+
+    /// 解析逗号分隔的多跳, 注释中可以包含逗号及嵌套的括号
+    pub fn parse_list(value: &str) -> WebResult<Vec<ViaHop>> {
+        let err = || WebError::from(HttpError::HeaderValue);
+        let mut hops = Vec::new();
+        let mut rest = value;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+            let end = rest.find([' ', '\t']).ok_or_else(err)?;
+            let (protocol, version) = match rest[..end].split_once('/') {
+                Some((name, version)) => (Some(name.to_string()), version),
+                None => (None, &rest[..end]),
+            };
+            if !is_token(version) || protocol.as_deref().is_some_and(|p| !is_token(p)) {
+                return Err(err());
+            }
+            rest = rest[end..].trim_start();
+            let end = rest.find([' ', '\t', ',']).unwrap_or(rest.len());
+            let received_by = &rest[..end];
+            if received_by.is_empty() {
+                return Err(err());
+            }
+            rest = rest[end..].trim_start();
+
+            let mut comment = None;
+            if rest.starts_with('(') {
+                let mut depth = 0;
+                let mut close = None;
+                for (i, c) in rest.char_indices() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                close = Some(i);
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                let close = close.ok_or_else(err)?;
+                comment = Some(rest[1..close].to_string());
+                rest = rest[close + 1..].trim_start();
+            }
+            if !rest.is_empty() && !rest.starts_with(',') {
+                return Err(err());
+            }
+            hops.push(ViaHop {
+                protocol,
+                version: version.to_string(),
+                received_by: received_by.to_string(),
+                comment,
+            });
+        }
+        Ok(hops)
+    }
+}
+
+impl fmt::Display for ViaHop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(protocol) = &self.protocol {
+            write!(f, "{}/", protocol)?;
+        }
+        write!(f, "{} {}", self.version, self.received_by)?;
+        if let Some(comment) = &self.comment {
+            write!(f, " ({})", comment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Warning头中的一条警告, 见RFC 7234 §5.5
+///
+/// `Warning: 110 anderson/1.3.37 "Response is stale"`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http::Warning;
+///
+/// let warnings = Warning::parse_list(r#"110 - "Response is stale", 199 proxy:8080 "say \"hi\", bye" "Wed, 21 Oct 2015 07:28:00 GMT""#).unwrap();
+/// assert_eq!(warnings.len(), 2);
+/// assert_eq!(warnings[0].code, 110);
+/// assert_eq!(warnings[0].agent, "-");
+/// assert_eq!(warnings[1].text, "say \"hi\", bye");
+/// assert_eq!(warnings[1].date.as_deref(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+/// assert_eq!(warnings[0].to_string(), r#"110 - "Response is stale""#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// 三位数字的警告码, 1xx在响应验证后需删除, 2xx需保留
+    pub code: u16,
+    /// 添加警告的`host[:port]`或别名, 未知时为`-`
+    pub agent: String,
+    pub text: String,
+    /// 可选的HTTP-date, 原样保存
+    pub date: Option<String>,
+}
+
+impl Warning {
+    pub fn new(code: u16, agent: &str, text: &str) -> Warning {
+        Warning {
+            code,
+            agent: agent.to_string(),
+            text: text.to_string(),
+            date: None,
+        }
+    }
+
+    /// 解析逗号分隔的多条警告, 文本中可以包含逗号及转义的引号
+    pub fn parse_list(value: &str) -> WebResult<Vec<Warning>> {
+        let err = || WebError::from(HttpError::HeaderValue);
+        let mut warnings = Vec::new();
+        let mut rest = value;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+            let end = rest.find([' ', '\t']).ok_or_else(err)?;
+            let code = &rest[..end];
+            if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+            let code = code.parse::<u16>().map_err(|_| err())?;
+            rest = rest[end..].trim_start();
+            let end = rest.find([' ', '\t']).ok_or_else(err)?;
+            let agent = &rest[..end];
+            rest = rest[end..].trim_start();
+
+            let (text, next) = Helper::parse_quoted(rest).ok_or_else(err)?;
+            rest = next.trim_start();
+            let mut date = None;
+            if rest.starts_with('"') {
+                let (value, next) = Helper::parse_quoted(rest).ok_or_else(err)?;
+                date = Some(value);
+                rest = next.trim_start();
+            }
+            if !rest.is_empty() && !rest.starts_with(',') {
+                return Err(err());
+            }
+            warnings.push(Warning {
+                code,
+                agent: agent.to_string(),
+                text,
+                date,
+            });
+        }
+        Ok(warnings)
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:03} {} ", self.code, self.agent)?;
+        Helper::write_quoted(f, &self.text)?;
+        if let Some(date) = &self.date {
+            f.write_str(" ")?;
+            Helper::write_quoted(f, date)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/15 14:36:20
+
+//! 解析时使用的io错误类型
+//!
+//! 开启`std`时即为`std::io`中的类型, 否则为仅依赖core的精简实现,
+//! 保证`Buf::try_get_*`等接口在两种模式下签名一致
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Result};
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    use core::fmt;
+
+    /// 与`std::io::ErrorKind`对应的错误类型, 仅保留解析中使用到的部分
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ErrorKind {
+        InvalidInput,
+        InvalidData,
+        UnexpectedEof,
+        WouldBlock,
+        WriteZero,
+        OutOfMemory,
+        Other,
+    }
+
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        msg: &'static str,
+    }
+
+    impl Error {
+        pub fn new(kind: ErrorKind, msg: &'static str) -> Error {
+            Error { kind, msg }
+        }
+
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:?}: {}", self.kind, self.msg)
+        }
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+}
+
+#[cfg(not(feature = "std"))]
+pub use imp::{Error, ErrorKind, Result};
@@ -10,27 +10,38 @@
 // -----
 // Created Date: 2023/08/14 04:49:33
 
-#[macro_use] extern crate bitflags;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg_attr(feature = "std", macro_use)] extern crate bitflags;
+#[macro_use] extern crate alloc;
 
 
 pub mod binary;
 pub mod http;
 mod error;
+pub mod io;
 pub mod url;
 #[macro_use] mod macros;
+mod prelude;
 mod helper;
 mod extensions;
 mod serialize;
+#[cfg(feature = "serde")]
+mod serde_impl;
 pub mod ws;
+pub mod proxy_protocol;
 
 
-pub use binary::{Binary, Buf, BinaryMut, BufMut, BinaryRef};
+pub use binary::{Binary, Buf, BinaryMut, BufMut, BinaryRef, MarkBuf};
+#[cfg(feature = "std")]
+pub use binary::{Reader, Writer};
 
-pub use http::{HeaderMap, HeaderName, HeaderValue, Method, Version, Request, Response, HttpError, StatusCode};
+pub use http::{HeaderMap, HeaderName, HeaderValue, Method, Version, Request, Response, HttpError, StatusCode, BuilderPolicy, ParseReport, ParseStats, ParseStatus, TargetForm, MediaType, ContentDisposition, DispositionType, CanonicalRequest};
+#[cfg(feature = "std")]
 pub use http::http2::{self, Http2Error};
-pub use error::{WebError, WebResult};
+pub use error::{WebError, WebResult, ParseError, Expected};
 // pub use buffer::Buffer;
-pub use url::{Url, Scheme, UrlError};
+pub use url::{Url, Scheme, UrlError, Authority, Host};
 pub use helper::Helper;
 pub use extensions::Extensions;
-pub use serialize::Serialize;
+pub use serialize::{Serialize, SizedSerialize};
@@ -69,7 +69,21 @@ macro_rules! expect {
 macro_rules! try_advance {
     ($flag:expr) => {
         if !$flag {
-            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "not enough"));
+            return Err($crate::io::Error::new($crate::io::ErrorKind::UnexpectedEof, "not enough"));
         }
     };
 }
+
+/// 开启`trace`特性时通过`log::trace!`输出, 否则不产生任何代码
+#[doc(hidden)]
+#[macro_export]
+macro_rules! web_trace {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "trace")]
+        log::trace!($($arg)*);
+        #[cfg(not(feature = "trace"))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
@@ -0,0 +1,321 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/21 15:30:12
+
+//! HAProxy的PROXY协议头, 位于连接最开始, 在解析http请求之前读取,
+//! 用于获取客户端真实的源地址及目标地址
+//!
+//! - v1为文本格式, 如`PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n`
+//! - v2为二进制格式, 以12字节的固定签名开始
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{Buf, BufMut, HttpError, WebResult};
+
+/// v2格式的固定签名
+pub const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// v1格式的最大长度, 包含结尾的CRLF
+const V1_MAX_LEN: usize = 107;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyVersion {
+    V1,
+    V2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyCommand {
+    /// 代理自身发起的连接, 如健康检查, 应使用连接本身的地址
+    Local,
+    /// 代理转发的连接
+    Proxy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyTransport {
+    Unknown,
+    Tcp,
+    Udp,
+}
+
+/// 解析后的PROXY协议头
+///
+/// # Examples
+///
+/// ```
+/// use webparse::proxy_protocol::{ProxyHeader, ProxyVersion};
+///
+/// let mut buf = &b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET / HTTP/1.1\r\n\r\n"[..];
+/// let header = ProxyHeader::parse(&mut buf).unwrap();
+/// assert_eq!(header.version, ProxyVersion::V1);
+/// assert_eq!(header.source.unwrap().to_string(), "192.168.0.1:56324");
+/// assert_eq!(header.destination.unwrap().port(), 443);
+/// assert!(buf.starts_with(b"GET"));
+///
+/// let mut v2 = vec![];
+/// header.encode_v2(&mut v2).unwrap();
+/// let parsed = ProxyHeader::parse(&mut &v2[..]).unwrap();
+/// assert_eq!(parsed.version, ProxyVersion::V2);
+/// assert_eq!(parsed.source, header.source);
+/// assert_eq!(parsed.destination, header.destination);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyHeader {
+    pub version: ProxyVersion,
+    pub command: ProxyCommand,
+    pub transport: ProxyTransport,
+    /// 源地址, UNKNOWN或LOCAL时可能为None
+    pub source: Option<SocketAddr>,
+    pub destination: Option<SocketAddr>,
+    /// v2中地址之后的TLV扩展, 如ALPN, 原样保留
+    pub tlvs: Vec<(u8, Vec<u8>)>,
+}
+
+impl ProxyHeader {
+    /// 代理转发的TCP连接
+    pub fn tcp(source: SocketAddr, destination: SocketAddr) -> ProxyHeader {
+        ProxyHeader {
+            version: ProxyVersion::V2,
+            command: ProxyCommand::Proxy,
+            transport: ProxyTransport::Tcp,
+            source: Some(source),
+            destination: Some(destination),
+            tlvs: Vec::new(),
+        }
+    }
+
+    /// 检查数据是否以PROXY协议头开始, 数据不足以判断时返回None
+    pub fn detect(buf: &[u8]) -> Option<bool> {
+        for sig in [&V2_SIGNATURE[..], &b"PROXY "[..]] {
+            let len = core::cmp::min(buf.len(), sig.len());
+            if buf[..len] == sig[..len] {
+                return if len == sig.len() { Some(true) } else { None };
+            }
+        }
+        Some(false)
+    }
+
+    /// 解析v1或v2的头, 成功时消耗对应的字节, 数据不足时返回Partial且不消耗数据
+    pub fn parse<B: Buf>(buf: &mut B) -> WebResult<ProxyHeader> {
+        let (header, len) = {
+            let data = buf.chunk();
+            match Self::detect(data) {
+                None => return Err(HttpError::Partial.into()),
+                Some(false) => return Err(HttpError::ProxyProtocol.into()),
+                Some(true) => {}
+            }
+            if data.starts_with(&V2_SIGNATURE) {
+                Self::parse_v2(data)?
+            } else {
+                Self::parse_v1(data)?
+            }
+        };
+        buf.advance(len);
+        Ok(header)
+    }
+
+    fn parse_v1(data: &[u8]) -> WebResult<(ProxyHeader, usize)> {
+        let end = match data.windows(2).position(|w| w == b"\r\n") {
+            Some(end) => end,
+            None if data.len() >= V1_MAX_LEN => return Err(HttpError::ProxyProtocol.into()),
+            None => return Err(HttpError::Partial.into()),
+        };
+        if end + 2 > V1_MAX_LEN {
+            return Err(HttpError::ProxyProtocol.into());
+        }
+        let line =
+            core::str::from_utf8(&data[..end]).map_err(|_| HttpError::ProxyProtocol)?;
+        let parts: Vec<&str> = line.split(' ').collect();
+        let mut header = ProxyHeader {
+            version: ProxyVersion::V1,
+            command: ProxyCommand::Proxy,
+            transport: ProxyTransport::Unknown,
+            source: None,
+            destination: None,
+            tlvs: Vec::new(),
+        };
+        match parts.get(1) {
+            Some(&"UNKNOWN") => return Ok((header, end + 2)),
+            Some(&"TCP4") | Some(&"TCP6") if parts.len() == 6 => {}
+            _ => return Err(HttpError::ProxyProtocol.into()),
+        }
+        let is_v4 = parts[1] == "TCP4";
+        let addr = |ip: &str, port: &str| -> WebResult<SocketAddr> {
+            let ip = if is_v4 {
+                IpAddr::V4(Ipv4Addr::from_str(ip).map_err(|_| HttpError::ProxyProtocol)?)
+            } else {
+                IpAddr::V6(Ipv6Addr::from_str(ip).map_err(|_| HttpError::ProxyProtocol)?)
+            };
+            let port = port.parse::<u16>().map_err(|_| HttpError::ProxyProtocol)?;
+            Ok(SocketAddr::new(ip, port))
+        };
+        header.transport = ProxyTransport::Tcp;
+        header.source = Some(addr(parts[2], parts[4])?);
+        header.destination = Some(addr(parts[3], parts[5])?);
+        Ok((header, end + 2))
+    }
+
+    fn parse_v2(data: &[u8]) -> WebResult<(ProxyHeader, usize)> {
+        if data.len() < 16 {
+            return Err(HttpError::Partial.into());
+        }
+        let ver_cmd = data[12];
+        if ver_cmd >> 4 != 2 {
+            return Err(HttpError::ProxyProtocol.into());
+        }
+        let command = match ver_cmd & 0x0F {
+            0 => ProxyCommand::Local,
+            1 => ProxyCommand::Proxy,
+            _ => return Err(HttpError::ProxyProtocol.into()),
+        };
+        let family = data[13] >> 4;
+        let transport = match data[13] & 0x0F {
+            1 => ProxyTransport::Tcp,
+            2 => ProxyTransport::Udp,
+            _ => ProxyTransport::Unknown,
+        };
+        let len = u16::from_be_bytes([data[14], data[15]]) as usize;
+        if data.len() < 16 + len {
+            return Err(HttpError::Partial.into());
+        }
+        let body = &data[16..16 + len];
+        let mut header = ProxyHeader {
+            version: ProxyVersion::V2,
+            command,
+            transport,
+            source: None,
+            destination: None,
+            tlvs: Vec::new(),
+        };
+        let addr_len = match family {
+            1 => 12,
+            2 => 36,
+            3 => 216,
+            _ => 0,
+        };
+        if body.len() < addr_len {
+            return Err(HttpError::ProxyProtocol.into());
+        }
+        match family {
+            1 => {
+                let ip = |i: usize| IpAddr::V4(Ipv4Addr::new(body[i], body[i + 1], body[i + 2], body[i + 3]));
+                let port = |i: usize| u16::from_be_bytes([body[i], body[i + 1]]);
+                header.source = Some(SocketAddr::new(ip(0), port(8)));
+                header.destination = Some(SocketAddr::new(ip(4), port(10)));
+            }
+            2 => {
+                let ip = |i: usize| {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(&body[i..i + 16]);
+                    IpAddr::V6(Ipv6Addr::from(octets))
+                };
+                let port = |i: usize| u16::from_be_bytes([body[i], body[i + 1]]);
+                header.source = Some(SocketAddr::new(ip(0), port(32)));
+                header.destination = Some(SocketAddr::new(ip(16), port(34)));
+            }
+            // unix地址及未指定的地址族不解析地址
+            _ => {}
+        }
+        let mut tlv = &body[addr_len..];
+        while !tlv.is_empty() {
+            if tlv.len() < 3 {
+                return Err(HttpError::ProxyProtocol.into());
+            }
+            let value_len = u16::from_be_bytes([tlv[1], tlv[2]]) as usize;
+            if tlv.len() < 3 + value_len {
+                return Err(HttpError::ProxyProtocol.into());
+            }
+            header.tlvs.push((tlv[0], tlv[3..3 + value_len].to_vec()));
+            tlv = &tlv[3 + value_len..];
+        }
+        Ok((header, 16 + len))
+    }
+
+    /// 编码为v2格式, 返回写入的字节数
+    pub fn encode_v2<B: BufMut>(&self, buf: &mut B) -> WebResult<usize> {
+        let command = match self.command {
+            ProxyCommand::Local => 0x20,
+            ProxyCommand::Proxy => 0x21,
+        };
+        let transport = match self.transport {
+            ProxyTransport::Unknown => 0,
+            ProxyTransport::Tcp => 1,
+            ProxyTransport::Udp => 2,
+        };
+        let mut body = Vec::new();
+        let family = match (self.source, self.destination) {
+            (Some(SocketAddr::V4(src)), Some(SocketAddr::V4(dst))) => {
+                body.extend_from_slice(&src.ip().octets());
+                body.extend_from_slice(&dst.ip().octets());
+                body.extend_from_slice(&src.port().to_be_bytes());
+                body.extend_from_slice(&dst.port().to_be_bytes());
+                1
+            }
+            (Some(SocketAddr::V6(src)), Some(SocketAddr::V6(dst))) => {
+                body.extend_from_slice(&src.ip().octets());
+                body.extend_from_slice(&dst.ip().octets());
+                body.extend_from_slice(&src.port().to_be_bytes());
+                body.extend_from_slice(&dst.port().to_be_bytes());
+                2
+            }
+            (None, None) => 0,
+            _ => return Err(HttpError::ProxyProtocol.into()),
+        };
+        for (kind, value) in &self.tlvs {
+            body.push(*kind);
+            body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            body.extend_from_slice(value);
+        }
+        if body.len() > u16::MAX as usize {
+            return Err(HttpError::ProxyProtocol.into());
+        }
+        let mut size = buf.put_slice(&V2_SIGNATURE);
+        buf.put_u8(command);
+        buf.put_u8(family << 4 | if family == 0 { 0 } else { transport });
+        buf.put_u16(body.len() as u16);
+        size += 4;
+        size += buf.put_slice(&body);
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProxyHeader, ProxyTransport};
+    use crate::Buf;
+
+    #[test]
+    fn proxy_v2_partial_and_tlv() {
+        let src = "[2001:db8::1]:1000".parse().unwrap();
+        let dst = "[2001:db8::2]:443".parse().unwrap();
+        let mut header = ProxyHeader::tcp(src, dst);
+        header.tlvs.push((0x01, b"h2".to_vec()));
+        let mut data = vec![];
+        let len = header.encode_v2(&mut data).unwrap();
+        assert_eq!(len, 16 + 36 + 5);
+        data.extend_from_slice(b"PRI");
+
+        for i in 0..len {
+            let err = ProxyHeader::parse(&mut &data[..i]).unwrap_err();
+            assert!(err.is_partial());
+        }
+        let mut buf = &data[..];
+        let parsed = ProxyHeader::parse(&mut buf).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(parsed.transport, ProxyTransport::Tcp);
+        assert_eq!(buf.chunk(), b"PRI");
+
+        assert!(ProxyHeader::parse(&mut &b"GET / HTTP/1.1\r\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/15 10:02:11
+
+//! 开启`serde`特性后, 为Url/Method/StatusCode/Version/HeaderMap等实现序列化,
+//! 方便以JSON等格式记录或回放请求
+//!
+//! - Url, Method, Version, HeaderName 序列化为字符串
+//! - StatusCode 序列化为数字
+//! - HeaderValue 为合法utf8时序列化为字符串, 否则为字节数组
+//! - HeaderMap 序列化为`[name, value]`的数组, 保留顺序及重复的头
+
+use core::fmt;
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::prelude::*;
+use crate::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Url, Version};
+
+macro_rules! serde_by_str {
+    ($ty:ty, $expect:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct StrVisitor;
+
+                impl<'de> Visitor<'de> for StrVisitor {
+                    type Value = $ty;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str($expect)
+                    }
+
+                    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                        v.parse::<$ty>().map_err(E::custom)
+                    }
+                }
+
+                deserializer.deserialize_str(StrVisitor)
+            }
+        }
+    };
+}
+
+serde_by_str!(Url, "a url string");
+serde_by_str!(Method, "a http method");
+serde_by_str!(Version, "a http version like HTTP/1.1");
+
+impl Serialize for StatusCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.as_u16())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = u16::deserialize(deserializer)?;
+        StatusCode::from_u16(code).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for HeaderName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        HeaderName::from_bytes(name.as_bytes())
+            .ok_or_else(|| de::Error::custom("invalid header name"))
+    }
+}
+
+impl Serialize for HeaderValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match core::str::from_utf8(self.as_bytes()) {
+            Ok(s) => serializer.serialize_str(s),
+            Err(_) => serializer.serialize_bytes(self.as_bytes()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = HeaderValue;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a header value string or bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(HeaderValue::from_bytes(v.as_bytes()))
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(HeaderValue::from_bytes(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(b) = seq.next_element::<u8>()? {
+                    bytes.push(b);
+                }
+                Ok(HeaderValue::Value(bytes))
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl Serialize for HeaderMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for value in self.iter() {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor;
+
+        impl<'de> Visitor<'de> for MapVisitor {
+            type Value = HeaderMap;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of [name, value] pairs")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut header = HeaderMap::new();
+                while let Some((name, value)) = seq.next_element::<(HeaderName, HeaderValue)>()? {
+                    header.push(name, value);
+                }
+                Ok(header)
+            }
+        }
+
+        deserializer.deserialize_seq(MapVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::{value::{Error, StrDeserializer, U16Deserializer}, Deserialize, IntoDeserializer};
+
+    use crate::{Method, StatusCode, Url, Version};
+
+    #[test]
+    fn serde_from_str() {
+        let method: StrDeserializer<Error> = "post".into_deserializer();
+        assert_eq!(Method::deserialize(method).unwrap(), Method::POST);
+        let version: StrDeserializer<Error> = "HTTP/1.1".into_deserializer();
+        assert_eq!(Version::deserialize(version).unwrap(), Version::HTTP11);
+        let url: StrDeserializer<Error> = "http://www.baidu.com/path?a=1".into_deserializer();
+        let url = Url::deserialize(url).unwrap();
+        assert_eq!(url.path, "/path");
+        let status: U16Deserializer<Error> = 404u16.into_deserializer();
+        assert_eq!(StatusCode::deserialize(status).unwrap(), StatusCode::NOT_FOUND);
+    }
+}
@@ -10,14 +10,34 @@
 // -----
 // Created Date: 2023/08/18 02:58:54
 
+use alloc::borrow::Cow;
+
+use crate::prelude::*;
 use crate::{WebResult, Buf, BufMut, Binary, BinaryMut};
 
+/// 可序列化的对象, 作为Request及Response的body, 写入到缓存中
+///
+/// 已实现的类型: `()`, `&str`, `String`, `Vec<u8>`, `&[u8]`, `Binary`,
+/// `BinaryMut`, `Cow<'_, [u8]>`, `Cow<'_, str>`
 pub trait Serialize {
     fn serialize<B: Buf+BufMut>(&mut self, buffer: &mut B) -> WebResult<usize>;
 }
 
+/// 可在序列化之前得出准确长度的对象, 用于自动设置Content-Length
+///
+/// # Examples
+///
+/// ```
+/// use webparse::SizedSerialize;
+///
+/// assert_eq!("hello".serialize_len(), 5);
+/// assert_eq!(().serialize_len(), 0);
+/// ```
+pub trait SizedSerialize: Serialize {
+    fn serialize_len(&self) -> usize;
+}
 
-impl Serialize for &'static str {
+impl Serialize for &str {
     fn serialize<B: Buf+BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
         Ok(buffer.put_slice(self.as_bytes()))
     }
@@ -47,6 +67,18 @@ impl Serialize for &[u8] {
     }
 }
 
+impl Serialize for Cow<'_, [u8]> {
+    fn serialize<B: Buf+BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        Ok(buffer.put_slice(self.as_ref()))
+    }
+}
+
+impl Serialize for Cow<'_, str> {
+    fn serialize<B: Buf+BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
+        Ok(buffer.put_slice(self.as_bytes()))
+    }
+}
+
 impl Serialize for Binary {
     fn serialize<B: Buf+BufMut>(&mut self, buffer: &mut B) -> WebResult<usize> {
         let len = self.remaining();
@@ -62,3 +94,85 @@ impl Serialize for BinaryMut {
         Ok(len)
     }
 }
+
+impl SizedSerialize for &str {
+    fn serialize_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizedSerialize for String {
+    fn serialize_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizedSerialize for () {
+    fn serialize_len(&self) -> usize {
+        0
+    }
+}
+
+impl SizedSerialize for Vec<u8> {
+    fn serialize_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizedSerialize for &[u8] {
+    fn serialize_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizedSerialize for Cow<'_, [u8]> {
+    fn serialize_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizedSerialize for Cow<'_, str> {
+    fn serialize_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SizedSerialize for Binary {
+    fn serialize_len(&self) -> usize {
+        self.remaining()
+    }
+}
+
+impl SizedSerialize for BinaryMut {
+    fn serialize_len(&self) -> usize {
+        self.remaining()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+
+    use crate::prelude::*;
+    use crate::{Binary, BinaryMut, Buf, SizedSerialize};
+
+    fn check<T: SizedSerialize>(mut value: T, expect: &[u8]) {
+        assert_eq!(value.serialize_len(), expect.len());
+        let mut buffer = BinaryMut::new();
+        assert_eq!(value.serialize(&mut buffer).unwrap(), expect.len());
+        assert_eq!(buffer.chunk(), expect);
+    }
+
+    #[test]
+    fn serialize_body() {
+        check((), b"");
+        check("abc", b"abc");
+        check("abc".to_string(), b"abc");
+        check(b"abc".to_vec(), b"abc");
+        check(&b"abc"[..], b"abc");
+        check(Cow::Borrowed(&b"abc"[..]), b"abc");
+        check(Cow::<str>::Owned("abc".to_string()), b"abc");
+        check(Binary::from_static(b"abc"), b"abc");
+        check(BinaryMut::from("abc"), b"abc");
+    }
+}
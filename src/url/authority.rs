@@ -0,0 +1,230 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/20 09:41:27
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::net::{Ipv4Addr, Ipv6Addr};
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::{Helper, UrlError, WebError, WebResult};
+
+/// Authority中的主机部分
+#[derive(Clone, Debug)]
+pub enum Host {
+    /// 域名, 保留原始大小写, 比较时不区分大小写
+    Domain(String),
+    Ipv4(Ipv4Addr),
+    /// 在Authority中以`[]`包围
+    Ipv6(Ipv6Addr),
+}
+
+impl Host {
+    /// 解析主机, IPv6地址可带或不带`[]`
+    pub fn parse(host: &str) -> WebResult<Host> {
+        if host.is_empty() {
+            return Err(WebError::from(UrlError::UrlInvalid));
+        }
+        let inner = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host);
+        if let Ok(ip) = Ipv6Addr::from_str(inner) {
+            return Ok(Host::Ipv6(ip));
+        }
+        if inner.len() != host.len() {
+            return Err(WebError::from(UrlError::UrlInvalid));
+        }
+        if let Ok(ip) = Ipv4Addr::from_str(host) {
+            return Ok(Host::Ipv4(ip));
+        }
+        if host.bytes().any(|b| !Self::is_domain_token(b)) {
+            return Err(WebError::from(UrlError::UrlInvalid));
+        }
+        Ok(Host::Domain(host.to_string()))
+    }
+
+    // reg-name = *( unreserved / pct-encoded / sub-delims )
+    fn is_domain_token(b: u8) -> bool {
+        Helper::is_alpha(b)
+            || Helper::is_digit(b)
+            || b"-._~%!$&'()*+,;=".contains(&b)
+    }
+
+    /// 主机的字符串形式, IPv6不带`[]`
+    pub fn to_host_string(&self) -> String {
+        match self {
+            Host::Domain(d) => d.clone(),
+            Host::Ipv4(ip) => ip.to_string(),
+            Host::Ipv6(ip) => ip.to_string(),
+        }
+    }
+
+    pub fn is_ip(&self) -> bool {
+        !matches!(self, Host::Domain(_))
+    }
+}
+
+impl PartialEq for Host {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Host::Domain(a), Host::Domain(b)) => a.eq_ignore_ascii_case(b),
+            (Host::Ipv4(a), Host::Ipv4(b)) => a == b,
+            (Host::Ipv6(a), Host::Ipv6(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Host {}
+
+impl Hash for Host {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Host::Domain(d) => {
+                for b in d.bytes() {
+                    state.write_u8(b.to_ascii_lowercase());
+                }
+            }
+            Host::Ipv4(ip) => ip.hash(state),
+            Host::Ipv6(ip) => ip.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Domain(d) => f.write_str(d),
+            Host::Ipv4(ip) => write!(f, "{}", ip),
+            Host::Ipv6(ip) => write!(f, "[{}]", ip),
+        }
+    }
+}
+
+/// `[userinfo@]host[:port]`, 用于Host头, CONNECT请求及Url中的认证部分
+///
+/// # Examples
+///
+/// ```
+/// use webparse::{Authority, Host};
+///
+/// let auth: Authority = "user@[::1]:8080".parse().unwrap();
+/// assert_eq!(auth.userinfo(), Some("user"));
+/// assert!(matches!(auth.host(), Host::Ipv6(_)));
+/// assert_eq!(auth.port(), Some(8080));
+/// assert_eq!(auth.to_string(), "user@[::1]:8080");
+///
+/// let a: Authority = "Example.COM:80".parse().unwrap();
+/// let b: Authority = "example.com:80".parse().unwrap();
+/// assert_eq!(a, b);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Authority {
+    userinfo: Option<String>,
+    host: Host,
+    port: Option<u16>,
+}
+
+impl Authority {
+    pub fn new(host: Host, port: Option<u16>) -> Authority {
+        Authority {
+            userinfo: None,
+            host,
+            port,
+        }
+    }
+
+    pub fn parse(value: &str) -> WebResult<Authority> {
+        let (userinfo, rest) = match value.rfind('@') {
+            Some(pos) => (Some(value[..pos].to_string()), &value[pos + 1..]),
+            None => (None, value),
+        };
+        // IPv6以`]`结尾, 之后的冒号才是端口
+        let split = match rest.rfind(']') {
+            Some(end) => rest[end..].find(':').map(|p| p + end),
+            None => rest.rfind(':'),
+        };
+        let (host, port) = match split {
+            Some(pos) => {
+                let port = &rest[pos + 1..];
+                let port = if port.is_empty() {
+                    None
+                } else {
+                    Some(port.parse::<u16>().map_err(|_| WebError::from(UrlError::UrlInvalid))?)
+                };
+                (&rest[..pos], port)
+            }
+            None => (rest, None),
+        };
+        // 不带`[]`的IPv6无法区分端口, 视为错误
+        if !host.starts_with('[') && host.contains(':') {
+            return Err(WebError::from(UrlError::UrlInvalid));
+        }
+        Ok(Authority {
+            userinfo,
+            host: Host::parse(host)?,
+            port,
+        })
+    }
+
+    pub fn userinfo(&self) -> Option<&str> {
+        self.userinfo.as_deref()
+    }
+
+    pub fn set_userinfo(&mut self, userinfo: Option<String>) {
+        self.userinfo = userinfo;
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    pub fn set_port(&mut self, port: Option<u16>) {
+        self.port = port;
+    }
+
+    /// 端口, 不存在时返回默认值
+    pub fn port_or(&self, default: u16) -> u16 {
+        self.port.unwrap_or(default)
+    }
+
+    /// 用于建立连接的地址, 如`example.com:443`或`[::1]:443`
+    pub fn to_connect_string(&self, default_port: u16) -> String {
+        format!("{}:{}", self.host, self.port_or(default_port))
+    }
+}
+
+impl FromStr for Authority {
+    type Err = WebError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Authority::parse(s)
+    }
+}
+
+impl fmt::Display for Authority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(userinfo) = &self.userinfo {
+            write!(f, "{}@", userinfo)?;
+        }
+        write!(f, "{}", self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
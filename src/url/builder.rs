@@ -10,6 +10,7 @@
 // -----
 // Created Date: 2023/08/18 01:53:49
 
+use crate::prelude::*;
 use crate::{WebResult, Url, Scheme, WebError};
 
 pub struct Builder {
@@ -102,7 +103,10 @@ impl Builder {
     }
 
     pub fn build(self) -> Result<Url, WebError> {
-        self.inner
+        self.inner.map(|mut url| {
+            url.update_authority();
+            url
+        })
     }
 }
 
@@ -10,11 +10,11 @@
 // -----
 // Created Date: 2023/08/21 06:16:49
 
-use std::fmt;
+use core::fmt;
 
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UrlError {
     UrlInvalid,
     UrlCodeInvalid,
@@ -37,3 +37,6 @@ impl fmt::Display for UrlError {
         f.write_str(self.description_str())
     }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for UrlError {}
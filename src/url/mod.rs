@@ -15,9 +15,11 @@ mod scheme;
 mod builder;
 mod error;
 mod url;
+mod authority;
 
 
 pub use scheme::Scheme;
 pub use builder::Builder;
 pub use error::UrlError;
-pub use url::Url;
\ No newline at end of file
+pub use url::Url;
+pub use authority::{Authority, Host};
\ No newline at end of file
@@ -10,9 +10,20 @@
 // -----
 // Created Date: 2023/08/16 09:53:49
 
-use std::{fmt::Display, str::FromStr};
+use core::{fmt::Display, str::FromStr};
 
+use crate::prelude::*;
 use crate::{byte_map, Buf, BufMut, Helper, HttpError, Serialize, WebError, WebResult};
+#[cfg(feature = "std")]
+use lazy_static::lazy_static;
+#[cfg(feature = "std")]
+use std::{collections::HashMap, sync::RwLock};
+
+#[cfg(feature = "std")]
+lazy_static! {
+    // 用户注册的扩展协议, 值为(默认端口, 是否加密)
+    static ref REGISTRY: RwLock<HashMap<String, (u16, bool)>> = RwLock::new(HashMap::new());
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Scheme {
@@ -22,6 +33,7 @@ pub enum Scheme {
     Ws,
     Wss,
     Ftp,
+    /// 其它协议, 统一存为小写, 可通过`Scheme::register`注册默认端口
     Extension(String),
 }
 
@@ -79,6 +91,69 @@ impl Scheme {
         }
     }
 
+    /// 注册扩展协议的默认端口及是否加密, 对内置的协议无效
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Scheme;
+    /// Scheme::register("redis", 6379, false);
+    /// Scheme::register("rediss", 6380, true);
+    /// let scheme: Scheme = "rediss".parse().unwrap();
+    /// assert_eq!(scheme.default_port(), Some(6380));
+    /// assert!(scheme.is_secure());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn register(scheme: &str, default_port: u16, secure: bool) {
+        if let Ok(mut registry) = REGISTRY.write() {
+            registry.insert(scheme.to_ascii_lowercase(), (default_port, secure));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn registered(name: &str) -> Option<(u16, bool)> {
+        REGISTRY.read().ok().and_then(|r| r.get(name).copied())
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn registered(_name: &str) -> Option<(u16, bool)> {
+        None
+    }
+
+    /// 协议的默认端口, 未知的协议返回None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::Scheme;
+    /// assert_eq!(Scheme::Https.default_port(), Some(443));
+    /// assert_eq!(Scheme::Ftp.default_port(), Some(21));
+    /// assert_eq!(Scheme::None.default_port(), None);
+    /// ```
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            Scheme::Http | Scheme::Ws => Some(80),
+            Scheme::Https | Scheme::Wss => Some(443),
+            Scheme::Ftp => Some(21),
+            Scheme::Extension(s) => Self::registered(s).map(|(port, _)| port),
+            Scheme::None => None,
+        }
+    }
+
+    /// 是否为基于TLS的协议
+    pub fn is_secure(&self) -> bool {
+        match self {
+            Scheme::Https | Scheme::Wss => true,
+            Scheme::Extension(s) => Self::registered(s).is_some_and(|(_, secure)| secure),
+            _ => false,
+        }
+    }
+
+    /// 端口是否为该协议的默认端口, 默认端口在序列化时可以省略
+    pub fn is_default_port(&self, port: u16) -> bool {
+        self.default_port() == Some(port)
+    }
+
     pub fn is_none(&self) -> bool {
         match self {
             Scheme::None => true,
@@ -117,7 +192,7 @@ impl Scheme {
 }
 
 impl Display for Scheme {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(&self.as_str())
     }
 }
@@ -138,13 +213,15 @@ impl TryFrom<&str> for Scheme {
         if value.len() > 64 {
             return Err(WebError::from(crate::UrlError::UrlInvalid));
         }
-        match value {
+        // 协议名不区分大小写
+        let value = value.to_ascii_lowercase();
+        match &*value {
             "http" => Ok(Scheme::Http),
             "https" => Ok(Scheme::Https),
             "ws" => Ok(Scheme::Ws),
             "wss" => Ok(Scheme::Wss),
             "ftp" => Ok(Scheme::Ftp),
-            _ => Ok(Scheme::Extension(value.to_string())),
+            _ => Ok(Scheme::Extension(value)),
         }
     }
 }
@@ -152,6 +229,6 @@ impl TryFrom<&str> for Scheme {
 impl FromStr for Scheme {
     type Err = WebError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Scheme::try_from(&*s.to_uppercase())
+        Scheme::try_from(s)
     }
 }
\ No newline at end of file
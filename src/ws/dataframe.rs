@@ -1,8 +1,9 @@
+use crate::prelude::*;
 use crate::{
-    ws::{frame_header, Masker, WsFrameHeader},
+    ws::{frame_header, Masker, WsError, WsFrameHeader},
     Buf, BufMut, WebResult,
 };
-use std::io::{self};
+use crate::io;
 
 use super::{frame_header::WsFrameFlags, mask};
 
@@ -29,6 +30,25 @@ impl DataFrame {
         }
     }
 
+    /// 设置保留位, 用于实现压缩等扩展
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::ws::{DataFrame, DataFrameable, Opcode};
+    ///
+    /// let frame = DataFrame::new(true, Opcode::Binary, vec![1]).with_reserved([true, false, false]);
+    /// let mut data = vec![];
+    /// frame.write_to(&mut data, None).unwrap();
+    /// assert_eq!(data, vec![0xc2, 0x01, 0x01]);
+    /// assert!(frame.reserved_allowed(&[true, false, false]));
+    /// assert!(!frame.reserved_allowed(&[false; 3]));
+    /// ```
+    pub fn with_reserved(mut self, reserved: [bool; 3]) -> DataFrame {
+        self.reserved = reserved;
+        self
+    }
+
     /// Take the body and header of a dataframe and combine it into a single
     /// Dataframe struct. A websocket message can be made up of many individual
     /// dataframes, use the methods from the Message or OwnedMessage structs to
@@ -46,14 +66,19 @@ impl DataFrame {
             header.flags.contains(WsFrameFlags::RSV3),
         ];
 
-        let opcode = Opcode::new(header.opcode).expect("Invalid header opcode!");
+        let opcode = match Opcode::new(header.opcode) {
+            Some(opcode) => opcode,
+            None => return Err(WsError::ProtocolError("Invalid header opcode").into()),
+        };
 
         let data = match header.mask {
             Some(mask) => {
                 // if !should_be_masked {
                 //     return Err(WsError::DataFrameError("Expected unmasked data frame").into());
                 // }
-                mask::mask_data(mask, &body)
+                let mut body = body;
+                mask::mask_in_place(&mut body, mask);
+                body
             }
             None => {
                 // if should_be_masked {
@@ -142,6 +167,14 @@ pub trait DataFrameable {
         + self.size()
     }
 
+    /// 设置的保留位是否都在协商过的扩展中
+    fn reserved_allowed(&self, allowed: &[bool; 3]) -> bool {
+        self.reserved()
+            .iter()
+            .zip(allowed.iter())
+            .all(|(reserved, allowed)| !reserved || *allowed)
+    }
+
     /// Write the payload to a writer
     fn write_payload(&self, socket: &mut dyn BufMut) -> WebResult<()>;
 
@@ -187,6 +220,19 @@ pub trait DataFrameable {
         writer.put_slice(data.as_slice());
         Ok(0)
     }
+
+    /// 按协商过的扩展写入, 使用了未协商的保留位时返回错误
+    fn write_to_with_extensions(
+        &self,
+        writer: &mut dyn BufMut,
+        masking_key: Option<[u8; 4]>,
+        allowed: &[bool; 3],
+    ) -> WebResult<usize> {
+        if !self.reserved_allowed(allowed) {
+            return Err(WsError::DataFrameError("Reserved bits not negotiated").into());
+        }
+        self.write_to(writer, masking_key)
+    }
 }
 
 impl DataFrameable for DataFrame {
@@ -1,3 +1,5 @@
+use core::fmt;
+
 use crate::WebError;
 
 
@@ -14,7 +16,8 @@ impl WsError {
     pub fn description_str(&self) -> &'static str {
         match *self {
             Self::DataFrameError(s) => s,
-            _ => "",
+            Self::ProtocolError(s) => s,
+            Self::NoDataAvailable => "no data available",
         }
     }
 
@@ -27,4 +30,13 @@ impl Into<WebError> for WsError {
     fn into(self) -> WebError {
         WebError::Ws(self)
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for WsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WsError {}
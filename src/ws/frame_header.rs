@@ -2,6 +2,9 @@ use bitflags::bitflags;
 
 use crate::{Buf, BufMut, WebResult, ws::WsError};
 
+/// 帧头的最大长度, 2字节的头, 8字节的扩展长度及4字节的掩码
+const MAX_HEADER_LEN: usize = 14;
+
 bitflags! {
     /// Flags relevant to a WebSocket data frame.
     #[derive(Debug, Clone, Copy, PartialEq)]
@@ -30,6 +33,102 @@ pub struct WsFrameHeader {
     pub len: u64,
 }
 
+impl WsFrameHeader {
+    /// 编码后头部的长度
+    pub fn header_len(&self) -> usize {
+        2 + match self.len {
+            0..=125 => 0,
+            126..=65535 => 2,
+            _ => 8,
+        } + if self.mask.is_some() { 4 } else { 0 }
+    }
+
+    /// 解析帧头, 数据不足时返回None且不消耗缓存, 长度未使用最短编码时返回错误
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::ws::WsFrameHeader;
+    /// use webparse::Buf;
+    ///
+    /// let data = [0x82, 0x7e, 0x01, 0x00, 0xff];
+    /// assert!(WsFrameHeader::parse(&mut &data[..3]).unwrap().is_none());
+    /// let mut buffer = &data[..];
+    /// let header = WsFrameHeader::parse(&mut buffer).unwrap().unwrap();
+    /// assert_eq!(header.len, 256);
+    /// assert_eq!(buffer.remaining(), 1);
+    ///
+    /// let mut encoded = vec![];
+    /// assert_eq!(header.encode(&mut encoded).unwrap(), 4);
+    /// assert_eq!(&encoded[..], &data[..4]);
+    ///
+    /// assert!(WsFrameHeader::parse(&mut &[0x82, 0x7e, 0x00, 0x7d][..]).is_err());
+    /// ```
+    pub fn parse<B: Buf>(buffer: &mut B) -> WebResult<Option<WsFrameHeader>> {
+        Self::parse_with_limit(buffer, u64::MAX)
+    }
+
+    /// 解析帧头, 负载长度超过`max_payload`时在读取负载前返回错误,
+    /// 通过`remaining`及`get`系列方法读取, 帧头可以跨越多个chunk
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::ws::WsFrameHeader;
+    ///
+    /// // 长度已知即可报错, 不必等待掩码
+    /// let data = [0x82, 0xff, 0, 0, 0, 0, 0x10, 0, 0, 0];
+    /// assert!(WsFrameHeader::parse_with_limit(&mut &data[..], 65536).is_err());
+    /// ```
+    pub fn parse_with_limit<B: Buf>(
+        buffer: &mut B,
+        max_payload: u64,
+    ) -> WebResult<Option<WsFrameHeader>> {
+        let remaining = buffer.remaining();
+        if remaining < 2 {
+            return Ok(None);
+        }
+        // 可能不完整时先由前两个字节算出帧头的长度, 不完整时不消耗数据
+        if remaining < MAX_HEADER_LEN {
+            let chunk = buffer.chunk();
+            let byte1 = match chunk.get(1) {
+                Some(b) => *b,
+                None => return Ok(None),
+            };
+            let ext = match byte1 & 0x7F {
+                126 => 2,
+                127 => 8,
+                _ => 0,
+            };
+            let used = 2 + ext + if byte1 & 0x80 == 0x80 { 4 } else { 0 };
+            if remaining < used {
+                // 长度已知即可报错, 不必等待掩码
+                let len = match ext {
+                    0 => Some(u64::from(byte1 & 0x7F)),
+                    _ => chunk
+                        .get(2..2 + ext)
+                        .map(|v| v.iter().fold(0, |len, b| len << 8 | u64::from(*b))),
+                };
+                if len.is_some_and(|len| len > max_payload) {
+                    return Err(WsError::DataFrameError("Payload length too large").into());
+                }
+                return Ok(None);
+            }
+        }
+        let header = read_header(buffer)?;
+        if header.len > max_payload {
+            return Err(WsError::DataFrameError("Payload length too large").into());
+        }
+        Ok(Some(header))
+    }
+
+    /// 以最短的长度编码写入帧头, 返回写入的字节数
+    pub fn encode<B: BufMut>(&self, buffer: &mut B) -> WebResult<usize> {
+        write_header(buffer, *self)?;
+        Ok(self.header_len())
+    }
+}
+
 /// Writes a data frame header.
 pub fn write_header(writer: &mut dyn BufMut, header: WsFrameHeader) -> WebResult<()> {
     if header.opcode > 0xF {
@@ -88,7 +187,8 @@ where
         }
         127 => {
             let len = reader.try_get_u64()?;
-            if len <= 65535 {
+            // 最高位必须为0
+            if len <= 65535 || len > i64::MAX as u64 {
                 return Err(WsError::DataFrameError("Invalid data frame length").into());
             }
             len
@@ -129,6 +229,7 @@ mod tests {
     
     use test;
     use super::*;
+    use crate::prelude::*;
 
     #[test]
     fn test_read_header_simple() {
@@ -171,6 +272,76 @@ mod tests {
         assert_eq!(obtained, expected);
     }
 
+    #[test]
+    fn parse_partial() {
+        let data = [0x81, 0xff, 0, 0, 0, 0, 0, 1, 0, 0, 1, 2, 3, 4];
+        for i in 0..data.len() {
+            let mut buffer = &data[..i];
+            assert!(WsFrameHeader::parse(&mut buffer).unwrap().is_none());
+            assert_eq!(buffer.len(), i);
+        }
+        let header = WsFrameHeader::parse(&mut &data[..]).unwrap().unwrap();
+        assert_eq!(header.len, 65536);
+        assert_eq!(header.mask, Some([1, 2, 3, 4]));
+        assert_eq!(header.header_len(), data.len());
+
+        assert!(WsFrameHeader::parse_with_limit(&mut &data[..10], 65535).is_err());
+        assert!(WsFrameHeader::parse(&mut &[0x81, 0x7f, 0x80, 0, 0, 0, 0, 0, 0, 0][..]).is_err());
+    }
+
+    /// 由两段数据组成的Buf, 用于验证跨chunk的解析
+    struct Split<'a>(&'a [u8], &'a [u8]);
+
+    impl<'a> Buf for Split<'a> {
+        fn remaining(&self) -> usize {
+            self.0.len() + self.1.len()
+        }
+
+        fn chunk(&self) -> &[u8] {
+            if self.0.is_empty() { self.1 } else { self.0 }
+        }
+
+        fn advance(&mut self, mut n: usize) {
+            let first = n.min(self.0.len());
+            self.0 = &self.0[first..];
+            n -= first;
+            self.1 = &self.1[n..];
+        }
+
+        fn advance_chunk(&mut self, n: usize) -> &[u8] {
+            let chunk: &'a [u8] = if self.0.is_empty() { self.1 } else { self.0 };
+            let ret = &chunk[..n];
+            self.advance(n);
+            ret
+        }
+
+        fn into_binary(self) -> crate::Binary {
+            crate::Binary::from([self.0, self.1].concat())
+        }
+
+        fn copy_to_slice(&mut self, dst: &mut [u8]) -> usize {
+            for v in dst.iter_mut() {
+                *v = self.get_u8();
+            }
+            dst.len()
+        }
+    }
+
+    #[test]
+    fn parse_across_chunks() {
+        let data = [0x81, 0xfe, 0x01, 0x00, 1, 2, 3, 4, 0xff];
+        for i in 2..data.len() {
+            let mut buffer = Split(&data[..i], &data[i..]);
+            let header = WsFrameHeader::parse(&mut buffer).unwrap().unwrap();
+            assert_eq!(header.len, 256);
+            assert_eq!(header.mask, Some([1, 2, 3, 4]));
+            assert_eq!(buffer.remaining(), 1);
+        }
+        let mut buffer = Split(&data[..3], &data[3..6]);
+        assert!(WsFrameHeader::parse(&mut buffer).unwrap().is_none());
+        assert_eq!(buffer.remaining(), 6);
+    }
+
     #[test]
     fn test_write_header_complex() {
         let header = WsFrameHeader {
@@ -0,0 +1,46 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/22 09:20:31
+
+//! websocket的握手, 根据Sec-WebSocket-Key计算Sec-WebSocket-Accept (RFC 6455 4.2.2)
+
+use crate::prelude::*;
+use crate::Helper;
+
+/// 拼接在Sec-WebSocket-Key之后的固定GUID
+pub const WS_GUID: &[u8] = b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// 由客户端的Sec-WebSocket-Key计算服务端应答的Sec-WebSocket-Accept
+///
+/// # Examples
+///
+/// ```
+/// use webparse::ws::handshake;
+/// let accept = handshake::accept_key(b"dGhlIHNhbXBsZSBub25jZQ==");
+/// assert_eq!(accept, "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+/// assert!(handshake::verify_accept(b"dGhlIHNhbXBsZSBub25jZQ==", b"s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+/// ```
+pub fn accept_key(key: &[u8]) -> String {
+    let mut data = Vec::with_capacity(key.len() + WS_GUID.len());
+    data.extend_from_slice(key);
+    data.extend_from_slice(WS_GUID);
+    Helper::base64_encode(&Helper::sha1(&data))
+}
+
+/// 客户端校验服务端返回的Sec-WebSocket-Accept
+pub fn verify_accept(key: &[u8], accept: &[u8]) -> bool {
+    accept_key(key).as_bytes() == accept
+}
+
+/// 由16字节的随机数生成客户端的Sec-WebSocket-Key
+pub fn generate_key(nonce: [u8; 16]) -> String {
+    Helper::base64_encode(&nonce)
+}
@@ -10,6 +10,7 @@
 // -----
 // Created Date: 2023/12/29 02:00:25
 
+use crate::prelude::*;
 use crate::BufMut;
 
 /// Struct to pipe data into another writer,
@@ -50,27 +51,76 @@ unsafe impl<'w> BufMut for Masker<'w> {
         self.end.advance_mut(cnt)
     }
 
-    fn chunk_mut(&mut self) -> &mut [std::mem::MaybeUninit<u8>] {
+    fn chunk_mut(&mut self) -> &mut [core::mem::MaybeUninit<u8>] {
         self.end.chunk_mut()
     }
 
+    /// 直接拷贝到目标的空余空间并原地掩码, 不产生中间的缓存
     fn put_slice(&mut self, src: &[u8]) -> usize {
-        let mut buf = Vec::with_capacity(src.len());
-		for &byte in src.iter() {
-			buf.push(byte ^ self.key[self.pos]);
-			self.pos = (self.pos + 1) % self.key.len();
-		}
-		self.inner_put_slice(&buf)
+        assert!(
+            self.remaining_mut() >= src.len(),
+            "buffer overflow; remaining = {}; src = {}",
+            self.remaining_mut(),
+            src.len()
+        );
+        let mut off = 0;
+        while off < src.len() {
+            let cnt;
+            unsafe {
+                let dst = self.end.chunk_mut();
+                cnt = core::cmp::min(dst.len(), src.len() - off);
+                let dst = dst.as_mut_ptr() as *mut u8;
+                core::ptr::copy_nonoverlapping(src[off..].as_ptr(), dst, cnt);
+                mask_in_place_offset(core::slice::from_raw_parts_mut(dst, cnt), self.key, self.pos);
+                self.end.advance_mut(cnt);
+            }
+            self.pos = (self.pos + cnt) % self.key.len();
+            off += cnt;
+        }
+        src.len()
+    }
+}
+
+/// 原地对数据进行掩码处理, 掩码与解码为同一操作,
+/// 每次处理8个字节, 不足8字节的部分逐字节处理
+///
+/// # Examples
+///
+/// ```
+/// use webparse::ws::mask_in_place;
+/// let key = [1u8, 2, 3, 4];
+/// let mut data = *b"hello websocket";
+/// mask_in_place(&mut data, key);
+/// assert_ne!(&data, b"hello websocket");
+/// mask_in_place(&mut data, key);
+/// assert_eq!(&data, b"hello websocket");
+/// ```
+pub fn mask_in_place(data: &mut [u8], key: [u8; 4]) {
+    mask_in_place_offset(data, key, 0)
+}
+
+/// 从掩码的第`offset`个字节开始处理, 用于分段写入的数据
+fn mask_in_place_offset(data: &mut [u8], key: [u8; 4], offset: usize) {
+    let mut key = key;
+    key.rotate_left(offset % 4);
+    let mut chunks = data.chunks_exact_mut(8);
+    let word = u64::from_ne_bytes([key[0], key[1], key[2], key[3], key[0], key[1], key[2], key[3]]);
+    for chunk in &mut chunks {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        let value = u64::from_ne_bytes(bytes) ^ word;
+        chunk.copy_from_slice(&value.to_ne_bytes());
+    }
+    // 8的倍数不影响掩码的起始位置
+    for (i, byte) in chunks.into_remainder().iter_mut().enumerate() {
+        *byte ^= key[i & 3];
     }
 }
 
 /// Masks data to send to a server and writes
 pub fn mask_data(mask: [u8; 4], data: &[u8]) -> Vec<u8> {
-	let mut out = Vec::with_capacity(data.len());
-	let zip_iter = data.iter().zip(mask.iter().cycle());
-	for (&buf_item, &key_item) in zip_iter {
-		out.push(buf_item ^ key_item);
-	}
+	let mut out = data.to_vec();
+	mask_in_place(&mut out, mask);
 	out
 }
 
@@ -92,4 +142,18 @@ mod tests {
 		assert_eq!(obtained, expected);
 	}
 
+	#[test]
+	fn test_masker_split() {
+		let key = [0x37u8, 0xfa, 0x21, 0x3d];
+		let data: Vec<u8> = (0..100u8).collect();
+		let expected: Vec<u8> = data.iter().enumerate().map(|(i, b)| b ^ key[i % 4]).collect();
+		for split in 0..data.len() {
+			let mut out = crate::BinaryMut::new();
+			let mut masker = Masker::new(key, &mut out);
+			masker.put_slice(&data[..split]);
+			masker.put_slice(&data[split..]);
+			assert_eq!(out.as_slice(), &expected[..]);
+		}
+	}
+
 }
@@ -1,10 +1,9 @@
-use std::borrow::Cow;
-use std::io;
-
-use std::str::from_utf8;
+use alloc::borrow::Cow;
+use crate::io;
 
+use crate::prelude::*;
 use crate::{
-    ws::{DataFrameable, Opcode, WsError},
+    ws::{DataFrameable, Opcode, Utf8Validator, WsError},
     Buf, BufMut, WebError, WebResult,
 };
 
@@ -42,6 +41,8 @@ pub struct Message<'a> {
     pub cd_status_code: Option<u16>,
     /// Main payload
     pub payload: Cow<'a, [u8]>,
+    /// 保留位, 仅在协商了扩展时使用
+    pub reserved: [bool; 3],
 }
 
 impl<'a> Message<'a> {
@@ -50,9 +51,34 @@ impl<'a> Message<'a> {
             opcode: code,
             cd_status_code: status,
             payload,
+            reserved: [false; 3],
         }
     }
 
+    /// 设置保留位, 如permessage-deflate压缩后的消息需设置RSV1
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webparse::ws::{DataFrameable, Message};
+    ///
+    /// let msg = Message::binary(vec![1]).with_reserved([true, false, false]);
+    /// let mut data = vec![];
+    /// assert!(msg.write_to_with_extensions(&mut data, None, &[false; 3]).is_err());
+    /// msg.write_to_with_extensions(&mut data, None, &[true, false, false]).unwrap();
+    /// assert_eq!(data, vec![0xc2, 0x01, 0x01]);
+    ///
+    /// let read = Message::from_dataframes_with_extensions(
+    ///     vec![webparse::ws::DataFrame::read_dataframe(&mut &data[..], false).unwrap()],
+    ///     &[true, false, false],
+    /// ).unwrap();
+    /// assert_eq!(read, msg);
+    /// ```
+    pub fn with_reserved(mut self, reserved: [bool; 3]) -> Self {
+        self.reserved = reserved;
+        self
+    }
+
     /// Create a new WebSocket message with text data
     pub fn text<S>(data: S) -> Self
     where
@@ -144,7 +170,7 @@ impl<'a> DataFrameable for Message<'a> {
 
     #[inline(always)]
     fn reserved(&self) -> &[bool; 3] {
-        FALSE_RESERVED_BITS
+        &self.reserved
     }
 
     fn size(&self) -> usize {
@@ -187,47 +213,56 @@ impl<'a> Message<'a> {
     where
         D: DataFrameable,
     {
-        let opcode = frames
-            .first()
-            .ok_or(WsError::ProtocolError("No dataframes provided"))
-            .map(DataFrameable::opcode)
-            .map_err(|e| WebError::Ws(e))?;
-        let opcode = Opcode::new(opcode);
-
-        let payload_size = frames.iter().map(DataFrameable::size).sum();
-
-        let mut data = Vec::with_capacity(payload_size);
+        Self::from_dataframes_with_extensions(frames, &[false; 3])
+    }
 
+    /// 由多个帧组成消息, `allowed`为协商过的扩展可使用的保留位,
+    /// 消息的保留位取自第一个帧, 设置了保留位的文本消息由扩展处理后再校验UTF-8.
+    /// 边接收边组装时使用`MessageAssembler`
+    pub fn from_dataframes_with_extensions<D>(
+        frames: Vec<D>,
+        allowed: &[bool; 3],
+    ) -> WebResult<Self>
+    where
+        D: DataFrameable,
+    {
+        if frames.is_empty() {
+            return Err(WsError::ProtocolError("No dataframes provided").into());
+        }
+        let mut assembler = MessageAssembler::with_extensions(*allowed);
+        let last = frames.len() - 1;
         for (i, dataframe) in frames.into_iter().enumerate() {
             if i > 0 && dataframe.opcode() != Opcode::Continuation as u8 {
                 return Err(
                     WsError::ProtocolError("Unexpected non-continuation data frame").into(),
                 );
             }
-            if *dataframe.reserved() != [false; 3] {
-                return Err(WsError::ProtocolError("Unsupported reserved bits received").into());
+            if dataframe.is_last() != (i == last) {
+                return Err(WsError::ProtocolError("Unexpected data frame fin bit").into());
             }
-            data.append(&mut dataframe.take_payload());
-        }
-
-        if opcode == Some(Opcode::Text) {
-            if let Err(_e) = from_utf8(data.as_slice()) {
-                return Err(crate::WebError::Extension("Convert Utf8 error"));
+            if let Some(msg) = assembler.push(dataframe)? {
+                return Ok(msg);
             }
         }
+        unreachable!("the last frame always completes the message")
+    }
 
+    /// 由完整的负载构建消息, 校验关闭帧的状态码及原因
+    fn build(opcode: Option<Opcode>, reserved: [bool; 3], data: Vec<u8>) -> WebResult<Self> {
         let msg = match opcode {
-            Some(Opcode::Text) => Message {
-                opcode: Type::Text,
-                cd_status_code: None,
-                payload: Cow::Owned(data),
-            },
+            Some(Opcode::Text) => Message::new(Type::Text, None, Cow::Owned(data)),
             Some(Opcode::Binary) => Message::binary(data),
             Some(Opcode::Close) => {
                 if !data.is_empty() {
+                    if data.len() < 2 {
+                        return Err(WsError::ProtocolError("Invalid close frame payload").into());
+                    }
                     let status_code = (&data[..]).try_get_u16()?;
-                    let reason = std::str::from_utf8(&data[2..])
-                        .map_err(|_| crate::WebError::Extension("Convert Utf8 error"))?
+                    if !is_valid_close_code(status_code) {
+                        return Err(WsError::ProtocolError("Invalid close status code").into());
+                    }
+                    let reason = core::str::from_utf8(&data[2..])
+                        .map_err(|_| WebError::Ws(WsError::DataFrameError("Invalid utf-8 close reason")))?
                         .to_string();
                     Message::close_because(status_code, reason)
                 } else {
@@ -238,8 +273,127 @@ impl<'a> Message<'a> {
             Some(Opcode::Pong) => Message::pong(data),
             _ => return Err(WsError::ProtocolError("Unsupported opcode received").into()),
         };
-        Ok(msg)
+        Ok(msg.with_reserved(reserved))
+    }
+}
+
+/// 边接收边组装消息, 每收到一个帧调用`push`, 文本消息的每个分片在收到时即校验UTF-8,
+/// 非法的数据在该帧返回错误, 不必缓存完整的消息. 控制帧可以出现在分片之间, 收到时直接返回
+///
+/// 返回错误后连接应当关闭, 需要复用时调用`reset`
+///
+/// # Examples
+///
+/// ```
+/// use webparse::ws::{DataFrame, MessageAssembler, Opcode, OwnedMessage};
+///
+/// let mut assembler = MessageAssembler::new();
+/// assert!(assembler.push(DataFrame::new(false, Opcode::Text, b"he".to_vec())).unwrap().is_none());
+/// let ping = assembler.push(DataFrame::new(true, Opcode::Ping, vec![])).unwrap().unwrap();
+/// assert_eq!(OwnedMessage::from(ping), OwnedMessage::Ping(vec![]));
+/// let msg = assembler.push(DataFrame::new(true, Opcode::Continuation, b"llo".to_vec())).unwrap().unwrap();
+/// assert_eq!(OwnedMessage::from(msg), OwnedMessage::Text("hello".to_string()));
+///
+/// // 非法的UTF-8在第一个分片即返回错误
+/// let mut assembler = MessageAssembler::new();
+/// assert!(assembler.push(DataFrame::new(false, Opcode::Text, b"a\xff".to_vec())).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct MessageAssembler {
+    allowed: [bool; 3],
+    /// 正在组装的消息的类型及保留位
+    current: Option<(Opcode, [bool; 3])>,
+    data: Vec<u8>,
+    validator: Option<Utf8Validator>,
+}
+
+impl MessageAssembler {
+    pub fn new() -> MessageAssembler {
+        MessageAssembler::default()
+    }
+
+    /// `allowed`为协商过的扩展可使用的保留位
+    pub fn with_extensions(allowed: [bool; 3]) -> MessageAssembler {
+        MessageAssembler {
+            allowed,
+            ..MessageAssembler::default()
+        }
+    }
+
+    /// 是否没有组装到一半的消息
+    pub fn is_idle(&self) -> bool {
+        self.current.is_none()
+    }
+
+    /// 已缓存的分片数据的长度
+    pub fn buffered(&self) -> usize {
+        self.data.len()
     }
+
+    pub fn reset(&mut self) {
+        self.current = None;
+        self.data.clear();
+        self.validator = None;
+    }
+
+    /// 加入收到的帧, 消息完整时返回该消息
+    pub fn push<D: DataFrameable>(&mut self, frame: D) -> WebResult<Option<Message<'static>>> {
+        if !frame.reserved_allowed(&self.allowed) {
+            return Err(WsError::ProtocolError("Unsupported reserved bits received").into());
+        }
+        let opcode = Opcode::new(frame.opcode());
+        let is_last = frame.is_last();
+        match opcode {
+            Some(Opcode::Continuation) => {
+                if self.current.is_none() {
+                    return Err(WsError::ProtocolError("Unexpected continuation data frame").into());
+                }
+            }
+            Some(op) if op as u8 >= 8 => {
+                if !is_last {
+                    return Err(WsError::ProtocolError("Illegal fragmented control frame").into());
+                }
+                let reserved = *frame.reserved();
+                return Message::build(opcode, reserved, frame.take_payload()).map(Some);
+            }
+            Some(op @ (Opcode::Text | Opcode::Binary)) => {
+                if self.current.is_some() {
+                    return Err(
+                        WsError::ProtocolError("Unexpected non-continuation data frame").into(),
+                    );
+                }
+                let reserved = *frame.reserved();
+                self.current = Some((op, reserved));
+                self.validator =
+                    (op == Opcode::Text && reserved == [false; 3]).then(Utf8Validator::new);
+            }
+            _ => return Err(WsError::ProtocolError("Unsupported opcode received").into()),
+        }
+
+        let payload = frame.take_payload();
+        if let Some(validator) = &mut self.validator {
+            validator.feed(&payload)?;
+        }
+        if self.data.is_empty() && is_last {
+            self.data = payload;
+        } else {
+            self.data.extend_from_slice(&payload);
+        }
+        if !is_last {
+            return Ok(None);
+        }
+        if let Some(validator) = &mut self.validator {
+            validator.finish()?;
+        }
+        let (op, reserved) = self.current.take().expect("checked above");
+        self.validator = None;
+        Message::build(Some(op), reserved, core::mem::take(&mut self.data)).map(Some)
+    }
+}
+
+/// 关闭帧中允许出现的状态码, 见RFC 6455 §7.4
+fn is_valid_close_code(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
 }
 
 /// Represents an owned WebSocket message.
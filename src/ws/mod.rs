@@ -4,11 +4,18 @@
 mod dataframe;
 mod error;
 pub mod frame_header;
+pub mod handshake;
 mod message;
 mod mask;
+mod utf8;
+#[cfg(feature = "std")]
+mod over_h2;
 
 pub use dataframe::{DataFrame, Opcode, DataFrameable};
 pub use error::WsError;
 pub use frame_header::WsFrameHeader;
-pub use message::{Message, OwnedMessage, CloseData, CloseCode};
-pub use mask::Masker;
\ No newline at end of file
+pub use message::{Message, MessageAssembler, OwnedMessage, CloseData, CloseCode};
+pub use mask::{Masker, mask_data, mask_in_place};
+pub use utf8::Utf8Validator;
+#[cfg(feature = "std")]
+pub use over_h2::WsOverH2;
\ No newline at end of file
@@ -0,0 +1,116 @@
+// Copyright 2022 - 2023 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2023/09/21 10:12:45
+
+//! RFC 8441中基于http2的websocket, ws帧作为字节流承载在同一个流的DATA帧中,
+//! 一个ws帧可能跨越多个DATA帧, 一个DATA帧也可能包含多个ws帧
+
+use crate::http2::frame::{Data, Flag, FrameHeader, Kind, StreamIdentifier};
+use crate::{io, Binary, BinaryMut, Buf, WebError, WebResult};
+
+use super::{DataFrame, DataFrameable};
+
+/// 在http2流上收发ws帧的适配器
+///
+/// # Examples
+///
+/// ```
+/// use webparse::http2::frame::StreamIdentifier;
+/// use webparse::ws::{DataFrame, Opcode, WsOverH2};
+///
+/// let mut client = WsOverH2::new(StreamIdentifier(1));
+/// let frame = DataFrame::new(true, Opcode::Text, b"hello".to_vec());
+/// let data = client.encode_frame(&frame, Some([1, 2, 3, 4]), false).unwrap();
+///
+/// let mut server = WsOverH2::new(StreamIdentifier(1));
+/// let bytes = data.into_payload();
+/// // 一个ws帧被拆分在两个DATA帧中
+/// server.recv(&bytes[..4]);
+/// assert!(server.poll_frame().unwrap().is_none());
+/// server.recv(&bytes[4..]);
+/// assert_eq!(server.poll_frame().unwrap(), Some(frame));
+/// ```
+#[derive(Debug)]
+pub struct WsOverH2 {
+    stream_id: StreamIdentifier,
+    buffer: BinaryMut,
+    max_frame_len: Option<usize>,
+}
+
+impl WsOverH2 {
+    pub fn new(stream_id: StreamIdentifier) -> WsOverH2 {
+        WsOverH2 {
+            stream_id,
+            buffer: BinaryMut::new(),
+            max_frame_len: None,
+        }
+    }
+
+    pub fn stream_id(&self) -> StreamIdentifier {
+        self.stream_id
+    }
+
+    /// 限制单个ws帧的负载大小, 超出时返回错误
+    pub fn set_max_frame_len(&mut self, len: usize) {
+        self.max_frame_len = Some(len);
+    }
+
+    /// 缓存中尚未组成完整ws帧的字节数
+    pub fn buffered(&self) -> usize {
+        self.buffer.remaining()
+    }
+
+    /// 追加收到的DATA帧负载
+    pub fn recv(&mut self, payload: &[u8]) {
+        self.buffer.put_slice(payload);
+    }
+
+    /// 追加收到的DATA帧, 流不匹配时返回错误
+    pub fn recv_data(&mut self, data: &Data<Binary>) -> WebResult<()> {
+        if data.stream_id() != self.stream_id {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "stream id not match").into());
+        }
+        self.recv(data.payload().chunk());
+        Ok(())
+    }
+
+    /// 取出一个完整的ws帧, 数据不足时返回None且不消耗缓存
+    pub fn poll_frame(&mut self) -> WebResult<Option<DataFrame>> {
+        let mut cursor = self.buffer.chunk();
+        let result = match self.max_frame_len {
+            Some(limit) => DataFrame::read_dataframe_with_limit(&mut cursor, false, limit),
+            None => DataFrame::read_dataframe(&mut cursor, false),
+        };
+        let used = self.buffer.remaining() - cursor.len();
+        match result {
+            Ok(frame) => {
+                self.buffer.advance(used);
+                Ok(Some(frame))
+            }
+            Err(WebError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 将ws帧编码为一个DATA帧, 客户端需传入掩码, 发送时可再用`Data::split`按窗口拆分
+    pub fn encode_frame(
+        &self,
+        frame: &DataFrame,
+        masking_key: Option<[u8; 4]>,
+        end_stream: bool,
+    ) -> WebResult<Data<Binary>> {
+        let mut payload = BinaryMut::with_capacity(frame.frame_size(masking_key.is_some()));
+        frame.write_to(&mut payload, masking_key)?;
+        let flag = if end_stream { Flag::end_stream() } else { Flag::zero() };
+        let head = FrameHeader::new(Kind::Data, flag, self.stream_id);
+        Ok(Data::new(head, payload.freeze()))
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2022 - 2024 Wenmeng See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+// Author: tickbh
+// -----
+// Created Date: 2024/01/08 10:21:37
+
+use core::str::from_utf8;
+
+use crate::{ws::WsError, WebError, WebResult};
+
+/// 增量的UTF-8校验, 用于分片的文本消息
+///
+/// 每收到一个分片调用`feed`, 跨分片被截断的字符会保留到下一次校验,
+/// 消息结束时调用`finish`确认没有未完成的字符
+///
+/// # Examples
+///
+/// ```
+/// use webparse::ws::Utf8Validator;
+///
+/// let mut validator = Utf8Validator::new();
+/// validator.feed(b"a\xce").unwrap();
+/// assert!(!validator.is_complete());
+/// validator.feed(b"\xba").unwrap();
+/// validator.finish().unwrap();
+///
+/// let mut validator = Utf8Validator::new();
+/// assert!(validator.feed(b"a\xce\xff").is_err());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Utf8Validator {
+    partial: [u8; 4],
+    len: usize,
+}
+
+impl Utf8Validator {
+    pub fn new() -> Utf8Validator {
+        Utf8Validator::default()
+    }
+
+    /// 是否没有被截断的字符
+    pub fn is_complete(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// 校验一段数据, 出现非法的字节序列时立即返回错误
+    pub fn feed(&mut self, data: &[u8]) -> WebResult<()> {
+        let mut data = data;
+        // 先补全上一个分片中被截断的字符
+        while self.len > 0 {
+            let Some((&b, rest)) = data.split_first() else {
+                return Ok(());
+            };
+            self.partial[self.len] = b;
+            self.len += 1;
+            data = rest;
+            match from_utf8(&self.partial[..self.len]) {
+                Ok(_) => self.len = 0,
+                Err(e) if e.error_len().is_some() => return Err(Self::invalid()),
+                Err(_) => {}
+            }
+        }
+
+        match from_utf8(data) {
+            Ok(_) => Ok(()),
+            Err(e) if e.error_len().is_some() => Err(Self::invalid()),
+            Err(e) => {
+                let rest = &data[e.valid_up_to()..];
+                self.partial[..rest.len()].copy_from_slice(rest);
+                self.len = rest.len();
+                Ok(())
+            }
+        }
+    }
+
+    /// 消息结束, 存在被截断的字符时返回错误
+    pub fn finish(&mut self) -> WebResult<()> {
+        if self.len > 0 {
+            self.len = 0;
+            return Err(Self::invalid());
+        }
+        Ok(())
+    }
+
+    fn invalid() -> WebError {
+        WebError::Ws(WsError::DataFrameError("Invalid utf-8 text payload"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Utf8Validator;
+
+    #[test]
+    fn any_split() {
+        let data = "a\u{3ba}\u{1f600}\u{800}z".as_bytes();
+        for step in 1..data.len() {
+            let mut validator = Utf8Validator::new();
+            for part in data.chunks(step) {
+                validator.feed(part).unwrap();
+            }
+            validator.finish().unwrap();
+        }
+
+        let mut validator = Utf8Validator::new();
+        validator.feed(b"\xf0\x9f").unwrap();
+        assert!(validator.finish().is_err());
+        assert!(validator.feed(b"\xed").is_ok());
+        assert!(validator.feed(b"\xa0\x80").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! fuzz发现的会导致panic或死循环的输入, 解析时应返回错误
+#![cfg(feature = "std")]
+
+use webparse::{
+    http2::{
+        frame::{Frame, FrameHeader},
+        Decoder,
+    },
+    BinaryRef, Buf, Helper,
+};
+
+#[test]
+fn chunk_size_overflow() {
+    let data = b"ffffffffffffffffffffffff\r\n";
+    assert!(Helper::parse_chunk_data(&mut BinaryRef::from(&data[..])).is_err());
+    let data = b"ffffffffffffffff\r\n";
+    assert!(Helper::parse_chunk_data(&mut BinaryRef::from(&data[..])).is_err());
+}
+
+#[test]
+fn hpack_size_update() {
+    // 动态表大小更新, 之前返回0字节导致死循环
+    let data = [0x3f, 0xe1, 0x1f];
+    let mut decoder = Decoder::new();
+    assert!(decoder.decode(&mut BinaryRef::from(&data[..])).unwrap().is_empty());
+    let data = [54, 244, 236];
+    assert!(decoder.decode(&mut BinaryRef::from(&data[..])).is_err());
+}
+
+#[test]
+fn data_frame_stream_zero() {
+    let data = [0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 157];
+    let mut buffer = BinaryRef::from(&data[..]);
+    let header = FrameHeader::parse(&mut buffer).unwrap().unwrap();
+    let payload = BinaryRef::from(buffer.chunk());
+    assert!(Frame::parse(header, payload, &mut Decoder::new(), 16_384).is_err());
+}
+
+#[test]
+fn hpack_header_list_bomb() {
+    // 哈夫曼编码的名字长度为1000字节, 在解码前就应拒绝
+    let mut data = vec![0x00, 0xff, 0xe9, 0x06];
+    data.extend_from_slice(&[0xff; 1000]);
+    let mut decoder = Decoder::new();
+    decoder.set_max_header_list_size(100);
+    assert!(decoder.decode(&mut BinaryRef::from(&data[..])).is_err());
+}
@@ -0,0 +1,478 @@
+//! RFC 7541 附录C中的HPACK示例, 以及各类帧的二进制编解码往返
+#![cfg(feature = "std")]
+
+use webparse::{
+    http2::{
+        encoder::{Encoder, Indexing},
+        frame::{Flag, Frame, FrameHeader, Kind, Settings, StreamIdentifier},
+        Decoder, HeaderIndex,
+    },
+    Binary, BinaryMut, BinaryRef, Buf, HeaderName, HeaderValue,
+};
+
+fn hex(s: &str) -> Vec<u8> {
+    let s: String = s.split_whitespace().collect();
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn headers(list: &[(&str, &str)]) -> Vec<(HeaderName, HeaderValue)> {
+    list.iter()
+        .map(|(n, v)| {
+            (
+                HeaderName::from_bytes(n.as_bytes()).unwrap(),
+                HeaderValue::Value(v.as_bytes().to_vec()),
+            )
+        })
+        .collect()
+}
+
+fn assert_headers(actual: &[(HeaderName, HeaderValue)], expect: &[(&str, &str)]) {
+    assert_eq!(actual.len(), expect.len());
+    for (a, e) in actual.iter().zip(expect) {
+        assert_eq!(a.0.name(), e.0);
+        assert_eq!(a.1.as_bytes(), e.1.as_bytes());
+    }
+}
+
+/// 校验动态表, 从最新的条目开始
+fn assert_table(index: &HeaderIndex, expect: &[(&str, &str)], size: usize) {
+    let table = index.to_vec();
+    assert_headers(&table, expect);
+    assert_eq!(index.get_size(), size);
+}
+
+/// 一组连续的头块, 依次为编码后的数据, 解码后的头, 解码后的动态表及其大小
+type Story<'a> = [(&'a str, &'a [(&'a str, &'a str)], &'a [(&'a str, &'a str)], usize)];
+
+fn decode_story(table_size: usize, story: &Story) {
+    let mut decoder = Decoder::new_index(HeaderIndex::shared(table_size));
+    for (block, expect, table, size) in story {
+        let data = hex(block);
+        let decoded = decoder.decode(&mut BinaryRef::from(&data[..])).unwrap();
+        assert_headers(&decoded, expect);
+        assert_table(&decoder.index.read().unwrap(), table, *size);
+    }
+}
+
+fn encode_story(table_size: usize, story: &Story) {
+    let mut encoder = Encoder::new_index(HeaderIndex::shared(table_size), 16_384);
+    for (block, expect, table, size) in story {
+        let list = headers(expect);
+        let encoded = encoder.encode(list.iter().map(|(n, v)| (n, v)));
+        assert_eq!(encoded.chunk(), &hex(block)[..]);
+        assert_table(&encoder.index.read().unwrap(), table, *size);
+    }
+}
+
+const REQ1: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "http"),
+    (":path", "/"),
+    (":authority", "www.example.com"),
+];
+const REQ2: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "http"),
+    (":path", "/"),
+    (":authority", "www.example.com"),
+    ("cache-control", "no-cache"),
+];
+const REQ3: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "https"),
+    (":path", "/index.html"),
+    (":authority", "www.example.com"),
+    ("custom-key", "custom-value"),
+];
+const REQ_TABLE1: &[(&str, &str)] = &[(":authority", "www.example.com")];
+const REQ_TABLE2: &[(&str, &str)] = &[
+    ("cache-control", "no-cache"),
+    (":authority", "www.example.com"),
+];
+const REQ_TABLE3: &[(&str, &str)] = &[
+    ("custom-key", "custom-value"),
+    ("cache-control", "no-cache"),
+    (":authority", "www.example.com"),
+];
+
+const RES1: &[(&str, &str)] = &[
+    (":status", "302"),
+    ("cache-control", "private"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("location", "https://www.example.com"),
+];
+const RES2: &[(&str, &str)] = &[
+    (":status", "307"),
+    ("cache-control", "private"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("location", "https://www.example.com"),
+];
+const RES3: &[(&str, &str)] = &[
+    (":status", "200"),
+    ("cache-control", "private"),
+    ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+    ("location", "https://www.example.com"),
+    ("content-encoding", "gzip"),
+    ("set-cookie", "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1"),
+];
+const RES_TABLE1: &[(&str, &str)] = &[
+    ("location", "https://www.example.com"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("cache-control", "private"),
+    (":status", "302"),
+];
+const RES_TABLE2: &[(&str, &str)] = &[
+    (":status", "307"),
+    ("location", "https://www.example.com"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("cache-control", "private"),
+];
+const RES_TABLE3: &[(&str, &str)] = &[
+    ("set-cookie", "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1"),
+    ("content-encoding", "gzip"),
+    ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+];
+
+/// C.3 不使用Huffman编码的请求
+const C3: &Story = &[
+    ("8286 8441 0f77 7777 2e65 7861 6d70 6c65 2e63 6f6d", REQ1, REQ_TABLE1, 57),
+    ("8286 84be 5808 6e6f 2d63 6163 6865", REQ2, REQ_TABLE2, 110),
+    (
+        "8287 85bf 400a 6375 7374 6f6d 2d6b 6579 0c63 7573 746f 6d2d 7661 6c75 65",
+        REQ3,
+        REQ_TABLE3,
+        164,
+    ),
+];
+
+/// C.4 使用Huffman编码的请求
+const C4: &Story = &[
+    ("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff", REQ1, REQ_TABLE1, 57),
+    ("8286 84be 5886 a8eb 1064 9cbf", REQ2, REQ_TABLE2, 110),
+    (
+        "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+        REQ3,
+        REQ_TABLE3,
+        164,
+    ),
+];
+
+/// C.5 不使用Huffman编码的响应, 动态表大小为256
+const C5: &Story = &[
+    (
+        "4803 3330 3258 0770 7269 7661 7465 611d 4d6f 6e2c 2032 3120 4f63 7420 3230 3133
+         2032 303a 3133 3a32 3120 474d 546e 1768 7474 7073 3a2f 2f77 7777 2e65 7861 6d70
+         6c65 2e63 6f6d",
+        RES1,
+        RES_TABLE1,
+        222,
+    ),
+    ("4803 3330 37c1 c0bf", RES2, RES_TABLE2, 222),
+    (
+        "88c1 611d 4d6f 6e2c 2032 3120 4f63 7420 3230 3133 2032 303a 3133 3a32 3220 474d
+         54c0 5a04 677a 6970 7738 666f 6f3d 4153 444a 4b48 514b 425a 584f 5157 454f 5049
+         5541 5851 5745 4f49 553b 206d 6178 2d61 6765 3d33 3630 303b 2076 6572 7369 6f6e
+         3d31",
+        RES3,
+        RES_TABLE3,
+        215,
+    ),
+];
+
+/// C.6 使用Huffman编码的响应, 动态表大小为256
+const C6: &Story = &[
+    (
+        "4882 6402 5885 aec3 771a 4b61 96d0 7abe 9410 54d4 44a8 2005 9504 0b81 66e0 82a6
+         2d1b ff6e 919d 29ad 1718 63c7 8f0b 97c8 e9ae 82ae 43d3",
+        RES1,
+        RES_TABLE1,
+        222,
+    ),
+    ("4883 640e ffc1 c0bf", RES2, RES_TABLE2, 222),
+    (
+        "88c1 6196 d07a be94 1054 d444 a820 0595 040b 8166 e084 a62d 1bff c05a 839b d9ab
+         77ad 94e7 821d d7f2 e6c7 b335 dfdf cd5b 3960 d5af 2708 7f36 72c1 ab27 0fb5 291f
+         9587 3160 65c0 03ed 4ee5 b106 3d50 07",
+        RES3,
+        RES_TABLE3,
+        215,
+    ),
+];
+
+#[test]
+fn c2_literal_with_indexing() {
+    decode_story(
+        4096,
+        &[(
+            "400a 6375 7374 6f6d 2d6b 6579 0d63 7573 746f 6d2d 6865 6164 6572",
+            &[("custom-key", "custom-header")],
+            &[("custom-key", "custom-header")],
+            55,
+        )],
+    );
+}
+
+#[test]
+fn c2_literal_without_indexing() {
+    decode_story(
+        4096,
+        &[("040c 2f73 616d 706c 652f 7061 7468", &[(":path", "/sample/path")], &[], 0)],
+    );
+}
+
+#[test]
+fn c2_literal_never_indexed() {
+    decode_story(
+        4096,
+        &[("1008 7061 7373 776f 7264 0673 6563 7265 74", &[("password", "secret")], &[], 0)],
+    );
+}
+
+#[test]
+fn c2_indexed() {
+    decode_story(4096, &[("82", &[(":method", "GET")], &[], 0)]);
+}
+
+#[test]
+fn encoder_indexing_policy() {
+    let mut encoder = Encoder::new();
+    encoder.use_common_indexing();
+    encoder.set_indexing(HeaderName::from_static("x-trace-id"), Indexing::Without);
+    let mut decoder = Decoder::new();
+    let expect = &[
+        (":path", "/api/1"),
+        ("content-type", "application/json"),
+        ("authorization", "secret"),
+        ("x-trace-id", "abc"),
+    ];
+    let list = headers(expect);
+    let first = encoder.encode(list.iter().map(|(n, v)| (n, v)));
+    // :path为静态表中的名字, authorization为never indexed
+    assert_eq!(first[0], 0x04);
+    let decoded = decoder.decode(&mut BinaryRef::from(first.chunk())).unwrap();
+    assert_headers(&decoded, expect);
+    let table = &[("content-type", "application/json")];
+    assert_table(&encoder.index.read().unwrap(), table, 60);
+    assert_table(&decoder.index.read().unwrap(), table, 60);
+
+    let second = encoder.encode(list.iter().map(|(n, v)| (n, v)));
+    assert!(second.len() < first.len());
+    let decoded = decoder.decode(&mut BinaryRef::from(second.chunk())).unwrap();
+    assert_headers(&decoded, expect);
+}
+
+#[test]
+fn static_table() {
+    decode_story(
+        4096,
+        &[(
+            "8f 90 bd",
+            &[
+                ("accept-charset", ""),
+                ("accept-encoding", "gzip, deflate"),
+                ("www-authenticate", ""),
+            ],
+            &[],
+            0,
+        )],
+    );
+}
+
+#[test]
+fn c3_requests() {
+    decode_story(4096, C3);
+}
+
+#[test]
+fn c4_requests_huffman() {
+    decode_story(4096, C4);
+    encode_story(4096, C4);
+}
+
+#[test]
+fn c5_responses() {
+    decode_story(256, C5);
+}
+
+#[test]
+fn c6_responses_huffman() {
+    decode_story(256, C6);
+    encode_story(256, C6);
+}
+
+/// 解析一个完整的帧后重新编码, 应与原数据一致
+fn round_trip(data: &[u8]) -> Frame<Binary> {
+    let mut buffer = BinaryRef::from(data);
+    let header = FrameHeader::parse(&mut buffer).unwrap().unwrap();
+    let len = header.length as usize;
+    assert_eq!(buffer.remaining(), len);
+    let payload = Binary::from(buffer.chunk().to_vec());
+    let frame = Frame::parse(header, payload, &mut Decoder::new(), 16_384).unwrap();
+
+    let again = Frame::parse(
+        FrameHeader::parse(&mut BinaryRef::from(data)).unwrap().unwrap(),
+        Binary::from(data[9..].to_vec()),
+        &mut Decoder::new(),
+        16_384,
+    )
+    .unwrap();
+    let mut out = BinaryMut::new();
+    again.encode(&mut out, &mut Encoder::new()).unwrap();
+    assert_eq!(out.chunk(), data);
+    frame
+}
+
+#[test]
+fn frame_data() {
+    let frame = round_trip(&hex("000005 00 01 00000001 68656c6c6f"));
+    assert!(frame.is_data() && frame.is_end_stream());
+}
+
+#[test]
+fn frame_headers() {
+    // :status 200, END_STREAM | END_HEADERS
+    let frame = round_trip(&hex("000001 01 05 00000001 88"));
+    assert!(frame.is_header() && frame.is_end_headers());
+}
+
+#[test]
+fn frame_priority() {
+    // 独占依赖于流1, 权重15
+    let frame = round_trip(&hex("000005 02 00 00000003 80000001 0f"));
+    match frame {
+        Frame::Priority(p) => {
+            assert_eq!(p.dependency_id().0, 1);
+            assert_eq!(p.weight(), 15);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn frame_reset() {
+    let frame = round_trip(&hex("000004 03 00 00000001 00000008"));
+    assert!(matches!(frame, Frame::Reset(_)));
+}
+
+#[test]
+fn frame_settings() {
+    let frame = round_trip(&hex("00000c 04 00 00000000 0003 00000064 0004 0000ffff"));
+    match frame {
+        Frame::Settings(s) => {
+            assert_eq!(s.max_concurrent_streams(), Some(100));
+            assert_eq!(s.initial_window_size(), Some(65_535));
+        }
+        _ => unreachable!(),
+    }
+    let frame = round_trip(&hex("000000 04 01 00000000"));
+    assert!(matches!(frame, Frame::Settings(s) if s.is_ack()));
+}
+
+#[test]
+fn frame_push_promise() {
+    // 承诺流2, 头块为:method GET, :authority www.example.com, :scheme http, :path /
+    let frame = round_trip(&hex(
+        "000015 05 04 00000001 00000002 82 418cf1e3c2e5f23a6ba0ab90f4ff 86 84",
+    ));
+    assert!(matches!(frame, Frame::PushPromise(_)));
+}
+
+#[test]
+fn frame_ping() {
+    let frame = round_trip(&hex("000008 06 01 00000000 0102030405060708"));
+    assert!(matches!(frame, Frame::Ping(_)));
+}
+
+#[test]
+fn frame_go_away() {
+    let frame = round_trip(&hex("00000a 07 00 00000000 00000003 00000000 6869"));
+    assert!(matches!(frame, Frame::GoAway(_)));
+}
+
+#[test]
+fn frame_window_update() {
+    let frame = round_trip(&hex("000004 08 00 00000001 00001000"));
+    assert!(matches!(frame, Frame::WindowUpdate(_)));
+}
+
+#[test]
+fn frame_header_golden() {
+    let cases = [
+        ("000000 04 01 00000000", 0, Kind::Settings, 0x1, 0),
+        ("004000 00 01 00000003", 16384, Kind::Data, 0x1, 3),
+        ("ffffff 01 24 7fffffff", (1 << 24) - 1, Kind::Headers, 0x24, (1 << 31) - 1),
+        ("000003 fa 00 00000005", 3, Kind::Unregistered(0xfa), 0, 5),
+    ];
+    for (data, length, kind, flag, id) in cases {
+        let data = hex(data);
+        let mut buffer = BinaryRef::from(&data[..]);
+        let head = FrameHeader::parse(&mut buffer).unwrap().unwrap();
+        assert_eq!(buffer.remaining(), 0);
+        assert_eq!(head.length, length);
+        assert_eq!(*head.kind(), kind);
+        assert_eq!(head.flag().bits(), flag);
+        assert_eq!(head.stream_id(), StreamIdentifier(id));
+        let mut out = BinaryMut::new();
+        assert_eq!(head.encode(&mut out).unwrap(), FrameHeader::LENGTH);
+        assert_eq!(out.chunk(), &data[..]);
+    }
+
+    // 保留位在解析时忽略, 编码时写入0
+    let data = hex("000000 04 00 80000001");
+    let (head, reserved) = FrameHeader::parse_reserved(&mut BinaryRef::from(&data[..])).unwrap().unwrap();
+    assert_eq!(head.stream_id(), StreamIdentifier(1));
+    assert!(reserved);
+    assert_eq!(head, FrameHeader::new(Kind::Settings, Flag::zero(), StreamIdentifier(1)));
+    assert!(FrameHeader::parse_strict(&mut BinaryRef::from(&data[..])).is_err());
+    let mut out = BinaryMut::new();
+    FrameHeader::new(Kind::Ping, Flag::zero(), StreamIdentifier(0x8000_0001))
+        .encode(&mut out)
+        .unwrap();
+    assert_eq!(out.chunk(), &hex("000000 06 00 00000001")[..]);
+
+    let data = hex("000008 06 00 000000");
+    let mut buffer = BinaryRef::from(&data[..]);
+    assert!(FrameHeader::parse(&mut buffer).unwrap().is_none());
+    assert_eq!(buffer.remaining(), 8);
+    let mut head = FrameHeader::new(Kind::Data, Flag::zero(), StreamIdentifier(1));
+    head.length = 1 << 24;
+    assert!(head.encode(&mut BinaryMut::new()).is_err());
+}
+
+/// RFC 7540 3.2.1, HTTP2-Settings为SETTINGS帧负载的base64url编码, 不带填充
+#[test]
+fn http2_settings_header() {
+    let mut settings = Settings::default();
+    settings.set_max_concurrent_streams(Some(100));
+    settings.set_enable_push(false);
+    settings.set_max_frame_size(Some(16384));
+    let value = settings.encode_http2_settings_header();
+    assert_eq!(value, HeaderValue::from_static("AAIAAAAAAAMAAABkAAUAAEAA"));
+    let decoded = Settings::decode_http2_settings_header(&value).unwrap();
+    assert_eq!(decoded.encode_http2_settings_header(), value);
+    assert_eq!(decoded.max_frame_size(), Some(16384));
+
+    // 空的设置及首尾空白
+    let empty = Settings::decode_http2_settings_header(&HeaderValue::from_static("")).unwrap();
+    assert_eq!(empty.encode_http2_settings_header(), HeaderValue::from_static(""));
+    assert!(Settings::decode_http2_settings_header(&HeaderValue::from_static(" AAMAAABk ")).is_ok());
+
+    for invalid in [
+        // 标准base64字符
+        "AAMAAABkAAQAAP//",
+        "AAMAAABkAAQAAP++",
+        // 长度不是6的倍数
+        "AAMAAABkAA",
+        // SETTINGS_ENABLE_PUSH只能为0或1
+        "AAIAAAAC",
+    ] {
+        assert!(
+            Settings::decode_http2_settings_header(&HeaderValue::from_static(invalid)).is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! 解析后输出再解析应得到相同的结果: parse ∘ display ∘ parse == parse
+
+use proptest::prelude::*;
+use webparse::{BinaryMut, Buf, Request, Url};
+
+/// 各组成部分的字符, 包含需要转码及会影响分段的字符
+fn piece() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            "[a-zA-Z0-9._~-]",
+            "[!$&'()*+,;=:@/?]",
+            Just("%20".to_string()),
+            Just("%25".to_string()),
+            Just("%2F".to_string()),
+            Just("%3A".to_string()),
+            Just("%3F".to_string()),
+            Just("%40".to_string()),
+            Just("%23".to_string()),
+            Just("%E4%BD%A0".to_string()),
+        ],
+        0..12,
+    )
+    .prop_map(|v| v.concat())
+}
+
+fn userinfo() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            "[a-zA-Z0-9._~-]",
+            Just("%3A".to_string()),
+            Just("%40".to_string()),
+            Just("%2F".to_string()),
+            Just("%3F".to_string()),
+            Just("%20".to_string()),
+        ],
+        0..6,
+    )
+    .prop_map(|v| v.concat())
+}
+
+fn url_string() -> impl Strategy<Value = String> {
+    (
+        prop::option::of(prop::sample::select(vec!["http", "https", "ws", "wss"])),
+        prop::option::of((userinfo(), userinfo())),
+        "[a-z0-9][a-z0-9.-]{0,15}",
+        prop::option::of(0u16..),
+        piece(),
+        prop::option::of(piece()),
+    )
+        .prop_map(|(scheme, userinfo, host, port, path, query)| {
+            let mut s = String::new();
+            if let Some(scheme) = scheme {
+                s.push_str(scheme);
+                s.push_str("://");
+                if let Some((user, pass)) = userinfo {
+                    s.push_str(&format!("{}:{}@", user, pass));
+                }
+                s.push_str(&host);
+                if let Some(port) = port {
+                    s.push_str(&format!(":{}", port));
+                }
+            }
+            s.push('/');
+            s.push_str(&path);
+            if let Some(query) = query {
+                s.push('?');
+                s.push_str(&query);
+            }
+            s
+        })
+}
+
+fn request_string() -> impl Strategy<Value = String> {
+    (
+        prop::sample::select(vec!["GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "PATCH"]),
+        piece(),
+        prop::sample::select(vec!["HTTP/1.0", "HTTP/1.1"]),
+        prop::collection::vec(("[a-zA-Z][a-zA-Z0-9-]{0,10}", "[ -~]{0,20}"), 0..6),
+    )
+        .prop_map(|(method, path, version, headers)| {
+            let mut s = format!("{} /{} {}\r\n", method, path, version);
+            for (name, value) in headers {
+                s.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            s.push_str("\r\n");
+            s
+        })
+}
+
+proptest! {
+    #[test]
+    fn url_display(s in url_string()) {
+        if let Ok(url) = Url::try_from(s.as_str()) {
+            let display = url.to_string();
+            let reparsed = Url::try_from(display.as_str());
+            prop_assert_eq!(reparsed.as_ref().ok(), Some(&url), "{} => {}", s, display);
+        }
+    }
+
+    #[test]
+    fn request_head(s in request_string()) {
+        let mut req = Request::new();
+        if req.parse(s.as_bytes()).is_ok() {
+            let mut buffer = BinaryMut::new();
+            req.encode_header(&mut buffer).unwrap();
+            let encoded = buffer.chunk().to_vec();
+            let mut other = Request::new();
+            prop_assert_eq!(other.parse(&encoded).ok(), Some(encoded.len()));
+            prop_assert_eq!(other.method(), req.method());
+            prop_assert_eq!(other.path(), req.path());
+            prop_assert_eq!(other.version(), req.version());
+            prop_assert_eq!(other.url(), req.url());
+            prop_assert_eq!(other.headers(), req.headers());
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! 仿autobahn的websocket协议一致性测试, 每个用例为一段帧数据及期望的消息或错误
+
+use webparse::{
+    ws::{CloseData, DataFrame, OwnedMessage, WsError},
+    Buf, WebError, WebResult,
+};
+
+enum Expect {
+    Messages(Vec<OwnedMessage>),
+    Protocol(&'static str),
+    Frame(&'static str),
+}
+
+use Expect::*;
+
+/// 读取所有的帧并组装成消息, 控制帧可以插在分片消息中间
+fn run(mut data: &[u8]) -> WebResult<Vec<OwnedMessage>> {
+    let mut messages = vec![];
+    let mut pending = vec![];
+    while data.has_remaining() {
+        let frame = DataFrame::read_dataframe(&mut data, false)?;
+        if frame.opcode as u8 >= 8 {
+            messages.push(OwnedMessage::from_dataframes(vec![frame])?);
+            continue;
+        }
+        let finished = frame.finished;
+        pending.push(frame);
+        if finished {
+            messages.push(OwnedMessage::from_dataframes(std::mem::take(&mut pending))?);
+        }
+    }
+    assert!(pending.is_empty(), "unfinished message");
+    Ok(messages)
+}
+
+fn text(s: &str) -> OwnedMessage {
+    OwnedMessage::Text(s.to_string())
+}
+
+fn corpus() -> Vec<(&'static str, Vec<u8>, Expect)> {
+    vec![
+        // 1.x 基础帧
+        ("1.1 text", b"\x81\x05hello".to_vec(), Messages(vec![text("hello")])),
+        ("1.2 binary", b"\x82\x03\x00\x01\x02".to_vec(), Messages(vec![OwnedMessage::Binary(vec![0, 1, 2])])),
+        ("1.3 masked text", b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58".to_vec(), Messages(vec![text("Hello")])),
+        // 2.x 控制帧
+        ("2.1 ping", b"\x89\x04ping".to_vec(), Messages(vec![OwnedMessage::Ping(b"ping".to_vec())])),
+        ("2.2 ping 125", [&b"\x89\x7d"[..], &[b'a'; 125]].concat(), Messages(vec![OwnedMessage::Ping(vec![b'a'; 125])])),
+        ("2.3 ping 126", [&b"\x89\x7e\x00\x7e"[..], &[b'a'; 126]].concat(), Frame("Control frame length too long")),
+        ("2.4 fragmented ping", b"\x09\x00\x80\x00".to_vec(), Protocol("Illegal fragmented control frame")),
+        // 3.x 保留位及保留opcode
+        ("3.1 rsv1", b"\xc1\x02ok".to_vec(), Protocol("Unsupported reserved bits received")),
+        ("3.2 rsv3 ping", b"\x99\x00".to_vec(), Protocol("Unsupported reserved bits received")),
+        ("3.3 rsv2 continuation", b"\x01\x01a\xa0\x01b".to_vec(), Protocol("Unsupported reserved bits received")),
+        ("4.1 opcode 3", b"\x83\x00".to_vec(), Protocol("Unsupported opcode received")),
+        ("4.2 opcode 11", b"\x8b\x00".to_vec(), Protocol("Unsupported opcode received")),
+        // 5.x 分片
+        ("5.1 fragmented text", b"\x01\x03hel\x00\x01l\x80\x01o".to_vec(), Messages(vec![text("hello")])),
+        ("5.2 ping between fragments", b"\x01\x03hel\x89\x01p\x80\x02lo".to_vec(), Messages(vec![OwnedMessage::Ping(b"p".to_vec()), text("hello")])),
+        ("5.3 pong and close between fragments", b"\x02\x01a\x8a\x00\x88\x02\x03\xe8\x80\x01b".to_vec(), Messages(vec![OwnedMessage::Pong(vec![]), OwnedMessage::Close(Some(CloseData::normal())), OwnedMessage::Binary(b"ab".to_vec())])),
+        ("5.4 continuation without start", b"\x80\x02ab".to_vec(), Protocol("Unexpected continuation data frame")),
+        ("5.5 text inside fragments", b"\x01\x01a\x81\x01b".to_vec(), Protocol("Unexpected non-continuation data frame")),
+        ("5.6 binary inside fragments", b"\x01\x01a\x02\x01b\x80\x01c".to_vec(), Protocol("Unexpected non-continuation data frame")),
+        // 6.x utf-8
+        ("6.1 valid utf-8", b"\x81\x06\xce\xba\xf0\x9f\x98\x80".to_vec(), Messages(vec![text("\u{3ba}\u{1f600}")])),
+        ("6.2 codepoint split across fragments", b"\x01\x01\xce\x80\x01\xba".to_vec(), Messages(vec![text("\u{3ba}")])),
+        ("6.3 invalid utf-8", b"\x81\x04\xce\xba\xe1\xbd".to_vec(), Frame("Invalid utf-8 text payload")),
+        ("6.4 invalid utf-8 in fragment", b"\x01\x01a\x00\x01\xff\x80\x01b".to_vec(), Frame("Invalid utf-8 text payload")),
+        ("6.5 surrogate", b"\x81\x03\xed\xa0\x80".to_vec(), Frame("Invalid utf-8 text payload")),
+        // 7.x 关闭帧
+        ("7.1 empty close", b"\x88\x00".to_vec(), Messages(vec![OwnedMessage::Close(None)])),
+        ("7.2 close with reason", b"\x88\x05\x03\xe9bye".to_vec(), Messages(vec![OwnedMessage::Close(Some(CloseData::new(1001u16, "bye".to_string())))])),
+        ("7.3 close one byte", b"\x88\x01\x03".to_vec(), Protocol("Invalid close frame payload")),
+        ("7.4 close code 1005", b"\x88\x02\x03\xed".to_vec(), Protocol("Invalid close status code")),
+        ("7.5 close code 999", b"\x88\x02\x03\xe7".to_vec(), Protocol("Invalid close status code")),
+        ("7.6 close code 5000", b"\x88\x02\x13\x88".to_vec(), Protocol("Invalid close status code")),
+        ("7.7 close invalid reason", b"\x88\x03\x03\xe8\xff".to_vec(), Frame("Invalid utf-8 close reason")),
+    ]
+}
+
+#[test]
+fn conformance() {
+    for (name, data, expect) in corpus() {
+        match (run(&data), expect) {
+            (Ok(messages), Messages(expected)) => assert_eq!(messages, expected, "{}", name),
+            (Err(WebError::Ws(WsError::ProtocolError(msg))), Protocol(expected)) => {
+                assert_eq!(msg, expected, "{}", name)
+            }
+            (Err(WebError::Ws(WsError::DataFrameError(msg))), Frame(expected)) => {
+                assert_eq!(msg, expected, "{}", name)
+            }
+            (result, _) => panic!("{}: unexpected result {:?}", name, result),
+        }
+    }
+}